@@ -0,0 +1,150 @@
+//! Environment diagnostics for `pipeline-kit doctor`.
+//!
+//! Loads the project configuration and reports, for every configured agent,
+//! the adapter it resolves to, the program it spawns (path and version),
+//! the environment variables it needs and whether its availability check
+//! passes. The exit code is non-zero when any agent cannot run, so the
+//! command doubles as a CI preflight check.
+
+use crate::report::EXIT_CONFIG_ERROR;
+use crate::report::EXIT_FAILURE;
+use crate::report::EXIT_SUCCESS;
+use colored::Colorize;
+use pk_core::agents::manager::AgentManager;
+use pk_core::config::loader::load_config;
+use pk_protocol::AgentDiagnosis;
+use std::path::Path;
+
+/// Diagnose the agents configured under `root` and print the report.
+///
+/// # Arguments
+///
+/// * `root` - Project root containing `.pipeline-kit/`
+/// * `json` - Print the diagnoses as a JSON array instead of text
+///
+/// # Returns
+///
+/// The process exit code: success when every agent is available, failure
+/// when one is not, and the configuration error code when the config
+/// cannot be loaded.
+pub async fn run(root: &Path, json: bool) -> i32 {
+    let config = match load_config(root).await {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{} Failed to load config: {}", "Error:".red().bold(), e);
+            return EXIT_CONFIG_ERROR;
+        }
+    };
+
+    let diagnoses = AgentManager::new(config.agents).diagnose().await;
+    if json {
+        match serde_json::to_string_pretty(&diagnoses) {
+            Ok(text) => println!("{}", text),
+            Err(e) => eprintln!("Failed to serialize diagnostics: {}", e),
+        }
+    } else {
+        for line in render(&diagnoses) {
+            println!("{}", line);
+        }
+    }
+
+    if diagnoses.iter().all(|d| d.available) {
+        EXIT_SUCCESS
+    } else {
+        EXIT_FAILURE
+    }
+}
+
+/// Human-readable report, one block per agent and a summary line.
+pub fn render(diagnoses: &[AgentDiagnosis]) -> Vec<String> {
+    if diagnoses.is_empty() {
+        return vec!["No agents configured in .pipeline-kit/agents/".to_string()];
+    }
+
+    let mut lines = Vec::new();
+    for diagnosis in diagnoses {
+        let header = format!("{} ({})", diagnosis.agent_name, diagnosis.adapter_type);
+        lines.push(if diagnosis.available {
+            format!("✓ {}", header).green().to_string()
+        } else {
+            format!("✗ {}", header).red().to_string()
+        });
+
+        if let Some(binary) = &diagnosis.binary {
+            let found = match &diagnosis.binary_path {
+                Some(path) => format!(
+                    "{}  {}",
+                    path,
+                    diagnosis.version.as_deref().unwrap_or("(version unknown)")
+                ),
+                None => format!("{} not found on PATH", binary).red().to_string(),
+            };
+            lines.push(format!("    binary  {}", found));
+        }
+
+        for env_var in &diagnosis.env_vars {
+            let status = if env_var.present {
+                "set".green().to_string()
+            } else {
+                "missing".red().to_string()
+            };
+            lines.push(format!("    env     {} {}", env_var.name, status));
+        }
+    }
+
+    let available = diagnoses.iter().filter(|d| d.available).count();
+    lines.push(String::new());
+    lines.push(format!(
+        "{} of {} agents available",
+        available,
+        diagnoses.len()
+    ));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pk_protocol::EnvVarStatus;
+
+    #[test]
+    fn test_render_reports_missing_setup() {
+        colored::control::set_override(false);
+        let diagnoses = vec![
+            AgentDiagnosis {
+                agent_name: "developer".to_string(),
+                adapter_type: "Claude".to_string(),
+                binary: Some("claude".to_string()),
+                binary_path: Some("/usr/local/bin/claude".to_string()),
+                version: Some("1.0.3".to_string()),
+                env_vars: vec![],
+                available: true,
+            },
+            AgentDiagnosis {
+                agent_name: "reviewer".to_string(),
+                adapter_type: "Codex".to_string(),
+                binary: Some("codex".to_string()),
+                binary_path: None,
+                version: None,
+                env_vars: vec![EnvVarStatus {
+                    name: "OPENAI_API_KEY".to_string(),
+                    present: false,
+                }],
+                available: false,
+            },
+        ];
+
+        assert_eq!(
+            render(&diagnoses),
+            vec![
+                "✓ developer (Claude)",
+                "    binary  /usr/local/bin/claude  1.0.3",
+                "✗ reviewer (Codex)",
+                "    binary  codex not found on PATH",
+                "    env     OPENAI_API_KEY missing",
+                "",
+                "1 of 2 agents available",
+            ]
+        );
+    }
+}
@@ -1,12 +1,28 @@
 use clap::{Parser, Subcommand};
 use colored::Colorize;
+use pk_core::agents::permission_bridge;
 use pk_core::config::loader::load_config;
 use pk_core::engine::PipelineEngine;
 use pk_core::init::{generate_pipeline_kit_structure, InitOptions};
 use pk_core::state::process::create_process;
 use pk_protocol::ipc::Event;
+use pk_protocol::process_models::ProcessStatus;
+use report::OutputFormat;
+use report::Reporter;
+use report::RunOutcome;
+use review::ReviewOutcome;
+use review::ReviewPolicy;
+use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
 use tokio::sync::mpsc;
+use uuid::Uuid;
+
+mod doctor;
+mod report;
+mod review;
+mod server;
+mod stdio;
 
 #[derive(Parser)]
 #[command(name = "pipeline-kit")]
@@ -42,9 +58,79 @@ enum Commands {
         #[arg(long, value_name = "PATH")]
         reference_file: Option<PathBuf>,
 
-        /// Run without launching the TUI and print events as JSON Lines
+        /// Run without launching the TUI (see --output for the report format)
         #[arg(long)]
         no_tui: bool,
+
+        /// Approve every HUMAN_REVIEW step automatically (headless only)
+        #[arg(long, requires = "no_tui")]
+        auto_approve: bool,
+
+        /// Seconds to wait for a review decision before applying --review-default
+        #[arg(long, value_name = "SECS", requires = "no_tui")]
+        review_timeout: Option<u64>,
+
+        /// Outcome applied when --review-timeout elapses
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = ReviewOutcome::Reject,
+            requires = "no_tui",
+            requires = "review_timeout"
+        )]
+        review_default: ReviewOutcome,
+
+        /// How headless progress is reported
+        #[arg(long, value_enum, default_value_t = OutputFormat::Jsonl, requires = "no_tui")]
+        output: OutputFormat,
+
+        /// Abort the headless run after this many seconds
+        #[arg(long, value_name = "SECS", requires = "no_tui")]
+        timeout: Option<u64>,
+    },
+
+    /// Approve (or reject) a process paused for human review in `run --no-tui`
+    Approve {
+        /// ID of the paused process, as printed in its processStarted event
+        process_id: Uuid,
+
+        /// Reject instead of approving; the process fails
+        #[arg(long)]
+        reject: bool,
+    },
+
+    /// Check that every configured agent can run
+    ///
+    /// Reports each agent's adapter, program path and version, required
+    /// environment variables and availability; exits non-zero when an
+    /// agent is unavailable.
+    Doctor {
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Serve the local HTTP API on 127.0.0.1, or JSON-RPC over stdio
+    ///
+    /// HTTP requests must carry the access token from PIPELINE_KIT_API_TOKEN
+    /// (a random token is generated and printed when it is unset).
+    Serve {
+        /// Port to listen on (0 picks a free port)
+        #[arg(long, default_value_t = 7420)]
+        port: u16,
+
+        /// Read Op requests from stdin and write responses and Events to stdout as JSON Lines
+        #[arg(long, conflicts_with = "port")]
+        stdio: bool,
+    },
+
+    /// MCP server Claude asks before running a tool (launched by the Claude
+    /// adapter; the token comes from PIPELINE_KIT_BRIDGE_TOKEN)
+    #[command(hide = true)]
+    PermissionBridge {
+        /// Address of the agent's permission bridge
+        #[arg(long)]
+        addr: String,
     },
 }
 
@@ -129,8 +215,13 @@ async fn main() -> color_eyre::Result<()> {
         }
         Some(Commands::Run {
             pipeline,
-            reference_file: _reference_file,
+            reference_file,
             no_tui,
+            auto_approve,
+            review_timeout,
+            review_default,
+            output,
+            timeout,
         }) => {
             if !no_tui {
                 // For now, default to launching the TUI when --no-tui is not provided
@@ -139,61 +230,191 @@ async fn main() -> color_eyre::Result<()> {
                     .map_err(|e| color_eyre::eyre::eyre!(e));
             }
 
-            // Headless mode: load config, run pipeline, and print Events as JSON Lines
             let root = std::env::current_dir()?;
-            let config = load_config(&root)
-                .await
-                .map_err(|e| color_eyre::eyre::eyre!(format!("Failed to load config: {}", e)))?;
-
-            // Find the requested pipeline
-            let pipeline_def = config
-                .pipelines
-                .iter()
-                .find(|p| p.name == pipeline)
-                .cloned()
-                .ok_or_else(|| {
-                    color_eyre::eyre::eyre!(format!("Pipeline not found: {}", pipeline))
-                })?;
-
-            // Initialize AgentManager with configured agents
-            let manager = pk_core::agents::manager::AgentManager::new(config.agents);
-            let engine = PipelineEngine::new(manager);
-
-            // Create channels for event streaming
-            let (tx, mut rx) = mpsc::channel::<Event>(100);
-
-            // Create initial process
-            let process = create_process(pipeline_def.name.clone());
-
-            // Spawn printer task for JSON Lines output
-            let printer = tokio::spawn(async move {
-                while let Some(event) = rx.recv().await {
-                    match serde_json::to_string(&event) {
-                        Ok(line) => {
-                            println!("{}", line);
-                        }
-                        Err(e) => {
-                            eprintln!("Failed to serialize event: {}", e);
-                        }
-                    }
-                }
-            });
+            let policy = ReviewPolicy::new(
+                &root,
+                auto_approve,
+                review_timeout.map(Duration::from_secs),
+                review_default,
+            );
 
-            // Run the pipeline
-            let result = engine.run(&pipeline_def, process, tx).await;
+            let outcome = run_headless(
+                &root,
+                &pipeline,
+                reference_file,
+                output,
+                timeout.map(Duration::from_secs),
+                &policy,
+            )
+            .await;
+            if let RunOutcome::ConfigError(e) = &outcome {
+                eprintln!("{} {}", "Error:".red().bold(), e);
+            }
+            std::process::exit(outcome.exit_code());
+        }
+        Some(Commands::Approve { process_id, reject }) => {
+            let root = std::env::current_dir()?;
+            let outcome = if reject {
+                ReviewOutcome::Reject
+            } else {
+                ReviewOutcome::Approve
+            };
+            review::write_approval(&review::approvals_dir(&root), process_id, outcome)?;
+            println!(
+                "{}",
+                format!("✓ Recorded {:?} for process {}", outcome, process_id).green()
+            );
+            Ok(())
+        }
+        Some(Commands::Doctor { json }) => {
+            let root = std::env::current_dir()?;
+            std::process::exit(doctor::run(&root, json).await);
+        }
+        Some(Commands::Serve { port, stdio }) => {
+            if stdio {
+                stdio::serve_stdio().await
+            } else {
+                server::serve(port).await
+            }
+        }
+        Some(Commands::PermissionBridge { addr }) => {
+            let token = std::env::var(permission_bridge::TOKEN_ENV).unwrap_or_default();
+            let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+            permission_bridge::serve_mcp(stdin, tokio::io::stdout(), &addr, &token).await?;
+            Ok(())
+        }
+    }
+}
 
-            // Ensure printer task completes
-            let _ = printer.await;
+/// Run a pipeline without the TUI.
+///
+/// Events are rendered by a `Reporter` in the requested format, HUMAN_REVIEW
+/// pauses are resolved through `policy`, and the run is aborted once
+/// `timeout` elapses.
+///
+/// # Returns
+///
+/// How the run ended; the caller turns it into the process exit code.
+async fn run_headless(
+    root: &Path,
+    pipeline: &str,
+    reference_file: Option<PathBuf>,
+    output: OutputFormat,
+    timeout: Option<Duration>,
+    policy: &ReviewPolicy,
+) -> RunOutcome {
+    // 1. Load config and find the requested pipeline
+    let config = match load_config(root).await {
+        Ok(config) => config,
+        Err(e) => return RunOutcome::ConfigError(format!("Failed to load config: {}", e)),
+    };
+    let Some(pipeline_def) = config
+        .pipelines
+        .iter()
+        .find(|p| p.name == pipeline)
+        .cloned()
+    else {
+        return RunOutcome::ConfigError(format!("Pipeline not found: {}", pipeline));
+    };
 
-            match result {
-                Ok(_) => Ok(()),
-                Err(e) => Err(color_eyre::eyre::eyre!(format!(
-                    "Pipeline execution failed: {}",
-                    e
-                ))),
+    // 2. Start the engine in the background so reviews can be answered here
+    let manager = pk_core::agents::manager::AgentManager::new(config.agents)
+        .with_default_fallback(config.global.fallback);
+    let engine = PipelineEngine::new(manager);
+    let (tx, mut rx) = mpsc::channel::<Event>(100);
+    let mut process = create_process(pipeline_def.name.clone());
+    process.reference_file = reference_file.map(|p| p.to_string_lossy().to_string());
+    let process_id = process.id;
+    let resume_notifier = process.resume_notifier.clone();
+    let permission_replies = process.permission_replies.clone();
+
+    let mut reporter = Reporter::new(output, &pipeline_def);
+    let mut engine_task = tokio::spawn(async move { engine.run(&pipeline_def, process, tx).await });
+
+    let deadline = async {
+        match timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::pin!(deadline);
+    let timed_out = || {
+        RunOutcome::TimedOut(format!(
+            "Timed out after {}s",
+            timeout.map(|t| t.as_secs()).unwrap_or_default()
+        ))
+    };
+
+    // 3. Render events, answering HUMAN_REVIEW pauses and permission requests
+    //    as they come
+    let outcome = loop {
+        let event = tokio::select! {
+            event = rx.recv() => event,
+            _ = &mut deadline => break timed_out(),
+        };
+        let Some(event) = event else {
+            break match (&mut engine_task).await {
+                Ok(Ok(_)) => RunOutcome::Succeeded,
+                Ok(Err(e)) => RunOutcome::Failed(e.to_string()),
+                Err(e) => RunOutcome::Failed(format!("Pipeline task panicked: {}", e)),
+            };
+        };
+        reporter.event(&event);
+
+        if let Event::PermissionRequested { request, .. } = &event {
+            let allowed = tokio::select! {
+                allowed = review::await_permission(policy, request) => allowed,
+                _ = &mut deadline => break timed_out(),
+            };
+            let (reply, allowed) = match permission_replies.lock() {
+                Ok(mut replies) => (replies.remove(&request.request_id), allowed),
+                // A panic elsewhere poisoned the map: still settle the
+                // request so the agent stops waiting, but deny it
+                Err(poisoned) => (poisoned.into_inner().remove(&request.request_id), false),
+            };
+            if let Some(reply) = reply {
+                let _ = reply.send(allowed);
+                reporter.event(&Event::PermissionResolved {
+                    process_id,
+                    request_id: request.request_id,
+                    allowed,
+                });
+            }
+        }
+
+        if matches!(
+            event,
+            Event::ProcessStatusUpdate {
+                status: ProcessStatus::HumanReview,
+                ..
+            }
+        ) {
+            let review = tokio::select! {
+                review = review::await_review(policy, process_id) => review,
+                _ = &mut deadline => break timed_out(),
+            };
+            match review {
+                ReviewOutcome::Approve => {
+                    reporter.event(&Event::ProcessResumed { process_id });
+                    resume_notifier.notify_one();
+                }
+                ReviewOutcome::Reject => break RunOutcome::Rejected,
             }
         }
+    };
+
+    // 4. Stop the engine if the run ended early and report
+    if matches!(outcome, RunOutcome::TimedOut(_) | RunOutcome::Rejected) {
+        engine_task.abort();
+        if let Some(error) = outcome.error() {
+            reporter.event(&Event::ProcessError {
+                process_id,
+                error: error.to_string(),
+            });
+        }
     }
+    reporter.finish(&outcome);
+    outcome
 }
 
 #[cfg(test)]
@@ -297,12 +518,169 @@ mod tests {
                 pipeline,
                 reference_file,
                 no_tui,
+                auto_approve,
+                review_timeout,
+                review_default,
+                output,
+                timeout,
             }) => {
                 assert_eq!(pipeline, "simple-task");
                 assert!(reference_file.is_none());
                 assert!(no_tui);
+                assert!(!auto_approve);
+                assert!(review_timeout.is_none());
+                assert_eq!(review_default, ReviewOutcome::Reject);
+                assert_eq!(output, OutputFormat::Jsonl);
+                assert!(timeout.is_none());
             }
             _ => panic!("Expected Run command"),
         }
     }
+
+    #[test]
+    fn test_cli_parsing_run_review_flags() {
+        let cli = Cli::try_parse_from([
+            "pipeline-kit",
+            "run",
+            "code-review",
+            "--no-tui",
+            "--review-timeout",
+            "600",
+            "--review-default",
+            "approve",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run {
+                review_timeout: Some(600),
+                review_default: ReviewOutcome::Approve,
+                ..
+            })
+        ));
+
+        // Review flags only make sense in headless mode
+        assert!(
+            Cli::try_parse_from(["pipeline-kit", "run", "code-review", "--auto-approve"]).is_err()
+        );
+
+        // --review-default only applies once --review-timeout elapses
+        assert!(Cli::try_parse_from([
+            "pipeline-kit",
+            "run",
+            "code-review",
+            "--no-tui",
+            "--review-default",
+            "approve",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_run_output_and_timeout() {
+        let cli = Cli::try_parse_from([
+            "pipeline-kit",
+            "run",
+            "simple-task",
+            "--no-tui",
+            "--output",
+            "junit",
+            "--timeout",
+            "300",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run {
+                output: OutputFormat::Junit,
+                timeout: Some(300),
+                ..
+            })
+        ));
+
+        assert!(Cli::try_parse_from([
+            "pipeline-kit",
+            "run",
+            "simple-task",
+            "--no-tui",
+            "--output",
+            "xml"
+        ])
+        .is_err());
+        assert!(
+            Cli::try_parse_from(["pipeline-kit", "run", "simple-task", "--output", "pretty"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_parsing_approve() {
+        let id = Uuid::new_v4();
+        let cli =
+            Cli::try_parse_from(["pipeline-kit", "approve", &id.to_string(), "--reject"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Approve { process_id, reject: true }) if process_id == id
+        ));
+
+        assert!(Cli::try_parse_from(["pipeline-kit", "approve", "not-a-uuid"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_doctor() {
+        let cli = Cli::try_parse_from(["pipeline-kit", "doctor"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Doctor { json: false })
+        ));
+
+        let cli = Cli::try_parse_from(["pipeline-kit", "doctor", "--json"]).unwrap();
+        assert!(matches!(cli.command, Some(Commands::Doctor { json: true })));
+    }
+
+    #[test]
+    fn test_cli_parsing_serve_default_port() {
+        let cli = Cli::try_parse_from(["pipeline-kit", "serve"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Serve {
+                port: 7420,
+                stdio: false
+            })
+        ));
+    }
+
+    #[test]
+    fn test_cli_parsing_serve_with_port() {
+        let cli = Cli::try_parse_from(["pipeline-kit", "serve", "--port", "0"]).unwrap();
+        assert!(matches!(cli.command, Some(Commands::Serve { port: 0, .. })));
+    }
+
+    #[test]
+    fn test_cli_parsing_serve_stdio() {
+        let cli = Cli::try_parse_from(["pipeline-kit", "serve", "--stdio"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Serve { stdio: true, .. })
+        ));
+
+        // --stdio and --port are mutually exclusive
+        assert!(Cli::try_parse_from(["pipeline-kit", "serve", "--stdio", "--port", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_permission_bridge() {
+        // The Claude adapter launches the bridge by this subcommand name
+        let cli = Cli::try_parse_from([
+            "pipeline-kit",
+            permission_bridge::SUBCOMMAND,
+            "--addr",
+            "127.0.0.1:4100",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::PermissionBridge { addr }) if addr == "127.0.0.1:4100"
+        ));
+    }
 }
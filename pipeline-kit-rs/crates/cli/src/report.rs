@@ -0,0 +1,729 @@
+//! Output formats, final summary and exit codes for `run --no-tui`.
+//!
+//! The `Reporter` consumes the `Event` stream of a headless run. Depending
+//! on the selected `OutputFormat` it prints every event as it arrives
+//! (`jsonl`, `pretty`, `github`) or only a report at the end (`junit`).
+//! Every format ends with a short summary; machine-readable formats write
+//! it to stderr so stdout stays parseable.
+
+use clap::ValueEnum;
+use colored::Colorize;
+use pk_protocol::ipc::Event;
+use pk_protocol::pipeline_models::Pipeline;
+use pk_protocol::process_models::StepKind;
+use pk_protocol::process_models::StepRecord;
+use pk_protocol::process_models::StepStatus;
+use pk_protocol::process_models::TokenUsage;
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::Instant;
+
+/// Exit code of a run that completed every step.
+pub const EXIT_SUCCESS: i32 = 0;
+
+/// Exit code of a run that failed (agent error, exceeded budget, ...).
+pub const EXIT_FAILURE: i32 = 1;
+
+/// Exit code when the configuration could not be loaded or the pipeline
+/// does not exist.
+pub const EXIT_CONFIG_ERROR: i32 = 2;
+
+/// Exit code when `--timeout` elapsed before the run finished.
+pub const EXIT_TIMEOUT: i32 = 3;
+
+/// Exit code when a HUMAN_REVIEW step was rejected.
+pub const EXIT_REJECTED: i32 = 4;
+
+/// How a headless run reports its progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// One `Event` JSON object per line.
+    Jsonl,
+    /// Human-readable progress.
+    Pretty,
+    /// GitHub Actions workflow commands (log groups, annotations, step summary).
+    Github,
+    /// A JUnit XML report with one test case per step, printed at the end.
+    Junit,
+}
+
+/// Final result of a headless run.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunOutcome {
+    /// Every step completed.
+    Succeeded,
+    /// The pipeline failed with the given error.
+    Failed(String),
+    /// The run was stopped by `--timeout`.
+    TimedOut(String),
+    /// A HUMAN_REVIEW step was rejected.
+    Rejected,
+    /// The run could not start because of a configuration problem.
+    ConfigError(String),
+}
+
+impl RunOutcome {
+    /// Process exit code for this outcome.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RunOutcome::Succeeded => EXIT_SUCCESS,
+            RunOutcome::Failed(_) => EXIT_FAILURE,
+            RunOutcome::TimedOut(_) => EXIT_TIMEOUT,
+            RunOutcome::Rejected => EXIT_REJECTED,
+            RunOutcome::ConfigError(_) => EXIT_CONFIG_ERROR,
+        }
+    }
+
+    /// Error message, if the run did not succeed.
+    pub fn error(&self) -> Option<&str> {
+        match self {
+            RunOutcome::Succeeded => None,
+            RunOutcome::Failed(e) | RunOutcome::TimedOut(e) | RunOutcome::ConfigError(e) => Some(e),
+            RunOutcome::Rejected => Some("Rejected at human review"),
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            RunOutcome::Succeeded => "succeeded",
+            RunOutcome::Failed(_) => "failed",
+            RunOutcome::TimedOut(_) => "timed out",
+            RunOutcome::Rejected => "was rejected",
+            RunOutcome::ConfigError(_) => "could not start",
+        }
+    }
+}
+
+/// Collects the events of a headless run and renders them.
+pub struct Reporter {
+    format: OutputFormat,
+    pipeline_name: String,
+    /// Display name of every step in `master.process`, by index.
+    step_names: Vec<String>,
+    /// Latest record of each step that has started, by index.
+    steps: Vec<StepRecord>,
+    /// Log output captured while each step was running, by index.
+    step_logs: HashMap<usize, String>,
+    usage: TokenUsage,
+    warnings: usize,
+    started: Instant,
+}
+
+impl Reporter {
+    /// Create a reporter for a pipeline run.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - The output format
+    /// * `pipeline` - The pipeline being run, used to list steps that never started
+    pub fn new(format: OutputFormat, pipeline: &Pipeline) -> Self {
+        let step_names = pipeline
+            .master
+            .process
+            .iter()
+            .map(|step| step.agent_name().unwrap_or("HUMAN_REVIEW").to_string())
+            .collect();
+
+        Self {
+            format,
+            pipeline_name: pipeline.name.clone(),
+            step_names,
+            steps: Vec::new(),
+            step_logs: HashMap::new(),
+            usage: TokenUsage::default(),
+            warnings: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// Record an event and print its live rendering, if the format has one.
+    pub fn event(&mut self, event: &Event) {
+        let lines = self.record(event);
+        let mut stdout = std::io::stdout().lock();
+        for line in lines {
+            let _ = writeln!(stdout, "{}", line);
+        }
+    }
+
+    /// Record an event and return the lines to print for it.
+    fn record(&mut self, event: &Event) -> Vec<String> {
+        match event {
+            Event::StepStarted { step, .. }
+            | Event::StepCompleted { step, .. }
+            | Event::StepFailed { step, .. } => self.upsert_step(step),
+            Event::ProcessLogChunk { content, .. } => {
+                // Only output produced while a step runs belongs to it
+                if let Some(step) = self
+                    .steps
+                    .last()
+                    .filter(|s| s.status == StepStatus::Running)
+                {
+                    let logs = self.step_logs.entry(step.index).or_default();
+                    logs.push_str(content);
+                    if !content.ends_with('\n') {
+                        logs.push('\n');
+                    }
+                }
+            }
+            Event::UsageReported { usage, .. } => self.usage.accumulate(usage),
+            Event::AgentWarning { .. } | Event::BudgetWarning { .. } => self.warnings += 1,
+            _ => {}
+        }
+
+        match self.format {
+            OutputFormat::Jsonl => match serde_json::to_string(event) {
+                Ok(line) => vec![line],
+                Err(e) => {
+                    eprintln!("Failed to serialize event: {}", e);
+                    Vec::new()
+                }
+            },
+            OutputFormat::Pretty => render_pretty(event),
+            OutputFormat::Github => render_github(event),
+            OutputFormat::Junit => Vec::new(),
+        }
+    }
+
+    fn upsert_step(&mut self, step: &StepRecord) {
+        match self.steps.iter_mut().find(|s| s.index == step.index) {
+            Some(existing) => *existing = step.clone(),
+            None => self.steps.push(step.clone()),
+        }
+    }
+
+    /// Print the end-of-run report and summary.
+    ///
+    /// # Arguments
+    ///
+    /// * `outcome` - How the run ended
+    pub fn finish(&self, outcome: &RunOutcome) {
+        let summary = self.summary(outcome);
+
+        match self.format {
+            OutputFormat::Jsonl => eprintln!("{}", summary),
+            OutputFormat::Pretty => println!("\n{}", summary),
+            OutputFormat::Github => {
+                println!("{}", summary);
+                if let Some(path) = std::env::var_os("GITHUB_STEP_SUMMARY") {
+                    let markdown = self.github_step_summary(outcome);
+                    let written = std::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(path)
+                        .and_then(|mut file| file.write_all(markdown.as_bytes()));
+                    if let Err(e) = written {
+                        eprintln!("Failed to write GitHub step summary: {}", e);
+                    }
+                }
+            }
+            OutputFormat::Junit => {
+                print!("{}", self.junit(outcome));
+                eprintln!("{}", summary);
+            }
+        }
+    }
+
+    /// One-paragraph plain-text summary of the run.
+    fn summary(&self, outcome: &RunOutcome) -> String {
+        let completed = self.count(StepStatus::Completed);
+        let failed = self.count(StepStatus::Failed);
+
+        let mut summary = format!(
+            "Pipeline '{}' {} in {:.1}s (exit code {})\n  Steps: {}/{} completed",
+            self.pipeline_name,
+            outcome.label(),
+            self.started.elapsed().as_secs_f64(),
+            outcome.exit_code(),
+            completed,
+            self.step_names.len(),
+        );
+        if failed > 0 {
+            summary.push_str(&format!(", {} failed", failed));
+        }
+        if self.usage != TokenUsage::default() {
+            summary.push_str(&format!("\n  Usage: {}", format_usage(&self.usage)));
+        }
+        if self.warnings > 0 {
+            summary.push_str(&format!("\n  Warnings: {}", self.warnings));
+        }
+        if let Some(error) = outcome.error() {
+            summary.push_str(&format!("\n  Error: {}", error));
+        }
+
+        match outcome {
+            RunOutcome::Succeeded => summary.green().to_string(),
+            _ => summary.red().to_string(),
+        }
+    }
+
+    fn count(&self, status: StepStatus) -> usize {
+        self.steps.iter().filter(|s| s.status == status).count()
+    }
+
+    /// Markdown table appended to `$GITHUB_STEP_SUMMARY`.
+    fn github_step_summary(&self, outcome: &RunOutcome) -> String {
+        let mut md = format!(
+            "### Pipeline `{}` {}\n\n| Step | Agent | Status | Duration |\n| --- | --- | --- | --- |\n",
+            self.pipeline_name,
+            outcome.label()
+        );
+        for (index, name) in self.step_names.iter().enumerate() {
+            let (status, duration) = match self.steps.iter().find(|s| s.index == index) {
+                Some(step) => (
+                    format!("{:?}", step.status),
+                    step.duration_ms
+                        .map(|ms| format!("{:.1}s", ms as f64 / 1000.0))
+                        .unwrap_or_default(),
+                ),
+                None => ("Skipped".to_string(), String::new()),
+            };
+            md.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                index + 1,
+                name,
+                status,
+                duration
+            ));
+        }
+        if let Some(error) = outcome.error() {
+            md.push_str(&format!("\n**Error:** {}\n", error));
+        }
+        md.push('\n');
+        md
+    }
+
+    /// JUnit XML report with one test case per pipeline step.
+    ///
+    /// Steps that never started are reported as skipped. A step still
+    /// running when the run ended (timeout, rejected review) is reported as
+    /// a failure carrying the run's error.
+    fn junit(&self, outcome: &RunOutcome) -> String {
+        let mut cases = String::new();
+        let mut failures = 0;
+        let mut skipped = 0;
+
+        for (index, name) in self.step_names.iter().enumerate() {
+            let step = self.steps.iter().find(|s| s.index == index);
+            let time = step
+                .and_then(|s| s.duration_ms)
+                .map(|ms| ms as f64 / 1000.0)
+                .unwrap_or(0.0);
+
+            cases.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+                xml_escape(&self.pipeline_name),
+                xml_escape(&format!("step {}: {}", index + 1, name)),
+                time
+            ));
+
+            let failure = match step {
+                None => {
+                    skipped += 1;
+                    cases.push_str(">\n      <skipped/>\n    </testcase>\n");
+                    continue;
+                }
+                Some(step) => match step.status {
+                    StepStatus::Completed => None,
+                    StepStatus::Failed => Some(
+                        step.error
+                            .clone()
+                            .unwrap_or_else(|| "Step failed".to_string()),
+                    ),
+                    StepStatus::Running => {
+                        Some(outcome.error().unwrap_or("Step did not finish").to_string())
+                    }
+                },
+            };
+
+            cases.push_str(">\n");
+            if let Some(message) = failure {
+                failures += 1;
+                cases.push_str(&format!(
+                    "      <failure message=\"{}\"/>\n",
+                    xml_escape(&message)
+                ));
+            }
+            if let Some(logs) = self.step_logs.get(&index) {
+                cases.push_str(&format!(
+                    "      <system-out>{}</system-out>\n",
+                    xml_escape(logs)
+                ));
+            }
+            cases.push_str("    </testcase>\n");
+        }
+
+        let tests = self.step_names.len();
+        let time = self.started.elapsed().as_secs_f64();
+        let name = xml_escape(&self.pipeline_name);
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <testsuites name=\"pipeline-kit\" tests=\"{tests}\" failures=\"{failures}\" skipped=\"{skipped}\" time=\"{time:.3}\">\n\
+             \x20 <testsuite name=\"{name}\" tests=\"{tests}\" failures=\"{failures}\" skipped=\"{skipped}\" time=\"{time:.3}\">\n\
+             {cases}\
+             \x20 </testsuite>\n\
+             </testsuites>\n"
+        )
+    }
+}
+
+/// Label of a step for progress output, e.g. "Step 2: reviewer (Claude)".
+fn step_label(step: &StepRecord) -> String {
+    match step.kind {
+        StepKind::HumanReview => format!("Step {}: HUMAN_REVIEW", step.index + 1),
+        StepKind::Agent => {
+            let mut agent = step.agent_name.as_deref().unwrap_or("agent").to_string();
+            if let Some(served_by) = &step.served_by {
+                agent = format!("{} → {}", agent, served_by);
+            }
+            match &step.adapter_type {
+                Some(adapter) => format!("Step {}: {} ({})", step.index + 1, agent, adapter),
+                None => format!("Step {}: {}", step.index + 1, agent),
+            }
+        }
+    }
+}
+
+fn format_duration(step: &StepRecord) -> String {
+    step.duration_ms
+        .map(|ms| format!(" in {:.1}s", ms as f64 / 1000.0))
+        .unwrap_or_default()
+}
+
+fn format_usage(usage: &TokenUsage) -> String {
+    match usage.cost_usd {
+        Some(cost) => format!("{} tokens, ${:.2}", usage.total_tokens(), cost),
+        None => format!("{} tokens", usage.total_tokens()),
+    }
+}
+
+/// Human-readable rendering of an event.
+fn render_pretty(event: &Event) -> Vec<String> {
+    match event {
+        Event::ProcessStarted {
+            process_id,
+            pipeline_name,
+        } => vec![
+            format!("▶ Pipeline '{}' started ({})", pipeline_name, process_id)
+                .bold()
+                .to_string(),
+        ],
+        Event::StepStarted { step, .. } => match step.kind {
+            StepKind::HumanReview => vec![format!("⏸ {} waiting for review", step_label(step))
+                .yellow()
+                .to_string()],
+            StepKind::Agent => vec![format!("▶ {}", step_label(step)).cyan().to_string()],
+        },
+        Event::StepCompleted { step, .. } => {
+            vec![format!("✓ {}{}", step_label(step), format_duration(step))
+                .green()
+                .to_string()]
+        }
+        Event::StepFailed { step, .. } => vec![format!(
+            "✗ {} failed{}: {}",
+            step_label(step),
+            format_duration(step),
+            step.error.as_deref().unwrap_or("unknown error")
+        )
+        .red()
+        .to_string()],
+        Event::ProcessLogChunk { content, .. } => content
+            .lines()
+            .map(|line| format!("  │ {}", line).dimmed().to_string())
+            .collect(),
+        Event::ToolCallStarted { name, .. } => vec![format!("  → {}", name)],
+        Event::ToolCallCompleted {
+            output,
+            is_error: true,
+            ..
+        } => vec![format!(
+            "  ✗ tool error: {}",
+            output.lines().next().unwrap_or_default()
+        )
+        .red()
+        .to_string()],
+        Event::FileChanged { path, kind, .. } => vec![format!("  ✎ {:?} {}", kind, path)],
+        Event::AgentWarning {
+            agent_name,
+            message,
+            ..
+        } => vec![format!("⚠ {}: {}", agent_name, message)
+            .yellow()
+            .to_string()],
+        Event::BudgetWarning { message, .. } => vec![format!("⚠ {}", message).yellow().to_string()],
+        Event::BudgetExceeded { message, .. } => vec![format!("✗ {}", message).red().to_string()],
+        Event::PermissionResolved { allowed: true, .. } => {
+            vec!["  ✓ Permission granted".green().to_string()]
+        }
+        Event::PermissionResolved { allowed: false, .. } => {
+            vec!["  ✗ Permission denied".red().to_string()]
+        }
+        Event::ProcessResumed { .. } => vec!["▶ Resumed".cyan().to_string()],
+        Event::ProcessError { error, .. } => vec![format!("✗ {}", error).red().to_string()],
+        Event::ProcessKilled { .. } => vec!["✗ Killed".red().to_string()],
+        _ => Vec::new(),
+    }
+}
+
+/// GitHub Actions rendering of an event.
+///
+/// Each step becomes a collapsible log group; failures and warnings become
+/// annotations.
+fn render_github(event: &Event) -> Vec<String> {
+    match event {
+        Event::StepStarted { step, .. } => {
+            vec![format!("::group::{}", gh_escape_data(&step_label(step)))]
+        }
+        Event::StepCompleted { step, .. } => vec![
+            format!("✓ {}{}", step_label(step), format_duration(step)),
+            "::endgroup::".to_string(),
+        ],
+        Event::StepFailed { step, .. } => vec![
+            "::endgroup::".to_string(),
+            format!(
+                "::error title={}::{}",
+                gh_escape_property(&format!("{} failed", step_label(step))),
+                gh_escape_data(step.error.as_deref().unwrap_or("unknown error"))
+            ),
+        ],
+        Event::ProcessLogChunk { content, .. } => content.lines().map(str::to_string).collect(),
+        Event::ToolCallStarted { name, .. } => vec![format!("→ {}", name)],
+        Event::AgentWarning {
+            agent_name,
+            message,
+            ..
+        } => vec![format!(
+            "::warning title={}::{}",
+            gh_escape_property(agent_name),
+            gh_escape_data(message)
+        )],
+        Event::AgentFallback {
+            agent_name,
+            fallback_agent,
+            reason,
+            ..
+        } => vec![format!(
+            "::warning title={}::{}",
+            gh_escape_property(&format!("{} fell back to {}", agent_name, fallback_agent)),
+            gh_escape_data(reason)
+        )],
+        Event::BudgetWarning { message, .. } => {
+            vec![format!(
+                "::warning title=Budget::{}",
+                gh_escape_data(message)
+            )]
+        }
+        Event::BudgetExceeded { message, .. } => {
+            vec![format!("::error title=Budget::{}", gh_escape_data(message))]
+        }
+        Event::ProcessError { error, .. } => {
+            vec![format!("::error::{}", gh_escape_data(error))]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Escape a workflow command message.
+fn gh_escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a workflow command property value.
+fn gh_escape_property(s: &str) -> String {
+    gh_escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters other than tab/newline are not valid XML 1.0
+            c if c.is_control() && c != '\n' && c != '\t' && c != '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pk_protocol::pipeline_models::HumanReviewMarker;
+    use pk_protocol::pipeline_models::MasterAgentConfig;
+    use pk_protocol::pipeline_models::ProcessStep;
+    use uuid::Uuid;
+
+    fn pipeline() -> Pipeline {
+        Pipeline {
+            name: "ci".to_string(),
+            required_reference_file: HashMap::new(),
+            output_file: HashMap::new(),
+            master: MasterAgentConfig {
+                model: "test-model".to_string(),
+                system_prompt: String::new(),
+                process: vec![
+                    ProcessStep::Agent("developer".to_string()),
+                    ProcessStep::HumanReview(HumanReviewMarker),
+                    ProcessStep::Agent("reviewer".to_string()),
+                ],
+            },
+            sub_agents: vec!["developer".to_string(), "reviewer".to_string()],
+            budget: None,
+        }
+    }
+
+    fn step(index: usize, kind: StepKind, status: StepStatus, error: Option<&str>) -> StepRecord {
+        StepRecord {
+            index,
+            kind,
+            agent_name: Some("developer".to_string()),
+            adapter_type: Some("Claude".to_string()),
+            served_by: None,
+            status,
+            started_at: chrono::Utc::now(),
+            completed_at: None,
+            duration_ms: Some(1500),
+            error: error.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let codes = [
+            RunOutcome::Succeeded.exit_code(),
+            RunOutcome::Failed(String::new()).exit_code(),
+            RunOutcome::ConfigError(String::new()).exit_code(),
+            RunOutcome::TimedOut(String::new()).exit_code(),
+            RunOutcome::Rejected.exit_code(),
+        ];
+        assert_eq!(codes, [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_junit_one_case_per_step() {
+        let process_id = Uuid::new_v4();
+        let mut reporter = Reporter::new(OutputFormat::Junit, &pipeline());
+
+        let running = step(0, StepKind::Agent, StepStatus::Running, None);
+        assert!(reporter
+            .record(&Event::StepStarted {
+                process_id,
+                step: running,
+            })
+            .is_empty());
+        reporter.record(&Event::ProcessLogChunk {
+            process_id,
+            content: "built <crate> & ran tests\n".to_string(),
+        });
+        reporter.record(&Event::StepCompleted {
+            process_id,
+            step: step(0, StepKind::Agent, StepStatus::Completed, None),
+        });
+        reporter.record(&Event::StepStarted {
+            process_id,
+            step: step(1, StepKind::HumanReview, StepStatus::Running, None),
+        });
+
+        let xml = reporter.junit(&RunOutcome::Rejected);
+
+        assert!(xml.contains("tests=\"3\" failures=\"1\" skipped=\"1\""));
+        assert!(xml.contains("name=\"step 1: developer\" time=\"1.500\">"));
+        assert!(xml.contains("<system-out>built &lt;crate&gt; &amp; ran tests\n</system-out>"));
+        assert!(xml.contains("name=\"step 2: HUMAN_REVIEW\""));
+        assert!(xml.contains("<failure message=\"Rejected at human review\"/>"));
+        assert!(xml.contains("name=\"step 3: reviewer\" time=\"0.000\">\n      <skipped/>"));
+    }
+
+    #[test]
+    fn test_github_groups_and_annotations() {
+        let process_id = Uuid::new_v4();
+        let mut reporter = Reporter::new(OutputFormat::Github, &pipeline());
+
+        let started = reporter.record(&Event::StepStarted {
+            process_id,
+            step: step(0, StepKind::Agent, StepStatus::Running, None),
+        });
+        assert_eq!(started, vec!["::group::Step 1: developer (Claude)"]);
+
+        let failed = reporter.record(&Event::StepFailed {
+            process_id,
+            step: step(
+                0,
+                StepKind::Agent,
+                StepStatus::Failed,
+                Some("boom\n100% broken"),
+            ),
+        });
+        assert_eq!(failed[0], "::endgroup::");
+        assert_eq!(
+            failed[1],
+            "::error title=Step 1%3A developer (Claude) failed::boom%0A100%25 broken"
+        );
+
+        let fallback = reporter.record(&Event::AgentFallback {
+            process_id,
+            agent_name: "developer".to_string(),
+            fallback_agent: "backup".to_string(),
+            reason: "developer: API call failed: rate limit".to_string(),
+        });
+        assert_eq!(
+            fallback,
+            vec![
+                "::warning title=developer fell back to backup::developer: API call failed: rate limit"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pretty_renders_progress() {
+        let process_id = Uuid::new_v4();
+        let mut reporter = Reporter::new(OutputFormat::Pretty, &pipeline());
+
+        let lines = reporter.record(&Event::StepCompleted {
+            process_id,
+            step: step(0, StepKind::Agent, StepStatus::Completed, None),
+        });
+        assert!(lines[0].contains("✓ Step 1: developer (Claude) in 1.5s"));
+
+        let lines = reporter.record(&Event::ProcessLogChunk {
+            process_id,
+            content: "one\ntwo".to_string(),
+        });
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].contains("│ two"));
+    }
+
+    #[test]
+    fn test_summary_counts_steps_and_usage() {
+        let process_id = Uuid::new_v4();
+        let mut reporter = Reporter::new(OutputFormat::Jsonl, &pipeline());
+
+        reporter.record(&Event::StepCompleted {
+            process_id,
+            step: step(0, StepKind::Agent, StepStatus::Completed, None),
+        });
+        reporter.record(&Event::UsageReported {
+            process_id,
+            agent_name: "developer".to_string(),
+            usage: TokenUsage {
+                input_tokens: 100,
+                output_tokens: 50,
+                cost_usd: Some(0.01),
+                duration_ms: None,
+            },
+        });
+
+        let summary = reporter.summary(&RunOutcome::TimedOut("Timed out after 5s".to_string()));
+        assert!(summary.contains("Pipeline 'ci' timed out"));
+        assert!(summary.contains("exit code 3"));
+        assert!(summary.contains("Steps: 1/3 completed"));
+        assert!(summary.contains("Usage: 150 tokens, $0.01"));
+        assert!(summary.contains("Error: Timed out after 5s"));
+    }
+}
@@ -0,0 +1,427 @@
+//! Headless handling of HUMAN_REVIEW pauses for `run --no-tui`.
+//!
+//! Without the TUI nothing would ever resume a paused process. When the
+//! engine pauses, `await_review` decides the outcome from, in order of
+//! precedence:
+//!
+//! 1. `--auto-approve`
+//! 2. An approval file written by `pipeline-kit approve <id>`
+//! 3. An answer typed on stdin, when stdin is a terminal
+//! 4. `--review-timeout`, which falls back to `--review-default`
+//!
+//! Sources 2-4 race; the first one to produce an answer wins.
+//!
+//! Agent permission requests are answered by `await_permission`, which only
+//! consults `--auto-approve` and the stdin prompt and denies otherwise.
+//!
+//! Both prompts take their answers from `StdinAnswers`, a single reader of
+//! stdin, so a prompt that loses its race leaves no read pending.
+
+use clap::ValueEnum;
+use pk_protocol::PermissionRequest;
+use std::io::BufRead;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// How often the approvals directory is polled.
+const APPROVAL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Decision taken for a paused process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReviewOutcome {
+    /// Resume the process.
+    Approve,
+    /// Fail the process.
+    Reject,
+}
+
+/// Review settings collected from the `run` command line.
+#[derive(Debug, Clone)]
+pub struct ReviewPolicy {
+    /// Approve every review immediately.
+    pub auto_approve: bool,
+
+    /// Answers typed on stdin; `None` unless stdin is a terminal.
+    pub stdin: Option<StdinAnswers>,
+
+    /// Give up waiting after this long.
+    pub timeout: Option<Duration>,
+
+    /// Outcome applied when the timeout elapses.
+    pub default_outcome: ReviewOutcome,
+
+    /// Directory polled for approval files.
+    pub approvals_dir: PathBuf,
+}
+
+impl ReviewPolicy {
+    /// Build a policy for a project root.
+    ///
+    /// Approval files live in `<root>/.pipeline-kit/approvals/`. The stdin
+    /// prompt is enabled only when stdin is attached to a terminal.
+    pub fn new(
+        root: &Path,
+        auto_approve: bool,
+        timeout: Option<Duration>,
+        default_outcome: ReviewOutcome,
+    ) -> Self {
+        Self {
+            auto_approve,
+            stdin: std::io::stdin().is_terminal().then(StdinAnswers::spawn),
+            timeout,
+            default_outcome,
+            approvals_dir: approvals_dir(root),
+        }
+    }
+}
+
+/// Lines typed on stdin, read by one long-lived thread.
+///
+/// A blocking read of stdin cannot be cancelled, so prompts never read it
+/// themselves; they receive the next line from this channel instead.
+#[derive(Debug, Clone)]
+pub struct StdinAnswers {
+    lines: Arc<Mutex<mpsc::UnboundedReceiver<String>>>,
+}
+
+impl StdinAnswers {
+    /// Start the thread reading stdin.
+    ///
+    /// A detached thread rather than a blocking task, so a read still
+    /// pending at exit does not hold up the runtime's shutdown.
+    pub fn spawn() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Self::from_receiver(rx)
+    }
+
+    fn from_receiver(rx: mpsc::UnboundedReceiver<String>) -> Self {
+        Self {
+            lines: Arc::new(Mutex::new(rx)),
+        }
+    }
+
+    /// Wait for the answer to a prompt that has just been shown.
+    ///
+    /// Lines typed before the prompt appeared are discarded. Returns `None`
+    /// once stdin is closed.
+    async fn answer(&self) -> Option<String> {
+        let mut lines = self.lines.lock().await;
+        while lines.try_recv().is_ok() {}
+        lines.recv().await
+    }
+}
+
+/// Whether a typed answer means yes.
+fn is_yes(answer: &str) -> bool {
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+/// Directory holding approval files for a project root.
+pub fn approvals_dir(root: &Path) -> PathBuf {
+    root.join(".pipeline-kit").join("approvals")
+}
+
+/// Record a decision for a paused process.
+///
+/// # Arguments
+///
+/// * `dir` - The approvals directory
+/// * `process_id` - The process being reviewed
+/// * `outcome` - The decision to record
+///
+/// # Errors
+///
+/// Returns an error if the approval file cannot be written.
+pub fn write_approval(dir: &Path, process_id: Uuid, outcome: ReviewOutcome) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let content = match outcome {
+        ReviewOutcome::Approve => "approve",
+        ReviewOutcome::Reject => "reject",
+    };
+
+    // Write then rename so the polling side never sees a partial file
+    let tmp = dir.join(format!("{}.tmp", process_id));
+    std::fs::write(&tmp, content)?;
+    std::fs::rename(tmp, dir.join(process_id.to_string()))
+}
+
+/// Read and consume a recorded decision, if there is one.
+///
+/// Only `approve` and `reject` are decisions. A file holding anything else
+/// is removed with a warning and the review keeps waiting, so a typo or an
+/// empty file never passes the gate.
+fn take_approval(dir: &Path, process_id: Uuid) -> Option<ReviewOutcome> {
+    let path = dir.join(process_id.to_string());
+    let content = std::fs::read_to_string(&path).ok()?;
+    let _ = std::fs::remove_file(&path);
+    match content.trim() {
+        "approve" => Some(ReviewOutcome::Approve),
+        "reject" => Some(ReviewOutcome::Reject),
+        other => {
+            eprintln!(
+                "Ignoring approval file {}: expected 'approve' or 'reject', found '{}'",
+                path.display(),
+                other
+            );
+            None
+        }
+    }
+}
+
+/// Wait for the outcome of a review.
+///
+/// # Arguments
+///
+/// * `policy` - The review settings
+/// * `process_id` - The paused process
+///
+/// # Returns
+///
+/// The decision from the first source that answers.
+pub async fn await_review(policy: &ReviewPolicy, process_id: Uuid) -> ReviewOutcome {
+    if policy.auto_approve {
+        return ReviewOutcome::Approve;
+    }
+
+    eprintln!(
+        "Process {} is waiting for human review. Approve with `pipeline-kit approve {}`{}.",
+        process_id,
+        process_id,
+        if policy.stdin.is_some() {
+            " or answer below"
+        } else {
+            ""
+        }
+    );
+
+    let approval_file = async {
+        loop {
+            if let Some(outcome) = take_approval(&policy.approvals_dir, process_id) {
+                return outcome;
+            }
+            tokio::time::sleep(APPROVAL_POLL_INTERVAL).await;
+        }
+    };
+
+    let prompt = async {
+        let Some(stdin) = &policy.stdin else {
+            return std::future::pending().await;
+        };
+        eprint!("Approve and continue? [y/N] ");
+        match stdin.answer().await {
+            Some(line) if is_yes(&line) => ReviewOutcome::Approve,
+            Some(_) => ReviewOutcome::Reject,
+            // stdin closed: leave it to the other sources
+            None => std::future::pending().await,
+        }
+    };
+
+    let timeout = async {
+        match policy.timeout {
+            Some(timeout) => {
+                tokio::time::sleep(timeout).await;
+                eprintln!(
+                    "Review timed out after {}s; applying default: {:?}",
+                    timeout.as_secs(),
+                    policy.default_outcome
+                );
+                policy.default_outcome
+            }
+            None => std::future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        outcome = approval_file => outcome,
+        outcome = prompt => outcome,
+        outcome = timeout => outcome,
+    }
+}
+
+/// Decide whether an agent may run the tool it asked about.
+///
+/// # Arguments
+///
+/// * `policy` - The review settings
+/// * `request` - The pending permission request
+///
+/// # Returns
+///
+/// `true` to allow the tool call. Without `--auto-approve` or a terminal to
+/// ask on, the request is denied.
+pub async fn await_permission(policy: &ReviewPolicy, request: &PermissionRequest) -> bool {
+    if policy.auto_approve {
+        return true;
+    }
+    let Some(stdin) = &policy.stdin else {
+        eprintln!(
+            "Denied {} permission to run {} ({}); pass --auto-approve to allow tool requests.",
+            request.agent_name, request.title, request.tool
+        );
+        return false;
+    };
+
+    eprint!(
+        "{} asks to run {} ({}). Allow? [y/N] ",
+        request.agent_name, request.title, request.tool
+    );
+    stdin.answer().await.is_some_and(|line| is_yes(&line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn policy(dir: &Path) -> ReviewPolicy {
+        ReviewPolicy {
+            auto_approve: false,
+            stdin: None,
+            timeout: None,
+            default_outcome: ReviewOutcome::Reject,
+            approvals_dir: dir.to_path_buf(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auto_approve() {
+        let dir = tempdir().unwrap();
+        let mut policy = policy(dir.path());
+        policy.auto_approve = true;
+
+        assert_eq!(
+            await_review(&policy, Uuid::new_v4()).await,
+            ReviewOutcome::Approve
+        );
+    }
+
+    #[tokio::test]
+    async fn test_timeout_applies_default_outcome() {
+        let dir = tempdir().unwrap();
+        let mut policy = policy(dir.path());
+        policy.timeout = Some(Duration::from_millis(10));
+
+        assert_eq!(
+            await_review(&policy, Uuid::new_v4()).await,
+            ReviewOutcome::Reject
+        );
+    }
+
+    #[tokio::test]
+    async fn test_approval_file_resolves_review() {
+        let dir = tempdir().unwrap();
+        let policy = policy(dir.path());
+        let process_id = Uuid::new_v4();
+
+        let approvals = dir.path().to_path_buf();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            write_approval(&approvals, process_id, ReviewOutcome::Reject).unwrap();
+        });
+
+        let outcome =
+            tokio::time::timeout(Duration::from_secs(5), await_review(&policy, process_id))
+                .await
+                .unwrap();
+        assert_eq!(outcome, ReviewOutcome::Reject);
+
+        // The approval is consumed so a later review waits again
+        assert!(!dir.path().join(process_id.to_string()).exists());
+    }
+
+    #[tokio::test]
+    async fn test_stdin_answer_goes_to_the_current_prompt() {
+        let dir = tempdir().unwrap();
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut policy = policy(dir.path());
+        policy.stdin = Some(StdinAnswers::from_receiver(rx));
+        policy.timeout = Some(Duration::from_millis(10));
+
+        // A prompt that loses to the timeout leaves no read behind
+        assert_eq!(
+            await_review(&policy, Uuid::new_v4()).await,
+            ReviewOutcome::Reject
+        );
+
+        // Lines typed before a prompt appears are not taken as its answer
+        tx.send("n".to_string()).unwrap();
+        policy.timeout = None;
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            tx.send("y".to_string()).unwrap();
+        });
+        let outcome = tokio::time::timeout(
+            Duration::from_secs(5),
+            await_review(&policy, Uuid::new_v4()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(outcome, ReviewOutcome::Approve);
+    }
+
+    #[tokio::test]
+    async fn test_approval_file_wins_over_timeout() {
+        let dir = tempdir().unwrap();
+        let mut policy = policy(dir.path());
+        policy.timeout = Some(Duration::from_secs(60));
+        let process_id = Uuid::new_v4();
+
+        write_approval(dir.path(), process_id, ReviewOutcome::Approve).unwrap();
+
+        assert_eq!(
+            await_review(&policy, process_id).await,
+            ReviewOutcome::Approve
+        );
+    }
+
+    #[test]
+    fn test_take_approval_requires_exact_decision() {
+        let dir = tempdir().unwrap();
+        let process_id = Uuid::new_v4();
+        let path = dir.path().join(process_id.to_string());
+
+        for content in ["", "rejct", "appr"] {
+            std::fs::write(&path, content).unwrap();
+            assert_eq!(take_approval(dir.path(), process_id), None);
+            assert!(!path.exists());
+        }
+
+        std::fs::write(&path, "approve\n").unwrap();
+        assert_eq!(
+            take_approval(dir.path(), process_id),
+            Some(ReviewOutcome::Approve)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_permission_follows_auto_approve() {
+        let dir = tempdir().unwrap();
+        let mut policy = policy(dir.path());
+        let request = PermissionRequest {
+            request_id: Uuid::new_v4(),
+            agent_name: "developer".to_string(),
+            tool: "execute".to_string(),
+            title: "cargo test".to_string(),
+            arguments: Default::default(),
+        };
+
+        assert!(!await_permission(&policy, &request).await);
+
+        policy.auto_approve = true;
+        assert!(await_permission(&policy, &request).await);
+    }
+}
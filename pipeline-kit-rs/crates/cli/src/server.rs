@@ -0,0 +1,555 @@
+//! Local HTTP API for driving pipeline-kit from other tools.
+//!
+//! The server exposes the same `Op`/`Event` protocol the TUI uses:
+//!
+//! | Method | Path                              | Description                          |
+//! |--------|-----------------------------------|--------------------------------------|
+//! | GET    | `/api/health`                     | Liveness check (no token required)   |
+//! | GET    | `/api/pipelines`                  | List pipeline definitions            |
+//! | POST   | `/api/pipelines/{name}/start`     | Start a pipeline                     |
+//! | GET    | `/api/processes`                  | List processes                       |
+//! | GET    | `/api/processes/{id}`             | Get a single process                 |
+//! | POST   | `/api/processes/{id}/pause`       | Pause a process                      |
+//! | POST   | `/api/processes/{id}/resume`      | Resume a process                     |
+//! | POST   | `/api/processes/{id}/kill`        | Kill a process                       |
+//! | POST   | `/api/ops`                        | Submit any `Op` as JSON              |
+//! | GET    | `/api/events`                     | Server-Sent Events stream of `Event` |
+//!
+//! The listener is always bound to `127.0.0.1`, and every route except
+//! `/api/health` requires the access token, either as an
+//! `Authorization: Bearer <token>` header or as a `token` query parameter
+//! (for `EventSource` clients that cannot set headers).
+
+use axum::body::Bytes;
+use axum::extract::Path;
+use axum::extract::Query;
+use axum::extract::Request;
+use axum::extract::State;
+use axum::http::header::AUTHORIZATION;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::sse::KeepAlive;
+use axum::response::sse::Sse;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum::routing::get;
+use axum::routing::post;
+use axum::Json;
+use axum::Router;
+use pk_core::agents::manager::AgentManager;
+use pk_core::config::loader::load_config;
+use pk_core::state::dispatcher::OpDispatcher;
+use pk_core::state::manager::StateManager;
+use pk_protocol::ipc::Event;
+use pk_protocol::ipc::Op;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::Ipv4Addr;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::StreamExt;
+use uuid::Uuid;
+
+/// Environment variable used to provide a fixed access token.
+pub const TOKEN_ENV_VAR: &str = "PIPELINE_KIT_API_TOKEN";
+
+/// Capacity of the event broadcast buffer shared by all SSE subscribers.
+const EVENT_BUFFER: usize = 256;
+
+/// Shared state for all request handlers.
+#[derive(Clone)]
+pub struct ApiState {
+    /// Dispatcher that applies `Op`s to the state manager.
+    dispatcher: Arc<OpDispatcher>,
+
+    /// Broadcast of every core `Event`, one receiver per SSE client.
+    events: broadcast::Sender<Event>,
+
+    /// Access token every protected request must present.
+    token: Arc<str>,
+}
+
+impl ApiState {
+    /// Create the handler state.
+    ///
+    /// # Arguments
+    ///
+    /// * `dispatcher` - The dispatcher backing the API
+    /// * `events` - Broadcast sender the core events are forwarded into
+    /// * `token` - The access token clients must present
+    pub fn new(
+        dispatcher: Arc<OpDispatcher>,
+        events: broadcast::Sender<Event>,
+        token: &str,
+    ) -> Self {
+        Self {
+            dispatcher,
+            events,
+            token: Arc::from(token),
+        }
+    }
+}
+
+/// Build the API router.
+pub fn router(state: ApiState) -> Router {
+    let protected = Router::new()
+        .route("/api/pipelines", get(list_pipelines))
+        .route("/api/pipelines/{name}/start", post(start_pipeline))
+        .route("/api/processes", get(list_processes))
+        .route("/api/processes/{id}", get(get_process))
+        .route("/api/processes/{id}/pause", post(pause_process))
+        .route("/api/processes/{id}/resume", post(resume_process))
+        .route("/api/processes/{id}/kill", post(kill_process))
+        .route("/api/ops", post(submit_op))
+        .route("/api/events", get(stream_events))
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            require_token,
+        ));
+
+    Router::new()
+        .route("/api/health", get(health))
+        .merge(protected)
+        .with_state(state)
+}
+
+/// Run the API server until the process is terminated.
+///
+/// Loads the configuration from the current directory, wires a
+/// `StateManager` to a broadcast channel, and serves the router on
+/// `127.0.0.1:<port>`. Pass port `0` to let the OS pick a free port.
+///
+/// # Errors
+///
+/// Returns an error if the configuration cannot be loaded or the port
+/// cannot be bound.
+pub async fn serve(port: u16) -> color_eyre::Result<()> {
+    // 1. Load configuration and build the core
+    let root = std::env::current_dir()?;
+    let config = load_config(&root)
+        .await
+        .map_err(|e| color_eyre::eyre::eyre!("Failed to load config: {e}"))?;
+
+    let (core_event_tx, mut core_event_rx) = mpsc::channel::<Event>(100);
+    let (events, _) = broadcast::channel::<Event>(EVENT_BUFFER);
+
+    let agent_manager =
+        AgentManager::new(config.agents).with_default_fallback(config.global.fallback);
+    let state_manager = Arc::new(StateManager::new(agent_manager, core_event_tx));
+    let dispatcher = Arc::new(OpDispatcher::new(state_manager, config.pipelines));
+
+    // 2. Fan core events out to every subscriber
+    let forward = events.clone();
+    tokio::spawn(async move {
+        while let Some(event) = core_event_rx.recv().await {
+            // No subscribers is fine; the event is simply dropped
+            let _ = forward.send(event);
+        }
+    });
+
+    // 3. Resolve the access token
+    let token = std::env::var(TOKEN_ENV_VAR)
+        .ok()
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| Uuid::new_v4().simple().to_string());
+
+    // 4. Bind to localhost only and serve
+    let listener =
+        tokio::net::TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, port))).await?;
+    let addr = listener.local_addr()?;
+
+    eprintln!("pipeline-kit API listening on http://{addr}");
+    if std::env::var(TOKEN_ENV_VAR).is_err() {
+        eprintln!("Access token: {token}");
+    }
+
+    let state = ApiState::new(dispatcher, events, &token);
+    axum::serve(listener, router(state)).await?;
+
+    Ok(())
+}
+
+/// Reject requests that do not carry the access token.
+async fn require_token(
+    State(state): State<ApiState>,
+    Query(query): Query<HashMap<String, String>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let bearer = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let presented = bearer.or_else(|| query.get("token").map(String::as_str));
+
+    match presented {
+        // Compare in constant time so response timing does not leak the token
+        Some(token) if bool::from(token.as_bytes().ct_eq(state.token.as_bytes())) => {
+            next.run(request).await
+        }
+        _ => error_response(StatusCode::UNAUTHORIZED, "Missing or invalid access token"),
+    }
+}
+
+/// Build a JSON error body.
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+async fn health() -> Json<serde_json::Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+async fn list_pipelines(State(state): State<ApiState>) -> Response {
+    Json(state.dispatcher.pipelines()).into_response()
+}
+
+/// Body accepted by `POST /api/pipelines/{name}/start`.
+#[derive(Debug, Default, Deserialize)]
+struct StartRequest {
+    reference_file: Option<std::path::PathBuf>,
+}
+
+async fn start_pipeline(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    body: Bytes,
+) -> Response {
+    // The body is optional, so an empty payload means "no options"
+    let body = if body.is_empty() {
+        StartRequest::default()
+    } else {
+        match serde_json::from_slice::<StartRequest>(&body) {
+            Ok(body) => body,
+            Err(e) => return error_response(StatusCode::BAD_REQUEST, &e.to_string()),
+        }
+    };
+    let op = Op::StartPipeline {
+        name,
+        reference_file: body.reference_file,
+    };
+
+    match state.dispatcher.dispatch(op).await {
+        Ok(process_id) => (
+            StatusCode::CREATED,
+            Json(json!({ "processId": process_id })),
+        )
+            .into_response(),
+        Err(e) => error_response(StatusCode::NOT_FOUND, &e.to_string()),
+    }
+}
+
+async fn list_processes(State(state): State<ApiState>) -> Response {
+    let mut processes = state.dispatcher.state_manager().get_all_processes().await;
+    processes.sort_by_key(|p| p.started_at);
+    Json(processes).into_response()
+}
+
+async fn get_process(State(state): State<ApiState>, Path(id): Path<Uuid>) -> Response {
+    match state.dispatcher.state_manager().get_process(id).await {
+        Some(process) => Json(process).into_response(),
+        None => error_response(StatusCode::NOT_FOUND, &format!("Process {id} not found")),
+    }
+}
+
+async fn pause_process(State(state): State<ApiState>, Path(id): Path<Uuid>) -> Response {
+    process_op(&state, Op::PauseProcess { process_id: id }).await
+}
+
+async fn resume_process(State(state): State<ApiState>, Path(id): Path<Uuid>) -> Response {
+    process_op(&state, Op::ResumeProcess { process_id: id }).await
+}
+
+async fn kill_process(State(state): State<ApiState>, Path(id): Path<Uuid>) -> Response {
+    process_op(&state, Op::KillProcess { process_id: id }).await
+}
+
+/// Apply a process-scoped `Op`, mapping failures to `404 Not Found`.
+async fn process_op(state: &ApiState, op: Op) -> Response {
+    match state.dispatcher.dispatch(op).await {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => error_response(StatusCode::NOT_FOUND, &e.to_string()),
+    }
+}
+
+async fn submit_op(State(state): State<ApiState>, Json(op): Json<Op>) -> Response {
+    match state.dispatcher.dispatch(op).await {
+        Ok(process_id) => (
+            StatusCode::ACCEPTED,
+            Json(json!({ "processId": process_id })),
+        )
+            .into_response(),
+        Err(e) => error_response(StatusCode::UNPROCESSABLE_ENTITY, &e.to_string()),
+    }
+}
+
+async fn stream_events(
+    State(state): State<ApiState>,
+) -> Sse<impl tokio_stream::Stream<Item = Result<axum::response::sse::Event, Infallible>>> {
+    // Lagged receivers skip the missed events rather than closing the stream
+    let stream = BroadcastStream::new(state.events.subscribe()).filter_map(|event| {
+        let event = event.ok()?;
+        axum::response::sse::Event::default()
+            .json_data(&event)
+            .ok()
+            .map(Ok)
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request as HttpRequest;
+    use pk_protocol::agent_models::Agent as AgentConfig;
+    use pk_protocol::agent_models::AgentProvider;
+    use pk_protocol::pipeline_models::MasterAgentConfig;
+    use pk_protocol::pipeline_models::Pipeline;
+    use pk_protocol::pipeline_models::ProcessStep;
+    use pk_protocol::process_models::Process;
+    use tower::ServiceExt;
+
+    const TOKEN: &str = "test-token";
+
+    fn create_state() -> (ApiState, mpsc::Receiver<Event>) {
+        let agents = vec![AgentConfig {
+            name: "agent1".to_string(),
+            description: "Test agent".to_string(),
+            model: "test-model".to_string(),
+            provider: Some(AgentProvider::Mock),
+            color: "blue".to_string(),
+            system_prompt: "Test prompt".to_string(),
+            ..Default::default()
+        }];
+        let pipeline = Pipeline {
+            name: "test-pipeline".to_string(),
+            required_reference_file: HashMap::new(),
+            output_file: HashMap::new(),
+            master: MasterAgentConfig {
+                model: "test-model".to_string(),
+                system_prompt: "Test orchestration".to_string(),
+                process: vec![ProcessStep::Agent("agent1".to_string())],
+            },
+            sub_agents: vec!["agent1".to_string()],
+            budget: None,
+        };
+
+        let (tx, rx) = mpsc::channel(100);
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        let state_manager = Arc::new(StateManager::new(AgentManager::new(agents), tx));
+        let dispatcher = Arc::new(OpDispatcher::new(state_manager, vec![pipeline]));
+
+        (ApiState::new(dispatcher, events, TOKEN), rx)
+    }
+
+    fn authorized(method: &str, uri: &str, body: Body) -> HttpRequest<Body> {
+        HttpRequest::builder()
+            .method(method)
+            .uri(uri)
+            .header(AUTHORIZATION, format!("Bearer {TOKEN}"))
+            .header("content-type", "application/json")
+            .body(body)
+            .unwrap()
+    }
+
+    async fn body_bytes(response: Response) -> Bytes {
+        axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_health_does_not_require_token() {
+        let (state, _rx) = create_state();
+        let response = router(state)
+            .oneshot(HttpRequest::get("/api/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_missing_token_is_rejected() {
+        let (state, _rx) = create_state();
+        let response = router(state)
+            .oneshot(
+                HttpRequest::get("/api/pipelines")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_wrong_token_is_rejected() {
+        let (state, _rx) = create_state();
+        let response = router(state)
+            .oneshot(
+                HttpRequest::get("/api/pipelines")
+                    .header(AUTHORIZATION, "Bearer nope")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_token_query_parameter_is_accepted() {
+        let (state, _rx) = create_state();
+        let response = router(state)
+            .oneshot(
+                HttpRequest::get(format!("/api/pipelines?token={TOKEN}"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_list_pipelines() {
+        let (state, _rx) = create_state();
+        let response = router(state)
+            .oneshot(authorized("GET", "/api/pipelines", Body::empty()))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let pipelines: Vec<Pipeline> = serde_json::from_slice(&body_bytes(response).await).unwrap();
+        assert_eq!(pipelines.len(), 1);
+        assert_eq!(pipelines[0].name, "test-pipeline");
+    }
+
+    #[tokio::test]
+    async fn test_start_pipeline_and_fetch_process() {
+        let (state, _rx) = create_state();
+        let app = router(state);
+
+        let response = app
+            .clone()
+            .oneshot(authorized(
+                "POST",
+                "/api/pipelines/test-pipeline/start",
+                Body::empty(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+        let process_id = body["processId"].as_str().unwrap().to_string();
+
+        let response = app
+            .clone()
+            .oneshot(authorized(
+                "GET",
+                &format!("/api/processes/{process_id}"),
+                Body::empty(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let process: Process = serde_json::from_slice(&body_bytes(response).await).unwrap();
+        assert_eq!(process.pipeline_name, "test-pipeline");
+
+        let response = app
+            .oneshot(authorized("GET", "/api/processes", Body::empty()))
+            .await
+            .unwrap();
+        let processes: Vec<Process> = serde_json::from_slice(&body_bytes(response).await).unwrap();
+        assert_eq!(processes.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_start_unknown_pipeline_is_not_found() {
+        let (state, _rx) = create_state();
+        let response = router(state)
+            .oneshot(authorized(
+                "POST",
+                "/api/pipelines/missing/start",
+                Body::empty(),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_kill_unknown_process_is_not_found() {
+        let (state, _rx) = create_state();
+        let response = router(state)
+            .oneshot(authorized(
+                "POST",
+                &format!("/api/processes/{}/kill", Uuid::new_v4()),
+                Body::empty(),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_submit_op_accepts_protocol_json() {
+        let (state, _rx) = create_state();
+        let op = serde_json::to_string(&Op::StartPipeline {
+            name: "test-pipeline".to_string(),
+            reference_file: None,
+        })
+        .unwrap();
+
+        let response = router(state)
+            .oneshot(authorized("POST", "/api/ops", Body::from(op)))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+        assert!(body["processId"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_delivers_events() {
+        let (state, _rx) = create_state();
+        let events = state.events.clone();
+        let response = router(state)
+            .oneshot(authorized("GET", "/api/events", Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let process_id = Uuid::new_v4();
+        events.send(Event::ProcessCompleted { process_id }).unwrap();
+
+        let mut body = response.into_body().into_data_stream();
+        let chunk = tokio::time::timeout(std::time::Duration::from_secs(2), body.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let text = String::from_utf8(chunk.to_vec()).unwrap();
+
+        assert!(text.starts_with("data: "));
+        assert!(text.contains("processCompleted"));
+        assert!(text.contains(&process_id.to_string()));
+    }
+}
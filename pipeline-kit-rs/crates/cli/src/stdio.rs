@@ -0,0 +1,391 @@
+//! Stdio JSON-RPC mode for driving pipeline-kit from another process.
+//!
+//! `pipeline-kit serve --stdio` reads one `RpcRequest` per line from stdin
+//! and writes one `RpcMessage` per line to stdout. Every core `Event` is
+//! forwarded as it happens, so a parent process (the Node wrapper, an
+//! editor extension) gets the same view of the StateManager as the TUI.
+//!
+//! Diagnostics go to stderr; stdout carries protocol messages only.
+
+use pk_core::agents::manager::AgentManager;
+use pk_core::config::loader::load_config;
+use pk_core::state::dispatcher::OpDispatcher;
+use pk_core::state::manager::StateManager;
+use pk_protocol::ipc::Event;
+use pk_protocol::ipc::Op;
+use pk_protocol::rpc::RpcMessage;
+use pk_protocol::rpc::RpcRequest;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncBufRead;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWrite;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::sync::mpsc;
+
+/// Run the stdio server on the process's stdin/stdout.
+///
+/// Returns when stdin is closed or a `shutdown` op is received.
+///
+/// # Errors
+///
+/// Returns an error if the configuration cannot be loaded or stdout
+/// cannot be written.
+pub async fn serve_stdio() -> color_eyre::Result<()> {
+    let root = std::env::current_dir()?;
+    let config = load_config(&root)
+        .await
+        .map_err(|e| color_eyre::eyre::eyre!("Failed to load config: {e}"))?;
+
+    let (events_tx, events_rx) = mpsc::channel::<Event>(100);
+    let agent_manager =
+        AgentManager::new(config.agents).with_default_fallback(config.global.fallback);
+    let state_manager = Arc::new(StateManager::new(agent_manager, events_tx));
+    let dispatcher = Arc::new(OpDispatcher::new(state_manager, config.pipelines));
+
+    run(
+        dispatcher,
+        events_rx,
+        BufReader::new(tokio::io::stdin()),
+        tokio::io::stdout(),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// How long shutdown waits for queued events to be written.
+const EVENT_DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Drive the JSON-RPC loop over arbitrary reader/writer pairs.
+///
+/// # Arguments
+///
+/// * `dispatcher` - The dispatcher requests are applied to
+/// * `events_rx` - Core events to forward to the client
+/// * `reader` - Source of request lines
+/// * `writer` - Sink for response and event lines
+///
+/// # Errors
+///
+/// Returns an error if reading a request line fails.
+pub async fn run<R, W>(
+    dispatcher: Arc<OpDispatcher>,
+    mut events_rx: mpsc::Receiver<Event>,
+    reader: R,
+    writer: W,
+) -> std::io::Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    // 1. Single writer task so lines are never interleaved
+    let (out_tx, mut out_rx) = mpsc::unbounded_channel::<RpcMessage>();
+    let writer_task = tokio::spawn(async move {
+        let mut writer = writer;
+        while let Some(message) = out_rx.recv().await {
+            let mut line = match serde_json::to_string(&message) {
+                Ok(line) => line,
+                Err(e) => {
+                    eprintln!("Failed to serialize message: {e}");
+                    continue;
+                }
+            };
+            line.push('\n');
+            if writer.write_all(line.as_bytes()).await.is_err() || writer.flush().await.is_err() {
+                break;
+            }
+        }
+    });
+
+    // 2. Forward core events as they arrive
+    let events_out = out_tx.clone();
+    let events_task = tokio::spawn(async move {
+        while let Some(event) = events_rx.recv().await {
+            if events_out
+                .send(RpcMessage::Event {
+                    event: Box::new(event),
+                })
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    // 3. Apply requests until EOF or shutdown
+    let mut lines = reader.lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request = match serde_json::from_str::<RpcRequest>(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                let _ = out_tx.send(RpcMessage::Response {
+                    id: None,
+                    ok: false,
+                    process_id: None,
+                    error: Some(format!("Invalid request: {e}")),
+                });
+                continue;
+            }
+        };
+
+        let is_shutdown = matches!(request.op, Op::Shutdown);
+        let result = dispatcher.dispatch(request.op).await;
+
+        if let Some(id) = request.id {
+            let response = match result {
+                Ok(process_id) => RpcMessage::Response {
+                    id: Some(id),
+                    ok: true,
+                    process_id,
+                    error: None,
+                },
+                Err(e) => RpcMessage::Response {
+                    id: Some(id),
+                    ok: false,
+                    process_id: None,
+                    error: Some(e.to_string()),
+                },
+            };
+            let _ = out_tx.send(response);
+        }
+
+        if is_shutdown {
+            break;
+        }
+    }
+
+    // 4. Forward the events still queued, then stop. Dropping our handle on
+    //    the dispatcher closes the event channel once no pipeline task holds a
+    //    sender; the timeout bounds the wait for those that still run.
+    drop(dispatcher);
+    let mut events_task = events_task;
+    if tokio::time::timeout(EVENT_DRAIN_TIMEOUT, &mut events_task)
+        .await
+        .is_err()
+    {
+        events_task.abort();
+    }
+    drop(out_tx);
+    let _ = writer_task.await;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pk_protocol::agent_models::Agent as AgentConfig;
+    use pk_protocol::agent_models::AgentProvider;
+    use pk_protocol::pipeline_models::MasterAgentConfig;
+    use pk_protocol::pipeline_models::Pipeline;
+    use pk_protocol::pipeline_models::ProcessStep;
+    use pk_protocol::rpc::RequestId;
+    use std::collections::HashMap;
+    use tokio::io::duplex;
+    use tokio::io::AsyncReadExt;
+
+    fn create_dispatcher() -> (Arc<OpDispatcher>, mpsc::Receiver<Event>) {
+        let agents = vec![AgentConfig {
+            name: "agent1".to_string(),
+            description: "Test agent".to_string(),
+            model: "test-model".to_string(),
+            provider: Some(AgentProvider::Mock),
+            color: "blue".to_string(),
+            system_prompt: "Test prompt".to_string(),
+            ..Default::default()
+        }];
+        let pipeline = Pipeline {
+            name: "test-pipeline".to_string(),
+            required_reference_file: HashMap::new(),
+            output_file: HashMap::new(),
+            master: MasterAgentConfig {
+                model: "test-model".to_string(),
+                system_prompt: "Test orchestration".to_string(),
+                process: vec![ProcessStep::Agent("agent1".to_string())],
+            },
+            sub_agents: vec!["agent1".to_string()],
+            budget: None,
+        };
+
+        let (tx, rx) = mpsc::channel(100);
+        let state_manager = Arc::new(StateManager::new(AgentManager::new(agents), tx));
+        (
+            Arc::new(OpDispatcher::new(state_manager, vec![pipeline])),
+            rx,
+        )
+    }
+
+    /// Feed `input` to the server and collect every line it writes.
+    async fn run_with_input(input: &str) -> Vec<RpcMessage> {
+        let (dispatcher, events_rx) = create_dispatcher();
+        let (client, server) = duplex(64 * 1024);
+
+        run(
+            dispatcher,
+            events_rx,
+            BufReader::new(input.as_bytes()),
+            server,
+        )
+        .await
+        .unwrap();
+
+        let mut output = String::new();
+        let mut client = client;
+        client.read_to_string(&mut output).await.unwrap();
+
+        output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_start_pipeline_gets_correlated_response() {
+        let input = concat!(
+            r#"{"id":1,"op":{"type":"startPipeline","payload":{"name":"test-pipeline","reference_file":null}}}"#,
+            "\n"
+        );
+
+        let messages = run_with_input(input).await;
+
+        let response = messages
+            .iter()
+            .find(|m| matches!(m, RpcMessage::Response { .. }))
+            .expect("Should write a response");
+        match response {
+            RpcMessage::Response {
+                id,
+                ok,
+                process_id,
+                error,
+            } => {
+                assert_eq!(id, &Some(RequestId::Number(1)));
+                assert!(ok);
+                assert!(process_id.is_some());
+                assert!(error.is_none());
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_pipeline_returns_error_response() {
+        let input = concat!(
+            r#"{"id":"a","op":{"type":"startPipeline","payload":{"name":"missing","reference_file":null}}}"#,
+            "\n"
+        );
+
+        let messages = run_with_input(input).await;
+
+        assert!(messages.iter().any(|m| matches!(
+            m,
+            RpcMessage::Response { id: Some(RequestId::String(id)), ok: false, error: Some(error), .. }
+                if id == "a" && error.contains("missing")
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_line_returns_error_without_id() {
+        let messages = run_with_input("not json\n").await;
+
+        assert_eq!(messages.len(), 1);
+        assert!(matches!(
+            &messages[0],
+            RpcMessage::Response {
+                id: None,
+                ok: false,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_notification_gets_no_response() {
+        let messages = run_with_input("{\"op\":{\"type\":\"getDashboardState\"}}\n").await;
+
+        assert!(messages
+            .iter()
+            .all(|m| !matches!(m, RpcMessage::Response { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_writes_queued_events() {
+        let (dispatcher, _) = create_dispatcher();
+        let (events_tx, events_rx) = mpsc::channel(10);
+        let process_id = uuid::Uuid::new_v4();
+        events_tx
+            .send(Event::ProcessCompleted { process_id })
+            .await
+            .unwrap();
+        drop(events_tx);
+        let (client, server) = duplex(64 * 1024);
+
+        run(
+            dispatcher,
+            events_rx,
+            BufReader::new(&b"{\"id\":1,\"op\":{\"type\":\"shutdown\"}}\n"[..]),
+            server,
+        )
+        .await
+        .unwrap();
+
+        let mut output = String::new();
+        let mut client = client;
+        client.read_to_string(&mut output).await.unwrap();
+        assert!(output.lines().any(|line| matches!(
+            serde_json::from_str(line).unwrap(),
+            RpcMessage::Event { event } if matches!(*event, Event::ProcessCompleted { .. })
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_events_are_forwarded_until_shutdown() {
+        let (dispatcher, events_rx) = create_dispatcher();
+        let (client, server) = duplex(64 * 1024);
+        let (mut input_tx, input_rx) = duplex(64 * 1024);
+
+        let handle = tokio::spawn(run(dispatcher, events_rx, BufReader::new(input_rx), server));
+
+        input_tx
+            .write_all(
+                concat!(
+                    r#"{"id":1,"op":{"type":"startPipeline","payload":{"name":"test-pipeline","reference_file":null}}}"#,
+                    "\n"
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+
+        // Read until the pipeline reports completion
+        let mut lines = BufReader::new(client).lines();
+        let mut completed = false;
+        while let Some(line) = tokio::time::timeout(Duration::from_secs(5), lines.next_line())
+            .await
+            .unwrap()
+            .unwrap()
+        {
+            let message: RpcMessage = serde_json::from_str(&line).unwrap();
+            if matches!(
+                message,
+                RpcMessage::Event { event } if matches!(*event, Event::ProcessCompleted { .. })
+            ) {
+                completed = true;
+                break;
+            }
+        }
+        assert!(completed, "Should forward ProcessCompleted event");
+
+        input_tx
+            .write_all(b"{\"id\":2,\"op\":{\"type\":\"shutdown\"}}\n")
+            .await
+            .unwrap();
+        handle.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,1076 @@
+//! Client side of the Agent Client Protocol (ACP).
+//!
+//! ACP is JSON-RPC 2.0 over the stdin/stdout of an agent CLI (Qwen Code,
+//! Gemini CLI's ACP mode, ...). A prompt turn runs as follows:
+//!
+//! 1. `initialize` advertises the client's file system capabilities
+//! 2. `session/new` opens a session rooted at the working directory
+//! 3. `session/prompt` sends the instruction; while it runs the agent
+//!    streams `session/update` notifications and may call back into the
+//!    client with `session/request_permission`, `fs/read_text_file` and
+//!    `fs/write_text_file`
+//! 4. The prompt response carries the stop reason
+//!
+//! Attachments are sent as `resource` (files) and `image` blocks when the
+//! agent advertises support for them in its `promptCapabilities`; files
+//! otherwise fall back to `resource_link`s, and images fail the turn.
+//!
+//! The connection runs in a background task. Dropping the returned event
+//! stream sends `session/cancel` and gives the agent a moment to stop before
+//! the process is killed.
+
+use crate::agents::base::read_image_base64;
+use crate::agents::base::AgentError;
+use crate::agents::base::AgentEvent;
+use crate::agents::base::Attachment;
+use crate::agents::permissions::PermissionReply;
+use crate::agents::permissions::ToolPermissions;
+use pk_protocol::agent_models::PermissionAction;
+use pk_protocol::agent_models::PermissionMode;
+use pk_protocol::process_models::FileChangeKind;
+use serde_json::json;
+use serde_json::Value;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::io::Lines;
+use tokio::process::ChildStdin;
+use tokio::process::ChildStdout;
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+use tokio_stream::Stream;
+
+/// ACP protocol version implemented by this client.
+const PROTOCOL_VERSION: u64 = 1;
+
+/// How long a cancelled agent may take to finish its prompt turn.
+const CANCEL_GRACE: Duration = Duration::from_secs(5);
+
+/// JSON-RPC error code for an unknown method.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// JSON-RPC error code for a request the client refuses to serve.
+const REQUEST_FAILED: i64 = -32000;
+
+/// Stream of events produced by a prompt turn.
+pub type EventStream = Pin<Box<dyn Stream<Item = Result<AgentEvent, AgentError>> + Send>>;
+
+/// Settings for one ACP prompt turn.
+#[derive(Debug, Clone)]
+pub struct AcpSession {
+    /// Working directory of the session; file system calls are confined to it.
+    pub cwd: PathBuf,
+
+    /// Permission mode and rules used to answer permission requests and
+    /// writes.
+    pub permissions: ToolPermissions,
+}
+
+/// Client for agents that speak ACP over stdio.
+pub struct AcpClient;
+
+impl AcpClient {
+    /// Spawn an ACP agent and run one prompt turn.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The agent command, with its ACP flag and arguments set
+    /// * `session` - Working directory and permissions
+    /// * `prompt` - Prompt content blocks (e.g. `{"type": "text", "text": ...}`)
+    ///
+    /// # Returns
+    ///
+    /// A stream of `AgentEvent`s ending with `Completed` or an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::ExecutionError` if the process cannot be spawned.
+    pub fn prompt(
+        mut command: Command,
+        session: AcpSession,
+        prompt: Vec<Value>,
+    ) -> Result<EventStream, AgentError> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| AgentError::ExecutionError(format!("Failed to spawn ACP agent: {}", e)))?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| AgentError::ExecutionError("Failed to capture stdin".to_string()))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| AgentError::ExecutionError("Failed to capture stdout".to_string()))?;
+
+        let (tx, mut rx) = mpsc::channel(64);
+        let (cancel_tx, cancel_rx) = oneshot::channel::<()>();
+        let root = std::fs::canonicalize(&session.cwd).unwrap_or(session.cwd);
+
+        tokio::spawn(async move {
+            let mut connection = Connection {
+                stdin,
+                lines: BufReader::new(stdout).lines(),
+                next_id: 0,
+            };
+            let mut handler = Handler {
+                root,
+                permissions: session.permissions,
+                tx: tx.clone(),
+            };
+            let result = run_turn(&mut connection, &mut handler, prompt, cancel_rx).await;
+            let _ = tx.send(result.map(|()| AgentEvent::Completed)).await;
+            // Dropping the child kills the agent if it is still running
+            drop(child);
+        });
+
+        let stream = async_stream::stream! {
+            // Dropping the stream drops `cancel_tx`, which cancels the turn
+            let _cancel_on_drop = cancel_tx;
+            while let Some(item) = rx.recv().await {
+                let done = matches!(item, Ok(AgentEvent::Completed) | Err(_));
+                yield item;
+                if done {
+                    break;
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Build the prompt content blocks for an instruction and its attachments.
+///
+/// Attachments come first, files as embedded `resource` blocks and images
+/// as `image` blocks, followed by the text. Relative file paths are
+/// resolved against `project_root`, like the attachments themselves, for
+/// their `file://` URIs.
+///
+/// # Errors
+///
+/// Returns `AgentError::ExecutionError` if an image cannot be read.
+pub async fn prompt_blocks(
+    text: String,
+    attachments: &[Attachment],
+    project_root: &Path,
+) -> Result<Vec<Value>, AgentError> {
+    let mut blocks = Vec::new();
+    for attachment in attachments {
+        let block = match attachment {
+            Attachment::File { path, content } => json!({
+                "type": "resource",
+                "resource": {
+                    "uri": format!("file://{}", project_root.join(path).display()),
+                    "mimeType": "text/plain",
+                    "text": content,
+                },
+            }),
+            Attachment::Image { path, mime_type } => json!({
+                "type": "image",
+                "mimeType": mime_type,
+                "data": read_image_base64(path).await?,
+            }),
+        };
+        blocks.push(block);
+    }
+    blocks.push(json!({ "type": "text", "text": text }));
+    Ok(blocks)
+}
+
+/// Adapt prompt blocks to the capabilities the agent advertised in its
+/// `initialize` result.
+///
+/// Embedded resources become `resource_link`s, which every agent accepts,
+/// when `embeddedContext` is not supported.
+///
+/// # Errors
+///
+/// Returns `AgentError::Unsupported` for an image the agent cannot accept.
+fn fit_prompt(prompt: Vec<Value>, initialize: &Value) -> Result<Vec<Value>, AgentError> {
+    let capabilities = &initialize["agentCapabilities"]["promptCapabilities"];
+    let supports = |name: &str| capabilities[name].as_bool().unwrap_or(false);
+    prompt
+        .into_iter()
+        .map(|block| match block["type"].as_str() {
+            Some("image") if !supports("image") => Err(AgentError::Unsupported(
+                "ACP agent cannot receive image attachments".to_string(),
+            )),
+            Some("resource") if !supports("embeddedContext") => {
+                let uri = block["resource"]["uri"].as_str().unwrap_or_default();
+                let name = uri.rsplit('/').next().unwrap_or(uri);
+                Ok(json!({ "type": "resource_link", "uri": uri, "name": name }))
+            }
+            _ => Ok(block),
+        })
+        .collect()
+}
+
+/// Run the handshake and one prompt turn.
+async fn run_turn(
+    connection: &mut Connection,
+    handler: &mut Handler,
+    prompt: Vec<Value>,
+    mut cancel_rx: oneshot::Receiver<()>,
+) -> Result<(), AgentError> {
+    // 1. Initialize
+    let params = json!({
+        "protocolVersion": PROTOCOL_VERSION,
+        "clientCapabilities": {
+            "fs": { "readTextFile": true, "writeTextFile": true }
+        }
+    });
+    let id = connection.request("initialize", params).await?;
+    let result = connection.wait_for(id, handler).await?;
+    let prompt = fit_prompt(prompt, &result)?;
+
+    // 2. Create a session
+    let params = json!({
+        "cwd": handler.root.to_string_lossy(),
+        "mcpServers": [],
+    });
+    let id = connection.request("session/new", params).await?;
+    let result = connection.wait_for(id, handler).await?;
+    let session_id = result
+        .get("sessionId")
+        .and_then(Value::as_str)
+        .ok_or_else(|| {
+            AgentError::StreamParseError("session/new returned no sessionId".to_string())
+        })?
+        .to_string();
+
+    // 3. Prompt, cancelling if the consumer goes away
+    let params = json!({ "sessionId": session_id, "prompt": prompt });
+    let id = connection.request("session/prompt", params).await?;
+    let result = tokio::select! {
+        result = connection.wait_for(id, handler) => result?,
+        _ = &mut cancel_rx => {
+            connection
+                .notify("session/cancel", json!({ "sessionId": session_id }))
+                .await?;
+            // The turn is abandoned; give the agent a moment to wind down
+            let _ = tokio::time::timeout(CANCEL_GRACE, connection.wait_for(id, handler)).await;
+            return Err(AgentError::ExecutionError("Prompt turn cancelled".to_string()));
+        }
+    };
+
+    // 4. Interpret the stop reason
+    match result.get("stopReason").and_then(Value::as_str) {
+        Some("refusal") => Err(AgentError::ExecutionError(
+            "Agent refused the prompt".to_string(),
+        )),
+        Some("cancelled") => Err(AgentError::ExecutionError(
+            "Prompt turn cancelled".to_string(),
+        )),
+        Some(reason @ ("max_tokens" | "max_turn_requests")) => {
+            handler
+                .emit(Ok(AgentEvent::Warning(format!(
+                    "Agent stopped early: {}",
+                    reason
+                ))))
+                .await;
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// A message received from the agent.
+#[derive(Debug)]
+enum Message {
+    Response {
+        id: u64,
+        result: Result<Value, String>,
+    },
+    Request {
+        id: Value,
+        method: String,
+        params: Value,
+    },
+    Notification {
+        method: String,
+        params: Value,
+    },
+}
+
+impl Message {
+    /// Classify a JSON-RPC message; returns `None` for anything else.
+    fn parse(value: Value) -> Option<Self> {
+        let params = value.get("params").cloned().unwrap_or(Value::Null);
+        match (value.get("id"), value.get("method").and_then(Value::as_str)) {
+            (Some(id), Some(method)) => Some(Self::Request {
+                id: id.clone(),
+                method: method.to_string(),
+                params,
+            }),
+            (None, Some(method)) => Some(Self::Notification {
+                method: method.to_string(),
+                params,
+            }),
+            (Some(id), None) => {
+                let result = match value.get("error") {
+                    Some(error) => Err(error
+                        .get("message")
+                        .and_then(Value::as_str)
+                        .unwrap_or("unknown error")
+                        .to_string()),
+                    None => Ok(value.get("result").cloned().unwrap_or(Value::Null)),
+                };
+                Some(Self::Response {
+                    id: id.as_u64()?,
+                    result,
+                })
+            }
+            (None, None) => None,
+        }
+    }
+}
+
+/// JSON-RPC connection over the agent's stdio.
+struct Connection {
+    stdin: ChildStdin,
+    lines: Lines<BufReader<ChildStdout>>,
+    next_id: u64,
+}
+
+impl Connection {
+    /// Write one JSON-RPC message.
+    async fn send(&mut self, message: &Value) -> Result<(), AgentError> {
+        let mut line = message.to_string();
+        line.push('\n');
+        self.stdin
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| AgentError::ExecutionError(format!("Failed to write to agent: {}", e)))?;
+        self.stdin
+            .flush()
+            .await
+            .map_err(|e| AgentError::ExecutionError(format!("Failed to flush agent stdin: {}", e)))
+    }
+
+    /// Send a request and return its id.
+    async fn request(&mut self, method: &str, params: Value) -> Result<u64, AgentError> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+            .await?;
+        Ok(id)
+    }
+
+    /// Send a notification.
+    async fn notify(&mut self, method: &str, params: Value) -> Result<(), AgentError> {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+            .await
+    }
+
+    /// Process incoming messages until the response to `id` arrives.
+    ///
+    /// Notifications and reverse requests received in the meantime are
+    /// passed to the handler.
+    async fn wait_for(&mut self, id: u64, handler: &mut Handler) -> Result<Value, AgentError> {
+        loop {
+            let line = match self.lines.next_line().await {
+                Ok(Some(line)) => line,
+                Ok(None) | Err(_) => {
+                    return Err(AgentError::ExecutionError(
+                        "ACP agent exited before responding".to_string(),
+                    ))
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Ok(value) = serde_json::from_str::<Value>(&line) else {
+                // Agents may log non-protocol lines to stdout
+                continue;
+            };
+
+            match Message::parse(value) {
+                Some(Message::Response {
+                    id: response_id,
+                    result,
+                }) if response_id == id => {
+                    return result.map_err(AgentError::ApiError);
+                }
+                Some(Message::Request { id, method, params }) => {
+                    let response = match handler.on_request(&method, params).await {
+                        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                        Err((code, message)) => json!({
+                            "jsonrpc": "2.0",
+                            "id": id,
+                            "error": { "code": code, "message": message },
+                        }),
+                    };
+                    self.send(&response).await?;
+                }
+                Some(Message::Notification { method, params }) => {
+                    handler.on_notification(&method, params).await;
+                }
+                Some(Message::Response { .. }) | None => {}
+            }
+        }
+    }
+}
+
+/// Client-side handling of agent notifications and requests.
+struct Handler {
+    root: PathBuf,
+    permissions: ToolPermissions,
+    tx: mpsc::Sender<Result<AgentEvent, AgentError>>,
+}
+
+impl Handler {
+    /// Forward an event to the stream consumer.
+    async fn emit(&self, event: Result<AgentEvent, AgentError>) {
+        let _ = self.tx.send(event).await;
+    }
+
+    async fn on_notification(&mut self, method: &str, params: Value) {
+        if method != "session/update" {
+            return;
+        }
+        // Updates are nested under `update`; early agents sent them inline
+        let update = params.get("update").cloned().unwrap_or(params);
+        for event in convert_update(update) {
+            self.emit(Ok(event)).await;
+        }
+    }
+
+    async fn on_request(&mut self, method: &str, params: Value) -> Result<Value, (i64, String)> {
+        match method {
+            "session/request_permission" => Ok(self.request_permission(&params).await),
+            "fs/read_text_file" => self.read_text_file(&params).await,
+            "fs/write_text_file" => self.write_text_file(&params).await,
+            _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+        }
+    }
+
+    /// Answer a permission request from the permission mode and rules,
+    /// asking the user when neither decides.
+    async fn request_permission(&mut self, params: &Value) -> Value {
+        let tool_call = params.get("toolCall").cloned().unwrap_or(Value::Null);
+        let kind = tool_call
+            .get("kind")
+            .and_then(Value::as_str)
+            .unwrap_or("other");
+        let title = tool_call
+            .get("title")
+            .and_then(Value::as_str)
+            .unwrap_or(kind)
+            .to_string();
+
+        let allowed = match self.permissions.decide(kind) {
+            PermissionAction::Allow => true,
+            PermissionAction::Deny => {
+                self.emit(Ok(AgentEvent::Warning(format!(
+                    "Denied permission for '{}' under the agent's permission settings",
+                    title
+                ))))
+                .await;
+                false
+            }
+            PermissionAction::Ask => {
+                let (reply, answer) = PermissionReply::channel();
+                let arguments = tool_call.get("rawInput").cloned().unwrap_or(Value::Null);
+                self.emit(Ok(AgentEvent::PermissionRequest {
+                    tool: kind.to_string(),
+                    title,
+                    arguments,
+                    reply,
+                }))
+                .await;
+                // A dropped reply (nobody answering) denies the request
+                answer.await.unwrap_or(false)
+            }
+        };
+
+        let options = params
+            .get("options")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        permission_outcome(&options, allowed)
+    }
+
+    async fn read_text_file(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let path = self.path_param(params)?;
+        let content = tokio::fs::read_to_string(&path).await.map_err(|e| {
+            (
+                REQUEST_FAILED,
+                format!("Failed to read {}: {}", path.display(), e),
+            )
+        })?;
+
+        // `line` is 1-based; `limit` caps the number of lines returned
+        let line = params.get("line").and_then(Value::as_u64);
+        let limit = params.get("limit").and_then(Value::as_u64);
+        let content = if line.is_some() || limit.is_some() {
+            let skip = line.unwrap_or(1).saturating_sub(1) as usize;
+            let take = limit.map_or(usize::MAX, |l| l as usize);
+            content
+                .lines()
+                .skip(skip)
+                .take(take)
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else {
+            content
+        };
+
+        Ok(json!({ "content": content }))
+    }
+
+    async fn write_text_file(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        if self.permissions.mode == Some(PermissionMode::ReadOnly) {
+            return Err((
+                REQUEST_FAILED,
+                "Writes are not allowed in read-only mode".to_string(),
+            ));
+        }
+        let path = self.path_param(params)?;
+        let content = params
+            .get("content")
+            .and_then(Value::as_str)
+            .ok_or_else(|| (REQUEST_FAILED, "Missing content".to_string()))?;
+
+        let kind = if path.exists() {
+            FileChangeKind::Modified
+        } else {
+            FileChangeKind::Created
+        };
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| {
+                (
+                    REQUEST_FAILED,
+                    format!("Failed to create {}: {}", parent.display(), e),
+                )
+            })?;
+        }
+        tokio::fs::write(&path, content).await.map_err(|e| {
+            (
+                REQUEST_FAILED,
+                format!("Failed to write {}: {}", path.display(), e),
+            )
+        })?;
+
+        let relative = path.strip_prefix(&self.root).unwrap_or(&path);
+        self.emit(Ok(AgentEvent::FileChanged {
+            path: relative.to_string_lossy().to_string(),
+            kind,
+        }))
+        .await;
+        Ok(Value::Null)
+    }
+
+    /// Resolve the `path` parameter, which must lie inside the session root.
+    fn path_param(&self, params: &Value) -> Result<PathBuf, (i64, String)> {
+        let path = params
+            .get("path")
+            .and_then(Value::as_str)
+            .ok_or_else(|| (REQUEST_FAILED, "Missing path".to_string()))?;
+        resolve_in_root(&self.root, path).ok_or_else(|| {
+            (
+                REQUEST_FAILED,
+                format!("Path '{}' is outside the working directory", path),
+            )
+        })
+    }
+}
+
+/// Pick the permission option matching a decision.
+///
+/// One-time options are preferred so that a later turn asks again.
+fn permission_outcome(options: &[Value], allowed: bool) -> Value {
+    let preferred: &[&str] = if allowed {
+        &["allow_once", "allow_always"]
+    } else {
+        &["reject_once", "reject_always"]
+    };
+    let option_id = preferred.iter().find_map(|kind| {
+        options
+            .iter()
+            .find(|o| o.get("kind").and_then(Value::as_str) == Some(kind))
+            .and_then(|o| o.get("optionId").cloned())
+    });
+
+    match option_id {
+        Some(option_id) => json!({ "outcome": { "outcome": "selected", "optionId": option_id } }),
+        None => json!({ "outcome": { "outcome": "cancelled" } }),
+    }
+}
+
+/// Resolve a path against a root, rejecting paths that leave it.
+///
+/// `..` is resolved lexically; the longest existing prefix of the result is
+/// then canonicalized so that a symlink inside the root cannot lead out of
+/// it.
+fn resolve_in_root(root: &Path, path: &str) -> Option<PathBuf> {
+    let joined = root.join(path);
+    let mut resolved = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::CurDir => {}
+            other => resolved.push(other),
+        }
+    }
+    if !resolved.starts_with(root) {
+        return None;
+    }
+
+    // Without a root on disk there is no symlink to follow
+    let Ok(real_root) = root.canonicalize() else {
+        return Some(resolved);
+    };
+    // `symlink_metadata` also finds dangling links, which canonicalize rejects
+    let existing = resolved
+        .ancestors()
+        .find(|p| p.symlink_metadata().is_ok())?;
+    let real = existing.canonicalize().ok()?;
+    real.starts_with(&real_root).then_some(resolved)
+}
+
+/// Convert a `session/update` payload to AgentEvents.
+pub(crate) fn convert_update(update: Value) -> Vec<AgentEvent> {
+    let field = |key: &str| {
+        update
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    };
+    let tool_call_id = field("toolCallId");
+
+    // The kind is in `sessionUpdate`; early agents used `type`
+    let Some(kind) = field("sessionUpdate").or_else(|| field("type")) else {
+        return Vec::new();
+    };
+
+    match kind.as_str() {
+        "agent_message_chunk" | "agent_thought_chunk" => {
+            // Extract text content
+            let text = field("text").or_else(|| {
+                update
+                    .pointer("/content/text")
+                    .and_then(|t| t.as_str())
+                    .map(|s| s.to_string())
+            });
+
+            text.map(AgentEvent::MessageChunk).into_iter().collect()
+        }
+        "tool_call" => {
+            let name = field("title")
+                .or_else(|| field("name"))
+                .or_else(|| field("kind"))
+                .unwrap_or_default();
+            let arguments = ["rawInput", "input"]
+                .iter()
+                .find_map(|key| update.get(*key).cloned())
+                .unwrap_or_default();
+
+            vec![AgentEvent::ToolCall {
+                id: tool_call_id,
+                name,
+                arguments,
+            }]
+        }
+        "tool_call_update" => {
+            // Only terminal updates carry a result
+            let status = field("status").unwrap_or_default();
+            if status != "completed" && status != "failed" {
+                return Vec::new();
+            }
+
+            let items = update
+                .get("content")
+                .and_then(|c| c.as_array())
+                .cloned()
+                .unwrap_or_default();
+
+            let output = items
+                .iter()
+                .filter_map(|item| item.pointer("/content/text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            let mut events = vec![AgentEvent::ToolResult {
+                id: tool_call_id,
+                output,
+                is_error: status == "failed",
+            }];
+
+            // Diff content items describe edited files
+            for item in &items {
+                if item.get("type").and_then(|t| t.as_str()) != Some("diff") {
+                    continue;
+                }
+                if let Some(path) = item.get("path").and_then(|p| p.as_str()) {
+                    let kind = if item.get("oldText").is_none_or(|t| t.is_null()) {
+                        FileChangeKind::Created
+                    } else {
+                        FileChangeKind::Modified
+                    };
+                    events.push(AgentEvent::FileChanged {
+                        path: path.to_string(),
+                        kind,
+                    });
+                }
+            }
+
+            events
+        }
+        "plan" => {
+            // Render the plan as a checklist
+            let entries = update
+                .get("entries")
+                .and_then(|e| e.as_array())
+                .cloned()
+                .unwrap_or_default();
+            if entries.is_empty() {
+                return Vec::new();
+            }
+            let lines: Vec<String> = entries
+                .iter()
+                .map(|entry| {
+                    let mark = match entry.get("status").and_then(|s| s.as_str()) {
+                        Some("completed") => "x",
+                        Some("in_progress") => "~",
+                        _ => " ",
+                    };
+                    let content = entry.get("content").and_then(|c| c.as_str()).unwrap_or("");
+                    format!("[{}] {}", mark, content)
+                })
+                .collect();
+            vec![AgentEvent::Thought(format!("Plan:\n{}", lines.join("\n")))]
+        }
+        _ => {
+            // Ignore other update kinds (user message echoes, mode changes, ...)
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_prompt() {
+        let prompt = vec![
+            json!({
+                "type": "resource",
+                "resource": { "uri": "file:///work/docs/spec.md", "text": "Spec" },
+            }),
+            json!({ "type": "text", "text": "Go" }),
+        ];
+
+        let embedded = json!({
+            "agentCapabilities": { "promptCapabilities": { "embeddedContext": true } }
+        });
+        assert_eq!(fit_prompt(prompt.clone(), &embedded).unwrap(), prompt);
+
+        let fitted = fit_prompt(prompt, &json!({ "agentCapabilities": {} })).unwrap();
+        assert_eq!(
+            fitted[0],
+            json!({ "type": "resource_link", "uri": "file:///work/docs/spec.md", "name": "spec.md" })
+        );
+
+        let image = vec![json!({ "type": "image", "mimeType": "image/png", "data": "" })];
+        assert!(matches!(
+            fit_prompt(image, &embedded),
+            Err(AgentError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_message_parse() {
+        assert!(matches!(
+            Message::parse(
+                json!({"jsonrpc": "2.0", "id": 3, "result": {"stopReason": "end_turn"}})
+            ),
+            Some(Message::Response {
+                id: 3,
+                result: Ok(_)
+            })
+        ));
+        assert!(matches!(
+            Message::parse(json!({"jsonrpc": "2.0", "id": 4, "error": {"code": -1, "message": "boom"}})),
+            Some(Message::Response { id: 4, result: Err(msg) }) if msg == "boom"
+        ));
+        assert!(matches!(
+            Message::parse(json!({"jsonrpc": "2.0", "id": 0, "method": "fs/read_text_file", "params": {}})),
+            Some(Message::Request { method, .. }) if method == "fs/read_text_file"
+        ));
+        assert!(matches!(
+            Message::parse(json!({"jsonrpc": "2.0", "method": "session/update", "params": {}})),
+            Some(Message::Notification { .. })
+        ));
+    }
+
+    #[test]
+    fn test_permission_outcome() {
+        let options = vec![
+            json!({"optionId": "always", "name": "Always", "kind": "allow_always"}),
+            json!({"optionId": "once", "name": "Once", "kind": "allow_once"}),
+            json!({"optionId": "no", "name": "No", "kind": "reject_once"}),
+        ];
+        assert_eq!(
+            permission_outcome(&options, true)["outcome"]["optionId"],
+            "once"
+        );
+        assert_eq!(
+            permission_outcome(&options, false)["outcome"]["optionId"],
+            "no"
+        );
+        assert_eq!(
+            permission_outcome(&options[..2], false)["outcome"]["outcome"],
+            "cancelled"
+        );
+    }
+
+    #[test]
+    fn test_resolve_in_root() {
+        let root = Path::new("/repo");
+        assert_eq!(
+            resolve_in_root(root, "src/lib.rs"),
+            Some(PathBuf::from("/repo/src/lib.rs"))
+        );
+        assert_eq!(
+            resolve_in_root(root, "/repo/./a/../b.rs"),
+            Some(PathBuf::from("/repo/b.rs"))
+        );
+        assert_eq!(resolve_in_root(root, "../etc/passwd"), None);
+        assert_eq!(resolve_in_root(root, "/etc/passwd"), None);
+
+        // Symlinks must not lead out of the root, even for new files
+        #[cfg(unix)]
+        {
+            let root = tempfile::tempdir().unwrap();
+            let outside = tempfile::tempdir().unwrap();
+            std::fs::create_dir(root.path().join("src")).unwrap();
+            std::os::unix::fs::symlink(outside.path(), root.path().join("link")).unwrap();
+            std::os::unix::fs::symlink(
+                outside.path().join("missing"),
+                root.path().join("dangling"),
+            )
+            .unwrap();
+
+            assert_eq!(
+                resolve_in_root(root.path(), "src/new.rs"),
+                Some(root.path().join("src/new.rs"))
+            );
+            assert_eq!(resolve_in_root(root.path(), "link/passwd"), None);
+            assert_eq!(resolve_in_root(root.path(), "link"), None);
+            assert_eq!(resolve_in_root(root.path(), "dangling"), None);
+        }
+    }
+
+    #[test]
+    fn test_convert_update_message_chunk() {
+        let params = serde_json::json!({
+            "sessionUpdate": "agent_message_chunk",
+            "text": "Hello from the agent!"
+        });
+
+        let mut events = convert_update(params);
+        assert_eq!(events.len(), 1);
+
+        match events.remove(0) {
+            AgentEvent::MessageChunk(text) => assert_eq!(text, "Hello from the agent!"),
+            _ => panic!("Expected MessageChunk"),
+        }
+    }
+
+    #[test]
+    fn test_convert_update_thought_chunk() {
+        let params = serde_json::json!({
+            "type": "agent_thought_chunk",
+            "text": "Thinking..."
+        });
+
+        let mut events = convert_update(params);
+        assert_eq!(events.len(), 1);
+
+        match events.remove(0) {
+            AgentEvent::MessageChunk(text) => assert_eq!(text, "Thinking..."),
+            _ => panic!("Expected MessageChunk"),
+        }
+    }
+
+    #[test]
+    fn test_convert_update_tool_call() {
+        let params = serde_json::json!({
+            "type": "tool_call",
+            "name": "write",
+            "input": {"path": "test.txt", "content": "Hello"}
+        });
+
+        let mut events = convert_update(params);
+        assert_eq!(events.len(), 1);
+
+        match events.remove(0) {
+            AgentEvent::ToolCall {
+                name, arguments, ..
+            } => {
+                assert_eq!(name, "write");
+                assert_eq!(arguments["path"], "test.txt");
+            }
+            _ => panic!("Expected ToolCall"),
+        }
+    }
+
+    #[test]
+    fn test_convert_update_tool_call_update_with_diff() {
+        let params = serde_json::json!({
+            "sessionUpdate": "tool_call_update",
+            "toolCallId": "call_1",
+            "status": "completed",
+            "content": [
+                {"type": "content", "content": {"type": "text", "text": "Wrote file"}},
+                {"type": "diff", "path": "src/new.rs", "oldText": null, "newText": "fn main() {}"}
+            ]
+        });
+
+        let events = convert_update(params);
+        assert_eq!(
+            events,
+            vec![
+                AgentEvent::ToolResult {
+                    id: Some("call_1".to_string()),
+                    output: "Wrote file".to_string(),
+                    is_error: false,
+                },
+                AgentEvent::FileChanged {
+                    path: "src/new.rs".to_string(),
+                    kind: FileChangeKind::Created,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_update_in_progress_tool_call_update_is_ignored() {
+        let params = serde_json::json!({
+            "sessionUpdate": "tool_call_update",
+            "toolCallId": "call_1",
+            "status": "in_progress"
+        });
+
+        assert!(convert_update(params).is_empty());
+    }
+
+    #[test]
+    fn test_convert_update_unknown_type() {
+        let params = serde_json::json!({
+            "type": "unknown_event",
+            "data": "something"
+        });
+
+        let events = convert_update(params);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_convert_update_nested_message_chunk() {
+        let update = json!({
+            "sessionUpdate": "agent_message_chunk",
+            "content": {"type": "text", "text": "Hello"}
+        });
+        assert_eq!(
+            convert_update(update),
+            vec![AgentEvent::MessageChunk("Hello".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_convert_update_plan() {
+        let update = json!({
+            "sessionUpdate": "plan",
+            "entries": [
+                {"content": "Read code", "priority": "high", "status": "completed"},
+                {"content": "Fix bug", "priority": "high", "status": "in_progress"},
+                {"content": "Run tests", "priority": "medium", "status": "pending"}
+            ]
+        });
+        assert_eq!(
+            convert_update(update),
+            vec![AgentEvent::Thought(
+                "Plan:\n[x] Read code\n[~] Fix bug\n[ ] Run tests".to_string()
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handler_file_system() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::write(root.join("notes.txt"), "one\ntwo\nthree\n").unwrap();
+        let (tx, mut rx) = mpsc::channel(8);
+        let mut handler = Handler {
+            root: root.clone(),
+            permissions: ToolPermissions {
+                mode: Some(PermissionMode::Edit),
+                ..ToolPermissions::default()
+            },
+            tx,
+        };
+
+        let result = handler
+            .on_request(
+                "fs/read_text_file",
+                json!({"sessionId": "s", "path": root.join("notes.txt"), "line": 2, "limit": 1}),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result["content"], "two");
+
+        handler
+            .on_request(
+                "fs/write_text_file",
+                json!({"sessionId": "s", "path": root.join("out/new.txt"), "content": "hi"}),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(root.join("out/new.txt")).unwrap(),
+            "hi"
+        );
+        assert_eq!(
+            rx.recv().await.unwrap().unwrap(),
+            AgentEvent::FileChanged {
+                path: "out/new.txt".to_string(),
+                kind: FileChangeKind::Created,
+            }
+        );
+
+        let outside = handler
+            .on_request(
+                "fs/read_text_file",
+                json!({"sessionId": "s", "path": "/etc/hostname"}),
+            )
+            .await;
+        assert!(outside.is_err());
+
+        handler.permissions.mode = Some(PermissionMode::ReadOnly);
+        let denied = handler
+            .on_request(
+                "fs/write_text_file",
+                json!({"sessionId": "s", "path": root.join("x.txt"), "content": "x"}),
+            )
+            .await;
+        assert!(denied.is_err());
+        assert!(!root.join("x.txt").exists());
+    }
+}
@@ -0,0 +1,482 @@
+//! Anthropic adapter calling the Messages API over HTTP.
+//!
+//! Suited to agents that only need to read and answer (e.g. reviewers),
+//! where launching the full Claude Code CLI is unnecessary.
+
+use super::openai_adapter::next_sse_data;
+use crate::agents::base::read_image_base64;
+use crate::agents::base::Agent;
+use crate::agents::base::AgentError;
+use crate::agents::base::AgentEvent;
+use crate::agents::base::Attachment;
+use crate::agents::base::ExecutionContext;
+use crate::agents::base::Requirements;
+use crate::agents::permissions::ToolPermissions;
+use crate::agents::runtime::RuntimeOptions;
+use async_trait::async_trait;
+use pk_protocol::process_models::TokenUsage;
+use serde::Deserialize;
+use std::pin::Pin;
+use std::time::Duration;
+use std::time::Instant;
+use tokio_stream::Stream;
+use tokio_stream::StreamExt;
+
+/// Endpoint used when `base-url` is not set.
+const DEFAULT_BASE_URL: &str = "https://api.anthropic.com";
+
+/// Variable holding the API key when `api-key-env` is not set.
+const DEFAULT_API_KEY_ENV: &str = "ANTHROPIC_API_KEY";
+
+/// Messages API version sent with every request.
+const API_VERSION: &str = "2023-06-01";
+
+/// Upper bound on the length of one response.
+const MAX_TOKENS: u32 = 8192;
+
+/// How long `check_availability` waits for the server.
+const AVAILABILITY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Anthropic adapter streaming Messages API responses over SSE.
+pub struct AnthropicAdapter {
+    #[allow(dead_code)]
+    name: String,
+    model: String,
+    system_prompt: String,
+    base_url: String,
+    api_key_env: String,
+    client: reqwest::Client,
+}
+
+impl AnthropicAdapter {
+    /// Create a new Anthropic adapter for the public API.
+    pub fn new(name: String, model: String, system_prompt: String) -> Result<Self, AgentError> {
+        Ok(Self {
+            name,
+            model,
+            system_prompt,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            api_key_env: DEFAULT_API_KEY_ENV.to_string(),
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// Point the adapter at another server.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - API root without the version, e.g. `http://localhost:8080`
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Read the API key from another environment variable.
+    pub fn with_api_key_env(mut self, api_key_env: String) -> Self {
+        self.api_key_env = api_key_env;
+        self
+    }
+
+    /// Restrict the permission mode of this agent.
+    ///
+    /// The adapter exposes no tools to the model, so every permission mode
+    /// is satisfied; tool lists have nothing to filter.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::Unsupported` for tool lists or permission rules.
+    pub fn with_permissions(self, permissions: ToolPermissions) -> Result<Self, AgentError> {
+        permissions.reject_tool_lists("Anthropic")?;
+        permissions.reject_rules("Anthropic")?;
+        Ok(self)
+    }
+
+    /// Apply runtime settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::Unsupported` for `max-turns` and for settings
+    /// that only apply to a process (`env`, `working-dir`, `extra-args`).
+    pub fn with_runtime(self, runtime: RuntimeOptions) -> Result<Self, AgentError> {
+        runtime.reject_max_turns("Anthropic")?;
+        runtime.reject_process_options("Anthropic")?;
+        Ok(self)
+    }
+
+    /// Read the API key from the environment.
+    fn api_key(&self) -> Result<String, AgentError> {
+        match std::env::var(&self.api_key_env) {
+            Ok(key) if !key.is_empty() => Ok(key),
+            _ => Err(AgentError::NotAvailable(format!(
+                "environment variable {} is not set",
+                self.api_key_env
+            ))),
+        }
+    }
+
+    /// Build the JSON body of a streaming Messages request.
+    ///
+    /// # Arguments
+    ///
+    /// * `instruction` - The step instruction
+    /// * `attachments` - Content blocks placed before the instruction
+    fn request_body(
+        &self,
+        instruction: &str,
+        attachments: Vec<serde_json::Value>,
+    ) -> serde_json::Value {
+        let mut content = attachments;
+        content.push(serde_json::json!({ "type": "text", "text": instruction }));
+
+        let mut body = serde_json::json!({
+            "model": self.model,
+            "max_tokens": MAX_TOKENS,
+            "messages": [{ "role": "user", "content": content }],
+            "stream": true,
+        });
+        if !self.system_prompt.trim().is_empty() {
+            body["system"] = serde_json::Value::String(self.system_prompt.clone());
+        }
+        body
+    }
+}
+
+/// Convert an attachment to a Messages API content block.
+///
+/// Images are read from disk and sent inline; files become text documents.
+async fn content_block(attachment: &Attachment) -> Result<serde_json::Value, AgentError> {
+    match attachment {
+        Attachment::Image { path, mime_type } => Ok(serde_json::json!({
+            "type": "image",
+            "source": {
+                "type": "base64",
+                "media_type": mime_type,
+                "data": read_image_base64(path).await?,
+            },
+        })),
+        Attachment::File { path, content } => Ok(serde_json::json!({
+            "type": "document",
+            "title": path,
+            "source": {
+                "type": "text",
+                "media_type": "text/plain",
+                "data": content,
+            },
+        })),
+    }
+}
+
+#[async_trait]
+impl Agent for AnthropicAdapter {
+    async fn check_availability(&self) -> bool {
+        // The server is available if it answers the model listing
+        let Ok(api_key) = self.api_key() else {
+            return false;
+        };
+        let request = self
+            .client
+            .get(format!("{}/v1/models", self.base_url))
+            .header("x-api-key", api_key)
+            .header("anthropic-version", API_VERSION)
+            .timeout(AVAILABILITY_TIMEOUT);
+
+        match request.send().await {
+            Ok(response) => response.status().is_success(),
+            Err(_) => false,
+        }
+    }
+
+    fn requirements(&self) -> Requirements {
+        Requirements {
+            binary: None,
+            env_vars: vec![self.api_key_env.clone()],
+        }
+    }
+
+    async fn execute(
+        &self,
+        context: &ExecutionContext,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<AgentEvent, AgentError>> + Send>>, AgentError>
+    {
+        // 1. Build the request
+        let mut attachments = Vec::new();
+        for attachment in &context.attachments {
+            attachments.push(content_block(attachment).await?);
+        }
+        let request = self
+            .client
+            .post(format!("{}/v1/messages", self.base_url))
+            .header("x-api-key", self.api_key()?)
+            .header("anthropic-version", API_VERSION)
+            .json(&self.request_body(&context.instruction, attachments));
+
+        // 2. Send it and check the status
+        let started = Instant::now();
+        let response = request
+            .send()
+            .await
+            .map_err(|e| AgentError::ApiError(format!("Request failed: {}", e)))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AgentError::ApiError(format!("HTTP {}: {}", status, body)));
+        }
+
+        // 3. Convert SSE events to AgentEvents
+        let events_stream = async_stream::stream! {
+            let mut bytes = response.bytes_stream();
+            let mut buffer = Vec::new();
+            let mut usage = TokenUsage::default();
+
+            while let Some(chunk) = bytes.next().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        yield Err(AgentError::ApiError(format!("Stream interrupted: {}", e)));
+                        return;
+                    }
+                };
+                buffer.extend_from_slice(&chunk);
+
+                while let Some(data) = next_sse_data(&mut buffer) {
+                    let event = match serde_json::from_str::<StreamEvent>(&data) {
+                        Ok(event) => event,
+                        Err(e) => {
+                            yield Err(AgentError::StreamParseError(format!(
+                                "Failed to parse Messages stream event: {} (data: {})",
+                                e, data
+                            )));
+                            continue;
+                        }
+                    };
+                    for result in convert_stream_event(event, &mut usage, started) {
+                        let done = matches!(result, Ok(AgentEvent::Completed) | Err(_));
+                        yield result;
+                        if done {
+                            return;
+                        }
+                    }
+                }
+            }
+
+            yield Err(AgentError::ApiError(
+                "Stream ended before message_stop".to_string(),
+            ));
+        };
+
+        Ok(Box::pin(events_stream))
+    }
+}
+
+/// Server-sent event of a streaming Messages response.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamEvent {
+    MessageStart { message: StreamMessage },
+    ContentBlockStart {},
+    ContentBlockDelta { delta: ContentDelta },
+    ContentBlockStop {},
+    MessageDelta { usage: Option<StreamUsage> },
+    MessageStop,
+    Ping,
+    Error { error: StreamError },
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamMessage {
+    usage: Option<StreamUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentDelta {
+    TextDelta {
+        text: String,
+    },
+    ThinkingDelta {
+        thinking: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StreamUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: u64,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamError {
+    #[serde(rename = "type")]
+    kind: String,
+    message: String,
+}
+
+/// Convert a stream event to AgentEvents, accumulating token usage.
+///
+/// `message_stop` reports the usage and completes; an `error` event fails.
+fn convert_stream_event(
+    event: StreamEvent,
+    usage: &mut TokenUsage,
+    started: Instant,
+) -> Vec<Result<AgentEvent, AgentError>> {
+    match event {
+        StreamEvent::MessageStart { message } => {
+            if let Some(reported) = message.usage {
+                usage.input_tokens = reported.input_tokens
+                    + reported.cache_creation_input_tokens
+                    + reported.cache_read_input_tokens;
+                usage.output_tokens = reported.output_tokens;
+            }
+            Vec::new()
+        }
+        StreamEvent::ContentBlockDelta { delta } => match delta {
+            ContentDelta::TextDelta { text } if !text.is_empty() => {
+                vec![Ok(AgentEvent::MessageChunk(text))]
+            }
+            ContentDelta::ThinkingDelta { thinking } if !thinking.is_empty() => {
+                vec![Ok(AgentEvent::Thought(thinking))]
+            }
+            _ => Vec::new(),
+        },
+        StreamEvent::MessageDelta { usage: reported } => {
+            // Output tokens in message_delta are cumulative
+            if let Some(reported) = reported {
+                usage.output_tokens = reported.output_tokens;
+            }
+            Vec::new()
+        }
+        StreamEvent::MessageStop => {
+            usage.duration_ms = Some(started.elapsed().as_millis() as u64);
+            vec![Ok(AgentEvent::Usage(*usage)), Ok(AgentEvent::Completed)]
+        }
+        StreamEvent::Error { error } => vec![Err(AgentError::ApiError(format!(
+            "{}: {}",
+            error.kind, error.message
+        )))],
+        StreamEvent::ContentBlockStart {}
+        | StreamEvent::ContentBlockStop {}
+        | StreamEvent::Ping => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter() -> AnthropicAdapter {
+        AnthropicAdapter::new(
+            "reviewer".to_string(),
+            "claude-sonnet-4-5".to_string(),
+            "You review code.".to_string(),
+        )
+        .unwrap()
+    }
+
+    fn event(json: &str) -> StreamEvent {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_request_body() {
+        let body =
+            adapter().request_body("Review this", vec![serde_json::json!({"type": "document"})]);
+        assert_eq!(body["system"], "You review code.");
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["max_tokens"], MAX_TOKENS);
+        let content = &body["messages"][0]["content"];
+        assert_eq!(content[0]["type"], "document");
+        assert_eq!(content[1]["text"], "Review this");
+    }
+
+    #[tokio::test]
+    async fn test_content_block() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("diagram.png");
+        std::fs::write(&image, [0x89, b'P', b'N', b'G']).unwrap();
+
+        let block = content_block(&Attachment::Image {
+            path: image.to_string_lossy().to_string(),
+            mime_type: "image/png".to_string(),
+        })
+        .await
+        .unwrap();
+        assert_eq!(block["source"]["media_type"], "image/png");
+        assert_eq!(block["source"]["data"], "iVBORw==");
+
+        let block = content_block(&Attachment::File {
+            path: "src/lib.rs".to_string(),
+            content: "fn main() {}".to_string(),
+        })
+        .await
+        .unwrap();
+        assert_eq!(block["title"], "src/lib.rs");
+        assert_eq!(block["source"]["data"], "fn main() {}");
+    }
+
+    #[test]
+    fn test_convert_stream_events() {
+        let started = Instant::now();
+        let mut usage = TokenUsage::default();
+
+        let start = event(
+            r#"{"type":"message_start","message":{"id":"msg_1","usage":{"input_tokens":20,"cache_read_input_tokens":5,"output_tokens":1}}}"#,
+        );
+        assert!(convert_stream_event(start, &mut usage, started).is_empty());
+        assert_eq!(usage.input_tokens, 25);
+
+        let thinking = event(
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"Check tests"}}"#,
+        );
+        assert!(matches!(
+            &convert_stream_event(thinking, &mut usage, started)[0],
+            Ok(AgentEvent::Thought(t)) if t == "Check tests"
+        ));
+
+        let text = event(
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"text_delta","text":"LGTM"}}"#,
+        );
+        assert!(matches!(
+            &convert_stream_event(text, &mut usage, started)[0],
+            Ok(AgentEvent::MessageChunk(t)) if t == "LGTM"
+        ));
+
+        let delta = event(
+            r#"{"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":42}}"#,
+        );
+        assert!(convert_stream_event(delta, &mut usage, started).is_empty());
+
+        let events = convert_stream_event(event(r#"{"type":"message_stop"}"#), &mut usage, started);
+        assert!(matches!(
+            &events[0],
+            Ok(AgentEvent::Usage(u)) if u.input_tokens == 25 && u.output_tokens == 42
+        ));
+        assert!(matches!(events[1], Ok(AgentEvent::Completed)));
+    }
+
+    #[test]
+    fn test_convert_error_event() {
+        let error =
+            event(r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#);
+        assert!(matches!(
+            &convert_stream_event(error, &mut TokenUsage::default(), Instant::now())[0],
+            Err(AgentError::ApiError(msg)) if msg == "overloaded_error: Overloaded"
+        ));
+    }
+
+    #[test]
+    fn test_missing_api_key() {
+        let adapter = adapter().with_api_key_env("PK_TEST_UNSET_ANTHROPIC_KEY".to_string());
+        assert!(matches!(
+            adapter.api_key(),
+            Err(AgentError::NotAvailable(_))
+        ));
+    }
+}
@@ -4,16 +4,23 @@ use crate::agents::base::Agent;
 use crate::agents::base::AgentError;
 use crate::agents::base::AgentEvent;
 use crate::agents::base::ExecutionContext;
-use crate::agents::cli_executor::CliExecutor;
+use crate::agents::base::Requirements;
+use crate::agents::cli_executor::CliCommand;
+use crate::agents::cli_executor::CliOutput;
+use crate::agents::permission_bridge;
+use crate::agents::permission_bridge::PermissionBridge;
+use crate::agents::permissions::ToolPermissions;
+use crate::agents::runtime::RuntimeOptions;
 use async_trait::async_trait;
+use pk_protocol::agent_models::PermissionMode;
+use pk_protocol::process_models::FileChangeKind;
+use pk_protocol::process_models::TokenUsage;
 use serde::Deserialize;
 use serde::Serialize;
-use std::collections::HashMap;
 use std::pin::Pin;
 use std::process::Stdio;
-use std::sync::Arc;
-use std::sync::Mutex;
 use tokio::process::Command;
+use tokio::sync::mpsc;
 use tokio_stream::Stream;
 use tokio_stream::StreamExt;
 
@@ -26,10 +33,16 @@ pub struct ClaudeAdapter {
     name: String,
     model: String,
     system_prompt: String,
-    /// Session mapping: project_id -> session_id
-    session_mapping: Arc<Mutex<HashMap<String, String>>>,
+    permissions: ToolPermissions,
+    runtime: RuntimeOptions,
 }
 
+/// Tools enabled when the agent does not configure `allowed-tools`.
+const DEFAULT_TOOLS: &str = "Read,Write,Edit,MultiEdit,Bash,Glob,Grep,LS,WebFetch,WebSearch";
+
+/// Tools that modify files.
+const EDIT_TOOLS: [&str; 4] = ["Write", "Edit", "MultiEdit", "NotebookEdit"];
+
 impl ClaudeAdapter {
     /// Create a new Claude adapter.
     ///
@@ -43,45 +56,174 @@ impl ClaudeAdapter {
             name,
             model,
             system_prompt,
-            session_mapping: Arc::new(Mutex::new(HashMap::new())),
+            permissions: ToolPermissions::default(),
+            runtime: RuntimeOptions::default(),
         })
     }
 
-    /// Extract project ID from project path.
+    /// Restrict the tools and permission mode of this agent.
+    ///
+    /// Every setting maps onto a native Claude CLI flag:
+    /// - `read-only` → `--permission-mode plan` with edit tools and `Bash` disallowed
+    /// - `edit` → `--permission-mode acceptEdits` with `Bash` disallowed
+    /// - `full` (default) → `--permission-mode bypassPermissions`
+    /// - `allowed-tools` / `disallowed-tools` → `--allowed-tools` / `--disallowed-tools`
     ///
-    /// Uses the last component of the path as the project ID.
-    fn extract_project_id(project_path: &str) -> String {
-        std::path::Path::new(project_path)
-            .file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or(project_path)
-            .to_string()
+    /// With `permission-rules`, Claude runs in its `default` mode and asks
+    /// the `PermissionBridge` (`--permission-prompt-tool`) before every tool
+    /// call not pre-approved by `allowed-tools`; the mode above still
+    /// applies through the disallowed tools and `ToolPermissions::decide`.
+    pub fn with_permissions(mut self, permissions: ToolPermissions) -> Result<Self, AgentError> {
+        self.permissions = permissions;
+        Ok(self)
+    }
+
+    /// Set how the Claude CLI is launched.
+    ///
+    /// `max-turns` maps to `--max-turns`; environment, working directory and
+    /// extra arguments apply to the `claude` process.
+    pub fn with_runtime(mut self, runtime: RuntimeOptions) -> Result<Self, AgentError> {
+        self.runtime = runtime;
+        Ok(self)
+    }
+
+    /// Whether Claude's permission checks go through the bridge.
+    fn asks_bridge(&self) -> bool {
+        !self.permissions.rules.is_empty()
+    }
+
+    /// Build the permission and tool arguments for one invocation.
+    fn permission_args(&self, is_initial_prompt: bool) -> Vec<String> {
+        let mode = self.permissions.mode.unwrap_or(PermissionMode::Full);
+        let permission_mode = match mode {
+            // Rules decide every check, so Claude must ask for each one
+            _ if self.asks_bridge() => "default",
+            PermissionMode::ReadOnly => "plan",
+            PermissionMode::Edit => "acceptEdits",
+            PermissionMode::Full => "bypassPermissions",
+        };
+
+        let mut disallowed: Vec<String> = self.permissions.disallowed_tools.clone();
+        match mode {
+            PermissionMode::ReadOnly => {
+                disallowed.extend(EDIT_TOOLS.iter().map(|t| t.to_string()));
+                disallowed.push("Bash".to_string());
+            }
+            PermissionMode::Edit => disallowed.push("Bash".to_string()),
+            PermissionMode::Full => {}
+        }
+
+        // TodoWrite only after the initial prompt
+        if is_initial_prompt && self.permissions.allowed_tools.is_empty() {
+            disallowed.push("TodoWrite".to_string());
+        }
+        let allowed = if !self.permissions.allowed_tools.is_empty() {
+            Some(self.permissions.allowed_tools.join(","))
+        } else if self.asks_bridge() {
+            // Pre-approving the default set would skip the rules
+            None
+        } else if is_initial_prompt {
+            Some(DEFAULT_TOOLS.to_string())
+        } else {
+            Some(format!("{},TodoWrite", DEFAULT_TOOLS))
+        };
+
+        let mut args = vec!["--permission-mode".to_string(), permission_mode.to_string()];
+        if let Some(allowed) = allowed {
+            args.push("--allowed-tools".to_string());
+            args.push(allowed);
+        }
+        if !disallowed.is_empty() {
+            disallowed.sort();
+            disallowed.dedup();
+            args.push("--disallowed-tools".to_string());
+            args.push(disallowed.join(","));
+        }
+        args
+    }
+
+    /// Build the `--max-turns` flag and extra arguments from the runtime
+    /// options.
+    fn runtime_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(max_turns) = self.runtime.max_turns {
+            args.push("--max-turns".to_string());
+            args.push(max_turns.to_string());
+        }
+        args.extend(self.runtime.extra_args.iter().cloned());
+        args
+    }
+
+    /// Build the CLI arguments for one invocation.
+    ///
+    /// The instruction is not among them: it is written to stdin, which
+    /// keeps large prompts clear of argument length limits and out of `ps`.
+    /// Print mode (`-p`) makes Claude read that prompt and stream JSON Lines.
+    /// `mcp_config_path` points Claude at the permission bridge.
+    fn command_args(
+        &self,
+        context: &ExecutionContext,
+        settings_path: String,
+        mcp_config_path: Option<String>,
+    ) -> Vec<String> {
+        let mut args = vec![
+            "-p".to_string(),
+            "--output-format".to_string(),
+            "stream-json".to_string(),
+            "--verbose".to_string(),
+            "--settings".to_string(),
+            settings_path,
+            "--model".to_string(),
+            self.model.clone(),
+        ];
+
+        // Permission mode and tool filtering
+        args.extend(self.permission_args(context.is_initial_prompt));
+        if let Some(path) = mcp_config_path {
+            args.push("--mcp-config".to_string());
+            args.push(path);
+            args.push("--permission-prompt-tool".to_string());
+            args.push(permission_bridge::PROMPT_TOOL.to_string());
+        }
+
+        // Session resumption
+        if let Some(sid) = &context.session_id {
+            args.push("--resume".to_string());
+            args.push(sid.clone());
+        }
+
+        // Turn limit and user-supplied arguments
+        args.extend(self.runtime_args());
+        args
     }
 
     /// Create a temporary settings file for the Claude CLI.
     ///
     /// The settings file contains the system prompt.
     fn create_settings_file(&self) -> Result<tempfile::NamedTempFile, AgentError> {
-        use std::io::Write;
-
         let settings = serde_json::json!({
             "customSystemPrompt": self.system_prompt
         });
+        write_json_file(&settings)
+    }
+}
 
-        let mut temp_file = tempfile::NamedTempFile::new().map_err(|e| {
-            AgentError::ExecutionError(format!("Failed to create temp file: {}", e))
-        })?;
+/// Write a JSON value to a temporary file for the Claude CLI to read.
+fn write_json_file(value: &serde_json::Value) -> Result<tempfile::NamedTempFile, AgentError> {
+    use std::io::Write;
 
-        serde_json::to_writer(&mut temp_file, &settings)
-            .map_err(|e| AgentError::ExecutionError(format!("Failed to write settings: {}", e)))?;
+    let mut temp_file = tempfile::NamedTempFile::new()
+        .map_err(|e| AgentError::ExecutionError(format!("Failed to create temp file: {}", e)))?;
 
-        // Flush to ensure data is written
-        temp_file
-            .flush()
-            .map_err(|e| AgentError::ExecutionError(format!("Failed to flush settings: {}", e)))?;
+    serde_json::to_writer(&mut temp_file, value)
+        .map_err(|e| AgentError::ExecutionError(format!("Failed to write settings: {}", e)))?;
 
-        Ok(temp_file)
-    }
+    // Flush to ensure data is written
+    temp_file
+        .flush()
+        .map_err(|e| AgentError::ExecutionError(format!("Failed to flush settings: {}", e)))?;
+
+    Ok(temp_file)
 }
 
 #[async_trait]
@@ -100,6 +242,13 @@ impl Agent for ClaudeAdapter {
         }
     }
 
+    fn requirements(&self) -> Requirements {
+        Requirements {
+            binary: Some("claude".to_string()),
+            env_vars: vec![],
+        }
+    }
+
     async fn execute(
         &self,
         context: &ExecutionContext,
@@ -113,88 +262,144 @@ impl Agent for ClaudeAdapter {
             .ok_or_else(|| AgentError::ExecutionError("Invalid settings path".to_string()))?
             .to_string();
 
-        // 2. Get session ID for this project
-        let project_id = Self::extract_project_id(&context.project_path);
-        let session_id = {
-            let mapping = self.session_mapping.lock().unwrap();
-            mapping.get(&project_id).cloned()
-        };
-
-        // 3. Build command arguments
-        let mut args = vec![
-            "--settings".to_string(),
-            settings_path,
-            "--model".to_string(),
-            self.model.clone(),
-            "--permission-mode".to_string(),
-            "bypassPermissions".to_string(),
-            "--continue-conversation".to_string(),
-        ];
-
-        // Tool filtering based on is_initial_prompt
-        if context.is_initial_prompt {
-            // Initial prompt: exclude TodoWrite
-            args.push("--allowed-tools".to_string());
-            args.push("Read,Write,Edit,MultiEdit,Bash,Glob,Grep,LS,WebFetch,WebSearch".to_string());
-            args.push("--disallowed-tools".to_string());
-            args.push("TodoWrite".to_string());
+        // 2. Start the permission bridge when rules answer Claude's checks
+        let (bridge, mcp_config_file, mut bridge_events) = if self.asks_bridge() {
+            let (bridge, events) =
+                PermissionBridge::start(self.permissions.clone(), describe_tool).await?;
+            let config_file = write_json_file(&bridge.mcp_config()?)?;
+            (Some(bridge), Some(config_file), Some(events))
         } else {
-            // Subsequent prompts: include TodoWrite
-            args.push("--allowed-tools".to_string());
-            args.push(
-                "Read,Write,Edit,MultiEdit,Bash,Glob,Grep,LS,WebFetch,WebSearch,TodoWrite"
+            (None, None, None)
+        };
+        let mcp_config_path = match &mcp_config_file {
+            Some(file) => Some(
+                file.path()
+                    .to_str()
+                    .ok_or_else(|| {
+                        AgentError::ExecutionError("Invalid MCP config path".to_string())
+                    })?
                     .to_string(),
-            );
-        }
+            ),
+            None => None,
+        };
 
-        // Session resumption
-        if let Some(sid) = &session_id {
-            args.push("--resume-session-id".to_string());
-            args.push(sid.clone());
-        }
+        // 3. Spawn the CLI with the prompt on stdin; stray non-JSON lines
+        //    become warnings
+        let process = CliCommand::new(
+            "claude".to_string(),
+            self.command_args(context, settings_path, mcp_config_path),
+            self.runtime.resolve_dir(&context.project_path),
+        )
+        .with_env(self.runtime.env.clone())
+        .with_input(Some(prompt_text(context)))
+        .with_tolerant_parsing(true)
+        .spawn()?;
 
-        // Prompt
-        args.push("--prompt".to_string());
-        args.push(context.instruction.clone());
-
-        // 4. Execute CLI using CliExecutor
-        let json_stream =
-            CliExecutor::execute("claude".to_string(), args, context.project_path.clone());
-
-        // 5. Convert JSON stream to AgentEvents
-        let session_mapping = self.session_mapping.clone();
-        let project_id_clone = project_id.clone();
-
-        let events_stream = json_stream
-            .then(move |json_result| {
-                let session_mapping = session_mapping.clone();
-                let project_id = project_id_clone.clone();
-
-                async move {
-                    match json_result {
-                        Ok(json_value) => {
-                            // Parse as ClaudeMessage
-                            match serde_json::from_value::<ClaudeMessage>(json_value.clone()) {
-                                Ok(msg) => {
-                                    convert_claude_message(msg, session_mapping, project_id).await
+        // 4. Convert JSON stream to AgentEvents, interleaved with the
+        //    bridge's permission requests
+        let events_stream = async_stream::stream! {
+            // Claude reads these files after startup, and asks the bridge
+            // while it runs; keep them until the process is done
+            let _settings_file = settings_file;
+            let _mcp_config_file = mcp_config_file;
+            let _bridge = bridge;
+            let mut process = process;
+            loop {
+                let output = tokio::select! {
+                    output = process.next() => output,
+                    Some(event) = next_bridge_event(&mut bridge_events) => {
+                        yield Ok(event);
+                        continue;
+                    }
+                };
+                let Some(output) = output else { break };
+                match output {
+                    Ok(CliOutput::Json(json_value)) => {
+                        // Parse as ClaudeMessage
+                        match serde_json::from_value::<ClaudeMessage>(json_value.clone()) {
+                            Ok(msg) => {
+                                for event in convert_claude_message(msg) {
+                                    yield event;
                                 }
-                                Err(e) => Some(Err(AgentError::StreamParseError(format!(
-                                    "Failed to parse ClaudeMessage: {} (json: {})",
-                                    e, json_value
-                                )))),
                             }
+                            Err(e) => yield Err(AgentError::StreamParseError(format!(
+                                "Failed to parse ClaudeMessage: {} (json: {})",
+                                e, json_value
+                            ))),
                         }
-                        Err(e) => Some(Err(e)),
                     }
+                    Ok(CliOutput::Text(line)) => {
+                        yield Ok(AgentEvent::Warning(format!("Unexpected output: {}", line)));
+                    }
+                    Ok(CliOutput::Stderr(line)) => yield Ok(AgentEvent::Warning(line)),
+                    Err(e) => yield Err(e),
                 }
-            })
-            .filter_map(|opt| opt);
+            }
+        };
 
         Ok(Box::pin(events_stream))
     }
 }
 
-/// Claude CLI message types (JSON Lines output).
+/// Wait for the next event of the permission bridge, if there is one.
+async fn next_bridge_event(events: &mut Option<mpsc::Receiver<AgentEvent>>) -> Option<AgentEvent> {
+    match events {
+        Some(events) => events.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Describe a Claude tool call as a permission rule tool kind and a title.
+///
+/// The kinds are those of ACP agents, so the same `permission-rules` work
+/// for both.
+fn describe_tool(tool_name: &str, input: &serde_json::Value) -> (String, String) {
+    let kind = match tool_name {
+        "Read" | "NotebookRead" => "read",
+        "Glob" | "Grep" | "LS" | "WebSearch" => "search",
+        "Write" | "Edit" | "MultiEdit" | "NotebookEdit" => "edit",
+        "Bash" | "BashOutput" | "KillShell" => "execute",
+        "WebFetch" => "fetch",
+        "TodoWrite" | "Task" => "think",
+        _ => "other",
+    };
+    let detail = [
+        "command",
+        "file_path",
+        "notebook_path",
+        "path",
+        "pattern",
+        "url",
+    ]
+    .iter()
+    .find_map(|key| input.get(key).and_then(|v| v.as_str()));
+    let title = match detail {
+        Some(detail) => format!("{}: {}", tool_name, detail),
+        None => tool_name.to_string(),
+    };
+    (kind.to_string(), title)
+}
+
+/// The prompt written to Claude's stdin.
+///
+/// File attachments are inlined ahead of the instruction; images are listed
+/// by path after it, for Claude to open with its Read tool.
+fn prompt_text(context: &ExecutionContext) -> String {
+    let mut prompt = context.instruction_with_files();
+    let images = context.image_paths();
+    if !images.is_empty() {
+        prompt.push_str("\n\nAttached images:");
+        for path in images {
+            prompt.push_str(&format!("\n- {}", path));
+        }
+    }
+    prompt
+}
+
+/// Claude CLI message types (`--output-format stream-json` lines).
+///
+/// Assistant and user lines wrap an API message, whose `content` holds the
+/// blocks.
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type")]
 enum ClaudeMessage {
@@ -205,27 +410,55 @@ enum ClaudeMessage {
         model: Option<String>,
     },
     #[serde(rename = "assistant")]
-    Assistant { content: Vec<ContentBlock> },
+    Assistant {
+        message: ClaudeApiMessage<Vec<ContentBlock>>,
+    },
     #[serde(rename = "user")]
     User {
-        #[allow(dead_code)]
-        content: String,
+        message: ClaudeApiMessage<UserContent>,
     },
     #[serde(rename = "result")]
     Result {
-        session_id: Option<String>,
         #[allow(dead_code)]
+        session_id: Option<String>,
         duration_ms: Option<u64>,
-        #[allow(dead_code)]
         total_cost_usd: Option<f64>,
         #[allow(dead_code)]
         num_turns: Option<u32>,
-        #[allow(dead_code)]
         is_error: Option<bool>,
+        #[serde(default)]
+        usage: Option<ClaudeUsage>,
+        #[serde(default)]
+        result: Option<String>,
+        #[serde(default)]
+        permission_denials: Vec<PermissionDenial>,
     },
+    /// Line types that carry nothing to report.
+    #[serde(other)]
+    Other,
 }
 
-/// Content blocks within an assistant message.
+/// The API message wrapped by an assistant or user line.
+#[derive(Debug, Deserialize)]
+struct ClaudeApiMessage<C> {
+    content: C,
+}
+
+/// A tool use Claude refused under its permission mode.
+#[derive(Debug, Deserialize)]
+struct PermissionDenial {
+    tool_name: String,
+}
+
+/// Content of a user message: plain text echoes or tool results.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum UserContent {
+    Text(#[allow(dead_code)] String),
+    Blocks(Vec<ContentBlock>),
+}
+
+/// Content blocks within an assistant or user message.
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(tag = "type")]
 enum ContentBlock {
@@ -240,69 +473,164 @@ enum ContentBlock {
     #[serde(rename = "tool_result")]
     ToolResult {
         tool_use_id: String,
-        content: String,
+        content: serde_json::Value,
+        #[serde(default)]
+        is_error: bool,
     },
+    #[serde(rename = "thinking")]
+    Thinking { thinking: String },
+    /// Block types that are not shown.
+    #[serde(other)]
+    Other,
+}
+
+/// Token counts reported in the final `result` message.
+#[derive(Debug, Deserialize)]
+struct ClaudeUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
 }
 
-/// Convert Claude CLI message to AgentEvent.
-async fn convert_claude_message(
-    msg: ClaudeMessage,
-    session_mapping: Arc<Mutex<HashMap<String, String>>>,
-    project_id: String,
-) -> Option<Result<AgentEvent, AgentError>> {
+/// Convert Claude CLI message to AgentEvents.
+///
+/// A single message may carry several content blocks (text followed by a
+/// tool call, for example), so every block yields its own event.
+fn convert_claude_message(msg: ClaudeMessage) -> Vec<Result<AgentEvent, AgentError>> {
     match msg {
-        ClaudeMessage::System { session_id, .. } => {
-            // Save session ID
-            if let Some(sid) = session_id {
-                let mut mapping = session_mapping.lock().unwrap();
-                mapping.insert(project_id, sid);
+        // The init message carries the session id; nothing else is shown
+        ClaudeMessage::System { session_id, .. } => session_id
+            .map(|sid| Ok(AgentEvent::SessionStarted(sid)))
+            .into_iter()
+            .collect(),
+        ClaudeMessage::Assistant { message } => message
+            .content
+            .into_iter()
+            .flat_map(convert_content_block)
+            .map(Ok)
+            .collect(),
+        ClaudeMessage::User { message } => match message.content {
+            // Plain user messages (echoes) are not shown
+            UserContent::Text(_) => Vec::new(),
+            UserContent::Blocks(blocks) => blocks
+                .into_iter()
+                .filter(|block| matches!(block, ContentBlock::ToolResult { .. }))
+                .flat_map(convert_content_block)
+                .map(Ok)
+                .collect(),
+        },
+        ClaudeMessage::Result {
+            duration_ms,
+            total_cost_usd,
+            is_error,
+            usage,
+            result,
+            permission_denials,
+            ..
+        } => {
+            let usage = usage.unwrap_or(ClaudeUsage {
+                input_tokens: 0,
+                output_tokens: 0,
+            });
+            // Tool uses refused by the mode or the bridge end up here
+            let mut events: Vec<_> = permission_denials
+                .into_iter()
+                .map(|denial| {
+                    Ok(AgentEvent::Warning(format!(
+                        "Claude was denied permission to use {}",
+                        denial.tool_name
+                    )))
+                })
+                .collect();
+            events.push(Ok(AgentEvent::Usage(TokenUsage {
+                input_tokens: usage.input_tokens,
+                output_tokens: usage.output_tokens,
+                cost_usd: total_cost_usd,
+                duration_ms,
+            })));
+
+            // Signal completion, or surface the failure reported by the CLI
+            if is_error.unwrap_or(false) {
+                events.push(Err(AgentError::ExecutionError(result.unwrap_or_else(
+                    || "Claude reported an error without details".to_string(),
+                ))));
+            } else {
+                events.push(Ok(AgentEvent::Completed));
             }
-            // System messages are not shown to UI
-            None
+            events
         }
-        ClaudeMessage::Assistant { content } => {
-            // Process content blocks
-            for block in content {
-                match block {
-                    ContentBlock::Text { text } => {
-                        if !text.trim().is_empty() {
-                            return Some(Ok(AgentEvent::MessageChunk(text)));
-                        }
-                    }
-                    ContentBlock::ToolUse { name, input, .. } => {
-                        let tool_json = serde_json::to_string(&serde_json::json!({
-                            "name": name,
-                            "input": input
-                        }))
-                        .unwrap_or_else(|_| format!("{{\"name\":\"{}\"}}", name));
-                        return Some(Ok(AgentEvent::ToolCall(tool_json)));
-                    }
-                    ContentBlock::ToolResult { .. } => {
-                        // Tool results are not shown to UI
-                    }
-                }
+        ClaudeMessage::Other => Vec::new(),
+    }
+}
+
+/// Convert a single content block to AgentEvents.
+fn convert_content_block(block: ContentBlock) -> Vec<AgentEvent> {
+    match block {
+        ContentBlock::Text { text } => {
+            if text.trim().is_empty() {
+                Vec::new()
+            } else {
+                vec![AgentEvent::MessageChunk(text)]
             }
-            None
-        }
-        ClaudeMessage::User { .. } => {
-            // User messages (echoes) are not shown
-            None
         }
-        ClaudeMessage::Result { session_id, .. } => {
-            // Save session ID if present
-            if let Some(sid) = session_id {
-                let mut mapping = session_mapping.lock().unwrap();
-                mapping.insert(project_id, sid);
-            }
-            // Signal completion
-            Some(Ok(AgentEvent::Completed))
+        ContentBlock::ToolUse { id, name, input } => {
+            let file_change = edited_file_path(&name, &input).map(|path| AgentEvent::FileChanged {
+                path,
+                kind: FileChangeKind::Modified,
+            });
+            let mut events = vec![AgentEvent::ToolCall {
+                id: Some(id),
+                name,
+                arguments: input,
+            }];
+            events.extend(file_change);
+            events
         }
+        ContentBlock::ToolResult {
+            tool_use_id,
+            content,
+            is_error,
+        } => vec![AgentEvent::ToolResult {
+            id: Some(tool_use_id),
+            output: tool_result_text(&content),
+            is_error,
+        }],
+        ContentBlock::Thinking { thinking } => vec![AgentEvent::Thought(thinking)],
+        ContentBlock::Other => Vec::new(),
+    }
+}
+
+/// Return the file a file-editing tool call writes to, if any.
+fn edited_file_path(tool_name: &str, input: &serde_json::Value) -> Option<String> {
+    let key = match tool_name {
+        "Write" | "Edit" | "MultiEdit" => "file_path",
+        "NotebookEdit" => "notebook_path",
+        _ => return None,
+    };
+    input.get(key).and_then(|p| p.as_str()).map(str::to_string)
+}
+
+/// Flatten a tool result's content (a string or a list of text blocks).
+fn tool_result_text(content: &serde_json::Value) -> String {
+    match content {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        other => other.to_string(),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agents::base::Attachment;
+    use pk_protocol::agent_models::PermissionAction;
+    use pk_protocol::agent_models::PermissionRule;
+    use std::collections::HashMap;
 
     #[test]
     fn test_claude_adapter_new() {
@@ -315,13 +643,245 @@ mod tests {
     }
 
     #[test]
-    fn test_extract_project_id() {
+    fn test_prompt_text_delivers_attachments() {
+        let context = ExecutionContext::new("Compare".to_string()).with_attachments(vec![
+            Attachment::File {
+                path: "spec.md".to_string(),
+                content: "Spec\n".to_string(),
+            },
+            Attachment::Image {
+                path: "/tmp/before.png".to_string(),
+                mime_type: "image/png".to_string(),
+            },
+        ]);
+
+        assert_eq!(
+            prompt_text(&context),
+            "<file path=\"spec.md\">\nSpec\n</file>\n\nCompare\n\nAttached images:\n- /tmp/before.png"
+        );
+    }
+
+    #[test]
+    fn test_command_args_keep_prompt_off_argv() {
+        let adapter = ClaudeAdapter::new(
+            "test".to_string(),
+            "claude-sonnet-4.5".to_string(),
+            "test prompt".to_string(),
+        )
+        .unwrap();
+        let context = ExecutionContext::new("Refactor the parser".to_string());
+        let args = adapter.command_args(&context, "/tmp/settings.json".to_string(), None);
+
+        assert!(!args.iter().any(|a| a.contains("Refactor the parser")));
+        assert!(!args.contains(&"--prompt".to_string()));
+        assert_eq!(
+            &args[..6],
+            [
+                "-p",
+                "--output-format",
+                "stream-json",
+                "--verbose",
+                "--settings",
+                "/tmp/settings.json"
+            ]
+        );
+
+        // A continued session resumes Claude's conversation
+        let context = context.with_session_id(Some("8f3c1a52".to_string()));
+        let args = adapter.command_args(&context, "/tmp/settings.json".to_string(), None);
+        let resume = args.iter().position(|a| a == "--resume").unwrap();
+        assert_eq!(args[resume + 1], "8f3c1a52");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_settings_file_outlives_execute() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // A stand-in `claude` that fails unless its settings file still
+        // exists once `execute` has returned
+        let bin = tempfile::tempdir().unwrap();
+        let script = bin.path().join("claude");
+        std::fs::write(
+            &script,
+            r#"#!/bin/sh
+cat > /dev/null
+while [ $# -gt 0 ]; do
+  [ "$1" = "--settings" ] && settings="$2"
+  shift
+done
+sleep 0.2
+test -f "$settings" || { echo "settings file missing" >&2; exit 1; }
+"#,
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let path = format!(
+            "{}:{}",
+            bin.path().display(),
+            std::env::var("PATH").unwrap_or_default()
+        );
+        let adapter = ClaudeAdapter::new(
+            "test".to_string(),
+            "claude-sonnet-4.5".to_string(),
+            "test prompt".to_string(),
+        )
+        .unwrap()
+        .with_runtime(RuntimeOptions {
+            env: HashMap::from([("PATH".to_string(), path)]),
+            ..RuntimeOptions::default()
+        })
+        .unwrap();
+
+        let stream = adapter
+            .execute(&ExecutionContext::new("Say hi".to_string()))
+            .await
+            .unwrap();
+        let events: Vec<_> = stream.collect().await;
+        assert!(events.iter().all(|e| e.is_ok()), "{:?}", events);
+    }
+
+    fn adapter_with(permissions: ToolPermissions) -> ClaudeAdapter {
+        ClaudeAdapter::new("test".to_string(), "claude".to_string(), String::new())
+            .unwrap()
+            .with_permissions(permissions)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_permission_args_default_keeps_full_access() {
+        let args = adapter_with(ToolPermissions::default()).permission_args(true);
+        assert_eq!(
+            args,
+            vec![
+                "--permission-mode",
+                "bypassPermissions",
+                "--allowed-tools",
+                DEFAULT_TOOLS,
+                "--disallowed-tools",
+                "TodoWrite",
+            ]
+        );
+
+        let args = adapter_with(ToolPermissions::default()).permission_args(false);
+        assert_eq!(args[3], format!("{},TodoWrite", DEFAULT_TOOLS));
+        assert!(!args.contains(&"--disallowed-tools".to_string()));
+    }
+
+    #[test]
+    fn test_permission_args_read_only() {
+        let args = adapter_with(ToolPermissions {
+            mode: Some(PermissionMode::ReadOnly),
+            allowed_tools: vec!["Read".to_string(), "Grep".to_string()],
+            disallowed_tools: vec!["WebFetch".to_string()],
+            rules: vec![],
+        })
+        .permission_args(false);
+
+        assert_eq!(
+            &args[..4],
+            ["--permission-mode", "plan", "--allowed-tools", "Read,Grep"]
+        );
+        assert_eq!(args[4], "--disallowed-tools");
+        for tool in [
+            "Bash",
+            "Edit",
+            "MultiEdit",
+            "NotebookEdit",
+            "WebFetch",
+            "Write",
+        ] {
+            assert!(
+                args[5].split(',').any(|t| t == tool),
+                "{} not disallowed",
+                tool
+            );
+        }
+    }
+
+    #[test]
+    fn test_permission_args_edit_blocks_shell() {
+        let args = adapter_with(ToolPermissions {
+            mode: Some(PermissionMode::Edit),
+            ..ToolPermissions::default()
+        })
+        .permission_args(false);
+
+        assert_eq!(args[1], "acceptEdits");
+        assert_eq!(args[4..], ["--disallowed-tools", "Bash"]);
+    }
+
+    #[test]
+    fn test_permission_rules_route_checks_through_bridge() {
+        let adapter = adapter_with(ToolPermissions {
+            mode: Some(PermissionMode::Edit),
+            rules: vec![PermissionRule {
+                tool: "execute".to_string(),
+                action: PermissionAction::Ask,
+            }],
+            ..ToolPermissions::default()
+        });
+
+        // Claude must ask for every tool, and the default set is not
+        // pre-approved
+        assert_eq!(
+            adapter.permission_args(true),
+            [
+                "--permission-mode",
+                "default",
+                "--disallowed-tools",
+                "Bash,TodoWrite"
+            ]
+        );
+
+        let context = ExecutionContext::new("Fix the bug".to_string());
+        let args = adapter.command_args(
+            &context,
+            "/tmp/settings.json".to_string(),
+            Some("/tmp/mcp.json".to_string()),
+        );
+        let config = args.iter().position(|a| a == "--mcp-config").unwrap();
+        assert_eq!(
+            args[config..config + 4],
+            [
+                "--mcp-config",
+                "/tmp/mcp.json",
+                "--permission-prompt-tool",
+                permission_bridge::PROMPT_TOOL
+            ]
+        );
+    }
+
+    #[test]
+    fn test_describe_tool() {
         assert_eq!(
-            ClaudeAdapter::extract_project_id("/path/to/project"),
-            "project"
+            describe_tool("Bash", &serde_json::json!({"command": "cargo test"})),
+            ("execute".to_string(), "Bash: cargo test".to_string())
+        );
+        assert_eq!(
+            describe_tool("MultiEdit", &serde_json::json!({"file_path": "src/lib.rs"})),
+            ("edit".to_string(), "MultiEdit: src/lib.rs".to_string())
+        );
+        assert_eq!(
+            describe_tool("mcp__github__create_issue", &serde_json::json!({})),
+            ("other".to_string(), "mcp__github__create_issue".to_string())
         );
-        assert_eq!(ClaudeAdapter::extract_project_id("/tmp/test"), "test");
-        assert_eq!(ClaudeAdapter::extract_project_id("relative/path"), "path");
+    }
+
+    #[test]
+    fn test_runtime_args() {
+        let adapter = adapter_with(ToolPermissions::default());
+        assert!(adapter.runtime_args().is_empty());
+
+        let adapter = adapter
+            .with_runtime(RuntimeOptions {
+                max_turns: Some(12),
+                extra_args: vec!["--verbose".to_string()],
+                ..RuntimeOptions::default()
+            })
+            .unwrap();
+        assert_eq!(adapter.runtime_args(), ["--max-turns", "12", "--verbose"]);
     }
 
     #[test]
@@ -361,4 +921,138 @@ mod tests {
         // Just verify it doesn't panic
         let _ = available;
     }
+
+    /// Parse one line of `claude -p --output-format stream-json --verbose`.
+    fn convert(line: &str) -> Vec<Result<AgentEvent, AgentError>> {
+        let msg: ClaudeMessage = serde_json::from_str(line).unwrap();
+        convert_claude_message(msg)
+    }
+
+    #[test]
+    fn test_convert_system_reports_session() {
+        let events = convert(
+            r#"{"type":"system","subtype":"init","cwd":"/repo","session_id":"8f3c1a52-5b0e-4a8e-9d1f-2c6b7e0a9d41","tools":["Task","Bash","Edit","Read","Write"],"mcp_servers":[],"model":"claude-sonnet-4-5-20250929","permissionMode":"bypassPermissions","slash_commands":["compact","review"],"apiKeySource":"none","output_style":"default","uuid":"0b6f6f3e-3c1d-4e0c-9a55-7d2f0d6c1e12"}"#,
+        );
+
+        assert_eq!(
+            events,
+            vec![Ok(AgentEvent::SessionStarted(
+                "8f3c1a52-5b0e-4a8e-9d1f-2c6b7e0a9d41".to_string()
+            ))]
+        );
+    }
+
+    #[test]
+    fn test_convert_assistant_emits_every_block() {
+        let events = convert(
+            r#"{"type":"assistant","message":{"id":"msg_01XyZ","type":"message","role":"assistant","model":"claude-sonnet-4-5-20250929","content":[{"type":"thinking","thinking":"The fix is a one-line edit.","signature":"EqQBCkYIBxgCKkA"},{"type":"text","text":"Let me fix that."},{"type":"tool_use","id":"toolu_01A","name":"Edit","input":{"file_path":"/repo/src/lib.rs","old_string":"a","new_string":"b"}}],"stop_reason":null,"stop_sequence":null,"usage":{"input_tokens":4,"cache_creation_input_tokens":1520,"cache_read_input_tokens":12988,"output_tokens":2,"service_tier":"standard"}},"parent_tool_use_id":null,"session_id":"8f3c1a52-5b0e-4a8e-9d1f-2c6b7e0a9d41","uuid":"5a1d9e7c-64b2-4f0e-8c3a-1e2f3a4b5c6d"}"#,
+        );
+
+        assert_eq!(events.len(), 4);
+        assert_eq!(
+            events[0],
+            Ok(AgentEvent::Thought(
+                "The fix is a one-line edit.".to_string()
+            ))
+        );
+        assert_eq!(
+            events[1],
+            Ok(AgentEvent::MessageChunk("Let me fix that.".to_string()))
+        );
+        assert!(matches!(
+            &events[2],
+            Ok(AgentEvent::ToolCall { id: Some(id), name, arguments })
+                if id == "toolu_01A" && name == "Edit" && arguments["file_path"] == "/repo/src/lib.rs"
+        ));
+        assert_eq!(
+            events[3],
+            Ok(AgentEvent::FileChanged {
+                path: "/repo/src/lib.rs".to_string(),
+                kind: FileChangeKind::Modified,
+            })
+        );
+    }
+
+    #[test]
+    fn test_convert_user_tool_result_is_correlated() {
+        let events = convert(
+            r#"{"type":"user","message":{"role":"user","content":[{"tool_use_id":"toolu_01A","type":"tool_result","content":"<tool_use_error>String to replace not found in file.</tool_use_error>","is_error":true}]},"parent_tool_use_id":null,"session_id":"8f3c1a52-5b0e-4a8e-9d1f-2c6b7e0a9d41","uuid":"c3d4e5f6-a7b8-4c9d-8e0f-1a2b3c4d5e6f"}"#,
+        );
+
+        assert_eq!(
+            events,
+            vec![Ok(AgentEvent::ToolResult {
+                id: Some("toolu_01A".to_string()),
+                output: "<tool_use_error>String to replace not found in file.</tool_use_error>"
+                    .to_string(),
+                is_error: true,
+            })]
+        );
+
+        // Tool results may also carry a list of text blocks
+        let events = convert(
+            r#"{"type":"user","message":{"role":"user","content":[{"tool_use_id":"toolu_01B","type":"tool_result","content":[{"type":"text","text":"ok"}]}]},"parent_tool_use_id":null,"session_id":"8f3c1a52-5b0e-4a8e-9d1f-2c6b7e0a9d41","uuid":"d4e5f6a7-b8c9-4d0e-9f1a-2b3c4d5e6f70"}"#,
+        );
+        assert!(matches!(
+            &events[0],
+            Ok(AgentEvent::ToolResult { output, is_error: false, .. }) if output == "ok"
+        ));
+    }
+
+    #[test]
+    fn test_convert_ignores_unknown_lines() {
+        let events = convert(
+            r#"{"type":"stream_event","event":{"type":"message_start"},"session_id":"8f3c1a52-5b0e-4a8e-9d1f-2c6b7e0a9d41","uuid":"e5f6a7b8-c9d0-4e1f-8a2b-3c4d5e6f7081"}"#,
+        );
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_convert_result_reports_usage_and_completion() {
+        let events = convert(
+            r#"{"type":"result","subtype":"success","is_error":false,"duration_ms":1500,"duration_api_ms":1320,"num_turns":3,"result":"Fixed the off-by-one in src/lib.rs.","session_id":"8f3c1a52-5b0e-4a8e-9d1f-2c6b7e0a9d41","total_cost_usd":0.12,"usage":{"input_tokens":100,"cache_creation_input_tokens":1520,"cache_read_input_tokens":12988,"output_tokens":40,"server_tool_use":{"web_search_requests":0},"service_tier":"standard"},"permission_denials":[],"uuid":"f6a7b8c9-d0e1-4f2a-9b3c-4d5e6f708192"}"#,
+        );
+
+        assert_eq!(
+            events,
+            vec![
+                Ok(AgentEvent::Usage(TokenUsage {
+                    input_tokens: 100,
+                    output_tokens: 40,
+                    cost_usd: Some(0.12),
+                    duration_ms: Some(1500),
+                })),
+                Ok(AgentEvent::Completed),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_result_with_error_fails() {
+        let events = convert(
+            r#"{"type":"result","subtype":"success","is_error":true,"duration_ms":412,"duration_api_ms":0,"num_turns":1,"result":"Credit balance too low","session_id":"8f3c1a52-5b0e-4a8e-9d1f-2c6b7e0a9d41","total_cost_usd":0,"usage":{"input_tokens":0,"output_tokens":0},"permission_denials":[],"uuid":"a7b8c9d0-e1f2-4a3b-8c4d-5e6f708192a3"}"#,
+        );
+
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], Ok(AgentEvent::Usage(_))));
+        assert!(matches!(
+            &events[1],
+            Err(AgentError::ExecutionError(msg)) if msg == "Credit balance too low"
+        ));
+    }
+
+    #[test]
+    fn test_convert_result_reports_permission_denials() {
+        let events = convert(
+            r#"{"type":"result","subtype":"success","is_error":false,"duration_ms":5210,"duration_api_ms":4980,"num_turns":2,"result":"I need permission to run that command.","session_id":"8f3c1a52-5b0e-4a8e-9d1f-2c6b7e0a9d41","total_cost_usd":0.031,"usage":{"input_tokens":12,"output_tokens":85},"permission_denials":[{"tool_name":"Bash","tool_use_id":"toolu_01C","tool_input":{"command":"rm -rf build","description":"Remove build output"}}],"uuid":"b8c9d0e1-f2a3-4b4c-9d5e-6f708192a3b4"}"#,
+        );
+
+        assert_eq!(
+            events[0],
+            Ok(AgentEvent::Warning(
+                "Claude was denied permission to use Bash".to_string()
+            ))
+        );
+        assert_eq!(events.last(), Some(&Ok(AgentEvent::Completed)));
+    }
 }
@@ -7,21 +7,25 @@ use crate::agents::base::Agent;
 use crate::agents::base::AgentError;
 use crate::agents::base::AgentEvent;
 use crate::agents::base::ExecutionContext;
+use crate::agents::base::Requirements;
+use crate::agents::cli_executor::CliCommand;
+use crate::agents::cli_executor::CliOutput;
+use crate::agents::permissions::ToolPermissions;
+use crate::agents::runtime::RuntimeOptions;
 use async_trait::async_trait;
+use pk_protocol::agent_models::PermissionMode;
+use pk_protocol::process_models::FileChangeKind;
+use pk_protocol::process_models::TokenUsage;
 use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::pin::Pin;
 use std::process::Stdio;
-use std::sync::Arc;
-use std::sync::Mutex;
 use tokio::fs;
-use tokio::io::AsyncBufReadExt;
-use tokio::io::AsyncWriteExt;
-use tokio::io::BufReader;
 use tokio::process::Command;
 use tokio_stream::Stream;
 use tokio_stream::StreamExt;
+use uuid::Uuid;
 
 /// Codex adapter for executing instructions using OpenAI Codex CLI.
 ///
@@ -31,8 +35,8 @@ pub struct CodexAdapter {
     name: String,
     model: String,
     system_prompt: String,
-    /// Session mapping: project_id -> rollout_file_path
-    session_mapping: Arc<Mutex<HashMap<String, String>>>,
+    permissions: ToolPermissions,
+    runtime: RuntimeOptions,
 }
 
 impl CodexAdapter {
@@ -48,43 +52,59 @@ impl CodexAdapter {
             name,
             model,
             system_prompt,
-            session_mapping: Arc::new(Mutex::new(HashMap::new())),
+            permissions: ToolPermissions::default(),
+            runtime: RuntimeOptions::default(),
         })
     }
 
-    /// Extract project ID from project path.
-    fn extract_project_id(project_path: &str) -> String {
-        std::path::Path::new(project_path)
-            .file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or(project_path)
-            .to_string()
+    /// Restrict the permission mode of this agent.
+    ///
+    /// The mode maps onto the Codex sandbox (`--sandbox read-only`,
+    /// `workspace-write` or `danger-full-access`). Codex has no per-tool
+    /// filter, so `allowed-tools` and `disallowed-tools` are rejected.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::Unsupported` if tool lists or permission rules
+    /// are configured.
+    pub fn with_permissions(mut self, permissions: ToolPermissions) -> Result<Self, AgentError> {
+        permissions.reject_tool_lists("Codex")?;
+        permissions.reject_rules("Codex")?;
+        self.permissions = permissions;
+        Ok(self)
     }
 
-    /// Ensure AGENTS.md file exists in the project root.
+    /// Set how the Codex CLI is launched.
+    ///
+    /// Environment, working directory and extra arguments apply to the
+    /// `codex` process. Codex has no turn limit.
     ///
-    /// Codex uses AGENTS.md for system prompts.
-    async fn ensure_agent_md(&self, project_path: &str) -> Result<(), AgentError> {
-        let agent_md_path = std::path::Path::new(project_path).join("AGENTS.md");
+    /// # Errors
+    ///
+    /// Returns `AgentError::Unsupported` if `max-turns` is configured.
+    pub fn with_runtime(mut self, runtime: RuntimeOptions) -> Result<Self, AgentError> {
+        runtime.reject_max_turns("Codex")?;
+        self.runtime = runtime;
+        Ok(self)
+    }
 
-        // Skip if already exists
-        if agent_md_path.exists() {
-            return Ok(());
+    /// Sandbox arguments for the configured permission mode.
+    fn sandbox_args(&self) -> Vec<&'static str> {
+        match self.permissions.mode {
+            Some(PermissionMode::ReadOnly) => vec!["--sandbox", "read-only"],
+            Some(PermissionMode::Edit) => vec!["--sandbox", "workspace-write"],
+            Some(PermissionMode::Full) => vec!["--sandbox", "danger-full-access"],
+            None => Vec::new(),
         }
-
-        // Write system prompt to AGENTS.md
-        fs::write(&agent_md_path, &self.system_prompt)
-            .await
-            .map_err(|e| AgentError::ExecutionError(format!("Failed to write AGENTS.md: {}", e)))?;
-
-        Ok(())
     }
 
     /// Get or create rollout file path for session management.
+    ///
+    /// Each session has its own rollout file, named after the session id.
     async fn get_rollout_path(
         &self,
         project_path: &str,
-        project_id: &str,
+        session_id: &str,
     ) -> Result<String, AgentError> {
         // Create rollout directory: .pipeline-kit/codex_rollouts/
         let rollout_dir = std::path::Path::new(project_path)
@@ -95,8 +115,8 @@ impl CodexAdapter {
             AgentError::ExecutionError(format!("Failed to create rollout directory: {}", e))
         })?;
 
-        // Rollout file: <project_id>.yaml
-        let rollout_file = rollout_dir.join(format!("{}.yaml", project_id));
+        // Rollout file: <session_id>.yaml
+        let rollout_file = rollout_dir.join(format!("{}.yaml", session_id));
         Ok(rollout_file.to_string_lossy().to_string())
     }
 
@@ -129,117 +149,106 @@ impl Agent for CodexAdapter {
         cli_available && api_key_available
     }
 
+    fn requirements(&self) -> Requirements {
+        Requirements {
+            binary: Some(Self::get_executable_name().to_string()),
+            env_vars: vec!["OPENAI_API_KEY".to_string()],
+        }
+    }
+
     async fn execute(
         &self,
         context: &ExecutionContext,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<AgentEvent, AgentError>> + Send>>, AgentError>
     {
-        // 1. Ensure AGENTS.md exists
-        self.ensure_agent_md(&context.project_path).await?;
-
-        // 2. Get rollout file path
-        let project_id = Self::extract_project_id(&context.project_path);
+        // 1. Get rollout file path, continuing the given session if any
+        let session_id = context
+            .session_id
+            .clone()
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
         let rollout_path = self
-            .get_rollout_path(&context.project_path, &project_id)
+            .get_rollout_path(&context.project_path, &session_id)
             .await?;
 
-        // 3. Build command
-        let mut cmd = Command::new(Self::get_executable_name());
-        cmd.arg("--model").arg(&self.model);
-        cmd.arg("--approval-policy").arg("allow-all"); // Auto-approve all actions
-        cmd.arg("--rollout").arg(&rollout_path); // Session persistence
-        cmd.arg("--output-format").arg("jsonrpc"); // JSON-RPC output
+        // 2. Build command arguments
+        let mut args = vec![
+            "--model".to_string(),
+            self.model.clone(),
+            "--approval-policy".to_string(),
+            "allow-all".to_string(), // Never prompt; the sandbox limits actions
+        ];
+        args.extend(self.sandbox_args().into_iter().map(String::from));
+        args.push("--rollout".to_string()); // Session persistence
+        args.push(rollout_path);
+        args.push("--output-format".to_string()); // JSON-RPC output
+        args.push("jsonrpc".to_string());
+        for path in context.image_paths() {
+            args.push("--image".to_string()); // Image attachments
+            args.push(path.to_string());
+        }
+        args.extend(self.runtime.extra_args.iter().cloned());
 
         // API key from environment
+        let mut env = HashMap::new();
         if let Ok(api_key) = std::env::var("OPENAI_API_KEY") {
-            cmd.env("OPENAI_API_KEY", api_key);
+            env.insert("OPENAI_API_KEY".to_string(), api_key);
         }
+        env.extend(self.runtime.env.clone());
 
-        // Set working directory
-        cmd.current_dir(&context.project_path);
-        cmd.stdin(Stdio::piped());
-        cmd.stdout(Stdio::piped());
-        cmd.stderr(Stdio::piped());
-
-        // 4. Spawn process
-        let mut child = cmd
-            .spawn()
-            .map_err(|e| AgentError::ExecutionError(format!("Failed to spawn codex CLI: {}", e)))?;
-
-        let mut stdin = child
-            .stdin
-            .take()
-            .ok_or_else(|| AgentError::ExecutionError("Failed to capture stdin".to_string()))?;
-
-        let stdout = child
-            .stdout
-            .take()
-            .ok_or_else(|| AgentError::ExecutionError("Failed to capture stdout".to_string()))?;
-
-        // 5. Create JSON-RPC request
+        // 3. Create JSON-RPC request
         let request = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
             id: 1,
             method: "execute".to_string(),
             params: ExecuteParams {
-                prompt: context.instruction.clone(),
+                prompt: context.instruction_with_files(),
                 system: self.system_prompt.clone(),
             },
         };
-
-        // 6. Send request to Codex CLI
         let request_str = serde_json::to_string(&request).map_err(|e| {
             AgentError::ExecutionError(format!("Failed to serialize request: {}", e))
         })?;
 
-        stdin
-            .write_all(request_str.as_bytes())
-            .await
-            .map_err(|e| AgentError::ExecutionError(format!("Failed to write to stdin: {}", e)))?;
-        stdin
-            .write_all(b"\n")
-            .await
-            .map_err(|e| AgentError::ExecutionError(format!("Failed to write newline: {}", e)))?;
-        stdin
-            .flush()
-            .await
-            .map_err(|e| AgentError::ExecutionError(format!("Failed to flush stdin: {}", e)))?;
-
-        // Close stdin to signal end of input
-        drop(stdin);
-
-        // 7. Create stream from stdout
-        let reader = BufReader::new(stdout);
-        let lines = reader.lines();
-        let lines_stream = tokio_stream::wrappers::LinesStream::new(lines);
-
-        // 8. Save rollout path for this session
-        {
-            let mut mapping = self.session_mapping.lock().unwrap();
-            mapping.insert(project_id.clone(), rollout_path);
-        }
-
-        // 9. Parse JSON-RPC responses and convert to AgentEvents
-        let events_stream = lines_stream
-            .then(|line_result| async move {
-                match line_result {
-                    Ok(line) => {
-                        if line.trim().is_empty() {
-                            return None;
-                        }
-
-                        match serde_json::from_str::<JsonRpcResponse>(&line) {
-                            Ok(response) => convert_codex_response(response),
-                            Err(e) => Some(Err(AgentError::StreamParseError(format!(
-                                "Failed to parse JSON-RPC response: {} (line: {})",
-                                e, line
-                            )))),
+        // 4. Spawn the CLI with the request on stdin, which is then closed to
+        //    signal end of input
+        let process = CliCommand::new(
+            Self::get_executable_name().to_string(),
+            args,
+            self.runtime.resolve_dir(&context.project_path),
+        )
+        .with_env(env)
+        .with_input(Some(format!("{}\n", request_str)))
+        .with_tolerant_parsing(true)
+        .spawn()?;
+
+        // 5. Parse JSON-RPC responses and convert to AgentEvents, starting
+        //    with the session the rollout file belongs to
+        let events_stream = async_stream::stream! {
+            yield Ok(AgentEvent::SessionStarted(session_id));
+            let mut process = process;
+            while let Some(output) = process.next().await {
+                match output {
+                    Ok(CliOutput::Json(value)) => {
+                        match serde_json::from_value::<JsonRpcResponse>(value.clone()) {
+                            Ok(response) => {
+                                for event in convert_codex_response(response) {
+                                    yield event;
+                                }
+                            }
+                            Err(e) => yield Err(AgentError::StreamParseError(format!(
+                                "Failed to parse JSON-RPC response: {} (json: {})",
+                                e, value
+                            ))),
                         }
                     }
-                    Err(e) => Some(Err(AgentError::StreamParseError(e.to_string()))),
+                    Ok(CliOutput::Text(line)) => {
+                        yield Ok(AgentEvent::Warning(format!("Unexpected output: {}", line)));
+                    }
+                    Ok(CliOutput::Stderr(line)) => yield Ok(AgentEvent::Warning(line)),
+                    Err(e) => yield Err(e),
                 }
-            })
-            .filter_map(|opt| opt);
+            }
+        };
 
         Ok(Box::pin(events_stream))
     }
@@ -281,6 +290,8 @@ struct CodexResult {
     content: Option<String>,
     #[serde(default)]
     tool_event: Option<ToolEvent>,
+    #[serde(default)]
+    usage: Option<CodexUsage>,
 }
 
 /// Tool event structure.
@@ -294,6 +305,23 @@ struct ToolEvent {
     patch: Option<String>,
     #[serde(default)]
     query: Option<String>,
+    #[serde(default)]
+    call_id: Option<String>,
+    #[serde(default)]
+    output: Option<String>,
+    #[serde(default)]
+    exit_code: Option<i32>,
+    #[serde(default)]
+    files: Vec<String>,
+}
+
+/// Token usage reported with `usage` and `done` results.
+#[derive(Debug, Deserialize)]
+struct CodexUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
 }
 
 /// JSON-RPC error structure.
@@ -303,86 +331,147 @@ struct JsonRpcError {
     message: String,
 }
 
-/// Convert Codex JSON-RPC response to AgentEvent.
-fn convert_codex_response(response: JsonRpcResponse) -> Option<Result<AgentEvent, AgentError>> {
+/// Convert Codex JSON-RPC response to AgentEvents.
+fn convert_codex_response(response: JsonRpcResponse) -> Vec<Result<AgentEvent, AgentError>> {
     // Check for errors
     if let Some(error) = response.error {
-        return Some(Err(AgentError::ApiError(format!(
+        return vec![Err(AgentError::ApiError(format!(
             "Codex API error (code {}): {}",
             error.code, error.message
-        ))));
+        )))];
     }
 
     // Extract result
-    if let Some(result) = response.result {
-        match result.event_type.as_str() {
-            "message" => {
-                // Text message from assistant
-                if let Some(content) = result.content {
-                    if !content.trim().is_empty() {
-                        return Some(Ok(AgentEvent::MessageChunk(content)));
-                    }
+    let Some(result) = response.result else {
+        // No meaningful event
+        return Vec::new();
+    };
+
+    let usage_event = result.usage.map(|usage| {
+        Ok(AgentEvent::Usage(TokenUsage {
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cost_usd: None,
+            duration_ms: None,
+        }))
+    });
+
+    match result.event_type.as_str() {
+        "message" => {
+            // Text message from assistant
+            match result.content {
+                Some(content) if !content.trim().is_empty() => {
+                    vec![Ok(AgentEvent::MessageChunk(content))]
                 }
+                _ => Vec::new(),
             }
-            "tool_event" => {
-                // Tool execution event
-                if let Some(tool_event) = result.tool_event {
-                    let tool_json = match tool_event.tool_type.as_str() {
-                        "exec_command" => {
-                            let cmd = tool_event.command.unwrap_or_default();
-                            serde_json::json!({
-                                "type": "exec_command",
-                                "command": cmd
-                            })
-                        }
-                        "patch_apply" => {
-                            let patch = tool_event.patch.unwrap_or_default();
-                            serde_json::json!({
-                                "type": "patch_apply",
-                                "patch": patch
-                            })
-                        }
-                        "web_search" => {
-                            let query = tool_event.query.unwrap_or_default();
-                            serde_json::json!({
-                                "type": "web_search",
-                                "query": query
-                            })
-                        }
-                        "mcp_tool_call" => {
-                            serde_json::json!({
-                                "type": "mcp_tool_call"
-                            })
-                        }
-                        _ => {
-                            serde_json::json!({
-                                "type": "unknown",
-                                "tool_type": tool_event.tool_type
-                            })
-                        }
-                    };
-
-                    return Some(Ok(AgentEvent::ToolCall(tool_json.to_string())));
-                }
-            }
-            "done" | "completed" => {
-                // Execution completed
-                return Some(Ok(AgentEvent::Completed));
-            }
-            _ => {
-                // Unknown event type, skip
+        }
+        "tool_event" => {
+            // Tool execution started
+            let Some(tool_event) = result.tool_event else {
+                return Vec::new();
+            };
+
+            let arguments = match tool_event.tool_type.as_str() {
+                "exec_command" => serde_json::json!({
+                    "command": tool_event.command.unwrap_or_default()
+                }),
+                "patch_apply" => serde_json::json!({
+                    "patch": tool_event.patch.unwrap_or_default(),
+                    "files": tool_event.files
+                }),
+                "web_search" => serde_json::json!({
+                    "query": tool_event.query.unwrap_or_default()
+                }),
+                _ => serde_json::Value::Null,
+            };
+
+            let mut events = vec![Ok(AgentEvent::ToolCall {
+                id: tool_event.call_id,
+                name: tool_event.tool_type.clone(),
+                arguments,
+            })];
+            if tool_event.tool_type == "patch_apply" {
+                events.extend(tool_event.files.into_iter().map(|path| {
+                    Ok(AgentEvent::FileChanged {
+                        path,
+                        kind: FileChangeKind::Modified,
+                    })
+                }));
             }
+            events
+        }
+        "tool_result" => {
+            // Tool execution finished
+            let Some(tool_event) = result.tool_event else {
+                return Vec::new();
+            };
+            vec![Ok(AgentEvent::ToolResult {
+                id: tool_event.call_id,
+                output: tool_event.output.unwrap_or_default(),
+                is_error: tool_event.exit_code.is_some_and(|code| code != 0),
+            })]
+        }
+        "usage" => usage_event.into_iter().collect(),
+        "warning" => result
+            .content
+            .map(|message| Ok(AgentEvent::Warning(message)))
+            .into_iter()
+            .collect(),
+        "done" | "completed" => {
+            // Execution completed
+            let mut events: Vec<_> = usage_event.into_iter().collect();
+            events.push(Ok(AgentEvent::Completed));
+            events
+        }
+        _ => {
+            // Unknown event type, skip
+            Vec::new()
         }
     }
-
-    // No meaningful event
-    None
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn adapter() -> CodexAdapter {
+        CodexAdapter::new("test".to_string(), "codex".to_string(), String::new()).unwrap()
+    }
+
+    #[test]
+    fn test_permission_mode_maps_to_sandbox() {
+        assert!(adapter().sandbox_args().is_empty());
+
+        let read_only = adapter()
+            .with_permissions(ToolPermissions {
+                mode: Some(PermissionMode::ReadOnly),
+                ..ToolPermissions::default()
+            })
+            .unwrap();
+        assert_eq!(read_only.sandbox_args(), vec!["--sandbox", "read-only"]);
+
+        let edit = adapter()
+            .with_permissions(ToolPermissions {
+                mode: Some(PermissionMode::Edit),
+                ..ToolPermissions::default()
+            })
+            .unwrap();
+        assert_eq!(edit.sandbox_args(), vec!["--sandbox", "workspace-write"]);
+    }
+
+    #[test]
+    fn test_tool_lists_are_unsupported() {
+        let result = adapter().with_permissions(ToolPermissions {
+            allowed_tools: vec!["Read".to_string()],
+            ..ToolPermissions::default()
+        });
+        assert!(matches!(
+            result,
+            Err(AgentError::Unsupported(msg)) if msg.contains("allowed-tools")
+        ));
+    }
+
     #[test]
     fn test_codex_adapter_new() {
         let adapter = CodexAdapter::new(
@@ -393,15 +482,6 @@ mod tests {
         assert!(adapter.is_ok());
     }
 
-    #[test]
-    fn test_extract_project_id() {
-        assert_eq!(
-            CodexAdapter::extract_project_id("/path/to/project"),
-            "project"
-        );
-        assert_eq!(CodexAdapter::extract_project_id("/tmp/test"), "test");
-    }
-
     #[tokio::test]
     async fn test_check_availability_without_cli() {
         let adapter = CodexAdapter::new(
@@ -443,14 +523,15 @@ mod tests {
                 event_type: "message".to_string(),
                 content: Some("Hello, world!".to_string()),
                 tool_event: None,
+                usage: None,
             }),
             error: None,
         };
 
-        let event = convert_codex_response(response);
-        assert!(event.is_some());
+        let mut events = convert_codex_response(response);
+        assert_eq!(events.len(), 1);
 
-        let event = event.unwrap();
+        let event = events.remove(0);
         assert!(event.is_ok());
 
         match event.unwrap() {
@@ -472,21 +553,31 @@ mod tests {
                     command: Some("ls -la".to_string()),
                     patch: None,
                     query: None,
+                    call_id: Some("call_1".to_string()),
+                    output: None,
+                    exit_code: None,
+                    files: Vec::new(),
                 }),
+                usage: None,
             }),
             error: None,
         };
 
-        let event = convert_codex_response(response);
-        assert!(event.is_some());
+        let mut events = convert_codex_response(response);
+        assert_eq!(events.len(), 1);
 
-        let event = event.unwrap();
+        let event = events.remove(0);
         assert!(event.is_ok());
 
         match event.unwrap() {
-            AgentEvent::ToolCall(json) => {
-                assert!(json.contains("exec_command"));
-                assert!(json.contains("ls -la"));
+            AgentEvent::ToolCall {
+                id,
+                name,
+                arguments,
+            } => {
+                assert_eq!(id, Some("call_1".to_string()));
+                assert_eq!(name, "exec_command");
+                assert_eq!(arguments["command"], "ls -la");
             }
             _ => panic!("Expected ToolCall"),
         }
@@ -501,14 +592,15 @@ mod tests {
                 event_type: "done".to_string(),
                 content: None,
                 tool_event: None,
+                usage: None,
             }),
             error: None,
         };
 
-        let event = convert_codex_response(response);
-        assert!(event.is_some());
+        let mut events = convert_codex_response(response);
+        assert_eq!(events.len(), 1);
 
-        let event = event.unwrap();
+        let event = events.remove(0);
         assert!(event.is_ok());
 
         match event.unwrap() {
@@ -529,40 +621,13 @@ mod tests {
             }),
         };
 
-        let event = convert_codex_response(response);
-        assert!(event.is_some());
+        let mut events = convert_codex_response(response);
+        assert_eq!(events.len(), 1);
 
-        let event = event.unwrap();
+        let event = events.remove(0);
         assert!(event.is_err());
     }
 
-    #[tokio::test]
-    async fn test_ensure_agent_md() {
-        let adapter = CodexAdapter::new(
-            "test".to_string(),
-            "gpt-4".to_string(),
-            "test system prompt".to_string(),
-        )
-        .unwrap();
-
-        let temp_dir = tempfile::tempdir().unwrap();
-        let project_path = temp_dir.path().to_str().unwrap();
-
-        let result = adapter.ensure_agent_md(project_path).await;
-        assert!(result.is_ok());
-
-        // Verify file was created
-        let agent_md_path = temp_dir.path().join("AGENTS.md");
-        assert!(agent_md_path.exists());
-
-        let content = fs::read_to_string(&agent_md_path).await.unwrap();
-        assert_eq!(content, "test system prompt");
-
-        // Calling again should not error (idempotent)
-        let result2 = adapter.ensure_agent_md(project_path).await;
-        assert!(result2.is_ok());
-    }
-
     #[tokio::test]
     async fn test_get_rollout_path() {
         let adapter = CodexAdapter::new(
@@ -576,13 +641,13 @@ mod tests {
         let project_path = temp_dir.path().to_str().unwrap();
 
         let rollout_path = adapter
-            .get_rollout_path(project_path, "my-project")
+            .get_rollout_path(project_path, "session-1")
             .await
             .unwrap();
 
         assert!(rollout_path.contains(".pipeline-kit"));
         assert!(rollout_path.contains("codex_rollouts"));
-        assert!(rollout_path.contains("my-project.yaml"));
+        assert!(rollout_path.contains("session-1.yaml"));
 
         // Verify directory was created
         let rollout_dir = temp_dir.path().join(".pipeline-kit").join("codex_rollouts");
@@ -598,4 +663,72 @@ mod tests {
             assert_eq!(exe_name, "codex");
         }
     }
+
+    #[test]
+    fn test_convert_codex_response_with_patch_reports_file_changes() {
+        let response: JsonRpcResponse = serde_json::from_value(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "type": "tool_event",
+                "tool_event": {"type": "patch_apply", "patch": "...", "files": ["a.rs", "b.rs"]}
+            }
+        }))
+        .unwrap();
+
+        let events = convert_codex_response(response);
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[0], Ok(AgentEvent::ToolCall { .. })));
+        assert_eq!(
+            events[2],
+            Ok(AgentEvent::FileChanged {
+                path: "b.rs".to_string(),
+                kind: FileChangeKind::Modified,
+            })
+        );
+    }
+
+    #[test]
+    fn test_convert_codex_response_with_tool_result() {
+        let response: JsonRpcResponse = serde_json::from_value(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "type": "tool_result",
+                "tool_event": {"type": "exec_command", "call_id": "call_1", "output": "boom", "exit_code": 2}
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            convert_codex_response(response),
+            vec![Ok(AgentEvent::ToolResult {
+                id: Some("call_1".to_string()),
+                output: "boom".to_string(),
+                is_error: true,
+            })]
+        );
+    }
+
+    #[test]
+    fn test_convert_codex_response_done_with_usage() {
+        let response: JsonRpcResponse = serde_json::from_value(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {"type": "done", "usage": {"input_tokens": 12, "output_tokens": 3}}
+        }))
+        .unwrap();
+
+        let events = convert_codex_response(response);
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            events[0],
+            Ok(AgentEvent::Usage(TokenUsage {
+                input_tokens: 12,
+                output_tokens: 3,
+                ..
+            }))
+        ));
+        assert_eq!(events[1], Ok(AgentEvent::Completed));
+    }
 }
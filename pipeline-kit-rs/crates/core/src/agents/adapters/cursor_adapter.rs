@@ -3,16 +3,21 @@
 use crate::agents::base::Agent;
 use crate::agents::base::AgentError;
 use crate::agents::base::AgentEvent;
+use crate::agents::base::Attachment;
 use crate::agents::base::ExecutionContext;
-use crate::agents::cli_executor::CliExecutor;
+use crate::agents::base::Requirements;
+use crate::agents::cli_executor::CliCommand;
+use crate::agents::cli_executor::CliOutput;
+use crate::agents::permissions::unsupported;
+use crate::agents::permissions::ToolPermissions;
+use crate::agents::runtime::RuntimeOptions;
 use async_trait::async_trait;
+use pk_protocol::agent_models::PermissionMode;
+use pk_protocol::process_models::FileChangeKind;
+use pk_protocol::process_models::TokenUsage;
 use serde::Deserialize;
-use std::collections::HashMap;
 use std::pin::Pin;
 use std::process::Stdio;
-use std::sync::Arc;
-use std::sync::Mutex;
-use tokio::fs;
 use tokio::process::Command;
 use tokio_stream::Stream;
 use tokio_stream::StreamExt;
@@ -26,8 +31,8 @@ pub struct CursorAdapter {
     name: String,
     model: String,
     system_prompt: String,
-    /// Session mapping: project_id -> session_id
-    session_mapping: Arc<Mutex<HashMap<String, String>>>,
+    permissions: ToolPermissions,
+    runtime: RuntimeOptions,
 }
 
 impl CursorAdapter {
@@ -37,36 +42,72 @@ impl CursorAdapter {
             name,
             model,
             system_prompt,
-            session_mapping: Arc::new(Mutex::new(HashMap::new())),
+            permissions: ToolPermissions::default(),
+            runtime: RuntimeOptions::default(),
         })
     }
 
-    /// Extract project ID from project path.
-    fn extract_project_id(project_path: &str) -> String {
-        std::path::Path::new(project_path)
-            .file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or(project_path)
-            .to_string()
+    /// Restrict the permission mode of this agent.
+    ///
+    /// `full` (the default) passes `--force`; `read-only` omits it, so the
+    /// agent can only propose changes. Cursor has no flag that allows edits
+    /// while blocking commands, nor per-tool filters.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::Unsupported` for `edit` mode, tool lists or
+    /// permission rules.
+    pub fn with_permissions(mut self, permissions: ToolPermissions) -> Result<Self, AgentError> {
+        permissions.reject_tool_lists("Cursor")?;
+        permissions.reject_rules("Cursor")?;
+        if permissions.mode == Some(PermissionMode::Edit) {
+            return Err(unsupported("Cursor", "permission-mode: edit"));
+        }
+        self.permissions = permissions;
+        Ok(self)
     }
 
-    /// Ensure AGENTS.md file exists in the project root.
+    /// Set how the Cursor Agent CLI is launched.
+    ///
+    /// Environment, working directory and extra arguments apply to the
+    /// `cursor-agent` process. Cursor has no turn limit.
+    ///
+    /// # Errors
     ///
-    /// Cursor uses AGENTS.md for system prompts.
-    async fn ensure_agent_md(&self, project_path: &str) -> Result<(), AgentError> {
-        let agent_md_path = std::path::Path::new(project_path).join("AGENTS.md");
+    /// Returns `AgentError::Unsupported` if `max-turns` is configured.
+    pub fn with_runtime(mut self, runtime: RuntimeOptions) -> Result<Self, AgentError> {
+        runtime.reject_max_turns("Cursor")?;
+        self.runtime = runtime;
+        Ok(self)
+    }
 
-        // Skip if already exists
-        if agent_md_path.exists() {
-            return Ok(());
+    /// Build the CLI arguments for one invocation.
+    ///
+    /// The prompt is written to stdin rather than passed here, and
+    /// `cursor-agent` reads `CURSOR_API_KEY` from the inherited environment,
+    /// so neither shows up in `ps`.
+    fn command_args(&self, context: &ExecutionContext) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.permissions.mode != Some(PermissionMode::ReadOnly) {
+            args.push("--force".to_string());
         }
+        args.extend([
+            "-p".to_string(),
+            "--output-format".to_string(),
+            "stream-json".to_string(),
+            "-m".to_string(),
+            self.model.clone(),
+        ]);
 
-        // Write system prompt to AGENTS.md
-        fs::write(&agent_md_path, &self.system_prompt)
-            .await
-            .map_err(|e| AgentError::ExecutionError(format!("Failed to write AGENTS.md: {}", e)))?;
+        // Session resumption
+        if let Some(sid) = &context.session_id {
+            args.push("--resume".to_string());
+            args.push(sid.clone());
+        }
 
-        Ok(())
+        // User-supplied arguments
+        args.extend(self.runtime.extra_args.iter().cloned());
+        args
     }
 }
 
@@ -86,79 +127,63 @@ impl Agent for CursorAdapter {
         }
     }
 
+    fn requirements(&self) -> Requirements {
+        Requirements {
+            binary: Some("cursor-agent".to_string()),
+            env_vars: vec![],
+        }
+    }
+
     async fn execute(
         &self,
         context: &ExecutionContext,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<AgentEvent, AgentError>> + Send>>, AgentError>
     {
-        // 1. Ensure AGENTS.md exists
-        self.ensure_agent_md(&context.project_path).await?;
-
-        // 2. Get session ID
-        let project_id = Self::extract_project_id(&context.project_path);
-        let session_id = {
-            let mapping = self.session_mapping.lock().unwrap();
-            mapping.get(&project_id).cloned()
-        };
-
-        // 3. Build command arguments
-        let mut args = vec![
-            "--force".to_string(),
-            "-p".to_string(),
-            context.instruction.clone(),
-            "--output-format".to_string(),
-            "stream-json".to_string(),
-            "-m".to_string(),
-            self.model.clone(),
-        ];
-
-        // Session resumption
-        if let Some(sid) = &session_id {
-            args.push("--resume".to_string());
-            args.push(sid.clone());
-        }
-
-        // API key from environment
-        if let Ok(api_key) = std::env::var("CURSOR_API_KEY") {
-            args.push("--api-key".to_string());
-            args.push(api_key);
-        }
+        // 1. Files are inlined into the prompt; cursor-agent takes no images
+        context.check_attachments("Cursor", |a| matches!(a, Attachment::File { .. }))?;
 
-        // 4. Execute CLI using CliExecutor
-        let json_stream = CliExecutor::execute(
+        // 2. Spawn the CLI with the prompt on stdin; cursor-agent has no
+        //    system prompt flag, so a new conversation gets it ahead of the
+        //    instruction. Stray non-JSON lines become warnings
+        let process = CliCommand::new(
             "cursor-agent".to_string(),
-            args,
-            context.project_path.clone(),
-        );
-
-        // 5. Convert JSON stream to AgentEvents
-        let session_mapping = self.session_mapping.clone();
-        let project_id_clone = project_id.clone();
-
-        let events_stream = json_stream
-            .then(move |json_result| {
-                let session_mapping = session_mapping.clone();
-                let project_id = project_id_clone.clone();
-
-                async move {
-                    match json_result {
-                        Ok(json_value) => {
-                            // Parse as CursorEvent
-                            match serde_json::from_value::<CursorEvent>(json_value.clone()) {
-                                Ok(event) => {
-                                    convert_cursor_event(event, session_mapping, project_id).await
+            self.command_args(context),
+            self.runtime.resolve_dir(&context.project_path),
+        )
+        .with_env(self.runtime.env.clone())
+        .with_input(Some(
+            context.prompt_with_system_and_files(&self.system_prompt),
+        ))
+        .with_tolerant_parsing(true)
+        .spawn()?;
+
+        // 3. Convert JSON stream to AgentEvents
+        let events_stream = async_stream::stream! {
+            let mut process = process;
+            while let Some(output) = process.next().await {
+                match output {
+                    Ok(CliOutput::Json(json_value)) => {
+                        // Parse as CursorEvent
+                        match serde_json::from_value::<CursorEvent>(json_value.clone()) {
+                            Ok(event) => {
+                                for event in convert_cursor_event(event) {
+                                    yield event;
                                 }
-                                Err(e) => Some(Err(AgentError::StreamParseError(format!(
-                                    "Failed to parse CursorEvent: {} (json: {})",
-                                    e, json_value
-                                )))),
                             }
+                            Err(e) => yield Err(AgentError::StreamParseError(format!(
+                                "Failed to parse CursorEvent: {} (json: {})",
+                                e, json_value
+                            ))),
                         }
-                        Err(e) => Some(Err(e)),
                     }
+                    Ok(CliOutput::Text(line)) => {
+                        yield Ok(AgentEvent::Warning(format!("Unexpected output: {}", line)));
+                    }
+                    Ok(CliOutput::Stderr(line)) => yield Ok(AgentEvent::Warning(line)),
+                    Err(e) => yield Err(e),
                 }
-            })
-            .filter_map(|opt| opt);
+            }
+        };
 
         Ok(Box::pin(events_stream))
     }
@@ -172,70 +197,203 @@ struct CursorEvent {
     subtype: Option<String>,
     message: Option<serde_json::Value>,
     tool_call: Option<serde_json::Value>,
+    call_id: Option<String>,
     session_id: Option<String>,
-    #[allow(dead_code)]
     duration_ms: Option<u64>,
+    is_error: Option<bool>,
+    result: Option<serde_json::Value>,
+    usage: Option<serde_json::Value>,
 }
 
-/// Convert Cursor event to AgentEvent.
-async fn convert_cursor_event(
-    event: CursorEvent,
-    session_mapping: Arc<Mutex<HashMap<String, String>>>,
-    project_id: String,
-) -> Option<Result<AgentEvent, AgentError>> {
+/// Convert Cursor event to AgentEvents.
+fn convert_cursor_event(event: CursorEvent) -> Vec<Result<AgentEvent, AgentError>> {
     match event.event_type.as_str() {
-        "system" => {
-            // System initialization (hidden from UI)
-            None
-        }
+        // System initialization: only the session id is kept
+        "system" => event
+            .session_id
+            .map(|sid| Ok(AgentEvent::SessionStarted(sid)))
+            .into_iter()
+            .collect(),
         "user" => {
             // Echo back (suppress)
-            None
+            Vec::new()
         }
         "assistant" => {
             // Text delta
+            let mut events = Vec::new();
             if let Some(msg) = event.message {
                 if let Some(content_array) = msg.get("content").and_then(|c| c.as_array()) {
                     for item in content_array {
                         if let Some(text) = item.get("text").and_then(|t| t.as_str()) {
                             if !text.trim().is_empty() {
-                                return Some(Ok(AgentEvent::MessageChunk(text.to_string())));
+                                events.push(Ok(AgentEvent::MessageChunk(text.to_string())));
                             }
                         }
                     }
                 }
             }
-            None
+            events
         }
         "tool_call" => {
-            if let Some(subtype) = &event.subtype {
-                if subtype == "started" {
-                    // Tool call started
-                    if let Some(tool_call) = event.tool_call {
-                        return Some(Ok(AgentEvent::ToolCall(tool_call.to_string())));
+            // Tool calls are wrapped as {"<name>ToolCall": {"args": ..., "result": ...}}
+            let Some((name, body)) = event
+                .tool_call
+                .as_ref()
+                .and_then(|t| t.as_object())
+                .and_then(|t| t.iter().next())
+            else {
+                return Vec::new();
+            };
+
+            match event.subtype.as_deref() {
+                Some("started") => vec![Ok(AgentEvent::ToolCall {
+                    id: event.call_id,
+                    name: name.clone(),
+                    arguments: body.get("args").cloned().unwrap_or_default(),
+                })],
+                Some("completed") => {
+                    let result = body.get("result");
+                    let is_error = result.and_then(|r| r.get("error")).is_some();
+                    let output = result.map(|r| r.to_string()).unwrap_or_default();
+
+                    let mut events = vec![Ok(AgentEvent::ToolResult {
+                        id: event.call_id,
+                        output,
+                        is_error,
+                    })];
+                    if !is_error {
+                        if let Some(change) = file_change(name, body) {
+                            events.push(Ok(change));
+                        }
                     }
+                    events
                 }
-                // "completed" subtype is not shown to UI
+                _ => Vec::new(),
             }
-            None
         }
         "result" => {
-            // Save session ID
-            if let Some(sid) = event.session_id {
-                let mut mapping = session_mapping.lock().unwrap();
-                mapping.insert(project_id, sid);
+            let token_count = |key: &str| {
+                event
+                    .usage
+                    .as_ref()
+                    .and_then(|u| u.get(key))
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0)
+            };
+            let mut events = vec![Ok(AgentEvent::Usage(TokenUsage {
+                input_tokens: token_count("input_tokens"),
+                output_tokens: token_count("output_tokens"),
+                cost_usd: None,
+                duration_ms: event.duration_ms,
+            }))];
+
+            // Signal completion, or surface the failure reported by the CLI
+            if event.is_error.unwrap_or(false) {
+                let message = event
+                    .result
+                    .as_ref()
+                    .and_then(|r| r.as_str())
+                    .unwrap_or("Cursor reported an error without details")
+                    .to_string();
+                events.push(Err(AgentError::ExecutionError(message)));
+            } else {
+                events.push(Ok(AgentEvent::Completed));
             }
-            // Signal completion
-            Some(Ok(AgentEvent::Completed))
+            events
         }
-        _ => None,
+        _ => Vec::new(),
     }
 }
 
+/// Map a completed file tool call to a FileChanged event.
+fn file_change(tool_name: &str, body: &serde_json::Value) -> Option<AgentEvent> {
+    let kind = match tool_name {
+        "writeToolCall" | "editToolCall" => FileChangeKind::Modified,
+        "deleteToolCall" => FileChangeKind::Deleted,
+        _ => return None,
+    };
+    let path = body.get("args")?.get("path")?.as_str()?.to_string();
+    Some(AgentEvent::FileChanged { path, kind })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn adapter() -> CursorAdapter {
+        CursorAdapter::new("test".to_string(), "gpt-5".to_string(), String::new()).unwrap()
+    }
+
+    #[test]
+    fn test_command_args_keep_prompt_off_argv() {
+        let context = ExecutionContext::new("Refactor the parser".to_string())
+            .with_session_id(Some("chat-1".to_string()));
+        let args = adapter().command_args(&context);
+
+        assert!(!args.iter().any(|a| a.contains("Refactor the parser")));
+        assert!(!args.contains(&"--api-key".to_string()));
+        assert_eq!(&args[..2], &["--force".to_string(), "-p".to_string()]);
+        assert!(args.ends_with(&["--resume".to_string(), "chat-1".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn test_execute_rejects_image_attachments() {
+        let context =
+            ExecutionContext::new("Describe".to_string()).with_attachment(Attachment::Image {
+                path: "/tmp/shot.png".to_string(),
+                mime_type: "image/png".to_string(),
+            });
+
+        let result = adapter().execute(&context).await;
+        assert!(matches!(
+            result,
+            Err(AgentError::Unsupported(msg)) if msg == "Cursor cannot receive image attachments"
+        ));
+    }
+
+    #[test]
+    fn test_with_permissions() {
+        let read_only = ToolPermissions {
+            mode: Some(PermissionMode::ReadOnly),
+            ..ToolPermissions::default()
+        };
+        assert!(adapter().with_permissions(read_only).is_ok());
+
+        // Cursor cannot allow edits while blocking commands
+        let edit = ToolPermissions {
+            mode: Some(PermissionMode::Edit),
+            ..ToolPermissions::default()
+        };
+        assert!(matches!(
+            adapter().with_permissions(edit),
+            Err(AgentError::Unsupported(msg)) if msg.contains("permission-mode: edit")
+        ));
+
+        let tools = ToolPermissions {
+            disallowed_tools: vec!["Bash".to_string()],
+            ..ToolPermissions::default()
+        };
+        assert!(adapter().with_permissions(tools).is_err());
+    }
+
+    #[test]
+    fn test_with_runtime_rejects_max_turns() {
+        let runtime = RuntimeOptions {
+            extra_args: vec!["--verbose".to_string()],
+            ..RuntimeOptions::default()
+        };
+        assert!(adapter().with_runtime(runtime).is_ok());
+
+        let runtime = RuntimeOptions {
+            max_turns: Some(3),
+            ..RuntimeOptions::default()
+        };
+        assert!(matches!(
+            adapter().with_runtime(runtime),
+            Err(AgentError::Unsupported(msg)) if msg.contains("max-turns")
+        ));
+    }
+
     #[test]
     fn test_cursor_adapter_new() {
         let adapter = CursorAdapter::new(
@@ -246,15 +404,6 @@ mod tests {
         assert!(adapter.is_ok());
     }
 
-    #[test]
-    fn test_extract_project_id() {
-        assert_eq!(
-            CursorAdapter::extract_project_id("/path/to/project"),
-            "project"
-        );
-        assert_eq!(CursorAdapter::extract_project_id("/tmp/test"), "test");
-    }
-
     #[tokio::test]
     async fn test_check_availability() {
         let adapter = CursorAdapter::new(
@@ -269,30 +418,93 @@ mod tests {
         let _ = available;
     }
 
-    #[tokio::test]
-    async fn test_ensure_agent_md() {
-        let adapter = CursorAdapter::new(
-            "test".to_string(),
-            "gpt-5".to_string(),
-            "test system prompt".to_string(),
-        )
-        .unwrap();
+    fn convert(json: serde_json::Value) -> Vec<Result<AgentEvent, AgentError>> {
+        let event: CursorEvent = serde_json::from_value(json).unwrap();
+        convert_cursor_event(event)
+    }
 
-        let temp_dir = tempfile::tempdir().unwrap();
-        let project_path = temp_dir.path().to_str().unwrap();
+    #[test]
+    fn test_convert_tool_call_lifecycle() {
+        let started = convert(serde_json::json!({
+            "type": "tool_call",
+            "subtype": "started",
+            "call_id": "call_1",
+            "tool_call": {"writeToolCall": {"args": {"path": "README.md"}}}
+        }));
+        assert!(matches!(
+            &started[..],
+            [Ok(AgentEvent::ToolCall { id: Some(id), name, arguments })]
+                if id == "call_1" && name == "writeToolCall" && arguments["path"] == "README.md"
+        ));
+
+        let completed = convert(serde_json::json!({
+            "type": "tool_call",
+            "subtype": "completed",
+            "call_id": "call_1",
+            "tool_call": {"writeToolCall": {
+                "args": {"path": "README.md"},
+                "result": {"success": {"linesCreated": 3}}
+            }}
+        }));
+        assert_eq!(completed.len(), 2);
+        assert!(matches!(
+            &completed[0],
+            Ok(AgentEvent::ToolResult { id: Some(id), is_error: false, .. }) if id == "call_1"
+        ));
+        assert_eq!(
+            completed[1],
+            Ok(AgentEvent::FileChanged {
+                path: "README.md".to_string(),
+                kind: FileChangeKind::Modified,
+            })
+        );
+    }
 
-        let result = adapter.ensure_agent_md(project_path).await;
-        assert!(result.is_ok());
+    #[test]
+    fn test_convert_system_reports_session() {
+        let events = convert(serde_json::json!({
+            "type": "system",
+            "subtype": "init",
+            "session_id": "s-1"
+        }));
 
-        // Verify file was created
-        let agent_md_path = temp_dir.path().join("AGENTS.md");
-        assert!(agent_md_path.exists());
+        assert_eq!(
+            events,
+            vec![Ok(AgentEvent::SessionStarted("s-1".to_string()))]
+        );
+    }
 
-        let content = fs::read_to_string(&agent_md_path).await.unwrap();
-        assert_eq!(content, "test system prompt");
+    #[test]
+    fn test_convert_result_reports_duration() {
+        let events = convert(serde_json::json!({
+            "type": "result",
+            "subtype": "success",
+            "duration_ms": 4200,
+            "is_error": false,
+            "session_id": "s-1"
+        }));
+
+        assert!(matches!(
+            events[0],
+            Ok(AgentEvent::Usage(TokenUsage {
+                duration_ms: Some(4200),
+                ..
+            }))
+        ));
+        assert_eq!(events[1], Ok(AgentEvent::Completed));
+    }
 
-        // Calling again should not error (idempotent)
-        let result2 = adapter.ensure_agent_md(project_path).await;
-        assert!(result2.is_ok());
+    #[test]
+    fn test_convert_result_with_error_fails() {
+        let events = convert(serde_json::json!({
+            "type": "result",
+            "is_error": true,
+            "result": "Model quota exceeded"
+        }));
+
+        assert!(matches!(
+            &events[1],
+            Err(AgentError::ExecutionError(msg)) if msg == "Model quota exceeded"
+        ));
     }
 }
@@ -0,0 +1,576 @@
+//! Custom adapter for agent CLIs declared in configuration.
+//!
+//! A `provider: custom` agent names a program, its arguments and how its
+//! JSON Lines output maps to `AgentEvent`s, so a new tool can be used
+//! without writing an adapter for it. Programs speaking the Agent Client
+//! Protocol (`protocol: acp`) are driven by the shared ACP client instead.
+
+use crate::agents::acp::prompt_blocks;
+use crate::agents::acp::AcpClient;
+use crate::agents::acp::AcpSession;
+use crate::agents::acp::EventStream;
+use crate::agents::base::Agent;
+use crate::agents::base::AgentError;
+use crate::agents::base::AgentEvent;
+use crate::agents::base::Attachment;
+use crate::agents::base::ExecutionContext;
+use crate::agents::base::Requirements;
+use crate::agents::cli_executor::CliCommand;
+use crate::agents::cli_executor::CliOutput;
+use crate::agents::permissions::unsupported;
+use crate::agents::permissions::ToolPermissions;
+use crate::agents::runtime::RuntimeOptions;
+use async_trait::async_trait;
+use pk_protocol::agent_models::CommandProtocol;
+use pk_protocol::agent_models::CustomCommand;
+use pk_protocol::agent_models::EventMapping;
+use pk_protocol::agent_models::PromptInput;
+use serde_json::Value;
+use std::io::Write;
+use std::path::Path;
+use std::pin::Pin;
+use tokio::process::Command;
+use tokio_stream::Stream;
+use tokio_stream::StreamExt;
+
+/// Custom adapter running a user-defined command.
+pub struct CustomAdapter {
+    #[allow(dead_code)]
+    name: String,
+    model: String,
+    system_prompt: String,
+    command: CustomCommand,
+    permissions: ToolPermissions,
+    runtime: RuntimeOptions,
+}
+
+impl CustomAdapter {
+    /// Create a new custom adapter.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Agent name
+    /// * `model` - Model name, substituted for `{model}`
+    /// * `system_prompt` - System prompt, substituted for `{system_prompt}`
+    /// * `command` - The `command:` section of the agent configuration
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::Unsupported` if the program name is empty.
+    pub fn new(
+        name: String,
+        model: String,
+        system_prompt: String,
+        command: CustomCommand,
+    ) -> Result<Self, AgentError> {
+        if command.program.trim().is_empty() {
+            return Err(AgentError::Unsupported(
+                "`command.program` must name an executable".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            name,
+            model,
+            system_prompt,
+            command,
+            permissions: ToolPermissions::default(),
+            runtime: RuntimeOptions::default(),
+        })
+    }
+
+    /// Restrict the permission mode of this agent.
+    ///
+    /// A custom command's flags are opaque to Pipeline Kit, so any
+    /// restriction has to be expressed in `command.args` instead. ACP
+    /// programs are the exception: their permission requests and file
+    /// writes are answered according to the permission mode and rules.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::Unsupported` for tool lists, or for a permission
+    /// mode or permission rules unless the command speaks ACP.
+    pub fn with_permissions(mut self, permissions: ToolPermissions) -> Result<Self, AgentError> {
+        permissions.reject_tool_lists("custom")?;
+        if self.command.protocol != CommandProtocol::Acp {
+            if permissions.mode.is_some() {
+                return Err(unsupported("custom", "permission-mode"));
+            }
+            permissions.reject_rules("custom")?;
+        }
+        self.permissions = permissions;
+        Ok(self)
+    }
+
+    /// Set how the command is launched.
+    ///
+    /// Environment, working directory and extra arguments apply to the
+    /// program. A turn limit has to be passed through `command.args`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::Unsupported` if `max-turns` is configured.
+    pub fn with_runtime(mut self, runtime: RuntimeOptions) -> Result<Self, AgentError> {
+        runtime.reject_max_turns("custom")?;
+        self.runtime = runtime;
+        Ok(self)
+    }
+
+    /// Text handed to the program for an instruction.
+    ///
+    /// The system prompt is prepended unless an argument places it with
+    /// `{system_prompt}`.
+    fn prompt_text(&self, instruction: &str) -> String {
+        let placed = self
+            .command
+            .args
+            .iter()
+            .any(|arg| arg.contains("{system_prompt}"));
+        if placed || self.system_prompt.trim().is_empty() {
+            instruction.to_string()
+        } else {
+            format!("{}\n\n{}", self.system_prompt, instruction)
+        }
+    }
+
+    /// Build the argument list, substituting placeholders.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - The prompt text
+    /// * `prompt_file` - Path of the prompt file when `prompt: file`
+    fn build_args(&self, prompt: &str, prompt_file: Option<&str>) -> Vec<String> {
+        let substitute = |arg: &str| {
+            let arg = arg
+                .replace("{model}", &self.model)
+                .replace("{system_prompt}", &self.system_prompt)
+                .replace("{prompt_file}", prompt_file.unwrap_or_default());
+            match self.command.prompt {
+                PromptInput::Arg => arg.replace("{prompt}", prompt),
+                _ => arg,
+            }
+        };
+        let mut args: Vec<String> = self.command.args.iter().map(|a| substitute(a)).collect();
+
+        // Append the prompt when no argument places it
+        let uses = |placeholder: &str| self.command.args.iter().any(|a| a.contains(placeholder));
+        match self.command.prompt {
+            PromptInput::Arg if !uses("{prompt}") => args.push(prompt.to_string()),
+            PromptInput::File if !uses("{prompt_file}") => {
+                args.push(prompt_file.unwrap_or_default().to_string())
+            }
+            _ => {}
+        }
+
+        args.extend(self.runtime.extra_args.iter().cloned());
+        args
+    }
+
+    /// Run a prompt turn against a program speaking ACP.
+    ///
+    /// Only `{model}` and `{system_prompt}` are substituted; the prompt is
+    /// sent over the protocol, with attachments as resource and image blocks.
+    async fn execute_acp(&self, context: &ExecutionContext) -> Result<EventStream, AgentError> {
+        let args = self.command.args.iter().map(|arg| {
+            arg.replace("{model}", &self.model)
+                .replace("{system_prompt}", &self.system_prompt)
+        });
+
+        let mut command = Command::new(&self.command.program);
+        command
+            .args(args)
+            .args(&self.runtime.extra_args)
+            .envs(&self.runtime.env);
+        let session = AcpSession {
+            cwd: self.runtime.resolve_dir(&context.project_path).into(),
+            permissions: self.permissions.clone(),
+        };
+        let prompt = prompt_blocks(
+            self.prompt_text(&context.instruction),
+            &context.attachments,
+            Path::new(&context.project_path),
+        )
+        .await?;
+        AcpClient::prompt(command, session, prompt)
+    }
+}
+
+#[async_trait]
+impl Agent for CustomAdapter {
+    async fn check_availability(&self) -> bool {
+        which::which(&self.command.program).is_ok()
+    }
+
+    fn requirements(&self) -> Requirements {
+        Requirements {
+            binary: Some(self.command.program.clone()),
+            env_vars: vec![],
+        }
+    }
+
+    async fn execute(
+        &self,
+        context: &ExecutionContext,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<AgentEvent, AgentError>> + Send>>, AgentError>
+    {
+        if self.command.protocol == CommandProtocol::Acp {
+            return self.execute_acp(context).await;
+        }
+
+        // 1. Prepare the prompt; files are inlined and images cannot be passed
+        context.check_attachments("Custom command", |a| matches!(a, Attachment::File { .. }))?;
+        let prompt = self.prompt_text(&context.instruction_with_files());
+        let prompt_file = match self.command.prompt {
+            PromptInput::File => Some(write_prompt_file(&prompt)?),
+            _ => None,
+        };
+        let prompt_path = prompt_file
+            .as_ref()
+            .map(|f| f.path().to_string_lossy().to_string());
+
+        // 2. Build command arguments
+        let args = self.build_args(&prompt, prompt_path.as_deref());
+        let input = match self.command.prompt {
+            PromptInput::Stdin => Some(prompt),
+            _ => None,
+        };
+
+        // 3. Spawn the program; stray non-JSON lines become warnings
+        let process = CliCommand::new(
+            self.command.program.clone(),
+            args,
+            self.runtime.resolve_dir(&context.project_path),
+        )
+        .with_env(self.runtime.env.clone())
+        .with_input(input)
+        .with_tolerant_parsing(true)
+        .spawn()?;
+
+        // 4. Map JSON lines to AgentEvents
+        let mapping = self.command.events.clone();
+        let events_stream = async_stream::stream! {
+            // Keep the prompt file until the program has finished
+            let _prompt_file = prompt_file;
+            let mut process = process;
+            while let Some(output) = process.next().await {
+                match output {
+                    Ok(CliOutput::Json(line)) => match map_line(&mapping, &line) {
+                        Some(Ok(AgentEvent::Completed)) => {
+                            yield Ok(AgentEvent::Completed);
+                            return;
+                        }
+                        Some(Err(e)) => {
+                            yield Err(e);
+                            return;
+                        }
+                        Some(event) => yield event,
+                        None => {}
+                    },
+                    Ok(CliOutput::Text(line)) => {
+                        yield Ok(AgentEvent::Warning(format!("Unexpected output: {}", line)));
+                    }
+                    Ok(CliOutput::Stderr(line)) => yield Ok(AgentEvent::Warning(line)),
+                    Err(e) => {
+                        yield Err(e);
+                        return;
+                    }
+                }
+            }
+
+            if mapping.completed.is_some() {
+                yield Err(AgentError::ExecutionError(
+                    "Command exited without reporting completion".to_string(),
+                ));
+            } else {
+                yield Ok(AgentEvent::Completed);
+            }
+        };
+
+        Ok(Box::pin(events_stream))
+    }
+}
+
+/// Write the prompt to a temporary file for `prompt: file`.
+fn write_prompt_file(prompt: &str) -> Result<tempfile::NamedTempFile, AgentError> {
+    let mut file = tempfile::NamedTempFile::new()
+        .map_err(|e| AgentError::ExecutionError(format!("Failed to create prompt file: {}", e)))?;
+    file.write_all(prompt.as_bytes())
+        .map_err(|e| AgentError::ExecutionError(format!("Failed to write prompt file: {}", e)))?;
+    Ok(file)
+}
+
+/// Convert one output line to an event using the configured mapping.
+///
+/// Returns `None` for lines that match no rule.
+fn map_line(mapping: &EventMapping, line: &Value) -> Option<Result<AgentEvent, AgentError>> {
+    let kind = lookup(line, &mapping.type_field).map(text)?;
+
+    if mapping.completed.as_deref() == Some(kind.as_str()) {
+        return Some(Ok(AgentEvent::Completed));
+    }
+    if let Some(rule) = mapping.error.as_ref().filter(|r| r.when == kind) {
+        let message = lookup(line, &rule.field).map(text).unwrap_or(kind);
+        return Some(Err(AgentError::ExecutionError(message)));
+    }
+    if let Some(rule) = mapping.message.as_ref().filter(|r| r.when == kind) {
+        let chunk = lookup(line, &rule.field).map(text)?;
+        return Some(Ok(AgentEvent::MessageChunk(chunk)));
+    }
+    if let Some(rule) = mapping.thought.as_ref().filter(|r| r.when == kind) {
+        let thought = lookup(line, &rule.field).map(text)?;
+        return Some(Ok(AgentEvent::Thought(thought)));
+    }
+    if let Some(rule) = mapping.tool_call.as_ref().filter(|r| r.when == kind) {
+        let name = lookup(line, &rule.name).map(text)?;
+        let arguments = rule
+            .arguments
+            .as_ref()
+            .and_then(|path| lookup(line, path))
+            .cloned()
+            .unwrap_or(Value::Null);
+        let id = rule
+            .id
+            .as_ref()
+            .and_then(|path| lookup(line, path))
+            .map(text);
+        return Some(Ok(AgentEvent::ToolCall {
+            id,
+            name,
+            arguments,
+        }));
+    }
+    if let Some(rule) = mapping.tool_result.as_ref().filter(|r| r.when == kind) {
+        let output = lookup(line, &rule.output).map(text).unwrap_or_default();
+        let id = rule
+            .id
+            .as_ref()
+            .and_then(|path| lookup(line, path))
+            .map(text);
+        let is_error = rule
+            .is_error
+            .as_ref()
+            .and_then(|path| lookup(line, path))
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        return Some(Ok(AgentEvent::ToolResult {
+            id,
+            output,
+            is_error,
+        }));
+    }
+
+    None
+}
+
+/// Look up a dot-separated field path (e.g. `part.text`) in a JSON value.
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, key| match value {
+        Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => value.get(key),
+    })
+}
+
+/// Render a JSON value as text; strings are taken verbatim.
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pk_protocol::agent_models::TextRule;
+    use pk_protocol::agent_models::ToolCallRule;
+    use pk_protocol::agent_models::ToolResultRule;
+    use serde_json::json;
+
+    fn command(args: &[&str], prompt: PromptInput) -> CustomCommand {
+        CustomCommand {
+            program: "my-agent".to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            protocol: CommandProtocol::JsonLines,
+            prompt,
+            events: EventMapping::default(),
+        }
+    }
+
+    fn adapter(command: CustomCommand) -> CustomAdapter {
+        CustomAdapter::new(
+            "custom".to_string(),
+            "local-model".to_string(),
+            String::new(),
+            command,
+        )
+        .unwrap()
+    }
+
+    fn mapping() -> EventMapping {
+        EventMapping {
+            type_field: "event".to_string(),
+            message: Some(TextRule {
+                when: "text".to_string(),
+                field: "part.text".to_string(),
+            }),
+            thought: None,
+            tool_call: Some(ToolCallRule {
+                when: "tool_use".to_string(),
+                name: "tool".to_string(),
+                arguments: Some("input".to_string()),
+                id: Some("id".to_string()),
+            }),
+            tool_result: Some(ToolResultRule {
+                when: "tool_result".to_string(),
+                output: "output".to_string(),
+                id: Some("id".to_string()),
+                is_error: Some("failed".to_string()),
+            }),
+            error: Some(TextRule {
+                when: "error".to_string(),
+                field: "message".to_string(),
+            }),
+            completed: Some("done".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_build_args_substitutes_placeholders() {
+        let adapter = adapter(command(
+            &["run", "--model", "{model}", "--message", "{prompt}"],
+            PromptInput::Arg,
+        ));
+        assert_eq!(
+            adapter.build_args("fix it", None),
+            ["run", "--model", "local-model", "--message", "fix it"]
+        );
+    }
+
+    #[test]
+    fn test_build_args_appends_prompt() {
+        let arg = adapter(command(&["run"], PromptInput::Arg));
+        assert_eq!(arg.build_args("fix it", None), ["run", "fix it"]);
+
+        let file = adapter(command(&["--file", "{prompt_file}"], PromptInput::File));
+        assert_eq!(
+            file.build_args("fix it", Some("/tmp/prompt.md")),
+            ["--file", "/tmp/prompt.md"]
+        );
+
+        // Stdin prompts never appear on the command line
+        let stdin = adapter(command(&["run", "{prompt}"], PromptInput::Stdin));
+        assert_eq!(stdin.build_args("fix it", None), ["run", "{prompt}"]);
+    }
+
+    #[test]
+    fn test_prompt_text_prepends_system_prompt() {
+        let mut adapter = adapter(command(&[], PromptInput::Stdin));
+        adapter.system_prompt = "You review code.".to_string();
+        assert_eq!(adapter.prompt_text("Review"), "You review code.\n\nReview");
+
+        adapter.command.args = vec!["--system".to_string(), "{system_prompt}".to_string()];
+        assert_eq!(adapter.prompt_text("Review"), "Review");
+    }
+
+    #[test]
+    fn test_map_line() {
+        let mapping = mapping();
+
+        assert!(matches!(
+            map_line(&mapping, &json!({"event": "text", "part": {"text": "Hi"}})),
+            Some(Ok(AgentEvent::MessageChunk(text))) if text == "Hi"
+        ));
+        assert!(matches!(
+            map_line(&mapping, &json!({"event": "tool_use", "tool": "bash", "id": 7, "input": {"cmd": "ls"}})),
+            Some(Ok(AgentEvent::ToolCall { id: Some(id), name, arguments }))
+                if id == "7" && name == "bash" && arguments["cmd"] == "ls"
+        ));
+        assert!(matches!(
+            map_line(
+                &mapping,
+                &json!({"event": "tool_result", "id": "7", "output": "boom", "failed": true})
+            ),
+            Some(Ok(AgentEvent::ToolResult { is_error: true, .. }))
+        ));
+        assert!(matches!(
+            map_line(&mapping, &json!({"event": "error", "message": "quota"})),
+            Some(Err(AgentError::ExecutionError(msg))) if msg == "quota"
+        ));
+        assert!(matches!(
+            map_line(&mapping, &json!({"event": "done"})),
+            Some(Ok(AgentEvent::Completed))
+        ));
+        assert!(map_line(&mapping, &json!({"event": "heartbeat"})).is_none());
+        assert!(map_line(&mapping, &json!({"other": 1})).is_none());
+    }
+
+    #[test]
+    fn test_lookup_paths() {
+        let value = json!({"a": {"b": [{"c": "deep"}]}});
+        assert_eq!(lookup(&value, "a.b.0.c"), Some(&json!("deep")));
+        assert_eq!(lookup(&value, "a.x"), None);
+    }
+
+    #[test]
+    fn test_rejects_permissions_and_max_turns() {
+        let permissions = ToolPermissions {
+            mode: Some(pk_protocol::agent_models::PermissionMode::ReadOnly),
+            ..ToolPermissions::default()
+        };
+        assert!(adapter(command(&[], PromptInput::Arg))
+            .with_permissions(permissions)
+            .is_err());
+
+        let runtime = RuntimeOptions {
+            max_turns: Some(3),
+            ..RuntimeOptions::default()
+        };
+        assert!(adapter(command(&[], PromptInput::Arg))
+            .with_runtime(runtime)
+            .is_err());
+    }
+
+    #[test]
+    fn test_acp_accepts_permission_mode() {
+        let mut command = command(&["--experimental-acp"], PromptInput::Arg);
+        command.protocol = CommandProtocol::Acp;
+        let permissions = ToolPermissions {
+            mode: Some(pk_protocol::agent_models::PermissionMode::ReadOnly),
+            ..ToolPermissions::default()
+        };
+        assert!(adapter(command.clone())
+            .with_permissions(permissions)
+            .is_ok());
+
+        let permissions = ToolPermissions {
+            allowed_tools: vec!["read".to_string()],
+            ..ToolPermissions::default()
+        };
+        assert!(adapter(command).with_permissions(permissions).is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_with_stdin_prompt() {
+        let mut command = command(
+            &[
+                "-c",
+                r#"read line; printf '{"event":"text","part":{"text":"%s"}}\n{"event":"done"}\n' "$line""#,
+            ],
+            PromptInput::Stdin,
+        );
+        command.program = "sh".to_string();
+        command.events = mapping();
+
+        let events: Vec<_> = adapter(command)
+            .execute(&ExecutionContext::new("ping".to_string()))
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], Ok(AgentEvent::MessageChunk(text)) if text == "ping"));
+        assert!(matches!(events[1], Ok(AgentEvent::Completed)));
+    }
+}
@@ -6,15 +6,20 @@
 use crate::agents::base::Agent;
 use crate::agents::base::AgentError;
 use crate::agents::base::AgentEvent;
+use crate::agents::base::Attachment;
 use crate::agents::base::ExecutionContext;
+use crate::agents::base::Requirements;
+use crate::agents::cli_executor::CliCommand;
+use crate::agents::cli_executor::CliOutput;
+use crate::agents::permissions::ToolPermissions;
+use crate::agents::runtime::RuntimeOptions;
 use async_trait::async_trait;
+use pk_protocol::agent_models::PermissionMode;
+use pk_protocol::process_models::TokenUsage;
 use serde::Deserialize;
 use serde::Serialize;
 use std::pin::Pin;
 use std::process::Stdio;
-use tokio::io::AsyncBufReadExt;
-use tokio::io::AsyncWriteExt;
-use tokio::io::BufReader;
 use tokio::process::Command;
 use tokio_stream::Stream;
 use tokio_stream::StreamExt;
@@ -27,6 +32,8 @@ pub struct GeminiAdapter {
     name: String,
     model: String,
     system_prompt: String,
+    permissions: ToolPermissions,
+    runtime: RuntimeOptions,
 }
 
 impl GeminiAdapter {
@@ -42,8 +49,51 @@ impl GeminiAdapter {
             name,
             model,
             system_prompt,
+            permissions: ToolPermissions::default(),
+            runtime: RuntimeOptions::default(),
         })
     }
+
+    /// Restrict the permission mode of this agent.
+    ///
+    /// The mode maps onto `--approval-mode`: `read-only` → `default` (tools
+    /// that need confirmation are refused when running unattended), `edit` →
+    /// `auto_edit`, `full` → `yolo`. Tool lists are not supported.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::Unsupported` if tool lists or permission rules
+    /// are configured.
+    pub fn with_permissions(mut self, permissions: ToolPermissions) -> Result<Self, AgentError> {
+        permissions.reject_tool_lists("Gemini")?;
+        permissions.reject_rules("Gemini")?;
+        self.permissions = permissions;
+        Ok(self)
+    }
+
+    /// Set how the Gemini CLI is launched.
+    ///
+    /// Environment, working directory and extra arguments apply to the
+    /// `gemini-cli` process. The CLI has no turn limit flag.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::Unsupported` if `max-turns` is configured.
+    pub fn with_runtime(mut self, runtime: RuntimeOptions) -> Result<Self, AgentError> {
+        runtime.reject_max_turns("Gemini")?;
+        self.runtime = runtime;
+        Ok(self)
+    }
+}
+
+/// `--approval-mode` arguments shared by the Gemini and Qwen CLIs.
+pub(crate) fn approval_mode_args(mode: Option<PermissionMode>) -> Vec<&'static str> {
+    match mode {
+        Some(PermissionMode::ReadOnly) => vec!["--approval-mode", "default"],
+        Some(PermissionMode::Edit) => vec!["--approval-mode", "auto_edit"],
+        Some(PermissionMode::Full) => vec!["--approval-mode", "yolo"],
+        None => Vec::new(),
+    }
 }
 
 #[async_trait]
@@ -65,33 +115,21 @@ impl Agent for GeminiAdapter {
         cli_available && api_key_available
     }
 
+    fn requirements(&self) -> Requirements {
+        Requirements {
+            binary: Some("gemini-cli".to_string()),
+            env_vars: vec!["GEMINI_API_KEY".to_string()],
+        }
+    }
+
     async fn execute(
         &self,
         context: &ExecutionContext,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<AgentEvent, AgentError>> + Send>>, AgentError>
     {
-        // 1. Spawn gemini-cli process with stdin/stdout pipes
-        let mut child = Command::new("gemini-cli")
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .current_dir(&context.project_path)
-            .spawn()
-            .map_err(|e| {
-                AgentError::ExecutionError(format!("Failed to spawn gemini-cli: {}", e))
-            })?;
-
-        let mut stdin = child
-            .stdin
-            .take()
-            .ok_or_else(|| AgentError::ExecutionError("Failed to capture stdin".to_string()))?;
-
-        let stdout = child
-            .stdout
-            .take()
-            .ok_or_else(|| AgentError::ExecutionError("Failed to capture stdout".to_string()))?;
-
-        // 2. Create JSON-RPC request
+        // 1. Create JSON-RPC request; files are inlined into the prompt and
+        //    images are not supported
+        context.check_attachments("Gemini", |a| matches!(a, Attachment::File { .. }))?;
         let request = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
             id: 1,
@@ -99,57 +137,56 @@ impl Agent for GeminiAdapter {
             params: GenerateParams {
                 model: self.model.clone(),
                 system: self.system_prompt.clone(),
-                prompt: context.instruction.clone(),
+                prompt: context.instruction_with_files(),
             },
         };
-
-        // 3. Send request to Gemini CLI
         let request_str = serde_json::to_string(&request).map_err(|e| {
             AgentError::ExecutionError(format!("Failed to serialize request: {}", e))
         })?;
 
-        stdin
-            .write_all(request_str.as_bytes())
-            .await
-            .map_err(|e| AgentError::ExecutionError(format!("Failed to write to stdin: {}", e)))?;
-        stdin
-            .write_all(b"\n")
-            .await
-            .map_err(|e| AgentError::ExecutionError(format!("Failed to write newline: {}", e)))?;
-        stdin
-            .flush()
-            .await
-            .map_err(|e| AgentError::ExecutionError(format!("Failed to flush stdin: {}", e)))?;
-
-        // Close stdin to signal end of input
-        drop(stdin);
-
-        // 4. Create stream from stdout
-        let reader = BufReader::new(stdout);
-        let lines = reader.lines();
-        let lines_stream = tokio_stream::wrappers::LinesStream::new(lines);
-
-        // 5. Parse JSON-RPC responses and convert to AgentEvents
-        let events_stream = lines_stream
-            .then(|line_result| async move {
-                match line_result {
-                    Ok(line) => {
-                        if line.trim().is_empty() {
-                            return None;
-                        }
-
-                        match serde_json::from_str::<JsonRpcResponse>(&line) {
-                            Ok(response) => convert_gemini_response(response),
-                            Err(e) => Some(Err(AgentError::StreamParseError(format!(
-                                "Failed to parse JSON-RPC response: {} (line: {})",
-                                e, line
-                            )))),
+        // 2. Spawn gemini-cli with the request on stdin, which is then closed
+        //    to signal end of input
+        let mut args: Vec<String> = approval_mode_args(self.permissions.mode)
+            .into_iter()
+            .map(String::from)
+            .collect();
+        args.extend(self.runtime.extra_args.iter().cloned());
+        let process = CliCommand::new(
+            "gemini-cli".to_string(),
+            args,
+            self.runtime.resolve_dir(&context.project_path),
+        )
+        .with_env(self.runtime.env.clone())
+        .with_input(Some(format!("{}\n", request_str)))
+        .with_tolerant_parsing(true)
+        .spawn()?;
+
+        // 3. Parse JSON-RPC responses and convert to AgentEvents
+        let events_stream = async_stream::stream! {
+            let mut process = process;
+            while let Some(output) = process.next().await {
+                match output {
+                    Ok(CliOutput::Json(value)) => {
+                        match serde_json::from_value::<JsonRpcResponse>(value.clone()) {
+                            Ok(response) => {
+                                for event in convert_gemini_response(response) {
+                                    yield event;
+                                }
+                            }
+                            Err(e) => yield Err(AgentError::StreamParseError(format!(
+                                "Failed to parse JSON-RPC response: {} (json: {})",
+                                e, value
+                            ))),
                         }
                     }
-                    Err(e) => Some(Err(AgentError::StreamParseError(e.to_string()))),
+                    Ok(CliOutput::Text(line)) => {
+                        yield Ok(AgentEvent::Warning(format!("Unexpected output: {}", line)));
+                    }
+                    Ok(CliOutput::Stderr(line)) => yield Ok(AgentEvent::Warning(line)),
+                    Err(e) => yield Err(e),
                 }
-            })
-            .filter_map(|opt| opt);
+            }
+        };
 
         Ok(Box::pin(events_stream))
     }
@@ -190,51 +227,94 @@ struct JsonRpcError {
     message: String,
 }
 
-/// Convert Gemini JSON-RPC response to AgentEvent.
-fn convert_gemini_response(response: JsonRpcResponse) -> Option<Result<AgentEvent, AgentError>> {
+/// Convert Gemini JSON-RPC response to AgentEvents.
+fn convert_gemini_response(response: JsonRpcResponse) -> Vec<Result<AgentEvent, AgentError>> {
     // Check for errors
     if let Some(error) = response.error {
-        return Some(Err(AgentError::ApiError(format!(
+        return vec![Err(AgentError::ApiError(format!(
             "Gemini API error (code {}): {}",
             error.code, error.message
-        ))));
+        )))];
     }
 
-    // Extract result
-    if let Some(result) = response.result {
-        // Try to extract text from different possible structures
+    // No result and no error - signal completion
+    let Some(result) = response.result else {
+        return vec![Ok(AgentEvent::Completed)];
+    };
 
-        // Try result.text
-        if let Some(text) = result.get("text").and_then(|t| t.as_str()) {
-            if !text.trim().is_empty() {
-                return Some(Ok(AgentEvent::MessageChunk(text.to_string())));
-            }
+    let mut events = Vec::new();
+
+    // Try to extract text from different possible structures
+    if let Some(text) = extract_text(&result) {
+        events.push(Ok(AgentEvent::MessageChunk(text)));
+    }
+
+    // Function calls in result.parts[].functionCall (Google AI format)
+    if let Some(parts) = result.get("parts").and_then(|p| p.as_array()) {
+        for call in parts.iter().filter_map(|part| part.get("functionCall")) {
+            events.push(Ok(AgentEvent::ToolCall {
+                id: call.get("id").and_then(|i| i.as_str()).map(str::to_string),
+                name: call
+                    .get("name")
+                    .and_then(|n| n.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                arguments: call.get("args").cloned().unwrap_or_default(),
+            }));
         }
+    }
 
-        // Try result.parts[].text (Google AI format)
-        if let Some(parts) = result.get("parts").and_then(|p| p.as_array()) {
-            for part in parts {
-                if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
-                    if !text.trim().is_empty() {
-                        return Some(Ok(AgentEvent::MessageChunk(text.to_string())));
-                    }
-                }
-            }
+    // Token counts in result.usageMetadata
+    if let Some(usage) = result.get("usageMetadata") {
+        let count = |key: &str| usage.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+        events.push(Ok(AgentEvent::Usage(TokenUsage {
+            input_tokens: count("promptTokenCount"),
+            output_tokens: count("candidatesTokenCount"),
+            cost_usd: None,
+            duration_ms: None,
+        })));
+    }
+
+    // If no content found but result exists, signal completion
+    let has_content = events.iter().any(|e| {
+        matches!(
+            e,
+            Ok(AgentEvent::MessageChunk(_) | AgentEvent::ToolCall { .. })
+        )
+    });
+    if !has_content {
+        events.push(Ok(AgentEvent::Completed));
+    }
+
+    events
+}
+
+/// Extract response text from the supported result shapes.
+fn extract_text(result: &serde_json::Value) -> Option<String> {
+    // Try result.text
+    if let Some(text) = result.get("text").and_then(|t| t.as_str()) {
+        if !text.trim().is_empty() {
+            return Some(text.to_string());
         }
+    }
 
-        // Try result.content (alternative format)
-        if let Some(content) = result.get("content").and_then(|c| c.as_str()) {
-            if !content.trim().is_empty() {
-                return Some(Ok(AgentEvent::MessageChunk(content.to_string())));
+    // Try result.parts[].text (Google AI format)
+    if let Some(parts) = result.get("parts").and_then(|p| p.as_array()) {
+        for part in parts {
+            if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+                if !text.trim().is_empty() {
+                    return Some(text.to_string());
+                }
             }
         }
-
-        // If no text found but result exists, signal completion
-        return Some(Ok(AgentEvent::Completed));
     }
 
-    // No result and no error - signal completion
-    Some(Ok(AgentEvent::Completed))
+    // Try result.content (alternative format)
+    result
+        .get("content")
+        .and_then(|c| c.as_str())
+        .filter(|content| !content.trim().is_empty())
+        .map(str::to_string)
 }
 
 #[cfg(test)]
@@ -296,10 +376,10 @@ mod tests {
             error: None,
         };
 
-        let event = convert_gemini_response(response);
-        assert!(event.is_some());
+        let mut events = convert_gemini_response(response);
+        assert_eq!(events.len(), 1);
 
-        let event = event.unwrap();
+        let event = events.remove(0);
         assert!(event.is_ok());
 
         match event.unwrap() {
@@ -320,10 +400,46 @@ mod tests {
             }),
         };
 
-        let event = convert_gemini_response(response);
-        assert!(event.is_some());
+        let mut events = convert_gemini_response(response);
+        assert_eq!(events.len(), 1);
 
-        let event = event.unwrap();
+        let event = events.remove(0);
         assert!(event.is_err());
     }
+
+    #[test]
+    fn test_convert_gemini_response_with_function_call_and_usage() {
+        let response = JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: 1,
+            result: Some(serde_json::json!({
+                "parts": [
+                    {"text": "Reading the file."},
+                    {"functionCall": {"name": "read_file", "args": {"path": "a.txt"}}}
+                ],
+                "usageMetadata": {"promptTokenCount": 20, "candidatesTokenCount": 7}
+            })),
+            error: None,
+        };
+
+        let events = convert_gemini_response(response);
+        assert_eq!(events.len(), 3);
+        assert_eq!(
+            events[0],
+            Ok(AgentEvent::MessageChunk("Reading the file.".to_string()))
+        );
+        assert!(matches!(
+            &events[1],
+            Ok(AgentEvent::ToolCall { name, arguments, .. })
+                if name == "read_file" && arguments["path"] == "a.txt"
+        ));
+        assert!(matches!(
+            events[2],
+            Ok(AgentEvent::Usage(TokenUsage {
+                input_tokens: 20,
+                output_tokens: 7,
+                ..
+            }))
+        ));
+    }
 }
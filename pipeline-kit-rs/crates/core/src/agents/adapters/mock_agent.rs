@@ -5,23 +5,33 @@ use crate::agents::base::AgentError;
 use crate::agents::base::AgentEvent;
 use crate::agents::base::ExecutionContext;
 use async_trait::async_trait;
+use pk_protocol::process_models::FileChangeKind;
+use pk_protocol::process_models::TokenUsage;
 use std::pin::Pin;
 use tokio_stream::Stream;
+use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct MockAgent {
     available: bool,
     events: Vec<Result<AgentEvent, AgentError>>,
+    /// Report a session, or echo the one being continued, before the events.
+    sessions: bool,
 }
 
 impl MockAgent {
     pub fn new(available: bool, events: Vec<Result<AgentEvent, AgentError>>) -> Self {
-        Self { available, events }
+        Self {
+            available,
+            events,
+            sessions: false,
+        }
     }
 
     pub fn success() -> Self {
         Self {
             available: true,
+            sessions: false,
             events: vec![
                 Ok(AgentEvent::Thought("Mock agent thinking".to_string())),
                 Ok(AgentEvent::MessageChunk("Mock response".to_string())),
@@ -30,9 +40,40 @@ impl MockAgent {
         }
     }
 
+    pub fn with_activity() -> Self {
+        Self {
+            available: true,
+            sessions: false,
+            events: vec![
+                Ok(AgentEvent::ToolCall {
+                    id: Some("call_1".to_string()),
+                    name: "write_file".to_string(),
+                    arguments: serde_json::json!({"path": "out.txt"}),
+                }),
+                Ok(AgentEvent::ToolResult {
+                    id: Some("call_1".to_string()),
+                    output: "ok".to_string(),
+                    is_error: false,
+                }),
+                Ok(AgentEvent::FileChanged {
+                    path: "out.txt".to_string(),
+                    kind: FileChangeKind::Created,
+                }),
+                Ok(AgentEvent::Usage(TokenUsage {
+                    input_tokens: 100,
+                    output_tokens: 50,
+                    cost_usd: Some(0.01),
+                    duration_ms: None,
+                })),
+                Ok(AgentEvent::Completed),
+            ],
+        }
+    }
+
     pub fn unavailable() -> Self {
         Self {
             available: false,
+            sessions: false,
             events: vec![],
         }
     }
@@ -40,12 +81,48 @@ impl MockAgent {
     pub fn failing() -> Self {
         Self {
             available: true,
+            sessions: false,
             events: vec![
                 Ok(AgentEvent::Thought("Starting...".to_string())),
                 Err(AgentError::ExecutionError("Mock failure".to_string())),
             ],
         }
     }
+
+    /// An agent whose API call fails with a retryable error after it
+    /// started, before producing any output.
+    pub fn rate_limited() -> Self {
+        Self {
+            available: true,
+            sessions: false,
+            events: vec![
+                Ok(AgentEvent::Warning("Mock retrying".to_string())),
+                Err(AgentError::ApiError("Mock rate limit".to_string())),
+            ],
+        }
+    }
+
+    /// An agent whose API call fails with a retryable error after it has
+    /// streamed part of its response.
+    pub fn interrupted() -> Self {
+        Self {
+            available: true,
+            sessions: false,
+            events: vec![
+                Ok(AgentEvent::MessageChunk("Partial response".to_string())),
+                Err(AgentError::ApiError("Mock connection reset".to_string())),
+            ],
+        }
+    }
+
+    /// A successful agent that starts a new session unless the context
+    /// continues one, in which case it echoes the session id.
+    pub fn with_sessions() -> Self {
+        Self {
+            sessions: true,
+            ..Self::success()
+        }
+    }
 }
 
 #[async_trait]
@@ -56,7 +133,7 @@ impl Agent for MockAgent {
 
     async fn execute(
         &self,
-        _context: &ExecutionContext,
+        context: &ExecutionContext,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<AgentEvent, AgentError>> + Send>>, AgentError>
     {
         if !self.available {
@@ -65,7 +142,14 @@ impl Agent for MockAgent {
             ));
         }
 
-        let events = self.events.clone();
+        let mut events = self.events.clone();
+        if self.sessions {
+            let session_event = match &context.session_id {
+                Some(id) => AgentEvent::MessageChunk(format!("Continuing session {}", id)),
+                None => AgentEvent::SessionStarted(Uuid::new_v4().to_string()),
+            };
+            events.insert(0, Ok(session_event));
+        }
         let stream = tokio_stream::iter(events);
         Ok(Box::pin(stream))
     }
@@ -128,7 +212,11 @@ mod tests {
     #[tokio::test]
     async fn test_mock_agent_custom_events() {
         let custom_events = vec![
-            Ok(AgentEvent::ToolCall("read_file".to_string())),
+            Ok(AgentEvent::ToolCall {
+                id: None,
+                name: "read_file".to_string(),
+                arguments: serde_json::Value::Null,
+            }),
             Ok(AgentEvent::MessageChunk("File content".to_string())),
             Ok(AgentEvent::Completed),
         ];
@@ -140,6 +228,6 @@ mod tests {
         let events: Vec<_> = stream.collect().await;
 
         assert_eq!(events.len(), 3);
-        assert!(matches!(events[0], Ok(AgentEvent::ToolCall(_))));
+        assert!(matches!(events[0], Ok(AgentEvent::ToolCall { .. })));
     }
 }
@@ -1,15 +1,21 @@
 //! Agent adapter implementations.
 
+mod anthropic_adapter;
 mod claude_adapter;
 mod codex_adapter;
 mod cursor_adapter;
+mod custom_adapter;
 mod gemini_adapter;
 pub mod mock_agent;
+mod openai_adapter;
 mod qwen_adapter;
 
+pub use anthropic_adapter::AnthropicAdapter;
 pub use claude_adapter::ClaudeAdapter;
 pub use codex_adapter::CodexAdapter;
 pub use cursor_adapter::CursorAdapter;
+pub use custom_adapter::CustomAdapter;
 pub use gemini_adapter::GeminiAdapter;
 pub use mock_agent::MockAgent;
+pub use openai_adapter::OpenAiAdapter;
 pub use qwen_adapter::QwenAdapter;
@@ -0,0 +1,452 @@
+//! OpenAI-compatible adapter calling `/chat/completions` over HTTP.
+//!
+//! Unlike the CLI adapters this one needs no locally installed tool, so it
+//! also works with local model servers (vLLM, llama.cpp, Ollama, LM Studio)
+//! that expose the same API.
+
+use crate::agents::base::read_image_base64;
+use crate::agents::base::Agent;
+use crate::agents::base::AgentError;
+use crate::agents::base::AgentEvent;
+use crate::agents::base::Attachment;
+use crate::agents::base::ExecutionContext;
+use crate::agents::base::Requirements;
+use crate::agents::permissions::ToolPermissions;
+use crate::agents::runtime::RuntimeOptions;
+use async_trait::async_trait;
+use pk_protocol::process_models::TokenUsage;
+use serde::Deserialize;
+use std::pin::Pin;
+use std::time::Duration;
+use std::time::Instant;
+use tokio_stream::Stream;
+use tokio_stream::StreamExt;
+
+/// Endpoint used when `base-url` is not set.
+const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Variable holding the API key when `api-key-env` is not set.
+const DEFAULT_API_KEY_ENV: &str = "OPENAI_API_KEY";
+
+/// How long `check_availability` waits for the server.
+const AVAILABILITY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// OpenAI-compatible adapter streaming chat completions over SSE.
+pub struct OpenAiAdapter {
+    #[allow(dead_code)]
+    name: String,
+    model: String,
+    system_prompt: String,
+    base_url: String,
+    /// Variable holding the API key, and whether it was configured
+    /// explicitly (and is therefore required).
+    api_key_env: String,
+    api_key_required: bool,
+    client: reqwest::Client,
+}
+
+impl OpenAiAdapter {
+    /// Create a new OpenAI-compatible adapter for the public OpenAI API.
+    pub fn new(name: String, model: String, system_prompt: String) -> Result<Self, AgentError> {
+        Ok(Self {
+            name,
+            model,
+            system_prompt,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            api_key_env: DEFAULT_API_KEY_ENV.to_string(),
+            api_key_required: false,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// Point the adapter at another server.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - API root including the version, e.g. `http://localhost:8000/v1`
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Read the API key from another environment variable.
+    ///
+    /// Unlike the default `OPENAI_API_KEY`, which may be absent for local
+    /// servers, an explicitly configured variable must be set.
+    pub fn with_api_key_env(mut self, api_key_env: String) -> Self {
+        self.api_key_env = api_key_env;
+        self.api_key_required = true;
+        self
+    }
+
+    /// Restrict the permission mode of this agent.
+    ///
+    /// The adapter exposes no tools to the model, so every permission mode
+    /// is satisfied; tool lists have nothing to filter.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::Unsupported` for tool lists or permission rules.
+    pub fn with_permissions(self, permissions: ToolPermissions) -> Result<Self, AgentError> {
+        permissions.reject_tool_lists("OpenAI")?;
+        permissions.reject_rules("OpenAI")?;
+        Ok(self)
+    }
+
+    /// Apply runtime settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::Unsupported` for `max-turns` and for settings
+    /// that only apply to a process (`env`, `working-dir`, `extra-args`).
+    pub fn with_runtime(self, runtime: RuntimeOptions) -> Result<Self, AgentError> {
+        runtime.reject_max_turns("OpenAI")?;
+        runtime.reject_process_options("OpenAI")?;
+        Ok(self)
+    }
+
+    /// Read the API key from the environment.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::NotAvailable` if an explicitly configured
+    /// variable is not set.
+    fn api_key(&self) -> Result<Option<String>, AgentError> {
+        match std::env::var(&self.api_key_env) {
+            Ok(key) if !key.is_empty() => Ok(Some(key)),
+            _ if self.api_key_required => Err(AgentError::NotAvailable(format!(
+                "environment variable {} is not set",
+                self.api_key_env
+            ))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Build the JSON body of a streaming chat completion request.
+    ///
+    /// # Arguments
+    ///
+    /// * `instruction` - The step instruction, with file attachments inlined
+    /// * `images` - `image_url` content parts sent after the instruction
+    fn request_body(&self, instruction: &str, images: Vec<serde_json::Value>) -> serde_json::Value {
+        let mut messages = Vec::new();
+        if !self.system_prompt.trim().is_empty() {
+            messages.push(serde_json::json!({
+                "role": "system",
+                "content": self.system_prompt,
+            }));
+        }
+        let content = if images.is_empty() {
+            serde_json::json!(instruction)
+        } else {
+            let mut parts = vec![serde_json::json!({ "type": "text", "text": instruction })];
+            parts.extend(images);
+            serde_json::Value::Array(parts)
+        };
+        messages.push(serde_json::json!({
+            "role": "user",
+            "content": content,
+        }));
+
+        serde_json::json!({
+            "model": self.model,
+            "messages": messages,
+            "stream": true,
+            "stream_options": { "include_usage": true },
+        })
+    }
+}
+
+#[async_trait]
+impl Agent for OpenAiAdapter {
+    async fn check_availability(&self) -> bool {
+        // The server is available if it answers the model listing
+        let Ok(api_key) = self.api_key() else {
+            return false;
+        };
+        let mut request = self
+            .client
+            .get(format!("{}/models", self.base_url))
+            .timeout(AVAILABILITY_TIMEOUT);
+        if let Some(key) = api_key {
+            request = request.bearer_auth(key);
+        }
+
+        match request.send().await {
+            Ok(response) => response.status().is_success(),
+            Err(_) => false,
+        }
+    }
+
+    fn requirements(&self) -> Requirements {
+        Requirements {
+            binary: None,
+            env_vars: if self.api_key_required {
+                vec![self.api_key_env.clone()]
+            } else {
+                vec![]
+            },
+        }
+    }
+
+    async fn execute(
+        &self,
+        context: &ExecutionContext,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<AgentEvent, AgentError>> + Send>>, AgentError>
+    {
+        // 1. Build the request; files are inlined and images sent as data URLs
+        let mut images = Vec::new();
+        for attachment in &context.attachments {
+            if let Attachment::Image { path, mime_type } = attachment {
+                images.push(serde_json::json!({
+                    "type": "image_url",
+                    "image_url": {
+                        "url": format!("data:{};base64,{}", mime_type, read_image_base64(path).await?),
+                    },
+                }));
+            }
+        }
+        let mut request = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .json(&self.request_body(&context.instruction_with_files(), images));
+        if let Some(key) = self.api_key()? {
+            request = request.bearer_auth(key);
+        }
+
+        // 2. Send it and check the status
+        let started = Instant::now();
+        let response = request
+            .send()
+            .await
+            .map_err(|e| AgentError::ApiError(format!("Request failed: {}", e)))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AgentError::ApiError(format!("HTTP {}: {}", status, body)));
+        }
+
+        // 3. Convert SSE chunks to AgentEvents
+        let events_stream = async_stream::stream! {
+            let mut bytes = response.bytes_stream();
+            let mut buffer = Vec::new();
+            let mut finished = false;
+
+            while let Some(chunk) = bytes.next().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        yield Err(AgentError::ApiError(format!("Stream interrupted: {}", e)));
+                        return;
+                    }
+                };
+                buffer.extend_from_slice(&chunk);
+
+                while let Some(data) = next_sse_data(&mut buffer) {
+                    if data == "[DONE]" {
+                        yield Ok(AgentEvent::Completed);
+                        return;
+                    }
+                    match serde_json::from_str::<ChatChunk>(&data) {
+                        Ok(chunk) => {
+                            for event in convert_chunk(chunk, started, &mut finished) {
+                                yield Ok(event);
+                            }
+                        }
+                        Err(e) => yield Err(AgentError::StreamParseError(format!(
+                            "Failed to parse chat completion chunk: {} (data: {})",
+                            e, data
+                        ))),
+                    }
+                }
+            }
+
+            // Some servers close the stream without sending [DONE]
+            if finished {
+                yield Ok(AgentEvent::Completed);
+            } else {
+                yield Err(AgentError::ApiError(
+                    "Stream ended before the completion finished".to_string(),
+                ));
+            }
+        };
+
+        Ok(Box::pin(events_stream))
+    }
+}
+
+/// Take the next complete `data:` payload out of an SSE buffer.
+///
+/// Comments, `event:`/`id:` fields and blank separator lines are skipped.
+/// An incomplete trailing line stays in the buffer, so a multi-byte
+/// character split across network chunks is decoded once it is whole.
+pub(crate) fn next_sse_data(buffer: &mut Vec<u8>) -> Option<String> {
+    while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+        let line: Vec<u8> = buffer.drain(..=end).collect();
+        let line = String::from_utf8_lossy(&line);
+        let line = line.trim_end_matches(['\n', '\r']);
+        if let Some(data) = line.strip_prefix("data:") {
+            return Some(data.trim_start().to_string());
+        }
+    }
+    None
+}
+
+/// One streamed chat completion chunk.
+#[derive(Debug, Deserialize)]
+struct ChatChunk {
+    #[serde(default)]
+    choices: Vec<ChatChoice>,
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    #[serde(default)]
+    delta: ChatDelta,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChatDelta {
+    content: Option<String>,
+    /// Reasoning text streamed by some local servers (e.g. vLLM, DeepSeek).
+    reasoning_content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatUsage {
+    prompt_tokens: u64,
+    completion_tokens: u64,
+}
+
+/// Convert a chat completion chunk to AgentEvents.
+///
+/// Sets `finished` once a choice reports a finish reason.
+fn convert_chunk(chunk: ChatChunk, started: Instant, finished: &mut bool) -> Vec<AgentEvent> {
+    let mut events = Vec::new();
+
+    for choice in chunk.choices {
+        if let Some(thought) = choice.delta.reasoning_content.filter(|t| !t.is_empty()) {
+            events.push(AgentEvent::Thought(thought));
+        }
+        if let Some(content) = choice.delta.content.filter(|c| !c.is_empty()) {
+            events.push(AgentEvent::MessageChunk(content));
+        }
+        if choice.finish_reason.is_some() {
+            *finished = true;
+        }
+    }
+
+    if let Some(usage) = chunk.usage {
+        events.push(AgentEvent::Usage(TokenUsage {
+            input_tokens: usage.prompt_tokens,
+            output_tokens: usage.completion_tokens,
+            cost_usd: None,
+            duration_ms: Some(started.elapsed().as_millis() as u64),
+        }));
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter() -> OpenAiAdapter {
+        OpenAiAdapter::new(
+            "test".to_string(),
+            "local-model".to_string(),
+            "Be brief.".to_string(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_request_body() {
+        let body = adapter().request_body("Say hi", vec![]);
+        assert_eq!(body["model"], "local-model");
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["messages"][1]["content"], "Say hi");
+    }
+
+    #[test]
+    fn test_request_body_with_images() {
+        let image = serde_json::json!({
+            "type": "image_url",
+            "image_url": { "url": "data:image/png;base64,AAAA" },
+        });
+        let body = adapter().request_body("Describe", vec![image.clone()]);
+        assert_eq!(
+            body["messages"][1]["content"],
+            serde_json::json!([{ "type": "text", "text": "Describe" }, image])
+        );
+    }
+
+    #[test]
+    fn test_base_url_trailing_slash() {
+        let adapter = adapter().with_base_url("http://localhost:8000/v1/".to_string());
+        assert_eq!(adapter.base_url, "http://localhost:8000/v1");
+    }
+
+    #[test]
+    fn test_explicit_api_key_env_is_required() {
+        let adapter = adapter().with_api_key_env("PK_TEST_UNSET_API_KEY".to_string());
+        assert!(matches!(
+            adapter.api_key(),
+            Err(AgentError::NotAvailable(msg)) if msg.contains("PK_TEST_UNSET_API_KEY")
+        ));
+    }
+
+    #[test]
+    fn test_next_sse_data() {
+        let mut buffer = b": keep-alive\nevent: message\ndata: {\"a\":1}\r\n\ndata: [DO".to_vec();
+        assert_eq!(next_sse_data(&mut buffer).as_deref(), Some("{\"a\":1}"));
+        // The partial line waits for more bytes
+        assert_eq!(next_sse_data(&mut buffer), None);
+        buffer.extend_from_slice(b"NE]\n\n");
+        assert_eq!(next_sse_data(&mut buffer).as_deref(), Some("[DONE]"));
+    }
+
+    #[test]
+    fn test_convert_chunk() {
+        let mut finished = false;
+        let chunk: ChatChunk = serde_json::from_str(
+            r#"{"choices":[{"delta":{"reasoning_content":"hmm","content":"Hi"},"finish_reason":null}]}"#,
+        )
+        .unwrap();
+        let events = convert_chunk(chunk, Instant::now(), &mut finished);
+        assert!(matches!(&events[0], AgentEvent::Thought(t) if t == "hmm"));
+        assert!(matches!(&events[1], AgentEvent::MessageChunk(t) if t == "Hi"));
+        assert!(!finished);
+
+        let chunk: ChatChunk = serde_json::from_str(
+            r#"{"choices":[],"usage":{"prompt_tokens":12,"completion_tokens":3,"total_tokens":15}}"#,
+        )
+        .unwrap();
+        let events = convert_chunk(chunk, Instant::now(), &mut finished);
+        assert!(matches!(
+            &events[0],
+            AgentEvent::Usage(usage) if usage.input_tokens == 12 && usage.output_tokens == 3
+        ));
+
+        let chunk: ChatChunk =
+            serde_json::from_str(r#"{"choices":[{"delta":{},"finish_reason":"stop"}]}"#).unwrap();
+        assert!(convert_chunk(chunk, Instant::now(), &mut finished).is_empty());
+        assert!(finished);
+    }
+
+    #[test]
+    fn test_rejects_process_options() {
+        let runtime = RuntimeOptions {
+            working_dir: Some("api".to_string()),
+            ..RuntimeOptions::default()
+        };
+        assert!(matches!(
+            adapter().with_runtime(runtime),
+            Err(AgentError::Unsupported(msg)) if msg.contains("working-dir")
+        ));
+    }
+}
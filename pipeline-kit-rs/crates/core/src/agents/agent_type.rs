@@ -1,5 +1,10 @@
 //! Agent type enumeration for determining which adapter to use.
 
+use anyhow::bail;
+use anyhow::Result;
+use pk_protocol::agent_models::Agent;
+use pk_protocol::agent_models::AgentProvider;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum AgentType {
     Claude,
@@ -7,10 +12,49 @@ pub enum AgentType {
     Gemini,
     Codex,
     Qwen,
+    OpenAi,
+    Anthropic,
+    Custom,
     Mock,
 }
 
 impl AgentType {
+    /// Determine the agent type for an agent configuration.
+    ///
+    /// An explicit `provider:` always wins; otherwise the type is inferred
+    /// from the model name.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The agent configuration
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no provider is set and the model name matches no
+    /// known pattern, or if the provider is not supported.
+    pub fn resolve(config: &Agent) -> Result<Self> {
+        match config.provider {
+            Some(AgentProvider::Claude) => Ok(Self::Claude),
+            Some(AgentProvider::Cursor) => Ok(Self::Cursor),
+            Some(AgentProvider::Gemini) => Ok(Self::Gemini),
+            Some(AgentProvider::Codex) => Ok(Self::Codex),
+            Some(AgentProvider::Qwen) => Ok(Self::Qwen),
+            Some(AgentProvider::OpenAi) => Ok(Self::OpenAi),
+            Some(AgentProvider::Anthropic) => Ok(Self::Anthropic),
+            Some(AgentProvider::Mock) => Ok(Self::Mock),
+            Some(AgentProvider::Custom) => Ok(Self::Custom),
+            None => match Self::from_model_name(&config.model) {
+                Some(agent_type) => Ok(agent_type),
+                None => bail!(
+                    "Agent '{}': cannot infer a provider from model '{}'; \
+                     set `provider:` to one of claude, cursor, gemini, codex, qwen, openai, anthropic, custom or mock",
+                    config.name,
+                    config.model
+                ),
+            },
+        }
+    }
+
     /// Infer the agent type from a model name.
     ///
     /// # Arguments
@@ -19,23 +63,25 @@ impl AgentType {
     ///
     /// # Returns
     ///
-    /// The inferred `AgentType`. Defaults to `Mock` if the model doesn't match any known pattern.
+    /// The inferred `AgentType`, or `None` if the model doesn't match any
+    /// known pattern. The HTTP providers, `Custom` and `Mock` are never
+    /// inferred; they must be requested with `provider:`.
     ///
     /// # Examples
     ///
     /// ```
     /// use pk_core::agents::AgentType;
     ///
-    /// assert_eq!(AgentType::from_model_name("claude-sonnet-4.5"), AgentType::Claude);
-    /// assert_eq!(AgentType::from_model_name("gpt-5"), AgentType::Cursor);
-    /// assert_eq!(AgentType::from_model_name("gemini-2.5-pro"), AgentType::Gemini);
-    /// assert_eq!(AgentType::from_model_name("unknown-model"), AgentType::Mock);
+    /// assert_eq!(AgentType::from_model_name("claude-sonnet-4.5"), Some(AgentType::Claude));
+    /// assert_eq!(AgentType::from_model_name("gpt-5"), Some(AgentType::Cursor));
+    /// assert_eq!(AgentType::from_model_name("gemini-2.5-pro"), Some(AgentType::Gemini));
+    /// assert_eq!(AgentType::from_model_name("unknown-model"), None);
     /// ```
-    pub fn from_model_name(model: &str) -> Self {
+    pub fn from_model_name(model: &str) -> Option<Self> {
         let model_lower = model.to_lowercase();
 
         if model_lower.contains("claude") {
-            Self::Claude
+            Some(Self::Claude)
         } else if model_lower.starts_with("gpt")
             || model_lower.contains("cursor")
             || model_lower.starts_with("sonnet")
@@ -43,16 +89,15 @@ impl AgentType {
         {
             // Cursor uses GPT models and also has cursor-specific names
             // Also handles sonnet-4.5, opus-4.1 which are Cursor shorthand
-            Self::Cursor
+            Some(Self::Cursor)
         } else if model_lower.contains("gemini") {
-            Self::Gemini
+            Some(Self::Gemini)
         } else if model_lower.contains("codex") {
-            Self::Codex
+            Some(Self::Codex)
         } else if model_lower.contains("qwen") {
-            Self::Qwen
+            Some(Self::Qwen)
         } else {
-            // Default to Mock for unknown models
-            Self::Mock
+            None
         }
     }
 
@@ -64,6 +109,9 @@ impl AgentType {
             Self::Gemini => "Gemini",
             Self::Codex => "Codex",
             Self::Qwen => "Qwen",
+            Self::OpenAi => "OpenAI",
+            Self::Anthropic => "Anthropic",
+            Self::Custom => "Custom",
             Self::Mock => "Mock",
         }
     }
@@ -77,64 +125,130 @@ mod tests {
     fn test_from_model_name_claude() {
         assert_eq!(
             AgentType::from_model_name("claude-sonnet-4.5"),
-            AgentType::Claude
+            Some(AgentType::Claude)
         );
         assert_eq!(
             AgentType::from_model_name("claude-opus-4.1"),
-            AgentType::Claude
+            Some(AgentType::Claude)
         );
         assert_eq!(
             AgentType::from_model_name("Claude-Haiku-3.5"),
-            AgentType::Claude
+            Some(AgentType::Claude)
+        );
+        assert_eq!(
+            AgentType::from_model_name("claude"),
+            Some(AgentType::Claude)
         );
-        assert_eq!(AgentType::from_model_name("claude"), AgentType::Claude);
     }
 
     #[test]
     fn test_from_model_name_cursor() {
-        assert_eq!(AgentType::from_model_name("gpt-5"), AgentType::Cursor);
-        assert_eq!(AgentType::from_model_name("gpt-4o"), AgentType::Cursor);
+        assert_eq!(AgentType::from_model_name("gpt-5"), Some(AgentType::Cursor));
+        assert_eq!(
+            AgentType::from_model_name("gpt-4o"),
+            Some(AgentType::Cursor)
+        );
         assert_eq!(
             AgentType::from_model_name("cursor-model"),
-            AgentType::Cursor
+            Some(AgentType::Cursor)
+        );
+        assert_eq!(
+            AgentType::from_model_name("sonnet-4.5"),
+            Some(AgentType::Cursor)
+        );
+        assert_eq!(
+            AgentType::from_model_name("opus-4.1"),
+            Some(AgentType::Cursor)
         );
-        assert_eq!(AgentType::from_model_name("sonnet-4.5"), AgentType::Cursor);
-        assert_eq!(AgentType::from_model_name("opus-4.1"), AgentType::Cursor);
     }
 
     #[test]
     fn test_from_model_name_gemini() {
         assert_eq!(
             AgentType::from_model_name("gemini-2.5-pro"),
-            AgentType::Gemini
+            Some(AgentType::Gemini)
         );
         assert_eq!(
             AgentType::from_model_name("gemini-2.5-flash"),
-            AgentType::Gemini
+            Some(AgentType::Gemini)
+        );
+        assert_eq!(
+            AgentType::from_model_name("Gemini-Pro"),
+            Some(AgentType::Gemini)
         );
-        assert_eq!(AgentType::from_model_name("Gemini-Pro"), AgentType::Gemini);
     }
 
     #[test]
     fn test_from_model_name_codex() {
-        assert_eq!(AgentType::from_model_name("codex-model"), AgentType::Codex);
-        assert_eq!(AgentType::from_model_name("openai-codex"), AgentType::Codex);
+        assert_eq!(
+            AgentType::from_model_name("codex-model"),
+            Some(AgentType::Codex)
+        );
+        assert_eq!(
+            AgentType::from_model_name("openai-codex"),
+            Some(AgentType::Codex)
+        );
     }
 
     #[test]
     fn test_from_model_name_qwen() {
-        assert_eq!(AgentType::from_model_name("qwen-coder"), AgentType::Qwen);
+        assert_eq!(
+            AgentType::from_model_name("qwen-coder"),
+            Some(AgentType::Qwen)
+        );
         assert_eq!(
             AgentType::from_model_name("Qwen3-Coder-Plus"),
-            AgentType::Qwen
+            Some(AgentType::Qwen)
         );
     }
 
     #[test]
     fn test_from_model_name_unknown() {
-        assert_eq!(AgentType::from_model_name("unknown-model"), AgentType::Mock);
-        assert_eq!(AgentType::from_model_name(""), AgentType::Mock);
-        assert_eq!(AgentType::from_model_name("random-string"), AgentType::Mock);
+        assert_eq!(AgentType::from_model_name("unknown-model"), None);
+        assert_eq!(AgentType::from_model_name(""), None);
+        assert_eq!(AgentType::from_model_name("random-string"), None);
+        assert_eq!(AgentType::from_model_name("test-model"), None);
+    }
+
+    fn config(model: &str, provider: Option<AgentProvider>) -> Agent {
+        Agent {
+            name: "agent".to_string(),
+            model: model.to_string(),
+            provider,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_resolve_prefers_provider() {
+        // "sonnet-4.5" alone would be inferred as Cursor
+        assert_eq!(
+            AgentType::resolve(&config("sonnet-4.5", Some(AgentProvider::Claude))).unwrap(),
+            AgentType::Claude
+        );
+        assert_eq!(
+            AgentType::resolve(&config("sonnet-4.5", None)).unwrap(),
+            AgentType::Cursor
+        );
+        assert_eq!(
+            AgentType::resolve(&config("my-local-model", Some(AgentProvider::Qwen))).unwrap(),
+            AgentType::Qwen
+        );
+        assert_eq!(
+            AgentType::resolve(&config("aider", Some(AgentProvider::Custom))).unwrap(),
+            AgentType::Custom
+        );
+    }
+
+    #[test]
+    fn test_resolve_mock_must_be_explicit() {
+        assert_eq!(
+            AgentType::resolve(&config("test-model", Some(AgentProvider::Mock))).unwrap(),
+            AgentType::Mock
+        );
+
+        let err = AgentType::resolve(&config("claud-sonet", None)).unwrap_err();
+        assert!(err.to_string().contains("cannot infer a provider"));
     }
 
     #[test]
@@ -144,6 +258,9 @@ mod tests {
         assert_eq!(AgentType::Gemini.name(), "Gemini");
         assert_eq!(AgentType::Codex.name(), "Codex");
         assert_eq!(AgentType::Qwen.name(), "Qwen");
+        assert_eq!(AgentType::OpenAi.name(), "OpenAI");
+        assert_eq!(AgentType::Anthropic.name(), "Anthropic");
+        assert_eq!(AgentType::Custom.name(), "Custom");
         assert_eq!(AgentType::Mock.name(), "Mock");
     }
 
@@ -1,12 +1,17 @@
 //! Base Agent trait and supporting types.
 
+use crate::agents::permissions::PermissionReply;
 use async_trait::async_trait;
+use base64::Engine;
+use pk_protocol::process_models::FileChangeKind;
+use pk_protocol::process_models::TokenUsage;
+use std::path::Path;
 use std::pin::Pin;
 use thiserror::Error;
 use tokio_stream::Stream;
 
 /// Attachment types that can be included with an instruction.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Attachment {
     /// Image attachment with path and MIME type.
     Image { path: String, mime_type: String },
@@ -14,6 +19,73 @@ pub enum Attachment {
     File { path: String, content: String },
 }
 
+impl Attachment {
+    /// Load the file at `path`, resolved against `base_dir`.
+    ///
+    /// Images (by extension) keep their resolved path and are read when an
+    /// adapter sends them; any other file is read as text now and keeps the
+    /// path as given.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::ExecutionError` if the file is missing or is not
+    /// valid UTF-8 text.
+    pub fn from_path(path: &str, base_dir: &Path) -> Result<Self, AgentError> {
+        let full = base_dir.join(path);
+        if let Some(mime_type) = image_mime_type(&full) {
+            if !full.is_file() {
+                return Err(AgentError::ExecutionError(format!(
+                    "Attachment '{}' not found",
+                    path
+                )));
+            }
+            return Ok(Attachment::Image {
+                path: full.to_string_lossy().to_string(),
+                mime_type: mime_type.to_string(),
+            });
+        }
+        let content = std::fs::read_to_string(&full).map_err(|e| {
+            AgentError::ExecutionError(format!("Failed to read attachment '{}': {}", path, e))
+        })?;
+        Ok(Attachment::File {
+            path: path.to_string(),
+            content,
+        })
+    }
+
+    /// Short name of the attachment type, used in error messages.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Attachment::Image { .. } => "image",
+            Attachment::File { .. } => "file",
+        }
+    }
+}
+
+/// MIME type of an image file, judged by its extension.
+fn image_mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Read an image attachment and encode it as base64 for inline delivery.
+///
+/// # Errors
+///
+/// Returns `AgentError::ExecutionError` if the file cannot be read.
+pub async fn read_image_base64(path: &str) -> Result<String, AgentError> {
+    let data = tokio::fs::read(path).await.map_err(|e| {
+        AgentError::ExecutionError(format!("Failed to read image '{}': {}", path, e))
+    })?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(data))
+}
+
 /// Context information passed to agents during execution.
 #[derive(Debug, Clone)]
 pub struct ExecutionContext {
@@ -28,6 +100,9 @@ pub struct ExecutionContext {
 
     /// Additional context (images, files, etc.).
     pub attachments: Vec<Attachment>,
+
+    /// Conversation to continue, as reported earlier by the same agent.
+    pub session_id: Option<String>,
 }
 
 impl ExecutionContext {
@@ -37,6 +112,7 @@ impl ExecutionContext {
     /// - project_path: current directory
     /// - is_initial_prompt: false
     /// - attachments: empty
+    /// - session_id: none (start a new conversation)
     pub fn new(instruction: String) -> Self {
         Self {
             instruction,
@@ -46,6 +122,7 @@ impl ExecutionContext {
                 .unwrap_or_else(|| ".".to_string()),
             is_initial_prompt: false,
             attachments: vec![],
+            session_id: None,
         }
     }
 
@@ -72,13 +149,161 @@ impl ExecutionContext {
         self.attachments.extend(attachments);
         self
     }
+
+    /// The prompt text for CLIs that take no separate system prompt.
+    ///
+    /// A new conversation gets the system prompt ahead of the instruction; a
+    /// continued one already has it, so only the instruction is sent.
+    /// Attachments are not included.
+    pub fn prompt_with_system(&self, system_prompt: &str) -> String {
+        self.system_prefixed(system_prompt, self.instruction.clone())
+    }
+
+    /// Like `prompt_with_system`, with file attachments inlined as in
+    /// `instruction_with_files`.
+    pub fn prompt_with_system_and_files(&self, system_prompt: &str) -> String {
+        self.system_prefixed(system_prompt, self.instruction_with_files())
+    }
+
+    fn system_prefixed(&self, system_prompt: &str, text: String) -> String {
+        if self.session_id.is_some() || system_prompt.trim().is_empty() {
+            text
+        } else {
+            format!("{}\n\n{}", system_prompt, text)
+        }
+    }
+
+    /// The instruction preceded by the content of each file attachment, for
+    /// agents that only take text.
+    ///
+    /// Each file is wrapped in a `<file path="...">` block. Image
+    /// attachments are left out; see `image_paths`.
+    pub fn instruction_with_files(&self) -> String {
+        let mut text = String::new();
+        for attachment in &self.attachments {
+            if let Attachment::File { path, content } = attachment {
+                text.push_str(&format!("<file path=\"{}\">\n{}", path, content));
+                if !content.ends_with('\n') {
+                    text.push('\n');
+                }
+                text.push_str("</file>\n\n");
+            }
+        }
+        text.push_str(&self.instruction);
+        text
+    }
+
+    /// Paths of the image attachments, in order.
+    pub fn image_paths(&self) -> Vec<&str> {
+        self.attachments
+            .iter()
+            .filter_map(|attachment| match attachment {
+                Attachment::Image { path, .. } => Some(path.as_str()),
+                Attachment::File { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Fail if any attachment is of a type the adapter cannot deliver.
+    ///
+    /// # Arguments
+    ///
+    /// * `adapter` - Adapter name used in the error (e.g. "Cursor")
+    /// * `supported` - Whether the adapter can deliver the given attachment
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::Unsupported` naming the first attachment that
+    /// is not supported.
+    pub fn check_attachments(
+        &self,
+        adapter: &str,
+        supported: impl Fn(&Attachment) -> bool,
+    ) -> Result<(), AgentError> {
+        match self.attachments.iter().find(|a| !supported(a)) {
+            Some(attachment) => Err(AgentError::Unsupported(format!(
+                "{} cannot receive {} attachments",
+                adapter,
+                attachment.kind()
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Continue the given conversation instead of starting a new one.
+    pub fn with_session_id(mut self, session_id: Option<String>) -> Self {
+        self.session_id = session_id;
+        self
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Events streamed by an agent while it executes an instruction.
+#[derive(Debug, Clone, PartialEq)]
 pub enum AgentEvent {
+    /// Internal reasoning the agent chose to surface.
     Thought(String),
-    ToolCall(String),
+
+    /// The agent invoked a tool.
+    ToolCall {
+        /// Provider-assigned id used to correlate the matching `ToolResult`.
+        id: Option<String>,
+        /// Tool name (e.g. `Bash`, `Edit`).
+        name: String,
+        /// Tool arguments as reported by the agent.
+        arguments: serde_json::Value,
+    },
+
+    /// A tool call finished.
+    ToolResult {
+        /// Id of the `ToolCall` this result belongs to.
+        id: Option<String>,
+        /// Tool output.
+        output: String,
+        /// Whether the tool reported a failure.
+        is_error: bool,
+    },
+
+    /// A piece of the agent's response text.
     MessageChunk(String),
+
+    /// Token and cost usage for the invocation.
+    Usage(TokenUsage),
+
+    /// The agent created, modified or deleted a file.
+    FileChanged { path: String, kind: FileChangeKind },
+
+    /// A non-fatal problem (e.g. an unparseable line) the user should know about.
+    Warning(String),
+
+    /// The agent waits for permission to run a tool the configured rules do
+    /// not decide.
+    PermissionRequest {
+        /// Tool kind (e.g. `execute`).
+        tool: String,
+        /// Human-readable description of the call.
+        title: String,
+        /// Tool arguments as reported by the agent.
+        arguments: serde_json::Value,
+        /// Where the answer goes; dropping it denies the request.
+        reply: PermissionReply,
+    },
+
+    /// The agent's conversation id, which a later `ExecutionContext` can
+    /// pass back to continue the conversation.
+    SessionStarted(String),
+
+    /// The requested agent failed and the rest of the step is served by a
+    /// fallback agent.
+    FallbackUsed {
+        /// The agent that failed.
+        from: String,
+        /// The agent taking over.
+        to: String,
+        /// Why `from` was given up on.
+        reason: String,
+    },
+
+    /// The agent finished the instruction.
     Completed,
 }
 
@@ -92,6 +317,30 @@ pub enum AgentError {
     StreamParseError(String),
     #[error("Execution failed: {0}")]
     ExecutionError(String),
+    #[error("Unsupported configuration: {0}")]
+    Unsupported(String),
+}
+
+impl AgentError {
+    /// Whether another agent might succeed where this one failed.
+    ///
+    /// Unavailable agents and failed API calls (rate limits, outages) are
+    /// worth a fallback; errors in the agent's own work are not.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, AgentError::NotAvailable(_) | AgentError::ApiError(_))
+    }
+}
+
+/// What an agent needs from the environment to run.
+///
+/// Reported by `pipeline-kit doctor`; `check_availability` stays the
+/// authority on whether the agent can run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Requirements {
+    /// Program the adapter spawns, if any.
+    pub binary: Option<String>,
+    /// Environment variables that must be set.
+    pub env_vars: Vec<String>,
 }
 
 #[async_trait]
@@ -101,6 +350,12 @@ pub trait Agent: Send + Sync {
         &self,
         context: &ExecutionContext,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<AgentEvent, AgentError>> + Send>>, AgentError>;
+
+    /// What the agent needs from the environment. Agents that spawn nothing
+    /// and need no credentials keep the default.
+    fn requirements(&self) -> Requirements {
+        Requirements::default()
+    }
 }
 
 #[cfg(test)]
@@ -141,6 +396,76 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_prompt_with_system_only_for_new_sessions() {
+        let context = ExecutionContext::new("Fix the bug".to_string());
+        assert_eq!(
+            context.prompt_with_system("You are a developer."),
+            "You are a developer.\n\nFix the bug"
+        );
+        assert_eq!(context.prompt_with_system("  "), "Fix the bug");
+
+        let context = context.with_session_id(Some("s-1".to_string()));
+        assert_eq!(
+            context.prompt_with_system("You are a developer."),
+            "Fix the bug"
+        );
+    }
+
+    #[test]
+    fn test_instruction_with_files_inlines_file_attachments() {
+        let context = ExecutionContext::new("Review it".to_string()).with_attachments(vec![
+            Attachment::File {
+                path: "docs/style.md".to_string(),
+                content: "Use tabs".to_string(),
+            },
+            Attachment::Image {
+                path: "/tmp/shot.png".to_string(),
+                mime_type: "image/png".to_string(),
+            },
+        ]);
+
+        assert_eq!(
+            context.instruction_with_files(),
+            "<file path=\"docs/style.md\">\nUse tabs\n</file>\n\nReview it"
+        );
+        assert_eq!(context.image_paths(), vec!["/tmp/shot.png"]);
+        assert_eq!(
+            context.prompt_with_system("Be brief."),
+            "Be brief.\n\nReview it"
+        );
+
+        let result = context.check_attachments("Test", |a| matches!(a, Attachment::File { .. }));
+        assert!(matches!(
+            result,
+            Err(AgentError::Unsupported(msg)) if msg == "Test cannot receive image attachments"
+        ));
+    }
+
+    #[test]
+    fn test_attachment_from_path() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.md"), "# Notes").unwrap();
+        std::fs::write(dir.path().join("shot.PNG"), [0x89, 0x50]).unwrap();
+
+        assert_eq!(
+            Attachment::from_path("notes.md", dir.path()).unwrap(),
+            Attachment::File {
+                path: "notes.md".to_string(),
+                content: "# Notes".to_string(),
+            }
+        );
+        assert_eq!(
+            Attachment::from_path("shot.PNG", dir.path()).unwrap(),
+            Attachment::Image {
+                path: dir.path().join("shot.PNG").to_string_lossy().to_string(),
+                mime_type: "image/png".to_string(),
+            }
+        );
+        assert!(Attachment::from_path("missing.md", dir.path()).is_err());
+        assert!(Attachment::from_path("missing.png", dir.path()).is_err());
+    }
+
     #[tokio::test]
     async fn test_agent_check_availability() {
         let available_agent = TestAgent { available: true };
@@ -214,5 +539,17 @@ mod tests {
         let chunk1 = AgentEvent::MessageChunk("test".to_string());
         let chunk2 = AgentEvent::MessageChunk("test".to_string());
         assert_eq!(chunk1, chunk2);
+
+        let call1 = AgentEvent::ToolCall {
+            id: Some("1".to_string()),
+            name: "Bash".to_string(),
+            arguments: serde_json::json!({"command": "ls"}),
+        };
+        let call2 = AgentEvent::ToolCall {
+            id: Some("2".to_string()),
+            name: "Bash".to_string(),
+            arguments: serde_json::json!({"command": "ls"}),
+        };
+        assert_ne!(call1, call2);
     }
 }
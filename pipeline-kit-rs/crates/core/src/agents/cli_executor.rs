@@ -2,14 +2,300 @@
 //!
 //! This module provides a unified interface for executing CLI-based agents
 //! and parsing their JSON Lines / NDJSON output streams.
+//!
+//! `CliCommand` spawns the process and returns a `CliProcess`: a stream of
+//! stdout lines (parsed as JSON) interleaved with stderr lines, ending in an
+//! error if the process exits unsuccessfully. `CliExecutor` wraps it for
+//! callers that only want the JSON values.
 
 use crate::agents::base::AgentError;
+use std::collections::HashMap;
+use std::collections::VecDeque;
 use std::pin::Pin;
 use std::process::Stdio;
+use std::task::Context;
+use std::task::Poll;
 use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWriteExt;
 use tokio::io::BufReader;
 use tokio::process::Command;
+use tokio::sync::mpsc;
 use tokio_stream::Stream;
+use tokio_stream::StreamExt;
+
+/// Number of trailing stderr lines included in the error for a failed exit.
+const STDERR_TAIL_LINES: usize = 20;
+
+/// Parts of environment variable names that mark their values as secrets.
+const SECRET_NAME_MARKERS: [&str; 5] = ["KEY", "TOKEN", "SECRET", "PASSWORD", "CREDENTIAL"];
+
+/// Secret values shorter than this are too likely to occur in an argument by
+/// chance to be checked.
+const MIN_SECRET_LEN: usize = 8;
+
+/// One line of output from a CLI process.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliOutput {
+    /// A stdout line, parsed as JSON.
+    Json(serde_json::Value),
+    /// A stdout line that is not JSON (tolerant mode only).
+    Text(String),
+    /// A line the process wrote to stderr.
+    Stderr(String),
+}
+
+/// A CLI invocation, configured builder-style and started with `spawn`.
+///
+/// # Example
+///
+/// ```no_run
+/// use pk_core::agents::cli_executor::CliCommand;
+/// use tokio_stream::StreamExt;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut process = CliCommand::new("cat".to_string(), vec![], ".".to_string())
+///         .with_input(Some("{\"type\":\"test\"}\n".to_string()))
+///         .spawn()
+///         .unwrap();
+///
+///     println!("Started pid {:?}", process.pid());
+///     while let Some(output) = process.next().await {
+///         println!("{:?}", output);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CliCommand {
+    program: String,
+    args: Vec<String>,
+    working_dir: String,
+    env: HashMap<String, String>,
+    input: Option<String>,
+    tolerant: bool,
+}
+
+impl CliCommand {
+    /// Create a command running `program` with `args` in `working_dir`.
+    pub fn new(program: String, args: Vec<String>, working_dir: String) -> Self {
+        Self {
+            program,
+            args,
+            working_dir,
+            env: HashMap::new(),
+            input: None,
+            tolerant: false,
+        }
+    }
+
+    /// Add environment variables on top of the inherited environment.
+    pub fn with_env(mut self, env: HashMap<String, String>) -> Self {
+        self.env.extend(env);
+        self
+    }
+
+    /// Write `input` to the process's stdin, which is then closed so the
+    /// program sees end of input.
+    pub fn with_input(mut self, input: Option<String>) -> Self {
+        self.input = input;
+        self
+    }
+
+    /// Report stdout lines that are not JSON as `CliOutput::Text` instead of
+    /// `AgentError::StreamParseError`.
+    pub fn with_tolerant_parsing(mut self, tolerant: bool) -> Self {
+        self.tolerant = tolerant;
+        self
+    }
+
+    /// Spawn the process.
+    ///
+    /// The process is killed if the returned `CliProcess` is dropped before
+    /// it finishes (e.g. when a step times out).
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::ExecutionError` if the program cannot be started,
+    /// or if an argument contains the value of a secret environment variable
+    /// (argv is visible to every user through `ps`).
+    pub fn spawn(self) -> Result<CliProcess, AgentError> {
+        // 1. Credentials go through the environment, never the command line
+        if let Some(name) = secret_in_args(&self.args, &self.env) {
+            return Err(AgentError::ExecutionError(format!(
+                "Refusing to start '{}': an argument contains the value of {}",
+                self.program, name
+            )));
+        }
+
+        // 2. Build and spawn the command
+        let mut cmd = Command::new(&self.program);
+        cmd.args(&self.args);
+        cmd.envs(&self.env);
+        cmd.current_dir(&self.working_dir);
+        cmd.kill_on_drop(true);
+        if self.input.is_some() {
+            cmd.stdin(Stdio::piped());
+        } else {
+            cmd.stdin(Stdio::null());
+        }
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+
+        let mut child = cmd.spawn().map_err(|e| {
+            AgentError::ExecutionError(format!("Failed to spawn command '{}': {}", self.program, e))
+        })?;
+        let pid = child.id();
+
+        // 3. Feed stdin from a separate task so a large input cannot block
+        //    reading stdout
+        if let (Some(input), Some(mut stdin)) = (self.input, child.stdin.take()) {
+            tokio::spawn(async move {
+                let _ = stdin.write_all(input.as_bytes()).await;
+            });
+        }
+
+        // 4. Drain stderr continuously so a chatty program never blocks on a
+        //    full pipe
+        let (stderr_tx, mut stderr_rx) = mpsc::unbounded_channel::<String>();
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    if stderr_tx.send(line).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| AgentError::ExecutionError("Failed to capture stdout".to_string()))?;
+
+        // 5. Interleave stdout and stderr lines until both are closed, then
+        //    check the exit status
+        let program = self.program;
+        let tolerant = self.tolerant;
+        let stream = async_stream::stream! {
+            let mut lines = BufReader::new(stdout).lines();
+            let mut stdout_open = true;
+            let mut stderr_open = true;
+            let mut stderr_tail = VecDeque::with_capacity(STDERR_TAIL_LINES);
+
+            while stdout_open || stderr_open {
+                tokio::select! {
+                    line = lines.next_line(), if stdout_open => match line {
+                        Ok(Some(line)) => {
+                            if let Some(output) = parse_line(&line, tolerant) {
+                                yield output;
+                            }
+                        }
+                        _ => stdout_open = false,
+                    },
+                    line = stderr_rx.recv(), if stderr_open => match line {
+                        Some(line) => {
+                            if line.trim().is_empty() {
+                                continue;
+                            }
+                            if stderr_tail.len() == STDERR_TAIL_LINES {
+                                stderr_tail.pop_front();
+                            }
+                            stderr_tail.push_back(line.clone());
+                            yield Ok(CliOutput::Stderr(line));
+                        }
+                        None => stderr_open = false,
+                    },
+                }
+            }
+
+            match child.wait().await {
+                Ok(status) if status.success() => {}
+                Ok(status) => {
+                    let mut message = format!("'{}' exited with {}", program, status);
+                    if !stderr_tail.is_empty() {
+                        let tail: Vec<String> = stderr_tail.into_iter().collect();
+                        message.push_str(&format!(": {}", tail.join("\n")));
+                    }
+                    yield Err(AgentError::ExecutionError(message));
+                }
+                Err(e) => {
+                    yield Err(AgentError::ExecutionError(format!(
+                        "Failed to wait for '{}': {}",
+                        program, e
+                    )));
+                }
+            }
+        };
+
+        Ok(CliProcess {
+            pid,
+            stream: Box::pin(stream),
+        })
+    }
+}
+
+/// A running CLI process and its output.
+///
+/// Yields `CliOutput` items until the process exits; an unsuccessful exit
+/// ends the stream with `AgentError::ExecutionError` carrying the tail of
+/// stderr. Dropping it kills the process.
+pub struct CliProcess {
+    pid: Option<u32>,
+    stream: Pin<Box<dyn Stream<Item = Result<CliOutput, AgentError>> + Send>>,
+}
+
+impl CliProcess {
+    /// OS process id of the child, for signalling it (e.g. to cancel).
+    ///
+    /// `None` if the process had already exited when it was spawned.
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+}
+
+impl Stream for CliProcess {
+    type Item = Result<CliOutput, AgentError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.stream.as_mut().poll_next(cx)
+    }
+}
+
+/// Name of the secret environment variable whose value appears in `args`.
+///
+/// Both the inherited environment and the command's own `env` are checked;
+/// a variable holds a secret if its name contains one of
+/// `SECRET_NAME_MARKERS`.
+fn secret_in_args(args: &[String], env: &HashMap<String, String>) -> Option<String> {
+    let overrides = env.iter().map(|(k, v)| (k.clone(), v.clone()));
+    std::env::vars()
+        .chain(overrides)
+        .filter(|(name, value)| {
+            let name = name.to_uppercase();
+            value.len() >= MIN_SECRET_LEN
+                && SECRET_NAME_MARKERS
+                    .iter()
+                    .any(|marker| name.contains(marker))
+        })
+        .find(|(_, value)| args.iter().any(|arg| arg.contains(value.as_str())))
+        .map(|(name, _)| name)
+}
+
+/// Parse one stdout line; empty lines are skipped.
+fn parse_line(line: &str, tolerant: bool) -> Option<Result<CliOutput, AgentError>> {
+    if line.trim().is_empty() {
+        return None;
+    }
+    match serde_json::from_str::<serde_json::Value>(line) {
+        Ok(value) => Some(Ok(CliOutput::Json(value))),
+        Err(_) if tolerant => Some(Ok(CliOutput::Text(line.to_string()))),
+        Err(e) => Some(Err(AgentError::StreamParseError(format!(
+            "Failed to parse JSON: {} (line: {})",
+            e, line
+        )))),
+    }
+}
 
 /// CLI executor for agent adapters.
 ///
@@ -30,7 +316,9 @@ impl CliExecutor {
     ///
     /// A stream of `serde_json::Value` objects, one per line of JSON output.
     /// Empty lines are automatically filtered out. Lines that fail to parse
-    /// as JSON will yield `AgentError::StreamParseError`.
+    /// as JSON will yield `AgentError::StreamParseError`, and a non-zero
+    /// exit ends the stream with `AgentError::ExecutionError` carrying the
+    /// tail of stderr.
     ///
     /// # Example
     ///
@@ -55,66 +343,73 @@ impl CliExecutor {
         args: Vec<String>,
         working_dir: String,
     ) -> Pin<Box<dyn Stream<Item = Result<serde_json::Value, AgentError>> + Send>> {
-        // Create async stream using async_stream::stream macro
+        Self::execute_with_env(command, args, working_dir, HashMap::new())
+    }
+
+    /// Execute a CLI command with extra environment variables.
+    ///
+    /// Behaves like `execute`; `env` is added on top of the inherited
+    /// environment. The process is killed if the returned stream is dropped
+    /// before it finishes (e.g. when a step times out).
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command to execute
+    /// * `args` - Command line arguments
+    /// * `working_dir` - Working directory for the command
+    /// * `env` - Extra environment variables
+    pub fn execute_with_env(
+        command: String,
+        args: Vec<String>,
+        working_dir: String,
+        env: HashMap<String, String>,
+    ) -> Pin<Box<dyn Stream<Item = Result<serde_json::Value, AgentError>> + Send>> {
+        Self::execute_with_input(command, args, working_dir, env, None)
+    }
+
+    /// Execute a CLI command, optionally writing `input` to its stdin.
+    ///
+    /// Behaves like `execute_with_env`. When `input` is set, it is written
+    /// to the process's stdin, which is then closed so the program sees
+    /// end of input.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command to execute
+    /// * `args` - Command line arguments
+    /// * `working_dir` - Working directory for the command
+    /// * `env` - Extra environment variables
+    /// * `input` - Text to write to stdin
+    pub fn execute_with_input(
+        command: String,
+        args: Vec<String>,
+        working_dir: String,
+        env: HashMap<String, String>,
+        input: Option<String>,
+    ) -> Pin<Box<dyn Stream<Item = Result<serde_json::Value, AgentError>> + Send>> {
+        let process = CliCommand::new(command, args, working_dir)
+            .with_env(env)
+            .with_input(input)
+            .spawn();
+
         let stream = async_stream::stream! {
-            // Build and spawn the command
-            let mut cmd = Command::new(&command);
-            cmd.args(&args);
-            cmd.current_dir(&working_dir);
-            cmd.stdout(Stdio::piped());
-            cmd.stderr(Stdio::piped());
-
-            let mut child = match cmd.spawn() {
-                Ok(child) => child,
+            let mut process = match process {
+                Ok(process) => process,
                 Err(e) => {
-                    yield Err(AgentError::ExecutionError(format!(
-                        "Failed to spawn command '{}': {}",
-                        command, e
-                    )));
+                    yield Err(e);
                     return;
                 }
             };
 
-            // Capture stdout
-            let stdout = match child.stdout.take() {
-                Some(stdout) => stdout,
-                None => {
-                    yield Err(AgentError::ExecutionError(
-                        "Failed to capture stdout".to_string()
-                    ));
-                    return;
-                }
-            };
-
-            // Create buffered reader for line-by-line processing
-            let reader = BufReader::new(stdout);
-            let mut lines = reader.lines();
-
-            // Read lines and parse as JSON
-            while let Ok(Some(line)) = lines.next_line().await {
-                // Skip empty lines
-                if line.trim().is_empty() {
-                    continue;
-                }
-
-                // Try to parse as JSON
-                match serde_json::from_str::<serde_json::Value>(&line) {
-                    Ok(value) => {
-                        yield Ok(value);
-                    }
-                    Err(e) => {
-                        yield Err(AgentError::StreamParseError(format!(
-                            "Failed to parse JSON: {} (line: {})",
-                            e, line
-                        )));
-                    }
+            // Only JSON values are passed on; stderr is drained and reported
+            // with the exit status
+            while let Some(output) = process.next().await {
+                match output {
+                    Ok(CliOutput::Json(value)) => yield Ok(value),
+                    Ok(CliOutput::Text(_)) | Ok(CliOutput::Stderr(_)) => {}
+                    Err(e) => yield Err(e),
                 }
             }
-
-            // Wait for the process to complete
-            // We don't need to check exit status here, as any output errors
-            // will have been captured above
-            let _ = child.wait().await;
         };
 
         Box::pin(stream)
@@ -126,6 +421,165 @@ mod tests {
     use super::*;
     use tokio_stream::StreamExt;
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_with_env() {
+        let stream = CliExecutor::execute_with_env(
+            "sh".to_string(),
+            vec![
+                "-c".to_string(),
+                r#"printf '{"value":"%s"}\n' "$PK_TEST_VAR""#.to_string(),
+            ],
+            ".".to_string(),
+            HashMap::from([("PK_TEST_VAR".to_string(), "set".to_string())]),
+        );
+
+        let values: Vec<_> = stream.collect().await;
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0].as_ref().unwrap()["value"], "set");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_with_input() {
+        let stream = CliExecutor::execute_with_input(
+            "sh".to_string(),
+            vec![
+                "-c".to_string(),
+                r#"read line; printf '{"got":"%s"}\n' "$line""#.to_string(),
+            ],
+            ".".to_string(),
+            HashMap::new(),
+            Some("hello\n".to_string()),
+        );
+
+        let values: Vec<_> = stream.collect().await;
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0].as_ref().unwrap()["got"], "hello");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_reports_failed_exit_with_stderr_tail() {
+        let stream = CliExecutor::execute(
+            "sh".to_string(),
+            vec![
+                "-c".to_string(),
+                r#"echo '{"step":1}'; echo 'boom' >&2; exit 3"#.to_string(),
+            ],
+            ".".to_string(),
+        );
+
+        let results: Vec<_> = stream.collect().await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap()["step"], 1);
+        match &results[1] {
+            Err(AgentError::ExecutionError(msg)) => {
+                assert!(msg.contains("exit status: 3"), "{}", msg);
+                assert!(msg.ends_with("boom"), "{}", msg);
+            }
+            other => panic!("Expected ExecutionError, got {:?}", other),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_spawn_tolerant_reports_text_and_stderr() {
+        let process = CliCommand::new(
+            "sh".to_string(),
+            vec![
+                "-c".to_string(),
+                r#"echo 'Update available'; echo '{"ok":true}'; echo 'note' >&2"#.to_string(),
+            ],
+            ".".to_string(),
+        )
+        .with_tolerant_parsing(true)
+        .spawn()
+        .unwrap();
+        assert!(process.pid().is_some());
+
+        let outputs: Vec<_> = process
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Tolerant mode should not fail on text lines");
+
+        let stdout: Vec<_> = outputs
+            .iter()
+            .filter(|o| !matches!(o, CliOutput::Stderr(_)))
+            .collect();
+        assert_eq!(
+            stdout,
+            vec![
+                &CliOutput::Text("Update available".to_string()),
+                &CliOutput::Json(serde_json::json!({"ok": true})),
+            ]
+        );
+        assert!(outputs.contains(&CliOutput::Stderr("note".to_string())));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_spawn_drains_large_stderr() {
+        // More stderr than a pipe buffer holds must not stall the process
+        let process = CliCommand::new(
+            "sh".to_string(),
+            vec![
+                "-c".to_string(),
+                r#"i=0; while [ $i -lt 5000 ]; do echo "warning line $i" >&2; i=$((i+1)); done; echo '{"done":true}'"#
+                    .to_string(),
+            ],
+            ".".to_string(),
+        )
+        .spawn()
+        .unwrap();
+
+        let outputs: Vec<_> = tokio::time::timeout(
+            std::time::Duration::from_secs(30),
+            process.collect::<Vec<_>>(),
+        )
+        .await
+        .expect("Process should not deadlock");
+
+        let stderr_lines = outputs
+            .iter()
+            .filter(|o| matches!(o, Ok(CliOutput::Stderr(_))))
+            .count();
+        assert_eq!(stderr_lines, 5000);
+        assert!(outputs.contains(&Ok(CliOutput::Json(serde_json::json!({"done": true})))));
+    }
+
+    #[test]
+    fn test_spawn_refuses_secret_in_args() {
+        let env = HashMap::from([(
+            "PK_TEST_API_KEY".to_string(),
+            "sk-test-0123456789".to_string(),
+        )]);
+
+        let result = CliCommand::new(
+            "echo".to_string(),
+            vec!["--api-key=sk-test-0123456789".to_string()],
+            ".".to_string(),
+        )
+        .with_env(env.clone())
+        .spawn();
+
+        match result {
+            Err(AgentError::ExecutionError(msg)) => {
+                assert!(msg.contains("PK_TEST_API_KEY"), "{}", msg);
+                assert!(!msg.contains("sk-test-0123456789"));
+            }
+            _ => panic!("Expected the secret to be refused"),
+        }
+
+        // The same value is fine in the environment, and names without a
+        // secret marker are not checked
+        assert_eq!(secret_in_args(&["--verbose".to_string()], &env), None);
+        let plain = HashMap::from([("PK_TEST_MODEL".to_string(), "sonnet-large".to_string())]);
+        assert_eq!(secret_in_args(&["sonnet-large".to_string()], &plain), None);
+    }
+
     #[tokio::test]
     async fn test_execute_echo_json() {
         // Use echo to output a JSON line
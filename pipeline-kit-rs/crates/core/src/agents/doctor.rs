@@ -0,0 +1,171 @@
+//! Environment diagnostics for configured agents.
+//!
+//! `diagnose` collects what `pipeline-kit doctor` reports for one agent:
+//! the adapter behind it, the program it spawns and that program's version,
+//! the environment variables it needs, and whether its availability check
+//! passes.
+
+use crate::agents::agent_type::AgentType;
+use crate::agents::base::Agent;
+use pk_protocol::diagnostics::AgentDiagnosis;
+use pk_protocol::diagnostics::EnvVarStatus;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// How long `--version` may take before the version is reported unknown.
+const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Diagnose one agent.
+///
+/// # Arguments
+///
+/// * `agent_name` - Name of the agent
+/// * `agent_type` - Adapter backing the agent
+/// * `agent` - The agent instance
+///
+/// # Returns
+///
+/// The diagnosis; `available` is the result of `check_availability`.
+pub async fn diagnose(
+    agent_name: &str,
+    agent_type: AgentType,
+    agent: &dyn Agent,
+) -> AgentDiagnosis {
+    let requirements = agent.requirements();
+
+    // 1. Locate the program and ask for its version
+    let binary_path = requirements
+        .binary
+        .as_deref()
+        .and_then(|binary| which::which(binary).ok());
+    let version = match &binary_path {
+        Some(path) => binary_version(path).await,
+        None => None,
+    };
+
+    // 2. Check the required environment variables
+    let env_vars = requirements
+        .env_vars
+        .iter()
+        .map(|name| EnvVarStatus {
+            name: name.clone(),
+            present: std::env::var_os(name).is_some_and(|value| !value.is_empty()),
+        })
+        .collect();
+
+    // 3. Run the adapter's own availability check
+    let available = agent.check_availability().await;
+
+    AgentDiagnosis {
+        agent_name: agent_name.to_string(),
+        adapter_type: agent_type.name().to_string(),
+        binary: requirements.binary,
+        binary_path: binary_path.map(|p| p.to_string_lossy().to_string()),
+        version,
+        env_vars,
+        available,
+    }
+}
+
+/// First non-empty line of `<path> --version`, from stdout or else stderr.
+async fn binary_version(path: &Path) -> Option<String> {
+    let output = tokio::time::timeout(
+        VERSION_TIMEOUT,
+        Command::new(path)
+            .arg("--version")
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .ok()?
+    .ok()?;
+
+    [output.stdout, output.stderr].iter().find_map(|bytes| {
+        String::from_utf8_lossy(bytes)
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(str::to_string)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::base::AgentError;
+    use crate::agents::base::AgentEvent;
+    use crate::agents::base::ExecutionContext;
+    use crate::agents::base::Requirements;
+    use crate::agents::MockAgent;
+    use async_trait::async_trait;
+    use std::pin::Pin;
+    use tokio_stream::Stream;
+
+    /// An agent needing `cargo`, which every test run has on `PATH`.
+    struct CargoAgent;
+
+    #[async_trait]
+    impl Agent for CargoAgent {
+        async fn check_availability(&self) -> bool {
+            false
+        }
+
+        async fn execute(
+            &self,
+            _context: &ExecutionContext,
+        ) -> Result<Pin<Box<dyn Stream<Item = Result<AgentEvent, AgentError>> + Send>>, AgentError>
+        {
+            Err(AgentError::NotAvailable("test".to_string()))
+        }
+
+        fn requirements(&self) -> Requirements {
+            Requirements {
+                binary: Some("cargo".to_string()),
+                env_vars: vec![
+                    "PATH".to_string(),
+                    "PIPELINE_KIT_DOCTOR_TEST_UNSET".to_string(),
+                ],
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_diagnose_reports_binary_and_env() {
+        let diagnosis = diagnose("builder", AgentType::Custom, &CargoAgent).await;
+
+        assert_eq!(diagnosis.adapter_type, "Custom");
+        assert_eq!(diagnosis.binary.as_deref(), Some("cargo"));
+        assert!(diagnosis.binary_path.is_some());
+        assert!(diagnosis
+            .version
+            .as_deref()
+            .is_some_and(|v| v.starts_with("cargo ")));
+        assert_eq!(
+            diagnosis.env_vars,
+            vec![
+                EnvVarStatus {
+                    name: "PATH".to_string(),
+                    present: true,
+                },
+                EnvVarStatus {
+                    name: "PIPELINE_KIT_DOCTOR_TEST_UNSET".to_string(),
+                    present: false,
+                },
+            ]
+        );
+        assert!(!diagnosis.available);
+    }
+
+    #[tokio::test]
+    async fn test_diagnose_agent_without_requirements() {
+        let diagnosis = diagnose("mock", AgentType::Mock, &MockAgent::success()).await;
+
+        assert_eq!(diagnosis.binary, None);
+        assert_eq!(diagnosis.version, None);
+        assert!(diagnosis.env_vars.is_empty());
+        assert!(diagnosis.available);
+    }
+}
@@ -1,21 +1,30 @@
 //! Agent factory for creating agent instances from configurations.
 
+use crate::agents::adapters::AnthropicAdapter;
 use crate::agents::adapters::ClaudeAdapter;
 use crate::agents::adapters::CodexAdapter;
 use crate::agents::adapters::CursorAdapter;
+use crate::agents::adapters::CustomAdapter;
 use crate::agents::adapters::GeminiAdapter;
 use crate::agents::adapters::MockAgent;
+use crate::agents::adapters::OpenAiAdapter;
 use crate::agents::adapters::QwenAdapter;
 use crate::agents::agent_type::AgentType;
 use crate::agents::base::Agent;
+use crate::agents::permissions::ToolPermissions;
+use crate::agents::runtime::RuntimeOptions;
+use crate::agents::runtime::TimeoutAgent;
+use anyhow::bail;
 use anyhow::Result;
 use pk_protocol::agent_models;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Factory for creating agent instances based on configuration.
 ///
-/// The factory determines which adapter to use based on the model name
-/// and instantiates the appropriate agent type.
+/// The factory determines which adapter to use from the agent's `provider:`
+/// (or, failing that, its model name) and instantiates the appropriate
+/// agent type.
 pub struct AgentFactory;
 
 impl AgentFactory {
@@ -31,13 +40,23 @@ impl AgentFactory {
     ///
     /// # Behavior
     ///
-    /// The factory uses `AgentType::from_model_name()` to determine which adapter to create:
-    /// - Claude models → `ClaudeAdapter`
-    /// - Cursor/GPT models → `CursorAdapter`
-    /// - Gemini models → `GeminiAdapter`
-    /// - Codex models → `CodexAdapter` (Phase 2)
-    /// - Qwen models → `QwenAdapter` (Phase 2)
-    /// - Unknown models → `MockAgent`
+    /// The factory uses `AgentType::resolve()` to determine which adapter to create:
+    /// - `provider: claude` or Claude models → `ClaudeAdapter`
+    /// - `provider: cursor` or Cursor/GPT models → `CursorAdapter`
+    /// - `provider: gemini` or Gemini models → `GeminiAdapter`
+    /// - `provider: codex` or Codex models → `CodexAdapter`
+    /// - `provider: qwen` or Qwen models → `QwenAdapter`
+    /// - `provider: openai` → `OpenAiAdapter` calling `base-url`
+    /// - `provider: anthropic` → `AnthropicAdapter` calling the Messages API
+    /// - `provider: custom` → `CustomAdapter` running the `command:` section
+    /// - `provider: mock` → `MockAgent`
+    /// - Unknown models without a provider → error
+    ///
+    /// `permission-mode`, `allowed-tools` and `disallowed-tools` are passed to
+    /// the adapter, which fails if it cannot enforce them. The same goes for
+    /// `max-turns`; `env`, `working-dir` and `extra-args` are applied to the
+    /// adapter's process. With `timeout-secs` set, the agent is wrapped in a
+    /// `TimeoutAgent`.
     ///
     /// # Examples
     ///
@@ -47,76 +66,177 @@ impl AgentFactory {
     ///
     /// let config = AgentConfig {
     ///     name: "developer".to_string(),
-    ///     model: "claude-sonnet-4.5".to_string(),
     ///     description: "Developer agent".to_string(),
+    ///     model: "claude-sonnet-4.5".to_string(),
     ///     color: "blue".to_string(),
     ///     system_prompt: "You are a helpful developer.".to_string(),
+    ///     ..Default::default()
     /// };
     ///
     /// let agent = AgentFactory::create(&config).unwrap();
     /// ```
     pub fn create(config: &agent_models::Agent) -> Result<Arc<dyn Agent>> {
-        let agent_type = AgentType::from_model_name(&config.model);
+        let agent_type = AgentType::resolve(config)?;
+        if config.command.is_some() && agent_type != AgentType::Custom {
+            bail!(
+                "Agent '{}': `command:` is only used with `provider: custom`",
+                config.name
+            );
+        }
+        let permissions = ToolPermissions::from_config(config);
+        let runtime = RuntimeOptions::from_config(config)?;
 
-        match agent_type {
+        let agent: Arc<dyn Agent> = match agent_type {
             AgentType::Claude => {
                 let adapter = ClaudeAdapter::new(
                     config.name.clone(),
                     config.model.clone(),
                     config.system_prompt.clone(),
-                )?;
-                Ok(Arc::new(adapter))
+                )?
+                .with_permissions(permissions)?
+                .with_runtime(runtime)?;
+                Arc::new(adapter)
             }
             AgentType::Cursor => {
                 let adapter = CursorAdapter::new(
                     config.name.clone(),
                     config.model.clone(),
                     config.system_prompt.clone(),
-                )?;
-                Ok(Arc::new(adapter))
+                )?
+                .with_permissions(permissions)?
+                .with_runtime(runtime)?;
+                Arc::new(adapter)
             }
             AgentType::Gemini => {
                 let adapter = GeminiAdapter::new(
                     config.name.clone(),
                     config.model.clone(),
                     config.system_prompt.clone(),
-                )?;
-                Ok(Arc::new(adapter))
+                )?
+                .with_permissions(permissions)?
+                .with_runtime(runtime)?;
+                Arc::new(adapter)
             }
             AgentType::Codex => {
                 let adapter = CodexAdapter::new(
                     config.name.clone(),
                     config.model.clone(),
                     config.system_prompt.clone(),
-                )?;
-                Ok(Arc::new(adapter))
+                )?
+                .with_permissions(permissions)?
+                .with_runtime(runtime)?;
+                Arc::new(adapter)
             }
             AgentType::Qwen => {
                 let adapter = QwenAdapter::new(
                     config.name.clone(),
                     config.model.clone(),
                     config.system_prompt.clone(),
+                )?
+                .with_permissions(permissions)?
+                .with_runtime(runtime)?;
+                Arc::new(adapter)
+            }
+            AgentType::OpenAi => {
+                let mut adapter = OpenAiAdapter::new(
+                    config.name.clone(),
+                    config.model.clone(),
+                    config.system_prompt.clone(),
+                )?;
+                if let Some(base_url) = &config.base_url {
+                    adapter = adapter.with_base_url(base_url.clone());
+                }
+                if let Some(api_key_env) = &config.api_key_env {
+                    adapter = adapter.with_api_key_env(api_key_env.clone());
+                }
+                let adapter = adapter
+                    .with_permissions(permissions)?
+                    .with_runtime(runtime)?;
+                Arc::new(adapter)
+            }
+            AgentType::Anthropic => {
+                let mut adapter = AnthropicAdapter::new(
+                    config.name.clone(),
+                    config.model.clone(),
+                    config.system_prompt.clone(),
                 )?;
-                Ok(Arc::new(adapter))
+                if let Some(base_url) = &config.base_url {
+                    adapter = adapter.with_base_url(base_url.clone());
+                }
+                if let Some(api_key_env) = &config.api_key_env {
+                    adapter = adapter.with_api_key_env(api_key_env.clone());
+                }
+                let adapter = adapter
+                    .with_permissions(permissions)?
+                    .with_runtime(runtime)?;
+                Arc::new(adapter)
+            }
+            AgentType::Custom => {
+                let Some(command) = config.command.clone() else {
+                    bail!(
+                        "Agent '{}': provider 'custom' requires a `command:` section",
+                        config.name
+                    );
+                };
+                let adapter = CustomAdapter::new(
+                    config.name.clone(),
+                    config.model.clone(),
+                    config.system_prompt.clone(),
+                    command,
+                )?
+                .with_permissions(permissions)?
+                .with_runtime(runtime)?;
+                Arc::new(adapter)
             }
             AgentType::Mock => {
                 // Support different mock types for testing based on model name
                 if config.model == "test-failure-model" {
-                    Ok(Arc::new(MockAgent::failing()))
+                    Arc::new(MockAgent::failing())
                 } else if config.model == "test-unavailable-model" {
-                    Ok(Arc::new(MockAgent::unavailable()))
+                    Arc::new(MockAgent::unavailable())
+                } else if config.model == "test-activity-model" {
+                    Arc::new(MockAgent::with_activity())
+                } else if config.model == "test-session-model" {
+                    Arc::new(MockAgent::with_sessions())
+                } else if config.model == "test-rate-limited-model" {
+                    Arc::new(MockAgent::rate_limited())
+                } else if config.model == "test-interrupted-model" {
+                    Arc::new(MockAgent::interrupted())
                 } else {
-                    // Default to success for "test-model" and others
-                    Ok(Arc::new(MockAgent::success()))
+                    // Default to success for "test-model" and any other model
+                    Arc::new(MockAgent::success())
                 }
             }
+        };
+
+        match config.timeout_secs {
+            Some(secs) => Ok(Arc::new(TimeoutAgent::new(
+                agent,
+                Duration::from_secs(secs),
+            ))),
+            None => Ok(agent),
         }
     }
+
+    /// Check that an agent configuration can be turned into an agent.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error `create` would return, e.g. for an unknown model or
+    /// a permission setting the adapter cannot enforce.
+    pub fn validate(config: &agent_models::Agent) -> Result<()> {
+        Self::create(config).map(|_| ())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use pk_protocol::agent_models::AgentProvider;
+    use pk_protocol::agent_models::CommandProtocol;
+    use pk_protocol::agent_models::CustomCommand;
+    use pk_protocol::agent_models::EventMapping;
+    use pk_protocol::agent_models::PromptInput;
 
     fn create_test_config(name: &str, model: &str) -> agent_models::Agent {
         agent_models::Agent {
@@ -125,6 +245,7 @@ mod tests {
             description: format!("Test agent {}", name),
             color: "blue".to_string(),
             system_prompt: "Test prompt".to_string(),
+            ..Default::default()
         }
     }
 
@@ -190,7 +311,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_factory_create_mock() {
-        let config = create_test_config("mock-agent", "test-model");
+        let mut config = create_test_config("mock-agent", "test-model");
+        config.provider = Some(AgentProvider::Mock);
         let agent = AgentFactory::create(&config);
         assert!(agent.is_ok());
 
@@ -198,6 +320,67 @@ mod tests {
         assert!(agent.check_availability().await);
     }
 
+    #[test]
+    fn test_factory_rejects_unknown_model() {
+        // Without a provider a typo must not silently fall back to a mock
+        let config = create_test_config("typo", "claud-sonet");
+        assert!(AgentFactory::create(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_factory_provider_overrides_model_name() {
+        let mut config = create_test_config("local", "sonnet-4.5");
+        config.provider = Some(AgentProvider::Mock);
+        let agent = AgentFactory::create(&config).unwrap();
+        assert!(agent.check_availability().await);
+    }
+
+    #[test]
+    fn test_factory_applies_runtime_options() {
+        let mut config = create_test_config("claude-agent", "claude-sonnet-4.5");
+        config.max_turns = Some(10);
+        assert!(AgentFactory::create(&config).is_ok());
+
+        // Gemini has no turn limit to map `max-turns` onto
+        let mut config = create_test_config("gemini-agent", "gemini-2.5-pro");
+        config.max_turns = Some(10);
+        assert!(AgentFactory::create(&config).is_err());
+
+        let mut config = create_test_config("claude-agent", "claude-sonnet-4.5");
+        config.working_dir = Some("../elsewhere".to_string());
+        assert!(AgentFactory::create(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_factory_wraps_timeout() {
+        let mut config = create_test_config("mock-agent", "test-model");
+        config.provider = Some(AgentProvider::Mock);
+        config.timeout_secs = Some(30);
+        let agent = AgentFactory::create(&config).unwrap();
+        assert!(agent.check_availability().await);
+    }
+
+    #[test]
+    fn test_factory_create_custom() {
+        let mut config = create_test_config("aider", "gpt-4o");
+        config.provider = Some(AgentProvider::Custom);
+        // The command line is required
+        assert!(AgentFactory::create(&config).is_err());
+
+        config.command = Some(CustomCommand {
+            program: "aider".to_string(),
+            args: vec!["--message".to_string(), "{prompt}".to_string()],
+            protocol: CommandProtocol::JsonLines,
+            prompt: PromptInput::Arg,
+            events: EventMapping::default(),
+        });
+        assert!(AgentFactory::create(&config).is_ok());
+
+        // `command:` without `provider: custom` is a mistake
+        config.provider = None;
+        assert!(AgentFactory::create(&config).is_err());
+    }
+
     #[test]
     fn test_factory_returns_arc() {
         let config = create_test_config("test", "claude-sonnet-4.5");
@@ -4,18 +4,65 @@
 //! - Registering agent configurations
 //! - Looking up agents by name
 //! - Providing fallback logic when agents are unavailable
+//! - Caching availability checks, which `doctor` refreshes
 //! - Managing the lifecycle of agent instances
 
+use crate::agents::agent_type::AgentType;
 use crate::agents::base::Agent;
 use crate::agents::base::AgentError;
 use crate::agents::base::AgentEvent;
 use crate::agents::base::ExecutionContext;
+use crate::agents::doctor;
 use crate::agents::factory::AgentFactory;
 use pk_protocol::agent_models;
+use pk_protocol::diagnostics::AgentDiagnosis;
 use std::collections::HashMap;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
 use tokio_stream::Stream;
+use tokio_stream::StreamExt;
+
+/// Stream of events produced by an agent run.
+type AgentStream = Pin<Box<dyn Stream<Item = Result<AgentEvent, AgentError>> + Send>>;
+
+/// How long the result of an availability check is reused.
+const AVAILABILITY_TTL: Duration = Duration::from_secs(60);
+
+/// Recent availability check results, keyed by agent name.
+#[derive(Default)]
+struct AvailabilityCache {
+    checked: Mutex<HashMap<String, (bool, Instant)>>,
+}
+
+impl AvailabilityCache {
+    /// The cached result for an agent, or a fresh check once it expired.
+    async fn check(&self, name: &str, agent: &dyn Agent) -> bool {
+        let cached = self
+            .checked
+            .lock()
+            .ok()
+            .and_then(|checked| checked.get(name).copied())
+            .filter(|(_, at)| at.elapsed() < AVAILABILITY_TTL);
+        match cached {
+            Some((available, _)) => available,
+            None => {
+                let available = agent.check_availability().await;
+                self.record(name, available);
+                available
+            }
+        }
+    }
+
+    /// Store the result of a check made elsewhere.
+    fn record(&self, name: &str, available: bool) {
+        if let Ok(mut checked) = self.checked.lock() {
+            checked.insert(name.to_string(), (available, Instant::now()));
+        }
+    }
+}
 
 /// Manages all registered agents and provides orchestration logic.
 ///
@@ -24,7 +71,13 @@ use tokio_stream::Stream;
 /// automatic fallback support.
 pub struct AgentManager {
     agents: HashMap<String, Arc<dyn Agent>>,
-    fallback_agent_name: Option<String>,
+    agent_types: HashMap<String, AgentType>,
+    /// Per-agent fallback chains from the agents' front matter.
+    fallbacks: HashMap<String, Vec<String>>,
+    /// Chain for agents that declare no fallback of their own.
+    default_fallback: Vec<String>,
+    /// Availability results shared with running executions.
+    availability: Arc<AvailabilityCache>,
 }
 
 impl AgentManager {
@@ -41,15 +94,26 @@ impl AgentManager {
     /// # Note
     ///
     /// Uses `AgentFactory` to create appropriate agent adapters based on
-    /// the model name in the configuration.
+    /// the provider (or model name) in the configuration. Agents whose
+    /// adapter cannot be determined are skipped with a warning; the config
+    /// loader rejects them before they get here.
     pub fn new(configs: Vec<agent_models::Agent>) -> Self {
         let mut agents: HashMap<String, Arc<dyn Agent>> = HashMap::new();
+        let mut agent_types = HashMap::new();
+        let mut fallbacks = HashMap::new();
 
         // Use AgentFactory to create appropriate adapters
         for config in configs {
-            match AgentFactory::create(&config) {
-                Ok(agent) => {
+            let created = AgentType::resolve(&config).and_then(|agent_type| {
+                AgentFactory::create(&config).map(|agent| (agent_type, agent))
+            });
+            match created {
+                Ok((agent_type, agent)) => {
+                    agent_types.insert(config.name.clone(), agent_type);
                     agents.insert(config.name.clone(), agent);
+                    if !config.fallback.is_empty() {
+                        fallbacks.insert(config.name.clone(), config.fallback);
+                    }
                 }
                 Err(e) => {
                     eprintln!(
@@ -62,17 +126,30 @@ impl AgentManager {
 
         Self {
             agents,
-            fallback_agent_name: None,
+            agent_types,
+            fallbacks,
+            default_fallback: Vec::new(),
+            availability: Arc::default(),
         }
     }
 
-    /// Set the fallback agent to use when the requested agent is unavailable.
+    /// Add an agent to the end of the default fallback chain.
     ///
     /// # Arguments
     ///
     /// * `agent_name` - The name of the agent to use as fallback
     pub fn with_fallback(mut self, agent_name: String) -> Self {
-        self.fallback_agent_name = Some(agent_name);
+        self.default_fallback.push(agent_name);
+        self
+    }
+
+    /// Set the fallback chain for agents that declare none of their own.
+    ///
+    /// # Arguments
+    ///
+    /// * `agent_names` - Agents to try, in order (`fallback` in `config.toml`)
+    pub fn with_default_fallback(mut self, agent_names: Vec<String>) -> Self {
+        self.default_fallback = agent_names;
         self
     }
 
@@ -89,10 +166,83 @@ impl AgentManager {
         self.agents.get(name).cloned()
     }
 
+    /// Get the adapter type backing an agent.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The agent name to look up
+    ///
+    /// # Returns
+    ///
+    /// `Some(AgentType)` if the agent is registered, `None` otherwise.
+    pub fn agent_type(&self, name: &str) -> Option<AgentType> {
+        self.agent_types.get(name).cloned()
+    }
+
+    /// Check whether an agent can run, reusing a recent result.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The agent name to check
+    ///
+    /// # Returns
+    ///
+    /// `false` if the agent is not registered or its check failed.
+    pub async fn check_availability(&self, name: &str) -> bool {
+        match self.get_agent(name) {
+            Some(agent) => self.availability.check(name, agent.as_ref()).await,
+            None => false,
+        }
+    }
+
+    /// Diagnose the environment of every registered agent.
+    ///
+    /// The availability results replace cached ones, so the next executions
+    /// reuse them instead of checking again.
+    ///
+    /// # Returns
+    ///
+    /// One diagnosis per agent, ordered by agent name.
+    pub async fn diagnose(&self) -> Vec<AgentDiagnosis> {
+        let mut names = self.list_agents();
+        names.sort();
+
+        let mut diagnoses = Vec::new();
+        for name in names {
+            let (Some(agent), Some(agent_type)) = (self.get_agent(&name), self.agent_type(&name))
+            else {
+                continue;
+            };
+            let diagnosis = doctor::diagnose(&name, agent_type, agent.as_ref()).await;
+            self.availability.record(&name, diagnosis.available);
+            diagnoses.push(diagnosis);
+        }
+        diagnoses
+    }
+
+    /// The agents tried, in order, when the given agent fails.
+    ///
+    /// The agent's own `fallback` list wins over the default chain. The
+    /// agent itself, duplicates and unregistered names are left out.
+    pub fn fallback_chain(&self, agent_name: &str) -> Vec<String> {
+        let chain = self
+            .fallbacks
+            .get(agent_name)
+            .unwrap_or(&self.default_fallback);
+
+        let mut result: Vec<String> = Vec::new();
+        for name in chain {
+            if name != agent_name && self.has_agent(name) && !result.contains(name) {
+                result.push(name.clone());
+            }
+        }
+        result
+    }
+
     /// Execute an instruction with the specified agent.
     ///
     /// This method handles agent lookup and automatic fallback if the
-    /// requested agent is unavailable.
+    /// requested agent is unavailable or fails with a retryable error.
     ///
     /// # Arguments
     ///
@@ -105,43 +255,111 @@ impl AgentManager {
     ///
     /// # Behavior
     ///
-    /// 1. Look up the requested agent
-    /// 2. Check if it's available
-    /// 3. If unavailable and fallback is configured, try fallback agent
-    /// 4. Execute with the selected agent
+    /// 1. Look up the requested agent and its fallback chain
+    /// 2. Start the first agent of the chain that is available
+    /// 3. If a run fails with a retryable error before the agent produced
+    ///    any output, continue with the next agent of the chain, announced
+    ///    by `AgentEvent::FallbackUsed`
+    ///
+    /// Once an agent has streamed output or touched files, a retryable error
+    /// is returned as is: rerunning the instruction elsewhere would repeat
+    /// its output and its side effects. Fallback agents start a new
+    /// conversation: the session in `context` belongs to the requested agent.
     pub async fn execute(
         &self,
         agent_name: &str,
         context: &ExecutionContext,
-    ) -> Result<Pin<Box<dyn Stream<Item = Result<AgentEvent, AgentError>> + Send>>, AgentError>
-    {
-        // Try to get the requested agent
-        if let Some(agent) = self.get_agent(agent_name) {
-            if agent.check_availability().await {
-                return agent.execute(context).await;
+    ) -> Result<AgentStream, AgentError> {
+        // 1. Look up the requested agent and its fallback chain
+        let Some(agent) = self.get_agent(agent_name) else {
+            return Err(AgentError::NotAvailable(format!(
+                "Agent '{}' not found in registry",
+                agent_name
+            )));
+        };
+        let mut candidates = vec![(agent_name.to_string(), agent)];
+        for name in self.fallback_chain(agent_name) {
+            if let Some(fallback) = self.get_agent(&name) {
+                candidates.push((name, fallback));
             }
+        }
 
-            // Agent exists but is not available - try fallback
-            if let Some(ref fallback_name) = self.fallback_agent_name {
-                if fallback_name != agent_name {
-                    if let Some(fallback_agent) = self.get_agent(fallback_name) {
-                        if fallback_agent.check_availability().await {
-                            return fallback_agent.execute(context).await;
+        // 2. Start the first agent of the chain that is available
+        let mut fallback_context = context.clone();
+        fallback_context.session_id = None;
+        let availability = Arc::clone(&self.availability);
+        let (mut index, mut stream, skipped) =
+            match start_candidate(&availability, &candidates, 0, context, &fallback_context).await?
+            {
+                Some(started) => started,
+                None => {
+                    return Err(AgentError::NotAvailable(format!(
+                        "Agent '{}' is not available and no fallback succeeded",
+                        agent_name
+                    )))
+                }
+            };
+
+        // 3. Forward the events, moving down the chain on retryable errors
+        //    until the running agent has produced output
+        let events = async_stream::stream! {
+            let mut produced_output = false;
+            if index > 0 {
+                yield Ok(AgentEvent::FallbackUsed {
+                    from: candidates[0].0.clone(),
+                    to: candidates[index].0.clone(),
+                    reason: skipped,
+                });
+            }
+
+            while let Some(event) = stream.next().await {
+                let error = match event {
+                    Err(error) if error.is_retryable() && !produced_output => error,
+                    other => {
+                        if let Ok(event) = &other {
+                            produced_output |= is_output(event);
+                        }
+                        yield other;
+                        continue;
+                    }
+                };
+
+                let next = start_candidate(
+                    &availability,
+                    &candidates,
+                    index + 1,
+                    &fallback_context,
+                    &fallback_context,
+                )
+                .await;
+                match next {
+                    Ok(Some((next, next_stream, next_skipped))) => {
+                        let mut reason = format!("{}: {}", candidates[index].0, error);
+                        if !next_skipped.is_empty() {
+                            reason = format!("{}; {}", reason, next_skipped);
                         }
+                        yield Ok(AgentEvent::FallbackUsed {
+                            from: candidates[index].0.clone(),
+                            to: candidates[next].0.clone(),
+                            reason,
+                        });
+                        index = next;
+                        stream = next_stream;
+                        produced_output = false;
+                    }
+                    Ok(None) => {
+                        yield Err(error);
+                        break;
+                    }
+                    Err(next_error) => {
+                        yield Err(next_error);
+                        break;
                     }
                 }
             }
+        };
 
-            return Err(AgentError::NotAvailable(format!(
-                "Agent '{}' is not available and no fallback succeeded",
-                agent_name
-            )));
-        }
-
-        Err(AgentError::NotAvailable(format!(
-            "Agent '{}' not found in registry",
-            agent_name
-        )))
+        Ok(Box::pin(events))
     }
 
     /// List all registered agent names.
@@ -155,10 +373,69 @@ impl AgentManager {
     }
 }
 
+/// Whether `event` is output of the agent's work, as opposed to a report
+/// about the run (warnings, usage, sessions).
+fn is_output(event: &AgentEvent) -> bool {
+    !matches!(
+        event,
+        AgentEvent::Warning(_)
+            | AgentEvent::Usage(_)
+            | AgentEvent::SessionStarted(_)
+            | AgentEvent::FallbackUsed { .. }
+    )
+}
+
+/// Start the first available agent in `candidates` from `from` on.
+///
+/// The first candidate gets `context`, the others `fallback_context`.
+/// Returns the index of the started agent, its stream and why the agents
+/// before it were skipped, or `None` if every candidate was unavailable or
+/// failed to start with a retryable error.
+///
+/// # Errors
+///
+/// Returns the error of a candidate that failed to start with an error
+/// another agent would not fix.
+async fn start_candidate(
+    availability: &AvailabilityCache,
+    candidates: &[(String, Arc<dyn Agent>)],
+    from: usize,
+    context: &ExecutionContext,
+    fallback_context: &ExecutionContext,
+) -> Result<Option<(usize, AgentStream, String)>, AgentError> {
+    let mut skipped = Vec::new();
+    for (index, (name, agent)) in candidates.iter().enumerate().skip(from) {
+        if !availability.check(name, agent.as_ref()).await {
+            skipped.push(format!("{}: not available", name));
+            continue;
+        }
+
+        let context = if index == 0 {
+            context
+        } else {
+            fallback_context
+        };
+        match agent.execute(context).await {
+            Ok(stream) => return Ok(Some((index, stream, skipped.join("; ")))),
+            Err(e) if e.is_retryable() => {
+                // An agent that turned out unavailable is not tried again
+                // until its cached result expires
+                if matches!(e, AgentError::NotAvailable(_)) {
+                    availability.record(name, false);
+                }
+                skipped.push(format!("{}: {}", name, e));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(None)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use pk_protocol::agent_models::Agent as AgentConfig;
+    use pk_protocol::agent_models::AgentProvider;
     use tokio_stream::StreamExt;
 
     fn create_test_config(name: &str) -> AgentConfig {
@@ -166,8 +443,10 @@ mod tests {
             name: name.to_string(),
             description: format!("Test agent {}", name),
             model: "test-model".to_string(),
+            provider: Some(AgentProvider::Mock),
             color: "blue".to_string(),
             system_prompt: "Test prompt".to_string(),
+            ..Default::default()
         }
     }
 
@@ -262,6 +541,191 @@ mod tests {
         let configs = vec![create_test_config("agent1"), create_test_config("agent2")];
 
         let manager = AgentManager::new(configs).with_fallback("agent2".to_string());
-        assert_eq!(manager.fallback_agent_name, Some("agent2".to_string()));
+        assert_eq!(manager.fallback_chain("agent1"), vec!["agent2".to_string()]);
+        assert!(manager.fallback_chain("agent2").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_agent_manager_diagnose_all_agents() {
+        let configs = vec![
+            create_test_config("zeta"),
+            create_model_config("alpha", "test-unavailable-model", &[]),
+        ];
+        let manager = AgentManager::new(configs);
+
+        let diagnoses = manager.diagnose().await;
+        let summary: Vec<_> = diagnoses
+            .iter()
+            .map(|d| (d.agent_name.as_str(), d.adapter_type.as_str(), d.available))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("alpha", "Mock", false), ("zeta", "Mock", true)]
+        );
+
+        // The results are reused by later checks
+        assert!(!manager.check_availability("alpha").await);
+        assert!(manager.check_availability("zeta").await);
+        assert!(!manager.check_availability("missing").await);
+    }
+
+    fn create_model_config(name: &str, model: &str, fallback: &[&str]) -> AgentConfig {
+        AgentConfig {
+            model: model.to_string(),
+            fallback: fallback.iter().map(|s| s.to_string()).collect(),
+            ..create_test_config(name)
+        }
+    }
+
+    #[test]
+    fn test_agent_manager_fallback_chain_prefers_agent_list() {
+        let configs = vec![
+            create_model_config("primary", "test-model", &["second", "missing", "first"]),
+            create_test_config("other"),
+            create_test_config("first"),
+            create_test_config("second"),
+        ];
+
+        let manager = AgentManager::new(configs)
+            .with_default_fallback(vec!["first".to_string(), "other".to_string()]);
+
+        assert_eq!(manager.fallback_chain("primary"), vec!["second", "first"]);
+        assert_eq!(manager.fallback_chain("second"), vec!["first", "other"]);
+        assert_eq!(manager.fallback_chain("other"), vec!["first"]);
+    }
+
+    #[tokio::test]
+    async fn test_agent_manager_falls_back_when_unavailable() {
+        let configs = vec![
+            create_model_config("primary", "test-unavailable-model", &["down", "backup"]),
+            create_model_config("down", "test-unavailable-model", &[]),
+            create_test_config("backup"),
+        ];
+        let manager = AgentManager::new(configs);
+
+        let context = ExecutionContext::new("test instruction".to_string());
+        let events: Vec<_> = manager
+            .execute("primary", &context)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        assert_eq!(
+            events[0],
+            Ok(AgentEvent::FallbackUsed {
+                from: "primary".to_string(),
+                to: "backup".to_string(),
+                reason: "primary: not available; down: not available".to_string(),
+            })
+        );
+        assert_eq!(events.last(), Some(&Ok(AgentEvent::Completed)));
+    }
+
+    #[tokio::test]
+    async fn test_agent_manager_falls_back_on_retryable_error_mid_run() {
+        let configs = vec![
+            create_model_config("primary", "test-rate-limited-model", &["backup"]),
+            create_model_config("backup", "test-session-model", &[]),
+        ];
+        let manager = AgentManager::new(configs);
+
+        let context = ExecutionContext::new("test instruction".to_string())
+            .with_session_id(Some("primary-session".to_string()));
+        let events: Vec<_> = manager
+            .execute("primary", &context)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        assert_eq!(
+            events[0],
+            Ok(AgentEvent::Warning("Mock retrying".to_string()))
+        );
+        assert_eq!(
+            events[1],
+            Ok(AgentEvent::FallbackUsed {
+                from: "primary".to_string(),
+                to: "backup".to_string(),
+                reason: "primary: API call failed: Mock rate limit".to_string(),
+            })
+        );
+        // The fallback starts its own conversation
+        assert!(matches!(events[2], Ok(AgentEvent::SessionStarted(_))));
+        assert_eq!(events.last(), Some(&Ok(AgentEvent::Completed)));
+    }
+
+    #[tokio::test]
+    async fn test_agent_manager_does_not_fall_back_after_output() {
+        let configs = vec![
+            create_model_config("primary", "test-interrupted-model", &["backup"]),
+            create_test_config("backup"),
+        ];
+        let manager = AgentManager::new(configs);
+
+        let context = ExecutionContext::new("test instruction".to_string());
+        let events: Vec<_> = manager
+            .execute("primary", &context)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        // The partial response stands; the backup would repeat the work
+        assert_eq!(
+            events,
+            vec![
+                Ok(AgentEvent::MessageChunk("Partial response".to_string())),
+                Err(AgentError::ApiError("Mock connection reset".to_string())),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_agent_manager_does_not_fall_back_on_execution_error() {
+        let configs = vec![
+            create_model_config("primary", "test-failure-model", &["backup"]),
+            create_test_config("backup"),
+        ];
+        let manager = AgentManager::new(configs);
+
+        let context = ExecutionContext::new("test instruction".to_string());
+        let events: Vec<_> = manager
+            .execute("primary", &context)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        assert_eq!(
+            events.last(),
+            Some(&Err(AgentError::ExecutionError("Mock failure".to_string())))
+        );
+        assert!(!events
+            .iter()
+            .any(|e| matches!(e, Ok(AgentEvent::FallbackUsed { .. }))));
+    }
+
+    #[tokio::test]
+    async fn test_agent_manager_reports_last_error_when_chain_exhausted() {
+        let configs = vec![
+            create_model_config("primary", "test-rate-limited-model", &["backup"]),
+            create_model_config("backup", "test-unavailable-model", &[]),
+        ];
+        let manager = AgentManager::new(configs);
+
+        let context = ExecutionContext::new("test instruction".to_string());
+        let events: Vec<_> = manager
+            .execute("primary", &context)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        assert_eq!(
+            events.last(),
+            Some(&Err(AgentError::ApiError("Mock rate limit".to_string())))
+        );
     }
 }
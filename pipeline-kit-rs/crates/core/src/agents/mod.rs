@@ -3,12 +3,17 @@
 //! This module provides the `Agent` trait (Adapter Pattern) and
 //! the `AgentManager` for orchestrating multiple agent implementations.
 
+pub mod acp;
 pub mod adapters;
 pub mod agent_type;
 pub mod base;
 pub mod cli_executor;
+pub mod doctor;
 pub mod factory;
 pub mod manager;
+pub mod permission_bridge;
+pub mod permissions;
+pub mod runtime;
 
 pub use adapters::MockAgent;
 pub use agent_type::AgentType;
@@ -17,5 +22,8 @@ pub use base::AgentError;
 pub use base::AgentEvent;
 pub use base::Attachment;
 pub use base::ExecutionContext;
+pub use base::Requirements;
 pub use factory::AgentFactory;
 pub use manager::AgentManager;
+pub use permissions::ToolPermissions;
+pub use runtime::RuntimeOptions;
@@ -0,0 +1,509 @@
+//! Bridge answering Claude's permission prompts.
+//!
+//! In print mode Claude cannot show a prompt, but it can delegate each
+//! permission check to an MCP tool named by `--permission-prompt-tool`. The
+//! bridge has two halves:
+//!
+//! 1. `PermissionBridge` listens on a loopback port inside the agent's
+//!    process. Each request is decided by the agent's permission mode and
+//!    rules; undecided ones become `AgentEvent::PermissionRequest`s.
+//! 2. `serve_mcp` is the MCP server Claude launches over stdio, as the
+//!    hidden `permission-bridge` subcommand of the `pipeline` binary. It
+//!    forwards every call of its `approve` tool to the listener and turns
+//!    the answer into the result Claude expects.
+//!
+//! Both halves exchange one JSON line each way per request, authenticated
+//! by a random token passed to the MCP server in its environment.
+
+use crate::agents::base::AgentError;
+use crate::agents::base::AgentEvent;
+use crate::agents::permissions::PermissionReply;
+use crate::agents::permissions::ToolPermissions;
+use pk_protocol::agent_models::PermissionAction;
+use serde_json::json;
+use serde_json::Value;
+use std::net::SocketAddr;
+use subtle::ConstantTimeEq;
+use tokio::io::AsyncBufRead;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWrite;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::task::JoinSet;
+use uuid::Uuid;
+
+/// Subcommand of the `pipeline` binary that runs `serve_mcp`.
+pub const SUBCOMMAND: &str = "permission-bridge";
+
+/// Environment variable carrying the token to the MCP server.
+pub const TOKEN_ENV: &str = "PIPELINE_KIT_BRIDGE_TOKEN";
+
+/// Name of the MCP server in Claude's `--mcp-config`.
+const SERVER_NAME: &str = "pipeline_kit";
+
+/// Name of the tool the MCP server offers.
+const TOOL_NAME: &str = "approve";
+
+/// Value of Claude's `--permission-prompt-tool` flag.
+pub const PROMPT_TOOL: &str = "mcp__pipeline_kit__approve";
+
+/// JSON-RPC error code for an unknown method.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Describes a tool call as its tool kind (e.g. `execute`) and a title.
+pub type DescribeTool = fn(&str, &Value) -> (String, String);
+
+/// Listener answering the permission requests of one agent run.
+///
+/// Dropping the bridge stops the listener and denies the requests it was
+/// still waiting on.
+#[derive(Debug)]
+pub struct PermissionBridge {
+    addr: SocketAddr,
+    token: String,
+    task: JoinHandle<()>,
+}
+
+impl PermissionBridge {
+    /// Start listening on a free loopback port.
+    ///
+    /// # Arguments
+    ///
+    /// * `permissions` - Mode and rules deciding requests
+    /// * `describe` - Maps a tool name and input to its kind and title
+    ///
+    /// # Returns
+    ///
+    /// The bridge and the receiver of the events it emits (permission
+    /// requests and warnings for denied tools).
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::ExecutionError` if the port cannot be bound.
+    pub async fn start(
+        permissions: ToolPermissions,
+        describe: DescribeTool,
+    ) -> Result<(Self, mpsc::Receiver<AgentEvent>), AgentError> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.map_err(|e| {
+            AgentError::ExecutionError(format!("Failed to start permission bridge: {}", e))
+        })?;
+        let addr = listener.local_addr().map_err(|e| {
+            AgentError::ExecutionError(format!("Failed to start permission bridge: {}", e))
+        })?;
+        let token = Uuid::new_v4().to_string();
+        let (events_tx, events_rx) = mpsc::channel(16);
+
+        let expected = token.clone();
+        let task = tokio::spawn(async move {
+            // Requests are answered concurrently; the set aborts them on drop
+            let mut requests = JoinSet::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                requests.spawn(answer(
+                    stream,
+                    expected.clone(),
+                    permissions.clone(),
+                    describe,
+                    events_tx.clone(),
+                ));
+            }
+        });
+
+        Ok((Self { addr, token, task }, events_rx))
+    }
+
+    /// The `--mcp-config` that makes Claude launch the MCP server.
+    ///
+    /// The server is the running executable's `permission-bridge`
+    /// subcommand, pointed at this bridge.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::ExecutionError` if the executable cannot be
+    /// located.
+    pub fn mcp_config(&self) -> Result<Value, AgentError> {
+        let program = std::env::current_exe().map_err(|e| {
+            AgentError::ExecutionError(format!("Failed to locate the pipeline binary: {}", e))
+        })?;
+        Ok(json!({
+            "mcpServers": {
+                SERVER_NAME: {
+                    "command": program,
+                    "args": [SUBCOMMAND, "--addr", self.addr.to_string()],
+                    "env": { TOKEN_ENV: self.token },
+                }
+            }
+        }))
+    }
+}
+
+impl Drop for PermissionBridge {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Answer the request on one connection.
+async fn answer(
+    stream: TcpStream,
+    token: String,
+    permissions: ToolPermissions,
+    describe: DescribeTool,
+    events: mpsc::Sender<AgentEvent>,
+) {
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    if BufReader::new(reader).read_line(&mut line).await.is_err() {
+        return;
+    }
+    let request: Value = serde_json::from_str(&line).unwrap_or(Value::Null);
+
+    // 1. Only the MCP server launched for this run may ask
+    let sent = request.get("token").and_then(Value::as_str).unwrap_or("");
+    let allowed = if bool::from(sent.as_bytes().ct_eq(token.as_bytes())) {
+        // 2. Decide from the mode and rules, asking the user otherwise
+        let tool_name = request
+            .get("tool_name")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown");
+        let input = request.get("input").cloned().unwrap_or(Value::Null);
+        let (kind, title) = describe(tool_name, &input);
+        match permissions.decide(&kind) {
+            PermissionAction::Allow => true,
+            PermissionAction::Deny => {
+                let _ = events
+                    .send(AgentEvent::Warning(format!(
+                        "Denied permission for '{}' under the agent's permission settings",
+                        title
+                    )))
+                    .await;
+                false
+            }
+            PermissionAction::Ask => {
+                let (reply, answer) = PermissionReply::channel();
+                let _ = events
+                    .send(AgentEvent::PermissionRequest {
+                        tool: kind,
+                        title,
+                        arguments: input,
+                        reply,
+                    })
+                    .await;
+                // A dropped reply (nobody answering) denies the request
+                answer.await.unwrap_or(false)
+            }
+        }
+    } else {
+        false
+    };
+
+    let _ = writer
+        .write_all(format!("{}\n", json!({ "allow": allowed })).as_bytes())
+        .await;
+}
+
+/// Run the MCP server Claude calls for permission checks.
+///
+/// Serves `initialize`, `tools/list` and `tools/call` over newline-delimited
+/// JSON-RPC until `reader` is closed.
+///
+/// # Arguments
+///
+/// * `reader` - Claude's requests (the server's stdin)
+/// * `writer` - Responses to Claude (the server's stdout)
+/// * `addr` - Address of the `PermissionBridge`
+/// * `token` - Token of the `PermissionBridge`
+///
+/// # Errors
+///
+/// Returns an error if reading requests or writing responses fails.
+pub async fn serve_mcp<R, W>(
+    reader: R,
+    mut writer: W,
+    addr: &str,
+    token: &str,
+) -> std::io::Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut lines = reader.lines();
+    while let Some(line) = lines.next_line().await? {
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        // Notifications (no id) need no response
+        let Some(id) = message.get("id").cloned() else {
+            continue;
+        };
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        let response = match message.get("method").and_then(Value::as_str) {
+            Some("initialize") => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "result": {
+                    "protocolVersion": params.get("protocolVersion").cloned().unwrap_or(json!("2024-11-05")),
+                    "capabilities": { "tools": {} },
+                    "serverInfo": { "name": "pipeline-kit", "version": env!("CARGO_PKG_VERSION") },
+                },
+            }),
+            Some("tools/list") => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "result": { "tools": [tool_definition()] },
+            }),
+            Some("tools/call") => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "result": call_approve(&params, addr, token).await,
+            }),
+            Some("ping") => json!({ "jsonrpc": "2.0", "id": id, "result": {} }),
+            method => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {
+                    "code": METHOD_NOT_FOUND,
+                    "message": format!("Method not found: {}", method.unwrap_or("")),
+                },
+            }),
+        };
+        writer
+            .write_all(format!("{}\n", response).as_bytes())
+            .await?;
+        writer.flush().await?;
+    }
+    Ok(())
+}
+
+/// The `approve` tool as listed to Claude.
+fn tool_definition() -> Value {
+    json!({
+        "name": TOOL_NAME,
+        "description": "Ask pipeline-kit whether a tool call may run",
+        "inputSchema": {
+            "type": "object",
+            "properties": {
+                "tool_name": { "type": "string" },
+                "input": { "type": "object" },
+                "tool_use_id": { "type": "string" },
+            },
+            "required": ["tool_name", "input"],
+        },
+    })
+}
+
+/// Forward an `approve` call to the bridge and build Claude's result.
+///
+/// Any failure to reach the bridge denies the tool call.
+async fn call_approve(params: &Value, addr: &str, token: &str) -> Value {
+    let arguments = params.get("arguments").cloned().unwrap_or(Value::Null);
+    let input = arguments.get("input").cloned().unwrap_or(json!({}));
+    let request = json!({
+        "token": token,
+        "tool_name": arguments.get("tool_name").cloned().unwrap_or(Value::Null),
+        "input": input,
+    });
+
+    let allowed = match ask_bridge(addr, &request).await {
+        Ok(answer) => answer.get("allow").and_then(Value::as_bool) == Some(true),
+        Err(_) => false,
+    };
+    let decision = if allowed {
+        json!({ "behavior": "allow", "updatedInput": input })
+    } else {
+        json!({ "behavior": "deny", "message": "Permission denied by pipeline-kit" })
+    };
+    json!({ "content": [{ "type": "text", "text": decision.to_string() }] })
+}
+
+/// Send one request line to the bridge and read its answer line.
+async fn ask_bridge(addr: &str, request: &Value) -> std::io::Result<Value> {
+    let stream = TcpStream::connect(addr).await?;
+    let (reader, mut writer) = stream.into_split();
+    writer
+        .write_all(format!("{}\n", request).as_bytes())
+        .await?;
+    let mut line = String::new();
+    BufReader::new(reader).read_line(&mut line).await?;
+    serde_json::from_str(&line).map_err(std::io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pk_protocol::agent_models::PermissionRule;
+
+    fn describe(tool_name: &str, _input: &Value) -> (String, String) {
+        let kind = match tool_name {
+            "Read" => "read",
+            "Bash" => "execute",
+            _ => "fetch",
+        };
+        (kind.to_string(), tool_name.to_string())
+    }
+
+    async fn bridge() -> (PermissionBridge, mpsc::Receiver<AgentEvent>) {
+        let rule = |tool: &str, action| PermissionRule {
+            tool: tool.to_string(),
+            action,
+        };
+        let permissions = ToolPermissions {
+            rules: vec![
+                rule("read", PermissionAction::Allow),
+                rule("execute", PermissionAction::Ask),
+                rule("fetch", PermissionAction::Deny),
+            ],
+            ..ToolPermissions::default()
+        };
+        PermissionBridge::start(permissions, describe)
+            .await
+            .unwrap()
+    }
+
+    /// Run the MCP server over `requests` and return its responses.
+    async fn mcp(bridge: &PermissionBridge, token: &str, requests: &[Value]) -> Vec<Value> {
+        let input: String = requests.iter().map(|r| format!("{}\n", r)).collect();
+        let mut output = Vec::new();
+        serve_mcp(
+            input.as_bytes(),
+            &mut output,
+            &bridge.addr.to_string(),
+            token,
+        )
+        .await
+        .unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    fn call(id: u64, tool_name: &str) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": {
+                "name": TOOL_NAME,
+                "arguments": { "tool_name": tool_name, "input": { "command": "ls" } },
+            },
+        })
+    }
+
+    /// The decision Claude reads from a `tools/call` response.
+    fn behavior(response: &Value) -> Value {
+        let text = response["result"]["content"][0]["text"].as_str().unwrap();
+        serde_json::from_str::<Value>(text).unwrap()["behavior"].clone()
+    }
+
+    #[tokio::test]
+    async fn test_mcp_handshake_lists_approve_tool() {
+        let (bridge, _events) = bridge().await;
+        let responses = mcp(
+            &bridge,
+            &bridge.token,
+            &[
+                json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"protocolVersion": "2025-06-18"}}),
+                json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+                json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+                json!({"jsonrpc": "2.0", "id": 3, "method": "resources/list"}),
+            ],
+        )
+        .await;
+
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["result"]["protocolVersion"], "2025-06-18");
+        assert_eq!(responses[1]["result"]["tools"][0]["name"], TOOL_NAME);
+        assert_eq!(responses[2]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(PROMPT_TOOL, format!("mcp__{}__{}", SERVER_NAME, TOOL_NAME));
+    }
+
+    #[tokio::test]
+    async fn test_bridge_applies_rules() {
+        let (bridge, mut events) = bridge().await;
+        let responses = mcp(
+            &bridge,
+            &bridge.token,
+            &[call(1, "Read"), call(2, "WebFetch")],
+        )
+        .await;
+
+        assert_eq!(behavior(&responses[0]), "allow");
+        // Claude runs the tool with the input it asked about
+        let text = responses[0]["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap();
+        let decision: Value = serde_json::from_str(text).unwrap();
+        assert_eq!(decision["updatedInput"], json!({ "command": "ls" }));
+        assert_eq!(behavior(&responses[1]), "deny");
+        assert!(matches!(
+            events.recv().await,
+            Some(AgentEvent::Warning(w)) if w.contains("'WebFetch'")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_bridge_asks_the_user() {
+        let (bridge, mut events) = bridge().await;
+        let answering = tokio::spawn(async move {
+            let mut allowed = Vec::new();
+            for allow in [true, false] {
+                match events.recv().await {
+                    Some(AgentEvent::PermissionRequest {
+                        tool,
+                        arguments,
+                        reply,
+                        ..
+                    }) => {
+                        assert_eq!(tool, "execute");
+                        assert_eq!(arguments, json!({ "command": "ls" }));
+                        reply.take().unwrap().send(allow).unwrap();
+                        allowed.push(allow);
+                    }
+                    other => panic!("Expected a permission request, got {:?}", other),
+                }
+            }
+            allowed
+        });
+
+        let responses = mcp(&bridge, &bridge.token, &[call(1, "Bash"), call(2, "Bash")]).await;
+        assert_eq!(answering.await.unwrap(), [true, false]);
+        assert_eq!(behavior(&responses[0]), "allow");
+        assert_eq!(behavior(&responses[1]), "deny");
+    }
+
+    #[tokio::test]
+    async fn test_bridge_denies_without_token() {
+        let (bridge, _events) = bridge().await;
+        let responses = mcp(&bridge, "not-the-token", &[call(1, "Read")]).await;
+        assert_eq!(behavior(&responses[0]), "deny");
+
+        // Nothing listening denies as well
+        let addr = bridge.addr.to_string();
+        drop(bridge);
+        let mut output = Vec::new();
+        let input = format!("{}\n", call(1, "Read"));
+        serve_mcp(input.as_bytes(), &mut output, &addr, "token")
+            .await
+            .unwrap();
+        let response: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(behavior(&response), "deny");
+    }
+
+    #[tokio::test]
+    async fn test_mcp_config_launches_bridge_subcommand() {
+        let (bridge, _events) = bridge().await;
+        let config = bridge.mcp_config().unwrap();
+        let server = &config["mcpServers"][SERVER_NAME];
+
+        assert_eq!(
+            server["args"],
+            json!([SUBCOMMAND, "--addr", bridge.addr.to_string()])
+        );
+        assert_eq!(server["env"][TOKEN_ENV], bridge.token);
+    }
+}
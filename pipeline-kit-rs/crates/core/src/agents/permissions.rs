@@ -0,0 +1,202 @@
+//! Tool permissions requested by an agent configuration.
+//!
+//! Adapters translate a `ToolPermissions` into their CLI's native flags in
+//! `with_permissions`. A restriction an adapter cannot enforce is rejected
+//! with `AgentError::Unsupported` instead of being silently dropped, since
+//! running a "read-only" reviewer with full write access is worse than not
+//! running it at all.
+//!
+//! Adapters whose agents ask before running a tool (ACP, and Claude through
+//! the permission bridge) also answer those requests here: `decide` applies
+//! the mode and the permission rules, and requests nothing decides are
+//! forwarded to the user through `AgentEvent::PermissionRequest`.
+
+use crate::agents::base::AgentError;
+use pk_protocol::agent_models::Agent;
+use pk_protocol::agent_models::PermissionAction;
+use pk_protocol::agent_models::PermissionMode;
+use pk_protocol::agent_models::PermissionRule;
+use std::sync::Arc;
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+
+/// Permission settings from an agent's front matter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolPermissions {
+    /// Requested mode; `None` keeps the adapter's default.
+    pub mode: Option<PermissionMode>,
+
+    /// Tools the agent may use; empty keeps the adapter's default set.
+    pub allowed_tools: Vec<String>,
+
+    /// Tools the agent must not use.
+    pub disallowed_tools: Vec<String>,
+
+    /// Rules answering permission requests, first match wins.
+    pub rules: Vec<PermissionRule>,
+}
+
+impl ToolPermissions {
+    /// Collect the permission settings of an agent configuration.
+    pub fn from_config(config: &Agent) -> Self {
+        Self {
+            mode: config.permission_mode,
+            allowed_tools: config.allowed_tools.clone(),
+            disallowed_tools: config.disallowed_tools.clone(),
+            rules: config.permission_rules.clone(),
+        }
+    }
+
+    /// Fail if tool lists were requested from an adapter that has no way to
+    /// enforce them.
+    ///
+    /// # Arguments
+    ///
+    /// * `adapter` - Adapter name used in the error message
+    pub fn reject_tool_lists(&self, adapter: &str) -> Result<(), AgentError> {
+        if !self.allowed_tools.is_empty() {
+            return Err(unsupported(adapter, "allowed-tools"));
+        }
+        if !self.disallowed_tools.is_empty() {
+            return Err(unsupported(adapter, "disallowed-tools"));
+        }
+        Ok(())
+    }
+
+    /// Fail if permission rules were configured for an adapter whose agents
+    /// never ask for permission.
+    ///
+    /// # Arguments
+    ///
+    /// * `adapter` - Adapter name used in the error message
+    pub fn reject_rules(&self, adapter: &str) -> Result<(), AgentError> {
+        if !self.rules.is_empty() {
+            return Err(unsupported(adapter, "permission-rules"));
+        }
+        Ok(())
+    }
+
+    /// Decide a permission request for a tool kind.
+    ///
+    /// The permission mode is a ceiling: what it excludes is denied even if
+    /// a rule allows it. Otherwise the first matching rule decides; without
+    /// one, a configured mode grants the request and no mode asks the user.
+    ///
+    /// # Arguments
+    ///
+    /// * `tool` - Tool kind reported by the agent (e.g. `execute`)
+    pub fn decide(&self, tool: &str) -> PermissionAction {
+        if !mode_allows(self.mode, tool) {
+            return PermissionAction::Deny;
+        }
+        match self.rules.iter().find(|rule| rule.matches(tool)) {
+            Some(rule) => rule.action,
+            None if self.mode.is_some() => PermissionAction::Allow,
+            None => PermissionAction::Ask,
+        }
+    }
+}
+
+/// Whether a permission mode admits a tool kind.
+///
+/// Without a mode every tool is admitted, like the adapters' defaults.
+pub fn mode_allows(mode: Option<PermissionMode>, tool: &str) -> bool {
+    match mode {
+        None | Some(PermissionMode::Full) => true,
+        Some(PermissionMode::Edit) => tool != "execute",
+        Some(PermissionMode::ReadOnly) => matches!(tool, "read" | "search" | "think" | "fetch"),
+    }
+}
+
+/// Channel on which the answer to a permission request is sent back to the
+/// waiting agent.
+///
+/// Cloneable so it can travel inside an `AgentEvent`; only the first answer
+/// is delivered.
+#[derive(Debug, Clone)]
+pub struct PermissionReply(Arc<Mutex<Option<oneshot::Sender<bool>>>>);
+
+impl PermissionReply {
+    /// Create a reply channel and the receiver the agent waits on.
+    pub fn channel() -> (Self, oneshot::Receiver<bool>) {
+        let (tx, rx) = oneshot::channel();
+        (Self(Arc::new(Mutex::new(Some(tx)))), rx)
+    }
+
+    /// Take the sender, leaving the reply answered.
+    ///
+    /// Returns `None` if the reply was already taken.
+    pub fn take(&self) -> Option<oneshot::Sender<bool>> {
+        self.0.lock().ok()?.take()
+    }
+}
+
+impl PartialEq for PermissionReply {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Error for a permission setting an adapter cannot enforce.
+pub fn unsupported(adapter: &str, setting: &str) -> AgentError {
+    AgentError::Unsupported(format!(
+        "the {} adapter cannot enforce `{}`; remove it or use an adapter that supports it",
+        adapter, setting
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(tool: &str, action: PermissionAction) -> PermissionRule {
+        PermissionRule {
+            tool: tool.to_string(),
+            action,
+        }
+    }
+
+    #[test]
+    fn test_decide_applies_mode_then_rules() {
+        let permissions = ToolPermissions {
+            rules: vec![
+                rule("read", PermissionAction::Allow),
+                rule("Execute", PermissionAction::Ask),
+                rule("fetch", PermissionAction::Deny),
+            ],
+            ..ToolPermissions::default()
+        };
+        assert_eq!(permissions.decide("read"), PermissionAction::Allow);
+        assert_eq!(permissions.decide("execute"), PermissionAction::Ask);
+        assert_eq!(permissions.decide("fetch"), PermissionAction::Deny);
+        // Unmatched requests are asked without a mode
+        assert_eq!(permissions.decide("edit"), PermissionAction::Ask);
+
+        let permissions = ToolPermissions {
+            mode: Some(PermissionMode::Edit),
+            rules: vec![rule("*", PermissionAction::Allow)],
+            ..ToolPermissions::default()
+        };
+        // The mode is a ceiling a rule cannot lift
+        assert_eq!(permissions.decide("execute"), PermissionAction::Deny);
+        assert_eq!(permissions.decide("edit"), PermissionAction::Allow);
+
+        let permissions = ToolPermissions {
+            mode: Some(PermissionMode::ReadOnly),
+            ..ToolPermissions::default()
+        };
+        assert_eq!(permissions.decide("search"), PermissionAction::Allow);
+        assert_eq!(permissions.decide("edit"), PermissionAction::Deny);
+    }
+
+    #[test]
+    fn test_permission_reply_delivers_once() {
+        let (reply, mut rx) = PermissionReply::channel();
+        let copy = reply.clone();
+        assert_eq!(reply, copy);
+
+        copy.take().unwrap().send(true).unwrap();
+        assert!(reply.take().is_none());
+        assert_eq!(rx.try_recv(), Ok(true));
+    }
+}
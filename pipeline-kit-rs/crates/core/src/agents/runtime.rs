@@ -0,0 +1,272 @@
+//! Per-agent runtime parameters.
+//!
+//! `RuntimeOptions` carries the front matter settings that shape how an
+//! adapter launches its CLI (turn limit, environment, working directory and
+//! extra arguments). The per-step timeout is not an adapter concern; the
+//! factory wraps the adapter in a `TimeoutAgent` instead, so it applies to
+//! every adapter the same way.
+
+use crate::agents::base::Agent;
+use crate::agents::base::AgentError;
+use crate::agents::base::AgentEvent;
+use crate::agents::base::ExecutionContext;
+use crate::agents::base::Requirements;
+use anyhow::bail;
+use async_trait::async_trait;
+use pk_protocol::agent_models;
+use std::collections::HashMap;
+use std::path::Component;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_stream::Stream;
+use tokio_stream::StreamExt;
+
+/// Launch settings from an agent's front matter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuntimeOptions {
+    /// Maximum number of agentic turns per step.
+    pub max_turns: Option<u32>,
+
+    /// Extra environment variables for the agent process.
+    pub env: HashMap<String, String>,
+
+    /// Working directory relative to the project root.
+    pub working_dir: Option<String>,
+
+    /// Arguments appended to the CLI invocation.
+    pub extra_args: Vec<String>,
+}
+
+impl RuntimeOptions {
+    /// Collect the runtime settings of an agent configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `working-dir` is absolute or leaves the project
+    /// through `..`.
+    pub fn from_config(config: &agent_models::Agent) -> anyhow::Result<Self> {
+        if let Some(dir) = &config.working_dir {
+            let path = Path::new(dir);
+            let escapes = path
+                .components()
+                .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+            if escapes {
+                bail!(
+                    "Agent '{}': working-dir '{}' must be a relative path inside the project",
+                    config.name,
+                    dir
+                );
+            }
+        }
+
+        Ok(Self {
+            max_turns: config.max_turns,
+            env: config.env.clone(),
+            working_dir: config.working_dir.clone(),
+            extra_args: config.extra_args.clone(),
+        })
+    }
+
+    /// Directory the agent process runs in for a project.
+    ///
+    /// # Arguments
+    ///
+    /// * `project_path` - The project root from the execution context
+    pub fn resolve_dir(&self, project_path: &str) -> String {
+        match &self.working_dir {
+            Some(dir) => Path::new(project_path)
+                .join(dir)
+                .to_string_lossy()
+                .to_string(),
+            None => project_path.to_string(),
+        }
+    }
+
+    /// Fail if a turn limit was requested from an adapter whose CLI has no
+    /// way to enforce it.
+    ///
+    /// # Arguments
+    ///
+    /// * `adapter` - Adapter name used in the error message
+    pub fn reject_max_turns(&self, adapter: &str) -> Result<(), AgentError> {
+        if self.max_turns.is_some() {
+            return Err(AgentError::Unsupported(format!(
+                "the {} adapter cannot enforce `max-turns`; remove it or use an adapter that supports it",
+                adapter
+            )));
+        }
+        Ok(())
+    }
+
+    /// Fail if process settings were requested from an adapter that does not
+    /// launch a process (e.g. one calling an HTTP API).
+    ///
+    /// # Arguments
+    ///
+    /// * `adapter` - Adapter name used in the error message
+    pub fn reject_process_options(&self, adapter: &str) -> Result<(), AgentError> {
+        let setting = if !self.env.is_empty() {
+            "env"
+        } else if self.working_dir.is_some() {
+            "working-dir"
+        } else if !self.extra_args.is_empty() {
+            "extra-args"
+        } else {
+            return Ok(());
+        };
+        Err(AgentError::Unsupported(format!(
+            "the {} adapter does not run a process, so `{}` has no effect; remove it",
+            adapter, setting
+        )))
+    }
+}
+
+/// Agent decorator that fails a step running longer than its timeout.
+///
+/// The deadline covers the whole event stream of one `execute` call. When it
+/// passes, the stream yields an `ExecutionError` and ends; dropping the inner
+/// stream releases the underlying process.
+pub struct TimeoutAgent {
+    inner: Arc<dyn Agent>,
+    timeout: Duration,
+}
+
+impl TimeoutAgent {
+    /// Wrap an agent with a per-step timeout.
+    pub fn new(inner: Arc<dyn Agent>, timeout: Duration) -> Self {
+        Self { inner, timeout }
+    }
+}
+
+#[async_trait]
+impl Agent for TimeoutAgent {
+    async fn check_availability(&self) -> bool {
+        self.inner.check_availability().await
+    }
+
+    fn requirements(&self) -> Requirements {
+        self.inner.requirements()
+    }
+
+    async fn execute(
+        &self,
+        context: &ExecutionContext,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<AgentEvent, AgentError>> + Send>>, AgentError>
+    {
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        let mut inner = tokio::time::timeout_at(deadline, self.inner.execute(context))
+            .await
+            .map_err(|_| timed_out(self.timeout))??;
+        let timeout = self.timeout;
+
+        let stream = async_stream::stream! {
+            loop {
+                match tokio::time::timeout_at(deadline, inner.next()).await {
+                    Ok(Some(item)) => yield item,
+                    Ok(None) => break,
+                    Err(_) => {
+                        yield Err(timed_out(timeout));
+                        break;
+                    }
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+fn timed_out(timeout: Duration) -> AgentError {
+    AgentError::ExecutionError(format!("Timed out after {}s", timeout.as_secs()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::adapters::MockAgent;
+
+    fn config(working_dir: Option<&str>) -> agent_models::Agent {
+        agent_models::Agent {
+            name: "agent".to_string(),
+            model: "test-model".to_string(),
+            max_turns: Some(5),
+            env: HashMap::from([("RUST_LOG".to_string(), "debug".to_string())]),
+            working_dir: working_dir.map(str::to_string),
+            extra_args: vec!["--verbose".to_string()],
+            ..Default::default()
+        }
+    }
+
+    /// Agent whose stream never produces an event.
+    struct HangingAgent;
+
+    #[async_trait]
+    impl Agent for HangingAgent {
+        async fn check_availability(&self) -> bool {
+            true
+        }
+
+        async fn execute(
+            &self,
+            _context: &ExecutionContext,
+        ) -> Result<Pin<Box<dyn Stream<Item = Result<AgentEvent, AgentError>> + Send>>, AgentError>
+        {
+            Ok(Box::pin(tokio_stream::pending()))
+        }
+    }
+
+    #[test]
+    fn test_from_config() {
+        let options = RuntimeOptions::from_config(&config(Some("services/api"))).unwrap();
+        assert_eq!(options.max_turns, Some(5));
+        assert_eq!(options.env["RUST_LOG"], "debug");
+        assert_eq!(options.extra_args, vec!["--verbose"]);
+        assert_eq!(
+            Path::new(&options.resolve_dir("/repo")),
+            Path::new("/repo/services/api")
+        );
+
+        let options = RuntimeOptions::from_config(&config(None)).unwrap();
+        assert_eq!(options.resolve_dir("/repo"), "/repo");
+    }
+
+    #[test]
+    fn test_working_dir_must_stay_in_project() {
+        assert!(RuntimeOptions::from_config(&config(Some("../other"))).is_err());
+        assert!(RuntimeOptions::from_config(&config(Some("/etc"))).is_err());
+        assert!(RuntimeOptions::from_config(&config(Some("./a/b"))).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_timeout_agent_fails_slow_step() {
+        let agent = TimeoutAgent::new(Arc::new(HangingAgent), Duration::from_millis(20));
+        let events: Vec<_> = agent
+            .execute(&ExecutionContext::new("hang".to_string()))
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            Err(AgentError::ExecutionError(msg)) if msg.contains("Timed out")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_agent_passes_fast_step_through() {
+        let agent = TimeoutAgent::new(Arc::new(MockAgent::success()), Duration::from_secs(5));
+        let events: Vec<_> = agent
+            .execute(&ExecutionContext::new("work".to_string()))
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        assert!(events.iter().all(|e| e.is_ok()));
+        assert!(matches!(events.last(), Some(Ok(AgentEvent::Completed))));
+    }
+}
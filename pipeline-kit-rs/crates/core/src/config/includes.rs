@@ -0,0 +1,231 @@
+//! Prompt partials for agent Markdown files.
+//!
+//! Agents often share large chunks of guidance. An agent body can pull a
+//! shared file in with `{{> shared/standards.md}}`, and the front matter
+//! can list files to place ahead of the body:
+//!
+//! ```markdown
+//! ---
+//! name: developer
+//! model: claude-sonnet-4.5
+//! includes:
+//!   - shared/repo-layout.md
+//! ---
+//!
+//! You are a developer.
+//!
+//! {{> shared/standards.md}}
+//! ```
+//!
+//! Paths are relative to `.pipeline-kit/`. Partials may include other
+//! partials; a file that ends up including itself is rejected.
+
+use crate::config::error::ConfigError;
+use crate::config::error::ConfigResult;
+use serde::Deserialize;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Opening marker of an inline include.
+const INCLUDE_OPEN: &str = "{{>";
+
+/// Closing marker of an inline include.
+const INCLUDE_CLOSE: &str = "}}";
+
+/// The `includes:` list of an agent's front matter.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct FrontMatterIncludes {
+    #[serde(default)]
+    pub includes: Vec<String>,
+}
+
+/// Expand the partials of an agent body.
+///
+/// The files listed in `includes` come first, separated by blank lines,
+/// followed by `body` with every `{{> path}}` replaced by the file's content.
+///
+/// # Arguments
+///
+/// * `body` - The Markdown body of the agent file
+/// * `includes` - Files listed under `includes:` in the front matter
+/// * `pk_dir` - The `.pipeline-kit/` directory partials are resolved against
+/// * `agent_path` - The agent file, used for cycle detection and errors
+///
+/// # Errors
+///
+/// Returns `ConfigError::InvalidConfig` naming the including file if a
+/// partial is missing, unreadable, outside `.pipeline-kit/`, unterminated
+/// or part of an include cycle.
+pub(crate) fn expand(
+    body: &str,
+    includes: &[String],
+    pk_dir: &Path,
+    agent_path: &Path,
+) -> ConfigResult<String> {
+    let mut stack = vec![agent_path.to_path_buf()];
+    let mut parts = Vec::new();
+    for name in includes {
+        parts.push(include(name, pk_dir, &mut stack)?);
+    }
+    parts.push(expand_text(body, pk_dir, &mut stack)?);
+    Ok(parts.join("\n\n"))
+}
+
+/// Replace the inline includes of `text`, which belongs to the file on top
+/// of `stack`.
+fn expand_text(text: &str, pk_dir: &Path, stack: &mut Vec<PathBuf>) -> ConfigResult<String> {
+    let mut output = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(INCLUDE_OPEN) {
+        output.push_str(&rest[..start]);
+        let after_open = &rest[start + INCLUDE_OPEN.len()..];
+        let Some(end) = after_open.find(INCLUDE_CLOSE) else {
+            return Err(invalid(stack, "Unterminated '{{>' include".to_string()));
+        };
+        output.push_str(&include(after_open[..end].trim(), pk_dir, stack)?);
+        rest = &after_open[end + INCLUDE_CLOSE.len()..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Read and expand the partial `name`, included by the file on top of
+/// `stack`.
+fn include(name: &str, pk_dir: &Path, stack: &mut Vec<PathBuf>) -> ConfigResult<String> {
+    // 1. Partials must stay inside .pipeline-kit/
+    let relative = Path::new(name);
+    let inside = !name.is_empty()
+        && relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if !inside {
+        return Err(invalid(
+            stack,
+            format!(
+                "Include '{}' must be a relative path inside .pipeline-kit",
+                name
+            ),
+        ));
+    }
+    let path = pk_dir.join(relative);
+
+    // 2. Refuse to include a file that is already being expanded
+    if stack.contains(&path) {
+        let chain: Vec<String> = stack
+            .iter()
+            .chain(std::iter::once(&path))
+            .map(|p| display(p, pk_dir))
+            .collect();
+        return Err(invalid(
+            stack,
+            format!("Include cycle: {}", chain.join(" -> ")),
+        ));
+    }
+
+    // 3. Read the partial and expand its own includes
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| invalid(stack, format!("Failed to include '{}': {}", name, e)))?;
+    stack.push(path);
+    let expanded = expand_text(content.trim_end_matches('\n'), pk_dir, stack);
+    stack.pop();
+    expanded
+}
+
+/// An `InvalidConfig` error for the file on top of `stack`.
+fn invalid(stack: &[PathBuf], reason: String) -> ConfigError {
+    ConfigError::InvalidConfig {
+        path: stack.last().cloned().unwrap_or_default(),
+        reason,
+    }
+}
+
+/// `path` relative to `.pipeline-kit/` when possible.
+fn display(path: &Path, pk_dir: &Path) -> String {
+    path.strip_prefix(pk_dir)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, content) in files {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_expand_inline_and_front_matter_includes() {
+        let dir = setup(&[
+            ("shared/layout.md", "Layout: src/\n"),
+            (
+                "shared/standards.md",
+                "Standards:\n{{> shared/naming.md }}\n",
+            ),
+            ("shared/naming.md", "Use snake_case.\n"),
+        ]);
+        let agent = dir.path().join("agents/dev.md");
+
+        let prompt = expand(
+            "You are a developer.\n\n{{> shared/standards.md}}\n",
+            &["shared/layout.md".to_string()],
+            dir.path(),
+            &agent,
+        )
+        .unwrap();
+
+        assert_eq!(
+            prompt,
+            "Layout: src/\n\nYou are a developer.\n\nStandards:\nUse snake_case.\n"
+        );
+    }
+
+    #[test]
+    fn test_expand_detects_cycles() {
+        let dir = setup(&[
+            ("shared/a.md", "{{> shared/b.md}}"),
+            ("shared/b.md", "{{> shared/a.md}}"),
+        ]);
+        let agent = dir.path().join("agents/dev.md");
+
+        match expand("{{> shared/a.md}}", &[], dir.path(), &agent) {
+            Err(ConfigError::InvalidConfig { path, reason }) => {
+                assert_eq!(path, dir.path().join("shared/b.md"));
+                assert_eq!(
+                    reason,
+                    "Include cycle: agents/dev.md -> shared/a.md -> shared/b.md -> shared/a.md"
+                );
+            }
+            other => panic!("Expected an include cycle, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_expand_errors_point_to_including_file() {
+        let dir = setup(&[("shared/a.md", "{{> shared/missing.md}}")]);
+        let agent = dir.path().join("agents/dev.md");
+
+        match expand("{{> shared/a.md}}", &[], dir.path(), &agent) {
+            Err(ConfigError::InvalidConfig { path, reason }) => {
+                assert_eq!(path, dir.path().join("shared/a.md"));
+                assert!(reason.starts_with("Failed to include 'shared/missing.md'"));
+            }
+            other => panic!("Expected a missing include, got {:?}", other),
+        }
+
+        for body in ["{{> ../secrets.md}}", "{{> shared/a.md"] {
+            match expand(body, &[], dir.path(), &agent) {
+                Err(ConfigError::InvalidConfig { path, .. }) => assert_eq!(path, agent),
+                other => panic!("Expected an invalid include, got {:?}", other),
+            }
+        }
+    }
+}
@@ -3,7 +3,8 @@
 //! This module provides functionality to load and parse all configuration files
 //! from the `.pipeline-kit/` directory, including:
 //! - `config.toml`: Global settings
-//! - `agents/*.md`: Agent definitions with YAML front matter
+//! - `agents/*.md`: Agent definitions with YAML front matter, whose bodies
+//!   may include shared partials (see `includes`)
 //! - `pipelines/*.yaml`: Pipeline definitions
 //!
 //! Note: The `result_large_err` lint is suppressed for this module because
@@ -12,8 +13,11 @@
 
 #![allow(clippy::result_large_err)]
 
+use crate::agents::factory::AgentFactory;
 use crate::config::error::ConfigError;
 use crate::config::error::ConfigResult;
+use crate::config::includes;
+use crate::config::includes::FrontMatterIncludes;
 use crate::config::models::AppConfig;
 use gray_matter::engine::YAML;
 use gray_matter::Matter;
@@ -46,6 +50,9 @@ use walkdir::WalkDir;
 /// - Files exist but cannot be read
 /// - Files have invalid syntax (TOML, YAML, or Markdown front matter)
 /// - Required fields are missing in configuration files
+/// - An agent has no `provider:` and its model name matches no known adapter
+/// - An agent requests permissions its adapter cannot enforce
+/// - A fallback chain names an agent that is not defined
 ///
 /// # Example
 ///
@@ -72,9 +79,22 @@ pub async fn load_config(root: &Path) -> ConfigResult<AppConfig> {
 
     // Load agents
     let agents = load_agents(&pk_dir)?;
+    if let Some(name) = unknown_agent(&global.fallback, &agents) {
+        return Err(ConfigError::InvalidConfig {
+            path: pk_dir.join("config.toml"),
+            reason: format!("Fallback agent '{}' is not defined", name),
+        });
+    }
 
     // Load pipelines
-    let pipelines = load_pipelines(&pk_dir)?;
+    let mut pipelines = load_pipelines(&pk_dir)?;
+
+    // Pipelines without their own budget inherit the global default
+    if let Some(budget) = &global.budget {
+        for pipeline in pipelines.iter_mut().filter(|p| p.budget.is_none()) {
+            pipeline.budget = Some(budget.clone());
+        }
+    }
 
     Ok(AppConfig {
         global,
@@ -89,7 +109,11 @@ fn load_global_config(pk_dir: &Path) -> ConfigResult<GlobalConfig> {
 
     // If config.toml doesn't exist, return default
     if !config_path.exists() {
-        return Ok(GlobalConfig { git: false });
+        return Ok(GlobalConfig {
+            git: false,
+            budget: None,
+            fallback: Vec::new(),
+        });
     }
 
     let content =
@@ -117,6 +141,7 @@ fn load_agents(pk_dir: &Path) -> ConfigResult<Vec<Agent>> {
     }
 
     let mut agents = Vec::new();
+    let mut paths = Vec::new();
 
     // Walk through all .md files in the agents directory
     for entry in WalkDir::new(&agents_dir)
@@ -145,27 +170,53 @@ fn load_agents(pk_dir: &Path) -> ConfigResult<Vec<Agent>> {
         let matter = Matter::<YAML>::new();
         let result = matter.parse(&content);
 
-        let mut agent: Agent = result
-            .data
-            .ok_or_else(|| ConfigError::MarkdownParse {
-                path: path.to_path_buf(),
-                reason: "Missing YAML front matter".to_string(),
-            })?
-            .deserialize()
-            .map_err(|e| ConfigError::MarkdownParse {
-                path: path.to_path_buf(),
-                reason: format!("Failed to deserialize front matter: {}", e),
-            })?;
+        let front_matter = result.data.ok_or_else(|| ConfigError::MarkdownParse {
+            path: path.to_path_buf(),
+            reason: "Missing YAML front matter".to_string(),
+        })?;
+        let parse_error = |e: serde_json::Error| ConfigError::MarkdownParse {
+            path: path.to_path_buf(),
+            reason: format!("Failed to deserialize front matter: {}", e),
+        };
+        let mut agent: Agent = front_matter.deserialize().map_err(parse_error)?;
+        let includes: FrontMatterIncludes = front_matter.deserialize().map_err(parse_error)?;
+
+        // Reject agents whose adapter cannot be determined (so a typo in
+        // `model:` fails here instead of running a mock) or cannot enforce
+        // the requested permissions
+        AgentFactory::validate(&agent).map_err(|e| ConfigError::InvalidConfig {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
 
-        // Set the system prompt from the markdown body
-        agent.system_prompt = result.content;
+        // Set the system prompt from the markdown body, with its partials
+        agent.system_prompt = includes::expand(&result.content, &includes.includes, pk_dir, path)?;
 
         agents.push(agent);
+        paths.push(path.to_path_buf());
+    }
+
+    // Fallback chains may only name agents defined next to them
+    for (agent, path) in agents.iter().zip(paths) {
+        if let Some(name) = unknown_agent(&agent.fallback, &agents) {
+            return Err(ConfigError::InvalidConfig {
+                path,
+                reason: format!("Fallback agent '{}' is not defined", name),
+            });
+        }
     }
 
     Ok(agents)
 }
 
+/// The first name in `names` that is not one of `agents`.
+fn unknown_agent<'a>(names: &'a [String], agents: &[Agent]) -> Option<&'a str> {
+    names
+        .iter()
+        .find(|name| !agents.iter().any(|a| &a.name == *name))
+        .map(String::as_str)
+}
+
 /// Loads all pipeline definitions from `pipelines/*.yaml`.
 fn load_pipelines(pk_dir: &Path) -> ConfigResult<Vec<Pipeline>> {
     let pipelines_dir = pk_dir.join("pipelines");
@@ -216,6 +267,9 @@ fn load_pipelines(pk_dir: &Path) -> ConfigResult<Vec<Pipeline>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use pk_protocol::agent_models::AgentProvider;
+    use pk_protocol::agent_models::PermissionMode;
+    use pk_protocol::pipeline_models::BudgetAction;
     use std::fs;
     use tempfile::tempdir;
 
@@ -308,6 +362,66 @@ sub-agents:
         assert_eq!(pipeline.sub_agents[0], "code-reviewer");
     }
 
+    /// A global `[budget]` applies to pipelines that do not set their own.
+    #[tokio::test]
+    async fn test_load_config_budget_defaults_from_global() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let root = dir.path();
+        let pk_dir = root.join(".pipeline-kit");
+        fs::create_dir_all(pk_dir.join("pipelines")).expect("Failed to create pipelines dir");
+
+        let config_toml = r#"
+[budget]
+max-tokens = 1000
+on-exceeded = "fail"
+"#;
+        fs::write(pk_dir.join("config.toml"), config_toml).expect("Failed to write config.toml");
+
+        let inherits = r#"name: inherits
+master:
+  model: test-model
+  system-prompt: "Test"
+  process: []
+sub-agents: []
+"#;
+        let overrides = r#"name: overrides
+master:
+  model: test-model
+  system-prompt: "Test"
+  process: []
+sub-agents: []
+budget:
+  max-cost-usd: 2.5
+  warn-at: 0.5
+"#;
+        fs::write(pk_dir.join("pipelines/inherits.yaml"), inherits)
+            .expect("Failed to write pipeline file");
+        fs::write(pk_dir.join("pipelines/overrides.yaml"), overrides)
+            .expect("Failed to write pipeline file");
+
+        let config = load_config(root).await.expect("Failed to load config");
+
+        let find = |name: &str| {
+            config
+                .pipelines
+                .iter()
+                .find(|p| p.name == name)
+                .and_then(|p| p.budget.clone())
+                .expect("Pipeline should have a budget")
+        };
+
+        let inherited = find("inherits");
+        assert_eq!(inherited.max_tokens, Some(1000));
+        assert_eq!(inherited.on_exceeded, BudgetAction::Fail);
+        assert_eq!(inherited.warn_at, 0.8);
+
+        let own = find("overrides");
+        assert_eq!(own.max_tokens, None);
+        assert_eq!(own.max_cost_usd, Some(2.5));
+        assert_eq!(own.warn_at, 0.5);
+        assert_eq!(own.on_exceeded, BudgetAction::Pause);
+    }
+
     /// RED: Test loading from an empty directory (no .pipeline-kit folder).
     ///
     /// This should return a default/empty configuration, not an error.
@@ -454,6 +568,290 @@ Agent content"#;
     }
 
     /// REFACTOR: Test loading multiple agents and pipelines.
+    #[tokio::test]
+    async fn test_load_config_agent_unknown_model() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let root = dir.path();
+        let pk_dir = root.join(".pipeline-kit");
+
+        fs::create_dir_all(pk_dir.join("agents")).expect("Failed to create agents dir");
+
+        // A typo in the model name must not fall back to a mock agent
+        let typo = r#"---
+name: typo-agent
+description: Misspelled model
+model: claud-sonet-4
+---
+
+Agent content"#;
+        fs::write(pk_dir.join("agents/typo.md"), typo).expect("Failed to write agent file");
+
+        let result = load_config(root).await;
+        if let Err(ConfigError::InvalidConfig { path, reason }) = result {
+            assert!(path.ends_with("typo.md"));
+            assert!(reason.contains("claud-sonet-4"));
+            assert!(reason.contains("provider"));
+        } else {
+            panic!("Expected InvalidConfig error");
+        }
+
+        // An explicit provider accepts any model name
+        let explicit = r#"---
+name: typo-agent
+description: Custom model name
+model: my-finetune
+provider: claude
+---
+
+Agent content"#;
+        fs::write(pk_dir.join("agents/typo.md"), explicit).expect("Failed to write agent file");
+
+        let config = load_config(root).await.expect("Failed to load config");
+        assert_eq!(config.agents[0].provider, Some(AgentProvider::Claude));
+    }
+
+    #[tokio::test]
+    async fn test_load_config_agent_runtime_options() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let root = dir.path();
+        let pk_dir = root.join(".pipeline-kit");
+
+        fs::create_dir_all(pk_dir.join("agents")).expect("Failed to create agents dir");
+
+        let agent = r#"---
+name: api-developer
+description: Works on the API service
+model: claude-sonnet-4.5
+max-turns: 20
+timeout-secs: 600
+working-dir: services/api
+extra-args: ["--verbose"]
+env:
+  RUST_LOG: debug
+---
+
+Agent content"#;
+        fs::write(pk_dir.join("agents/api.md"), agent).expect("Failed to write agent file");
+
+        let config = load_config(root).await.expect("Failed to load config");
+        let agent = &config.agents[0];
+        assert_eq!(agent.max_turns, Some(20));
+        assert_eq!(agent.timeout_secs, Some(600));
+        assert_eq!(agent.working_dir.as_deref(), Some("services/api"));
+        assert_eq!(agent.extra_args, vec!["--verbose"]);
+        assert_eq!(agent.env["RUST_LOG"], "debug");
+
+        // The working directory must stay inside the project
+        let escaping = agent_with_dir("../other");
+        fs::write(pk_dir.join("agents/api.md"), escaping).expect("Failed to write agent file");
+        assert!(matches!(
+            load_config(root).await,
+            Err(ConfigError::InvalidConfig { reason, .. }) if reason.contains("working-dir")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_load_config_custom_agent() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let root = dir.path();
+        let pk_dir = root.join(".pipeline-kit");
+
+        fs::create_dir_all(pk_dir.join("agents")).expect("Failed to create agents dir");
+
+        let agent = r#"---
+name: aider
+description: Aider in JSON mode
+model: gpt-4o
+provider: custom
+command:
+  program: aider
+  args: [--model, "{model}", --message, "{prompt}"]
+  events:
+    message: { when: message, field: text }
+---
+
+Agent content"#;
+        fs::write(pk_dir.join("agents/aider.md"), agent).expect("Failed to write agent file");
+
+        let config = load_config(root).await.expect("Failed to load config");
+        let command = config.agents[0].command.as_ref().expect("command section");
+        assert_eq!(command.program, "aider");
+
+        // `provider: custom` without a command line cannot run
+        let missing = "---\nname: aider\ndescription: Test\nmodel: gpt-4o\nprovider: custom\n---\n\nAgent content";
+        fs::write(pk_dir.join("agents/aider.md"), missing).expect("Failed to write agent file");
+        assert!(matches!(
+            load_config(root).await,
+            Err(ConfigError::InvalidConfig { reason, .. }) if reason.contains("command")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_load_config_agent_includes() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let root = dir.path();
+        let pk_dir = root.join(".pipeline-kit");
+
+        fs::create_dir_all(pk_dir.join("agents")).expect("Failed to create agents dir");
+        fs::create_dir_all(pk_dir.join("shared")).expect("Failed to create shared dir");
+        fs::write(pk_dir.join("shared/layout.md"), "Code lives in src/.\n")
+            .expect("Failed to write partial");
+        fs::write(pk_dir.join("shared/standards.md"), "Write tests.\n")
+            .expect("Failed to write partial");
+
+        let agent = r#"---
+name: developer
+description: Developer agent
+model: claude-sonnet-4.5
+includes:
+  - shared/layout.md
+---
+
+You are a developer.
+
+{{> shared/standards.md}}"#;
+        fs::write(pk_dir.join("agents/developer.md"), agent).expect("Failed to write agent file");
+
+        let config = load_config(root).await.expect("Failed to load config");
+        assert_eq!(
+            config.agents[0].system_prompt,
+            "Code lives in src/.\n\nYou are a developer.\n\nWrite tests."
+        );
+
+        // A missing partial is reported against the file including it
+        fs::write(
+            pk_dir.join("shared/standards.md"),
+            "{{> shared/missing.md}}",
+        )
+        .expect("Failed to write partial");
+        assert!(matches!(
+            load_config(root).await,
+            Err(ConfigError::InvalidConfig { path, .. }) if path.ends_with("shared/standards.md")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_load_config_permission_rules() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let root = dir.path();
+        let pk_dir = root.join(".pipeline-kit");
+
+        fs::create_dir_all(pk_dir.join("agents")).expect("Failed to create agents dir");
+
+        let agent = |model: &str| {
+            format!(
+                "---\nname: developer\ndescription: Test\nmodel: {}\npermission-rules:\n  - {{ tool: execute, action: ask }}\n---\n\nAgent content",
+                model
+            )
+        };
+
+        // ACP agents ask the client, so rules apply
+        fs::write(pk_dir.join("agents/dev.md"), agent("qwen3-coder-plus"))
+            .expect("Failed to write agent file");
+        let config = load_config(root).await.expect("Failed to load config");
+        assert_eq!(config.agents[0].permission_rules.len(), 1);
+
+        // Claude asks through the permission bridge
+        fs::write(pk_dir.join("agents/dev.md"), agent("claude-sonnet-4.5"))
+            .expect("Failed to write agent file");
+        let config = load_config(root).await.expect("Failed to load config");
+        assert_eq!(config.agents[0].permission_rules.len(), 1);
+
+        // Codex never asks, so rules would be silently ignored
+        fs::write(pk_dir.join("agents/dev.md"), agent("gpt-5-codex"))
+            .expect("Failed to write agent file");
+        assert!(matches!(
+            load_config(root).await,
+            Err(ConfigError::InvalidConfig { reason, .. }) if reason.contains("permission")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_load_config_fallback_chains() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let root = dir.path();
+        let pk_dir = root.join(".pipeline-kit");
+
+        fs::create_dir_all(pk_dir.join("agents")).expect("Failed to create agents dir");
+        fs::write(
+            pk_dir.join("agents/developer.md"),
+            "---\nname: developer\ndescription: Test\nmodel: claude-sonnet-4.5\nfallback: [backup]\n---\n\nAgent content",
+        )
+        .expect("Failed to write agent file");
+        fs::write(
+            pk_dir.join("agents/backup.md"),
+            "---\nname: backup\ndescription: Test\nmodel: gpt-5\n---\n\nAgent content",
+        )
+        .expect("Failed to write agent file");
+        fs::write(pk_dir.join("config.toml"), "fallback = [\"backup\"]\n")
+            .expect("Failed to write config");
+
+        let config = load_config(root).await.expect("Failed to load config");
+        assert_eq!(config.global.fallback, vec!["backup"]);
+        let developer = config
+            .agents
+            .iter()
+            .find(|a| a.name == "developer")
+            .unwrap();
+        assert_eq!(developer.fallback, vec!["backup"]);
+
+        // A typo in a chain fails at load time rather than mid-run
+        fs::write(pk_dir.join("config.toml"), "fallback = [\"bakup\"]\n")
+            .expect("Failed to write config");
+        assert!(matches!(
+            load_config(root).await,
+            Err(ConfigError::InvalidConfig { reason, .. }) if reason.contains("'bakup'")
+        ));
+    }
+
+    fn agent_with_dir(dir: &str) -> String {
+        format!(
+            "---\nname: api-developer\ndescription: Test\nmodel: claude-sonnet-4.5\nworking-dir: {}\n---\n\nAgent content",
+            dir
+        )
+    }
+
+    #[tokio::test]
+    async fn test_load_config_agent_permissions() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let root = dir.path();
+        let pk_dir = root.join(".pipeline-kit");
+
+        fs::create_dir_all(pk_dir.join("agents")).expect("Failed to create agents dir");
+
+        let reviewer = r#"---
+name: reviewer
+description: Read-only reviewer
+model: claude-sonnet-4.5
+permission-mode: read-only
+allowed-tools: [Read, Grep, Glob]
+disallowed-tools:
+  - WebFetch
+---
+
+Review the code"#;
+        fs::write(pk_dir.join("agents/reviewer.md"), reviewer).expect("Failed to write agent");
+
+        let config = load_config(root).await.expect("Failed to load config");
+        let agent = &config.agents[0];
+        assert_eq!(agent.permission_mode, Some(PermissionMode::ReadOnly));
+        assert_eq!(agent.allowed_tools, vec!["Read", "Grep", "Glob"]);
+        assert_eq!(agent.disallowed_tools, vec!["WebFetch"]);
+
+        // Codex has no per-tool filter, so the same restriction is rejected
+        let codex = reviewer.replace("claude-sonnet-4.5", "codex-mini");
+        fs::write(pk_dir.join("agents/reviewer.md"), codex).expect("Failed to write agent");
+
+        let result = load_config(root).await;
+        if let Err(ConfigError::InvalidConfig { path, reason }) = result {
+            assert!(path.ends_with("reviewer.md"));
+            assert!(reason.contains("Codex"));
+            assert!(reason.contains("allowed-tools"));
+        } else {
+            panic!("Expected InvalidConfig error");
+        }
+    }
+
     #[tokio::test]
     async fn test_load_config_multiple_files() {
         let dir = tempdir().expect("Failed to create temp dir");
@@ -470,6 +868,7 @@ Agent content"#;
 name: agent-{}
 description: Test agent {}
 model: test-model
+provider: mock
 color: blue
 ---
 
@@ -528,6 +927,7 @@ sub-agents:
 name: valid-agent
 description: Valid agent
 model: test-model
+provider: mock
 ---
 
 Valid content"#;
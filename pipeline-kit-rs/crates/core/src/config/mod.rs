@@ -4,5 +4,6 @@
 //! from the `.pipeline-kit/` directory structure.
 
 pub mod error;
+pub mod includes;
 pub mod loader;
 pub mod models;
@@ -44,7 +44,11 @@ pub struct AppConfig {
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
-            global: GlobalConfig { git: false },
+            global: GlobalConfig {
+                git: false,
+                budget: None,
+                fallback: Vec::new(),
+            },
             agents: Vec::new(),
             pipelines: Vec::new(),
         }
@@ -0,0 +1,207 @@
+//! Token and cost budget tracking.
+//!
+//! The engine feeds the running usage total of a process into a
+//! `BudgetTracker` after every agent step. The tracker reports each
+//! threshold crossing once, so a process gets at most one warning and one
+//! exceeded notice per budget.
+
+use pk_protocol::pipeline_models::Budget;
+use pk_protocol::process_models::TokenUsage;
+
+/// Result of comparing usage against a budget.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BudgetStatus {
+    /// Usage is below the warning threshold of every limit.
+    WithinLimit,
+
+    /// Usage crossed the warning threshold of at least one limit.
+    Warning(String),
+
+    /// Usage reached at least one limit.
+    Exceeded(String),
+}
+
+/// Compare usage against a budget.
+///
+/// # Arguments
+///
+/// * `budget` - The limits to check against
+/// * `usage` - The accumulated usage of the process
+///
+/// # Returns
+///
+/// The most severe status across the token and cost limits.
+pub fn check_budget(budget: &Budget, usage: &TokenUsage) -> BudgetStatus {
+    let mut checks = Vec::new();
+
+    if let Some(max_tokens) = budget.max_tokens {
+        let used = usage.total_tokens();
+        checks.push((
+            ratio(used as f64, max_tokens as f64),
+            format!("{}/{} tokens", used, max_tokens),
+        ));
+    }
+
+    if let Some(max_cost) = budget.max_cost_usd {
+        let used = usage.cost_usd.unwrap_or(0.0);
+        checks.push((
+            ratio(used, max_cost),
+            format!("${:.2}/${:.2}", used, max_cost),
+        ));
+    }
+
+    // 1. Any exhausted limit wins
+    if let Some((_, detail)) = checks.iter().find(|(r, _)| *r >= 1.0) {
+        return BudgetStatus::Exceeded(format!("Budget exceeded: {}", detail));
+    }
+
+    // 2. Otherwise report the limit closest to exhaustion
+    checks
+        .into_iter()
+        .filter(|(r, _)| *r >= budget.warn_at)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(r, detail)| {
+            BudgetStatus::Warning(format!("Budget at {:.0}%: {}", r * 100.0, detail))
+        })
+        .unwrap_or(BudgetStatus::WithinLimit)
+}
+
+/// Fraction of `max` that has been used; a zero limit is exhausted immediately.
+fn ratio(used: f64, max: f64) -> f64 {
+    if max <= 0.0 {
+        f64::INFINITY
+    } else {
+        used / max
+    }
+}
+
+/// Tracks which budget notices have already been emitted for a process.
+#[derive(Debug, Default)]
+pub struct BudgetTracker {
+    budget: Option<Budget>,
+    warned: bool,
+    exceeded: bool,
+}
+
+impl BudgetTracker {
+    /// Create a tracker for an optional budget.
+    ///
+    /// A tracker without a budget never reports anything.
+    pub fn new(budget: Option<Budget>) -> Self {
+        Self {
+            budget,
+            warned: false,
+            exceeded: false,
+        }
+    }
+
+    /// Get the tracked budget, if any.
+    pub fn budget(&self) -> Option<&Budget> {
+        self.budget.as_ref()
+    }
+
+    /// Check usage and return a status only if it has not been reported yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `usage` - The accumulated usage of the process
+    ///
+    /// # Returns
+    ///
+    /// `Some` the first time the warning threshold or a limit is crossed,
+    /// `None` otherwise.
+    pub fn observe(&mut self, usage: &TokenUsage) -> Option<BudgetStatus> {
+        let budget = self.budget.as_ref()?;
+
+        match check_budget(budget, usage) {
+            BudgetStatus::Exceeded(message) if !self.exceeded => {
+                self.exceeded = true;
+                self.warned = true;
+                Some(BudgetStatus::Exceeded(message))
+            }
+            BudgetStatus::Warning(message) if !self.warned => {
+                self.warned = true;
+                Some(BudgetStatus::Warning(message))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(tokens: u64, cost: Option<f64>) -> TokenUsage {
+        TokenUsage {
+            input_tokens: tokens,
+            output_tokens: 0,
+            cost_usd: cost,
+            duration_ms: None,
+        }
+    }
+
+    #[test]
+    fn test_check_budget_token_limit() {
+        let budget = Budget {
+            max_tokens: Some(1000),
+            ..Budget::default()
+        };
+
+        assert_eq!(
+            check_budget(&budget, &usage(500, None)),
+            BudgetStatus::WithinLimit
+        );
+        assert!(matches!(
+            check_budget(&budget, &usage(850, None)),
+            BudgetStatus::Warning(msg) if msg.contains("85%")
+        ));
+        assert!(matches!(
+            check_budget(&budget, &usage(1000, None)),
+            BudgetStatus::Exceeded(msg) if msg.contains("1000/1000 tokens")
+        ));
+    }
+
+    #[test]
+    fn test_check_budget_cost_limit() {
+        let budget = Budget {
+            max_cost_usd: Some(2.0),
+            ..Budget::default()
+        };
+
+        assert_eq!(
+            check_budget(&budget, &usage(1_000_000, None)),
+            BudgetStatus::WithinLimit
+        );
+        assert!(matches!(
+            check_budget(&budget, &usage(0, Some(2.5))),
+            BudgetStatus::Exceeded(msg) if msg.contains("$2.50/$2.00")
+        ));
+    }
+
+    #[test]
+    fn test_tracker_reports_each_threshold_once() {
+        let mut tracker = BudgetTracker::new(Some(Budget {
+            max_tokens: Some(100),
+            ..Budget::default()
+        }));
+
+        assert_eq!(tracker.observe(&usage(10, None)), None);
+        assert!(matches!(
+            tracker.observe(&usage(90, None)),
+            Some(BudgetStatus::Warning(_))
+        ));
+        assert_eq!(tracker.observe(&usage(95, None)), None);
+        assert!(matches!(
+            tracker.observe(&usage(120, None)),
+            Some(BudgetStatus::Exceeded(_))
+        ));
+        assert_eq!(tracker.observe(&usage(200, None)), None);
+    }
+
+    #[test]
+    fn test_tracker_without_budget_is_silent() {
+        let mut tracker = BudgetTracker::new(None);
+        assert_eq!(tracker.observe(&usage(u64::MAX / 2, Some(1e9))), None);
+    }
+}
@@ -3,23 +3,42 @@
 //! The PipelineEngine is responsible for executing pipeline steps sequentially,
 //! managing agent interactions, and coordinating process state transitions.
 
+pub mod budget;
+
 use crate::agents::base::AgentEvent;
+use crate::agents::base::Attachment;
 use crate::agents::base::ExecutionContext;
 use crate::agents::manager::AgentManager;
+use crate::engine::budget::BudgetStatus;
+use crate::engine::budget::BudgetTracker;
 use crate::state::process::advance_step;
 use crate::state::process::complete_process;
+use crate::state::process::complete_step;
+use crate::state::process::expire_permissions;
 use crate::state::process::fail_process;
+use crate::state::process::fail_step;
 use crate::state::process::log_to_process;
 use crate::state::process::pause_for_human_review;
+use crate::state::process::record_fallback;
+use crate::state::process::record_session;
+use crate::state::process::request_permission;
 use crate::state::process::start_process;
+use crate::state::process::start_step;
 use anyhow::anyhow;
 use anyhow::Result;
 use pk_protocol::ipc::Event;
+use pk_protocol::pipeline_models::AgentStep;
+use pk_protocol::pipeline_models::BudgetAction;
 use pk_protocol::pipeline_models::Pipeline;
 use pk_protocol::pipeline_models::ProcessStep;
+use pk_protocol::pipeline_models::SessionMode;
+use pk_protocol::process_models::PermissionRequest;
 use pk_protocol::process_models::Process;
+use pk_protocol::process_models::StepKind;
+use std::path::Path;
 use tokio::sync::mpsc::Sender;
 use tokio_stream::StreamExt;
+use uuid::Uuid;
 
 /// The main pipeline execution engine.
 ///
@@ -39,6 +58,11 @@ impl PipelineEngine {
         Self { agent_manager }
     }
 
+    /// Get the agent manager executing the pipeline steps.
+    pub fn agent_manager(&self) -> &AgentManager {
+        &self.agent_manager
+    }
+
     /// Execute a pipeline and return the final Process state.
     ///
     /// This is the main entry point for pipeline execution. It:
@@ -83,6 +107,9 @@ impl PipelineEngine {
         // Start the process (transition to Running)
         start_process(&mut process, &events_tx).await;
 
+        let mut budget = BudgetTracker::new(pipeline.budget.clone());
+        let step_count = pipeline.master.process.len();
+
         // Execute each step in the pipeline
         for (step_index, step) in pipeline.master.process.iter().enumerate() {
             // Update current step
@@ -91,7 +118,10 @@ impl PipelineEngine {
             }
 
             match step {
-                ProcessStep::Agent(agent_name) => {
+                ProcessStep::Agent(agent_name)
+                | ProcessStep::AgentWithOptions(AgentStep {
+                    agent: agent_name, ..
+                }) => {
                     // Log the step
                     log_to_process(
                         &mut process,
@@ -100,11 +130,32 @@ impl PipelineEngine {
                     )
                     .await;
 
-                    // Execute the agent
+                    let adapter_type = self
+                        .agent_manager
+                        .agent_type(agent_name)
+                        .map(|t| t.name().to_string());
+                    start_step(
+                        &mut process,
+                        &events_tx,
+                        StepKind::Agent,
+                        Some(agent_name.clone()),
+                        adapter_type,
+                    )
+                    .await;
+
+                    // Execute the agent with the step's reference files
+                    let reference_files = step_reference_files(pipeline, &process, step_index);
                     if let Err(e) = self
-                        .execute_agent_step(&mut process, agent_name, &events_tx)
+                        .execute_agent_step(
+                            &mut process,
+                            agent_name,
+                            step.session_mode(),
+                            &reference_files,
+                            &events_tx,
+                        )
                         .await
                     {
+                        fail_step(&mut process, &events_tx, e.to_string()).await;
                         fail_process(
                             &mut process,
                             &events_tx,
@@ -121,6 +172,12 @@ impl PipelineEngine {
                         format!("Agent {} completed", agent_name),
                     )
                     .await;
+                    complete_step(&mut process, &events_tx).await;
+
+                    // Enforce the budget before moving on
+                    let has_more_steps = step_index + 1 < step_count;
+                    self.enforce_budget(&mut process, &mut budget, has_more_steps, &events_tx)
+                        .await?;
                 }
                 ProcessStep::HumanReview(_) => {
                     // Log the human review step
@@ -132,6 +189,7 @@ impl PipelineEngine {
                     .await;
 
                     // Pause for human review
+                    start_step(&mut process, &events_tx, StepKind::HumanReview, None, None).await;
                     pause_for_human_review(&mut process, &events_tx).await;
 
                     // Wait for resume signal via the notifier
@@ -146,6 +204,7 @@ impl PipelineEngine {
                         "Resumed from human review".to_string(),
                     )
                     .await;
+                    complete_step(&mut process, &events_tx).await;
 
                     // Continue to next step after resume
                 }
@@ -158,11 +217,87 @@ impl PipelineEngine {
         Ok(process)
     }
 
+    /// Check the process usage against its budget and act on the result.
+    ///
+    /// Emits `BudgetWarning` the first time the warning threshold is
+    /// crossed. Once a limit is reached, emits `BudgetExceeded` and then
+    /// either fails the process or pauses it for human review until the
+    /// overrun is confirmed by resuming.
+    ///
+    /// # Arguments
+    ///
+    /// * `process` - The current process state
+    /// * `budget` - The budget tracker for this run
+    /// * `has_more_steps` - Whether any steps remain; pausing is skipped otherwise
+    /// * `events_tx` - Channel for sending events
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the budget is exhausted and its action is `fail`.
+    async fn enforce_budget(
+        &self,
+        process: &mut Process,
+        budget: &mut BudgetTracker,
+        has_more_steps: bool,
+        events_tx: &Sender<Event>,
+    ) -> Result<()> {
+        match budget.observe(&process.usage) {
+            Some(BudgetStatus::Warning(message)) => {
+                log_to_process(process, events_tx, format!("[Budget] {}", message)).await;
+                let _ = events_tx
+                    .send(Event::BudgetWarning {
+                        process_id: process.id,
+                        usage: process.usage,
+                        message,
+                    })
+                    .await;
+            }
+            Some(BudgetStatus::Exceeded(message)) => {
+                log_to_process(process, events_tx, format!("[Budget] {}", message)).await;
+                let _ = events_tx
+                    .send(Event::BudgetExceeded {
+                        process_id: process.id,
+                        usage: process.usage,
+                        message: message.clone(),
+                    })
+                    .await;
+
+                let action = budget.budget().map(|b| b.on_exceeded).unwrap_or_default();
+                match action {
+                    BudgetAction::Fail => {
+                        fail_process(process, events_tx, message.clone()).await;
+                        return Err(anyhow!(message));
+                    }
+                    BudgetAction::Pause if has_more_steps => {
+                        pause_for_human_review(process, events_tx).await;
+
+                        // Resuming confirms the overrun
+                        let notifier = process.resume_notifier.clone();
+                        notifier.notified().await;
+
+                        log_to_process(
+                            process,
+                            events_tx,
+                            "Budget overrun confirmed, continuing".to_string(),
+                        )
+                        .await;
+                    }
+                    BudgetAction::Pause => {}
+                }
+            }
+            Some(BudgetStatus::WithinLimit) | None => {}
+        }
+
+        Ok(())
+    }
+
     /// Execute a single agent step.
     ///
     /// This method:
     /// 1. Looks up the agent by name
-    /// 2. Creates an execution context
+    /// 2. Creates an execution context, continuing the agent's session
+    ///    unless the step asks for a fresh one and attaching the reference
+    ///    files
     /// 3. Executes the agent
     /// 4. Streams events and logs from the agent
     ///
@@ -170,24 +305,41 @@ impl PipelineEngine {
     ///
     /// * `process` - The current process state
     /// * `agent_name` - The name of the agent to execute
+    /// * `session_mode` - Whether to continue the agent's earlier conversation
+    /// * `reference_files` - Files attached to the instruction, relative to
+    ///   the project directory
     /// * `events_tx` - Channel for sending events
     ///
     /// # Errors
     ///
-    /// Returns an error if the agent is not found or execution fails.
+    /// Returns an error if the agent is not found, a reference file cannot
+    /// be read, or execution fails.
     async fn execute_agent_step(
         &self,
         process: &mut Process,
         agent_name: &str,
+        session_mode: SessionMode,
+        reference_files: &[String],
         events_tx: &Sender<Event>,
     ) -> Result<()> {
         // Create execution context
         // For now, we use a simple instruction. Future versions may include
         // more context from the pipeline definition.
-        let context = ExecutionContext::new(format!(
+        let session_id = match session_mode {
+            SessionMode::Continue => process.sessions.get(agent_name).cloned(),
+            SessionMode::Fresh => None,
+        };
+        let mut context = ExecutionContext::new(format!(
             "Execute step for pipeline: {}",
             process.pipeline_name
-        ));
+        ))
+        .with_session_id(session_id);
+        for path in reference_files {
+            let attachment = Attachment::from_path(path, Path::new(&context.project_path))
+                .map_err(|e| anyhow!("Reference file unavailable: {}", e))?;
+            log_to_process(process, events_tx, format!("Attached {}", path)).await;
+            context = context.with_attachment(attachment);
+        }
 
         // Execute the agent
         let mut stream = self
@@ -196,38 +348,156 @@ impl PipelineEngine {
             .await
             .map_err(|e| anyhow!("Failed to execute agent {}: {}", agent_name, e))?;
 
-        // Process the event stream
+        // Process the event stream, attributing events to the agent that
+        // actually serves the step
+        let mut serving = agent_name.to_string();
+        let mut permission_requests = Vec::new();
         while let Some(event_result) = stream.next().await {
             match event_result {
                 Ok(AgentEvent::Thought(thought)) => {
                     log_to_process(process, events_tx, format!("[Thought] {}", thought)).await;
                 }
-                Ok(AgentEvent::ToolCall(tool)) => {
-                    log_to_process(process, events_tx, format!("[Tool Call] {}", tool)).await;
+                Ok(AgentEvent::ToolCall {
+                    id,
+                    name,
+                    arguments,
+                }) => {
+                    log_to_process(
+                        process,
+                        events_tx,
+                        format!("[Tool Call] {} {}", name, arguments),
+                    )
+                    .await;
+                    let _ = events_tx
+                        .send(Event::ToolCallStarted {
+                            process_id: process.id,
+                            agent_name: serving.clone(),
+                            call_id: id,
+                            name,
+                            arguments,
+                        })
+                        .await;
+                }
+                Ok(AgentEvent::ToolResult {
+                    id,
+                    output,
+                    is_error,
+                }) => {
+                    if is_error {
+                        log_to_process(process, events_tx, format!("[Tool Error] {}", output))
+                            .await;
+                    }
+                    let _ = events_tx
+                        .send(Event::ToolCallCompleted {
+                            process_id: process.id,
+                            agent_name: serving.clone(),
+                            call_id: id,
+                            output,
+                            is_error,
+                        })
+                        .await;
                 }
                 Ok(AgentEvent::MessageChunk(chunk)) => {
                     log_to_process(process, events_tx, chunk).await;
                 }
+                Ok(AgentEvent::Usage(usage)) => {
+                    process.usage.accumulate(&usage);
+                    let _ = events_tx
+                        .send(Event::UsageReported {
+                            process_id: process.id,
+                            agent_name: serving.clone(),
+                            usage,
+                        })
+                        .await;
+                }
+                Ok(AgentEvent::FileChanged { path, kind }) => {
+                    log_to_process(process, events_tx, format!("[File {:?}] {}", kind, path)).await;
+                    let _ = events_tx
+                        .send(Event::FileChanged {
+                            process_id: process.id,
+                            agent_name: serving.clone(),
+                            path,
+                            kind,
+                        })
+                        .await;
+                }
+                Ok(AgentEvent::Warning(message)) => {
+                    log_to_process(process, events_tx, format!("[Warning] {}", message)).await;
+                    let _ = events_tx
+                        .send(Event::AgentWarning {
+                            process_id: process.id,
+                            agent_name: serving.clone(),
+                            message,
+                        })
+                        .await;
+                }
+                Ok(AgentEvent::PermissionRequest {
+                    tool,
+                    title,
+                    arguments,
+                    reply,
+                }) => {
+                    let Some(reply) = reply.take() else {
+                        continue;
+                    };
+                    let request = PermissionRequest {
+                        request_id: Uuid::new_v4(),
+                        agent_name: serving.clone(),
+                        tool,
+                        title,
+                        arguments,
+                    };
+                    permission_requests.push(request.request_id);
+                    request_permission(process, events_tx, request, reply).await;
+                }
+                Ok(AgentEvent::SessionStarted(session_id)) => {
+                    record_session(process, events_tx, &serving, session_id).await;
+                }
+                Ok(AgentEvent::FallbackUsed { from, to, reason }) => {
+                    record_fallback(process, events_tx, &from, &to, reason).await;
+                    serving = to;
+                }
                 Ok(AgentEvent::Completed) => {
                     // Agent completed successfully
                     break;
                 }
                 Err(e) => {
                     // Agent execution error
+                    expire_permissions(process, events_tx, &permission_requests).await;
                     return Err(anyhow!("Agent error: {}", e));
                 }
             }
         }
 
+        // Nobody is waiting for answers to this step's requests any more
+        expire_permissions(process, events_tx, &permission_requests).await;
         Ok(())
     }
 }
 
+/// Reference files for the agent step at `step_index`: the process's
+/// reference file, then the pipeline's `required-reference-file` for the
+/// step (keyed by 1-based step number).
+fn step_reference_files(pipeline: &Pipeline, process: &Process, step_index: usize) -> Vec<String> {
+    let mut files: Vec<String> = process.reference_file.iter().cloned().collect();
+    if let Some(path) = pipeline
+        .required_reference_file
+        .get(&(step_index as u32 + 1))
+    {
+        if !files.contains(path) {
+            files.push(path.clone());
+        }
+    }
+    files
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::state::process::create_process;
     use pk_protocol::agent_models::Agent as AgentConfig;
+    use pk_protocol::agent_models::AgentProvider;
+    use pk_protocol::pipeline_models::Budget;
     use pk_protocol::pipeline_models::MasterAgentConfig;
     use pk_protocol::process_models::ProcessStatus;
     use std::collections::HashMap;
@@ -238,8 +508,10 @@ mod tests {
             name: name.to_string(),
             description: format!("Test agent {}", name),
             model: "test-model".to_string(),
+            provider: Some(AgentProvider::Mock),
             color: "blue".to_string(),
             system_prompt: "Test prompt".to_string(),
+            ..Default::default()
         }
     }
 
@@ -254,6 +526,7 @@ mod tests {
                 process: steps,
             },
             sub_agents: vec!["agent1".to_string(), "agent2".to_string()],
+            budget: None,
         }
     }
 
@@ -382,4 +655,347 @@ mod tests {
 
         let _ = handle.await;
     }
+
+    #[tokio::test]
+    async fn test_pipeline_engine_forwards_agent_activity() {
+        let mut config = create_test_agent_config("agent1");
+        config.model = "test-activity-model".to_string();
+        let engine = PipelineEngine::new(AgentManager::new(vec![config]));
+
+        let steps = vec![ProcessStep::Agent("agent1".to_string())];
+        let pipeline = create_test_pipeline("activity-test", steps);
+
+        let (tx, mut rx) = mpsc::channel(100);
+        let process = create_process("activity-test".to_string());
+
+        let result = engine.run(&pipeline, process, tx).await;
+        assert!(result.is_ok());
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+
+        assert!(events.iter().any(|e| matches!(
+            e,
+            Event::ToolCallStarted { agent_name, name, call_id: Some(id), .. }
+                if agent_name == "agent1" && name == "write_file" && id == "call_1"
+        )));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            Event::ToolCallCompleted {
+                is_error: false,
+                ..
+            }
+        )));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            Event::FileChanged { path, .. } if path == "out.txt"
+        )));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            Event::UsageReported { usage, .. } if usage.total_tokens() == 150
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_engine_keys_sessions_by_agent() {
+        let configs = ["agent1", "agent2"]
+            .iter()
+            .map(|name| {
+                let mut config = create_test_agent_config(name);
+                config.model = "test-session-model".to_string();
+                config
+            })
+            .collect();
+        let engine = PipelineEngine::new(AgentManager::new(configs));
+
+        let steps = vec![
+            ProcessStep::Agent("agent1".to_string()),
+            ProcessStep::Agent("agent1".to_string()),
+            ProcessStep::AgentWithOptions(AgentStep {
+                agent: "agent1".to_string(),
+                session: SessionMode::Fresh,
+            }),
+            ProcessStep::Agent("agent1".to_string()),
+            ProcessStep::Agent("agent2".to_string()),
+        ];
+        let pipeline = create_test_pipeline("session-test", steps);
+
+        let (tx, mut rx) = mpsc::channel(200);
+        let process = create_process("session-test".to_string());
+        let process = engine.run(&pipeline, process, tx).await.unwrap();
+
+        let mut started = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let Event::SessionStarted {
+                agent_name,
+                session_id,
+                ..
+            } = event
+            {
+                started.push((agent_name, session_id));
+            }
+        }
+
+        // agent1 starts, continues, starts fresh and continues the new one;
+        // agent2 never sees agent1's session
+        assert_eq!(started.len(), 3);
+        let (first, fresh) = (&started[0].1, &started[1].1);
+        assert_ne!(first, fresh);
+        assert_eq!(started[2].0, "agent2");
+        let continued: Vec<_> = process
+            .logs
+            .iter()
+            .filter_map(|log| log.strip_prefix("Continuing session "))
+            .collect();
+        assert_eq!(continued, vec![first.as_str(), fresh.as_str()]);
+        assert_eq!(process.sessions["agent1"], *fresh);
+        assert_eq!(process.sessions.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_engine_records_fallback_agent() {
+        let mut primary = create_test_agent_config("primary");
+        primary.model = "test-rate-limited-model".to_string();
+        let mut backup = create_test_agent_config("backup");
+        backup.model = "test-session-model".to_string();
+        let manager = AgentManager::new(vec![primary, backup])
+            .with_default_fallback(vec!["backup".to_string()]);
+        let engine = PipelineEngine::new(manager);
+
+        let steps = vec![ProcessStep::Agent("primary".to_string())];
+        let pipeline = create_test_pipeline("fallback-test", steps);
+
+        let (tx, mut rx) = mpsc::channel(200);
+        let process = create_process("fallback-test".to_string());
+        let process = engine.run(&pipeline, process, tx).await.unwrap();
+
+        assert_eq!(process.status, ProcessStatus::Completed);
+        assert_eq!(process.steps[0].agent_name.as_deref(), Some("primary"));
+        assert_eq!(process.steps[0].served_by.as_deref(), Some("backup"));
+        assert!(process
+            .logs
+            .iter()
+            .any(|log| log.starts_with("[Fallback] primary -> backup")));
+        // The session belongs to the agent that served the step
+        assert!(process.sessions.contains_key("backup"));
+        assert!(!process.sessions.contains_key("primary"));
+
+        let mut fallbacks = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let Event::AgentFallback {
+                agent_name,
+                fallback_agent,
+                ..
+            } = event
+            {
+                fallbacks.push((agent_name, fallback_agent));
+            }
+        }
+        assert_eq!(
+            fallbacks,
+            vec![("primary".to_string(), "backup".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_engine_attaches_reference_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let reference = dir.path().join("spec.md");
+        std::fs::write(&reference, "# Spec").unwrap();
+        let reference = reference.to_string_lossy().to_string();
+
+        let manager = AgentManager::new(vec![
+            create_test_agent_config("agent1"),
+            create_test_agent_config("agent2"),
+        ]);
+        let engine = PipelineEngine::new(manager);
+        let steps = vec![
+            ProcessStep::Agent("agent1".to_string()),
+            ProcessStep::Agent("agent2".to_string()),
+        ];
+        let mut pipeline = create_test_pipeline("reference-test", steps);
+        let missing = dir.path().join("missing.md").to_string_lossy().to_string();
+        pipeline.required_reference_file.insert(2, missing);
+
+        let (tx, _rx) = mpsc::channel(200);
+        let mut process = create_process("reference-test".to_string());
+        process.reference_file = Some(reference.clone());
+        let result = engine.run(&pipeline, process, tx).await;
+
+        // The first step gets the process's reference file; the second also
+        // needs a missing one and fails
+        let error = result.expect_err("Missing reference file should fail");
+        assert!(error.to_string().contains("Reference file unavailable"));
+
+        let (tx, _rx) = mpsc::channel(200);
+        let mut process = create_process("reference-test".to_string());
+        process.reference_file = Some(reference.clone());
+        pipeline.required_reference_file.clear();
+        let process = engine.run(&pipeline, process, tx).await.unwrap();
+        assert_eq!(
+            process
+                .logs
+                .iter()
+                .filter(|log| **log == format!("Attached {}", reference))
+                .count(),
+            2
+        );
+    }
+
+    fn create_budget_pipeline(action: BudgetAction) -> Pipeline {
+        let steps = vec![
+            ProcessStep::Agent("agent1".to_string()),
+            ProcessStep::Agent("agent2".to_string()),
+        ];
+        let mut pipeline = create_test_pipeline("budget-test", steps);
+        pipeline.budget = Some(Budget {
+            max_tokens: Some(100),
+            on_exceeded: action,
+            ..Budget::default()
+        });
+        pipeline
+    }
+
+    fn create_activity_manager() -> AgentManager {
+        let configs = ["agent1", "agent2"]
+            .iter()
+            .map(|name| {
+                let mut config = create_test_agent_config(name);
+                config.model = "test-activity-model".to_string();
+                config
+            })
+            .collect();
+        AgentManager::new(configs)
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_engine_budget_exceeded_fails_process() {
+        let engine = PipelineEngine::new(create_activity_manager());
+        let pipeline = create_budget_pipeline(BudgetAction::Fail);
+
+        let (tx, mut rx) = mpsc::channel(100);
+        let process = create_process("budget-test".to_string());
+
+        let result = engine.run(&pipeline, process, tx).await;
+        assert!(result.is_err());
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+
+        assert!(events.iter().any(
+            |e| matches!(e, Event::BudgetExceeded { usage, .. } if usage.total_tokens() == 150)
+        ));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            Event::ProcessStatusUpdate {
+                status: ProcessStatus::Failed,
+                ..
+            }
+        )));
+        // The second agent never ran
+        assert!(!events.iter().any(|e| matches!(
+            e,
+            Event::ToolCallStarted { agent_name, .. } if agent_name == "agent2"
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_engine_budget_exceeded_pauses_until_resumed() {
+        let engine = PipelineEngine::new(create_activity_manager());
+        let pipeline = create_budget_pipeline(BudgetAction::Pause);
+
+        let (tx, mut rx) = mpsc::channel(100);
+        let process = create_process("budget-test".to_string());
+        let notifier = process.resume_notifier.clone();
+
+        let handle = tokio::spawn(async move { engine.run(&pipeline, process, tx).await });
+
+        // Wait for the pause triggered by the budget
+        while let Some(event) = rx.recv().await {
+            if matches!(
+                event,
+                Event::ProcessStatusUpdate {
+                    status: ProcessStatus::HumanReview,
+                    ..
+                }
+            ) {
+                break;
+            }
+        }
+        assert!(!handle.is_finished());
+
+        // Confirm the overrun
+        notifier.notify_one();
+
+        let final_process = handle.await.unwrap().unwrap();
+        assert_eq!(final_process.status, ProcessStatus::Completed);
+        assert_eq!(final_process.usage.total_tokens(), 300);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_engine_records_step_timeline() {
+        let configs = vec![create_test_agent_config("agent1")];
+        let engine = PipelineEngine::new(AgentManager::new(configs));
+
+        let steps = vec![ProcessStep::Agent("agent1".to_string())];
+        let pipeline = create_test_pipeline("timeline-test", steps);
+
+        let (tx, mut rx) = mpsc::channel(100);
+        let process = create_process("timeline-test".to_string());
+
+        let final_process = engine.run(&pipeline, process, tx).await.unwrap();
+
+        assert_eq!(final_process.steps.len(), 1);
+        let step = &final_process.steps[0];
+        assert_eq!(step.kind, StepKind::Agent);
+        assert_eq!(step.agent_name.as_deref(), Some("agent1"));
+        assert_eq!(step.adapter_type.as_deref(), Some("Mock"));
+        assert_eq!(
+            step.status,
+            pk_protocol::process_models::StepStatus::Completed
+        );
+        assert!(step.duration_ms.is_some());
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        let started = events
+            .iter()
+            .position(|e| matches!(e, Event::StepStarted { .. }))
+            .expect("StepStarted should be emitted");
+        let completed = events
+            .iter()
+            .position(|e| matches!(e, Event::StepCompleted { .. }))
+            .expect("StepCompleted should be emitted");
+        assert!(started < completed);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_engine_emits_step_failed() {
+        let mut config = create_test_agent_config("agent1");
+        config.model = "test-failure-model".to_string();
+        let engine = PipelineEngine::new(AgentManager::new(vec![config]));
+
+        let steps = vec![ProcessStep::Agent("agent1".to_string())];
+        let pipeline = create_test_pipeline("failing-step", steps);
+
+        let (tx, mut rx) = mpsc::channel(100);
+        let process = create_process("failing-step".to_string());
+
+        assert!(engine.run(&pipeline, process, tx).await.is_err());
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        assert!(events.iter().any(|e| matches!(
+            e,
+            Event::StepFailed { step, .. } if step.error.as_deref().is_some_and(|e| e.contains("Mock failure"))
+        )));
+    }
 }
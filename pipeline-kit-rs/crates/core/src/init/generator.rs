@@ -312,7 +312,7 @@ mod tests {
         assert!(!options.force, "Default force should be false");
         assert!(!options.minimal, "Default minimal should be false");
         assert!(
-            options.target_dir.is_absolute() || options.target_dir == PathBuf::from("."),
+            options.target_dir.is_absolute() || options.target_dir == Path::new("."),
             "Default target_dir should be current directory"
         );
     }
@@ -0,0 +1,300 @@
+//! Op dispatching shared by every front end.
+//!
+//! The TUI, the local HTTP API and any other client speak the same `Op`
+//! protocol. `OpDispatcher` owns the loaded pipeline definitions and maps
+//! each `Op` onto the matching `StateManager` call, so the front ends only
+//! have to deal with transport concerns.
+
+use crate::state::manager::StateManager;
+use anyhow::Result;
+use pk_protocol::ipc::Event;
+use pk_protocol::ipc::Op;
+use pk_protocol::pipeline_models::Pipeline;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Applies `Op`s to a `StateManager`.
+pub struct OpDispatcher {
+    /// The state manager that owns all processes.
+    state_manager: Arc<StateManager>,
+
+    /// Pipeline definitions loaded from `.pipeline-kit/pipelines/`.
+    pipelines: Vec<Pipeline>,
+}
+
+impl OpDispatcher {
+    /// Create a new dispatcher.
+    ///
+    /// # Arguments
+    ///
+    /// * `state_manager` - The state manager to drive
+    /// * `pipelines` - The pipelines that can be started by name
+    pub fn new(state_manager: Arc<StateManager>, pipelines: Vec<Pipeline>) -> Self {
+        Self {
+            state_manager,
+            pipelines,
+        }
+    }
+
+    /// Get the underlying state manager.
+    pub fn state_manager(&self) -> &Arc<StateManager> {
+        &self.state_manager
+    }
+
+    /// Get all known pipeline definitions.
+    pub fn pipelines(&self) -> &[Pipeline] {
+        &self.pipelines
+    }
+
+    /// Find a pipeline definition by name.
+    pub fn find_pipeline(&self, name: &str) -> Option<&Pipeline> {
+        self.pipelines.iter().find(|p| p.name == name)
+    }
+
+    /// Apply a single `Op`.
+    ///
+    /// # Arguments
+    ///
+    /// * `op` - The operation to apply
+    ///
+    /// # Returns
+    ///
+    /// The ID of the newly created process for `Op::StartPipeline`,
+    /// `None` for every other operation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the requested pipeline or process does not exist.
+    pub async fn dispatch(&self, op: Op) -> Result<Option<Uuid>> {
+        match op {
+            Op::StartPipeline {
+                name,
+                reference_file,
+            } => {
+                let pipeline = self
+                    .find_pipeline(&name)
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("Pipeline not found: {}", name))?;
+                let reference_file = reference_file.map(|p| p.to_string_lossy().to_string());
+                let process_id = self
+                    .state_manager
+                    .start_pipeline_with_reference_file(pipeline, reference_file)
+                    .await;
+                Ok(Some(process_id))
+            }
+            Op::PauseProcess { process_id } => {
+                self.state_manager.pause_process_by_id(process_id).await?;
+                Ok(None)
+            }
+            Op::ResumeProcess { process_id } => {
+                self.state_manager.resume_process_by_id(process_id).await?;
+                Ok(None)
+            }
+            Op::KillProcess { process_id } => {
+                self.state_manager.kill_process(process_id).await?;
+                Ok(None)
+            }
+            Op::GetDashboardState => {
+                self.state_manager.emit_dashboard_state().await;
+                Ok(None)
+            }
+            Op::GetProcessDetail { process_id } => {
+                self.state_manager.emit_process_detail(process_id).await?;
+                Ok(None)
+            }
+            Op::AnswerPermission {
+                process_id,
+                request_id,
+                allow,
+            } => {
+                self.state_manager
+                    .answer_permission(process_id, request_id, allow)
+                    .await?;
+                Ok(None)
+            }
+            Op::Diagnose => {
+                self.state_manager.emit_diagnostics().await;
+                Ok(None)
+            }
+            Op::Shutdown => {
+                // Shutdown is handled by each front end; no-op here.
+                Ok(None)
+            }
+        }
+    }
+
+    /// Apply an `Op` and report the outcome as an `Event::CommandResult`.
+    ///
+    /// Used by front ends that have no request/response channel of their
+    /// own (the TUI), so failures are surfaced instead of swallowed.
+    ///
+    /// # Arguments
+    ///
+    /// * `op` - The operation to apply
+    pub async fn dispatch_and_report(&self, op: Op) {
+        let event = match self.dispatch(op.clone()).await {
+            Ok(process_id) => Event::CommandResult {
+                op,
+                ok: true,
+                process_id,
+                error: None,
+            },
+            Err(e) => Event::CommandResult {
+                op,
+                ok: false,
+                process_id: None,
+                error: Some(e.to_string()),
+            },
+        };
+        self.state_manager.emit_event(event).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::manager::AgentManager;
+    use pk_protocol::agent_models::Agent as AgentConfig;
+    use pk_protocol::agent_models::AgentProvider;
+    use pk_protocol::pipeline_models::MasterAgentConfig;
+    use pk_protocol::pipeline_models::ProcessStep;
+    use std::collections::HashMap;
+    use tokio::sync::mpsc;
+
+    fn create_dispatcher() -> OpDispatcher {
+        create_dispatcher_with_events().0
+    }
+
+    fn create_dispatcher_with_events() -> (OpDispatcher, mpsc::Receiver<Event>) {
+        let agents = vec![AgentConfig {
+            name: "agent1".to_string(),
+            description: "Test agent".to_string(),
+            model: "test-model".to_string(),
+            provider: Some(AgentProvider::Mock),
+            color: "blue".to_string(),
+            system_prompt: "Test prompt".to_string(),
+            ..Default::default()
+        }];
+        let pipeline = Pipeline {
+            name: "test-pipeline".to_string(),
+            required_reference_file: HashMap::new(),
+            output_file: HashMap::new(),
+            master: MasterAgentConfig {
+                model: "test-model".to_string(),
+                system_prompt: "Test orchestration".to_string(),
+                process: vec![ProcessStep::Agent("agent1".to_string())],
+            },
+            sub_agents: vec!["agent1".to_string()],
+            budget: None,
+        };
+        let (tx, rx) = mpsc::channel(100);
+        let state_manager = Arc::new(StateManager::new(AgentManager::new(agents), tx));
+        (OpDispatcher::new(state_manager, vec![pipeline]), rx)
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_start_pipeline_returns_process_id() {
+        let dispatcher = create_dispatcher();
+
+        let result = dispatcher
+            .dispatch(Op::StartPipeline {
+                name: "test-pipeline".to_string(),
+                reference_file: None,
+            })
+            .await
+            .unwrap();
+
+        let process_id = result.expect("StartPipeline should return a process id");
+        assert!(dispatcher
+            .state_manager()
+            .get_process(process_id)
+            .await
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_unknown_pipeline_is_error() {
+        let dispatcher = create_dispatcher();
+
+        let result = dispatcher
+            .dispatch(Op::StartPipeline {
+                name: "missing".to_string(),
+                reference_file: None,
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("missing"));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_unknown_process_is_error() {
+        let dispatcher = create_dispatcher();
+
+        let result = dispatcher
+            .dispatch(Op::KillProcess {
+                process_id: Uuid::new_v4(),
+            })
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_diagnose_emits_report() {
+        let (dispatcher, mut rx) = create_dispatcher_with_events();
+
+        dispatcher.dispatch(Op::Diagnose).await.unwrap();
+
+        let event = rx.recv().await.unwrap();
+        assert!(matches!(
+            event,
+            Event::DiagnosticsReport { agents }
+                if agents.len() == 1 && agents[0].agent_name == "agent1" && agents[0].available
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_and_report_emits_error_result() {
+        let (dispatcher, mut rx) = create_dispatcher_with_events();
+
+        dispatcher
+            .dispatch_and_report(Op::StartPipeline {
+                name: "missing".to_string(),
+                reference_file: None,
+            })
+            .await;
+
+        let event = rx.recv().await.unwrap();
+        assert!(matches!(
+            event,
+            Event::CommandResult {
+                op: Op::StartPipeline { name, .. },
+                ok: false,
+                process_id: None,
+                error: Some(error),
+            } if name == "missing" && error.contains("Pipeline not found")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_and_report_emits_success_result() {
+        let (dispatcher, mut rx) = create_dispatcher_with_events();
+
+        dispatcher
+            .dispatch_and_report(Op::StartPipeline {
+                name: "test-pipeline".to_string(),
+                reference_file: None,
+            })
+            .await;
+
+        // Lifecycle events may arrive first; find the command result
+        loop {
+            if let Event::CommandResult { ok, process_id, .. } = rx.recv().await.unwrap() {
+                assert!(ok);
+                assert!(process_id.is_some());
+                break;
+            }
+        }
+    }
+}
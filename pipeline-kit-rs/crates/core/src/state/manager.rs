@@ -6,6 +6,8 @@
 
 use crate::agents::manager::AgentManager;
 use crate::engine::PipelineEngine;
+use crate::state::process::answer_permission;
+use crate::state::process::apply_event;
 use crate::state::process::kill_process_state;
 use crate::state::process::pause_process;
 use crate::state::process::resume_process;
@@ -77,8 +79,31 @@ impl StateManager {
     ///
     /// The UUID of the newly created process.
     pub async fn start_pipeline(&self, pipeline: Pipeline) -> Uuid {
+        self.start_pipeline_with_reference_file(pipeline, None)
+            .await
+    }
+
+    /// Start executing a pipeline with a reference file attached to every
+    /// agent step.
+    ///
+    /// # Arguments
+    ///
+    /// * `pipeline` - The pipeline definition to execute
+    /// * `reference_file` - File given to the agents as context, relative
+    ///   to the project directory
+    ///
+    /// # Returns
+    ///
+    /// The UUID of the newly created process.
+    pub async fn start_pipeline_with_reference_file(
+        &self,
+        pipeline: Pipeline,
+        reference_file: Option<String>,
+    ) -> Uuid {
         // Create and register the process
-        let process_id = self.create_and_register_process(&pipeline.name).await;
+        let process_id = self
+            .create_and_register_process(&pipeline.name, reference_file)
+            .await;
 
         // Spawn the pipeline execution in the background
         self.spawn_pipeline_execution(process_id, pipeline).await;
@@ -95,15 +120,21 @@ impl StateManager {
     /// # Arguments
     ///
     /// * `pipeline_name` - The name of the pipeline
+    /// * `reference_file` - Reference file for the agent steps, if any
     ///
     /// # Returns
     ///
     /// The UUID of the newly created process.
-    async fn create_and_register_process(&self, pipeline_name: &str) -> Uuid {
+    async fn create_and_register_process(
+        &self,
+        pipeline_name: &str,
+        reference_file: Option<String>,
+    ) -> Uuid {
         let process_id = Uuid::new_v4();
 
         let mut initial_process = crate::state::process::create_process(pipeline_name.to_string());
         initial_process.id = process_id;
+        initial_process.reference_file = reference_file;
 
         let mut procs = self.processes.lock().await;
         procs.insert(process_id, Arc::new(Mutex::new(initial_process)));
@@ -139,7 +170,21 @@ impl StateManager {
         };
 
         let handle = tokio::spawn(async move {
-            match engine.run(&pipeline, process, events_tx.clone()).await {
+            // Mirror engine events into the registry so snapshots stay live
+            let (relay_tx, relay_rx) = mpsc::channel(100);
+            let relay = tokio::spawn(Self::relay_events(
+                processes.clone(),
+                process_id,
+                relay_rx,
+                events_tx,
+            ));
+
+            let result = engine.run(&pipeline, process, relay_tx).await;
+
+            // The engine dropped its sender; wait until every event is applied
+            let _ = relay.await;
+
+            match result {
                 Ok(final_process) => {
                     Self::update_process_state(processes.clone(), process_id, final_process).await;
                 }
@@ -158,6 +203,31 @@ impl StateManager {
         handles.insert(process_id, handle);
     }
 
+    /// Apply engine events to the registry copy of a process and forward them.
+    ///
+    /// Runs until the engine drops its sender.
+    ///
+    /// # Arguments
+    ///
+    /// * `processes` - The shared process registry
+    /// * `process_id` - The ID of the process being executed
+    /// * `relay_rx` - Events emitted by the engine
+    /// * `events_tx` - Channel for sending events to the UI
+    async fn relay_events(
+        processes: Arc<Mutex<HashMap<Uuid, Arc<Mutex<Process>>>>>,
+        process_id: Uuid,
+        mut relay_rx: mpsc::Receiver<Event>,
+        events_tx: mpsc::Sender<Event>,
+    ) {
+        while let Some(event) = relay_rx.recv().await {
+            let process_arc = processes.lock().await.get(&process_id).cloned();
+            if let Some(process_arc) = process_arc {
+                apply_event(&mut *process_arc.lock().await, &event);
+            }
+            let _ = events_tx.send(event).await;
+        }
+    }
+
     /// Update the stored process state after successful execution.
     ///
     /// # Arguments
@@ -283,6 +353,41 @@ impl StateManager {
         }
     }
 
+    /// Answer a permission request of a running agent.
+    ///
+    /// # Arguments
+    ///
+    /// * `process_id` - The UUID of the process whose agent asked
+    /// * `request_id` - The request to answer
+    /// * `allow` - Whether the tool call may run
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the process is not found or the request is no
+    /// longer pending.
+    pub async fn answer_permission(
+        &self,
+        process_id: Uuid,
+        request_id: Uuid,
+        allow: bool,
+    ) -> Result<()> {
+        let processes = self.processes.lock().await;
+
+        if let Some(process_arc) = processes.get(&process_id) {
+            let mut process = process_arc.lock().await;
+            if answer_permission(&mut process, &self.events_tx, request_id, allow).await {
+                Ok(())
+            } else {
+                Err(anyhow::anyhow!(
+                    "Permission request {} is not pending",
+                    request_id
+                ))
+            }
+        } else {
+            Err(anyhow::anyhow!("Process {} not found", process_id))
+        }
+    }
+
     /// Get the current state of a process.
     ///
     /// # Arguments
@@ -319,6 +424,48 @@ impl StateManager {
         result
     }
 
+    /// Send an event to the UI on the StateManager's channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event to send
+    pub async fn emit_event(&self, event: Event) {
+        let _ = self.events_tx.send(event).await;
+    }
+
+    /// Emit a `DashboardState` snapshot of all processes.
+    ///
+    /// Processes are ordered by start time.
+    pub async fn emit_dashboard_state(&self) {
+        let mut processes = self.get_all_processes().await;
+        processes.sort_by_key(|p| p.started_at);
+        self.emit_event(Event::DashboardState { processes }).await;
+    }
+
+    /// Emit a `ProcessDetail` snapshot of a single process.
+    ///
+    /// # Arguments
+    ///
+    /// * `process_id` - The UUID of the process to describe
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the process is not found.
+    pub async fn emit_process_detail(&self, process_id: Uuid) -> Result<()> {
+        let process = self
+            .get_process(process_id)
+            .await
+            .ok_or_else(|| anyhow::anyhow!("Process {} not found", process_id))?;
+        self.emit_event(Event::ProcessDetail { process }).await;
+        Ok(())
+    }
+
+    /// Diagnose every configured agent and emit a `DiagnosticsReport`.
+    pub async fn emit_diagnostics(&self) {
+        let agents = self.engine.agent_manager().diagnose().await;
+        self.emit_event(Event::DiagnosticsReport { agents }).await;
+    }
+
     /// Get the number of active processes.
     pub async fn process_count(&self) -> usize {
         let processes = self.processes.lock().await;
@@ -330,6 +477,7 @@ impl StateManager {
 mod tests {
     use super::*;
     use pk_protocol::agent_models::Agent as AgentConfig;
+    use pk_protocol::agent_models::AgentProvider;
     use pk_protocol::pipeline_models::MasterAgentConfig;
     use pk_protocol::pipeline_models::ProcessStep;
     use pk_protocol::process_models::ProcessStatus;
@@ -340,8 +488,10 @@ mod tests {
             name: name.to_string(),
             description: format!("Test agent {}", name),
             model: "test-model".to_string(),
+            provider: Some(AgentProvider::Mock),
             color: "blue".to_string(),
             system_prompt: "Test prompt".to_string(),
+            ..Default::default()
         }
     }
 
@@ -356,6 +506,7 @@ mod tests {
                 process: steps,
             },
             sub_agents: vec!["agent1".to_string()],
+            budget: None,
         }
     }
 
@@ -512,11 +663,10 @@ mod tests {
             "Pipeline should reach HUMAN_REVIEW state (verified via events)"
         );
 
-        // NOTE: We verify HumanReview state via events because StateManager's
-        // process map is only updated when engine.run() completes. While paused
-        // at HumanReview, engine.run() is blocked, so the process state in the
-        // map hasn't been updated yet. This is expected behavior - the events
-        // are the source of truth for real-time state updates.
+        // The registry mirrors engine events, so it reflects the pause too
+        let paused = state_manager.get_process(process_id).await.unwrap();
+        assert_eq!(paused.status, ProcessStatus::HumanReview);
+        assert!(!paused.logs.is_empty());
 
         // Resume the process
         let resume_result = state_manager.resume_process_by_id(process_id).await;
@@ -616,4 +766,61 @@ mod tests {
         // Verify the task handle was removed (implicitly tested by successful abort)
         // If the handle wasn't removed, subsequent operations would fail
     }
+
+    #[tokio::test]
+    async fn test_snapshots_reflect_running_process() {
+        let configs = vec![create_test_agent_config("agent1")];
+        let manager = AgentManager::new(configs);
+        let (tx, mut rx) = mpsc::channel(100);
+
+        let state_manager = StateManager::new(manager, tx);
+
+        let steps = vec![
+            ProcessStep::Agent("agent1".to_string()),
+            ProcessStep::HumanReview(pk_protocol::pipeline_models::HumanReviewMarker),
+        ];
+        let pipeline = create_test_pipeline("snapshot-pipeline", steps);
+        let process_id = state_manager.start_pipeline(pipeline).await;
+
+        // Wait until the process pauses mid-run
+        let timeout = tokio::time::Duration::from_secs(2);
+        while let Ok(Some(event)) = tokio::time::timeout(timeout, rx.recv()).await {
+            if matches!(
+                event,
+                Event::ProcessStatusUpdate {
+                    status: ProcessStatus::HumanReview,
+                    ..
+                }
+            ) {
+                break;
+            }
+        }
+
+        state_manager.emit_dashboard_state().await;
+        match rx.recv().await.unwrap() {
+            Event::DashboardState { processes } => {
+                assert_eq!(processes.len(), 1);
+                assert_eq!(processes[0].status, ProcessStatus::HumanReview);
+            }
+            other => panic!("Expected DashboardState, got {:?}", other),
+        }
+
+        state_manager.emit_process_detail(process_id).await.unwrap();
+        match rx.recv().await.unwrap() {
+            Event::ProcessDetail { process } => {
+                assert_eq!(process.id, process_id);
+                assert!(process
+                    .logs
+                    .iter()
+                    .any(|l| l.contains("Executing agent: agent1")));
+                assert_eq!(process.steps.len(), 2);
+            }
+            other => panic!("Expected ProcessDetail, got {:?}", other),
+        }
+
+        assert!(state_manager
+            .emit_process_detail(Uuid::new_v4())
+            .await
+            .is_err());
+    }
 }
@@ -3,6 +3,8 @@
 //! This module provides:
 //! - Process state machine logic
 //! - StateManager for coordinating multiple processes
+//! - OpDispatcher for applying protocol operations
 
+pub mod dispatcher;
 pub mod manager;
 pub mod process;
@@ -4,10 +4,16 @@
 //! including state transitions and event emission.
 
 use pk_protocol::ipc::Event;
+use pk_protocol::process_models::PermissionRequest;
 use pk_protocol::process_models::Process;
 use pk_protocol::process_models::ProcessStatus;
+use pk_protocol::process_models::StepKind;
+use pk_protocol::process_models::StepRecord;
+use pk_protocol::process_models::StepStatus;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::mpsc::Sender;
+use tokio::sync::oneshot;
 use tokio::sync::Notify;
 use uuid::Uuid;
 
@@ -30,6 +36,12 @@ pub fn create_process(pipeline_name: String) -> Process {
         completed_at: None,
         logs: Vec::new(),
         resume_notifier: Arc::new(Notify::new()),
+        usage: Default::default(),
+        steps: Vec::new(),
+        pending_permissions: Vec::new(),
+        sessions: HashMap::new(),
+        reference_file: None,
+        permission_replies: Default::default(),
     }
 }
 
@@ -191,6 +203,362 @@ pub fn advance_step(process: &mut Process) {
     process.current_step_index += 1;
 }
 
+/// Record the start of the current step and emit event.
+///
+/// # Arguments
+///
+/// * `process` - The process whose current step is starting
+/// * `events_tx` - Channel to send step events
+/// * `kind` - The kind of step
+/// * `agent_name` - The agent executed by the step, if any
+/// * `adapter_type` - The adapter backing the agent, if any
+pub async fn start_step(
+    process: &mut Process,
+    events_tx: &Sender<Event>,
+    kind: StepKind,
+    agent_name: Option<String>,
+    adapter_type: Option<String>,
+) {
+    let step = StepRecord {
+        index: process.current_step_index,
+        kind,
+        agent_name,
+        adapter_type,
+        served_by: None,
+        status: StepStatus::Running,
+        started_at: chrono::Utc::now(),
+        completed_at: None,
+        duration_ms: None,
+        error: None,
+    };
+    process.steps.push(step.clone());
+    let _ = events_tx
+        .send(Event::StepStarted {
+            process_id: process.id,
+            step,
+        })
+        .await;
+}
+
+/// Mark the running step as completed and emit event.
+///
+/// # Arguments
+///
+/// * `process` - The process whose current step finished
+/// * `events_tx` - Channel to send step events
+pub async fn complete_step(process: &mut Process, events_tx: &Sender<Event>) {
+    if let Some(step) = finish_step(process, StepStatus::Completed, None) {
+        let _ = events_tx
+            .send(Event::StepCompleted {
+                process_id: process.id,
+                step,
+            })
+            .await;
+    }
+}
+
+/// Mark the running step as failed and emit event.
+///
+/// # Arguments
+///
+/// * `process` - The process whose current step failed
+/// * `events_tx` - Channel to send step events
+/// * `error` - Error message describing the failure
+pub async fn fail_step(process: &mut Process, events_tx: &Sender<Event>, error: String) {
+    if let Some(step) = finish_step(process, StepStatus::Failed, Some(error)) {
+        let _ = events_tx
+            .send(Event::StepFailed {
+                process_id: process.id,
+                step,
+            })
+            .await;
+    }
+}
+
+/// Close the record of the current step, returning the updated record.
+fn finish_step(
+    process: &mut Process,
+    status: StepStatus,
+    error: Option<String>,
+) -> Option<StepRecord> {
+    let index = process.current_step_index;
+    let step = process
+        .steps
+        .iter_mut()
+        .rev()
+        .find(|s| s.index == index && s.status == StepStatus::Running)?;
+
+    let completed_at = chrono::Utc::now();
+    step.status = status;
+    step.duration_ms = Some((completed_at - step.started_at).num_milliseconds().max(0) as u64);
+    step.completed_at = Some(completed_at);
+    step.error = error;
+    Some(step.clone())
+}
+
+/// Apply an event emitted for a process to a copy of its state.
+///
+/// Used to keep the StateManager registry in sync with the process owned
+/// by a running PipelineEngine. Events for other processes are ignored.
+///
+/// # Arguments
+///
+/// * `process` - The process copy to update
+/// * `event` - The event emitted by the engine
+pub fn apply_event(process: &mut Process, event: &Event) {
+    match event {
+        Event::ProcessStatusUpdate {
+            process_id,
+            status,
+            step_index,
+        } if *process_id == process.id => {
+            process.status = *status;
+            process.current_step_index = *step_index;
+        }
+        Event::ProcessLogChunk {
+            process_id,
+            content,
+        } if *process_id == process.id => {
+            process.logs.push(content.clone());
+        }
+        Event::ProcessCompleted { process_id } | Event::ProcessError { process_id, .. }
+            if *process_id == process.id =>
+        {
+            process.completed_at = Some(chrono::Utc::now());
+        }
+        Event::UsageReported {
+            process_id, usage, ..
+        } if *process_id == process.id => {
+            process.usage.accumulate(usage);
+        }
+        Event::StepStarted { process_id, step } if *process_id == process.id => {
+            process.steps.push(step.clone());
+        }
+        Event::StepCompleted { process_id, step } | Event::StepFailed { process_id, step }
+            if *process_id == process.id =>
+        {
+            match process
+                .steps
+                .iter_mut()
+                .rev()
+                .find(|s| s.index == step.index && s.started_at == step.started_at)
+            {
+                Some(existing) => *existing = step.clone(),
+                None => process.steps.push(step.clone()),
+            }
+        }
+        Event::PermissionRequested {
+            process_id,
+            request,
+        } if *process_id == process.id => {
+            process.pending_permissions.push(request.clone());
+        }
+        Event::PermissionResolved {
+            process_id,
+            request_id,
+            ..
+        } if *process_id == process.id => {
+            process
+                .pending_permissions
+                .retain(|r| r.request_id != *request_id);
+        }
+        Event::SessionStarted {
+            process_id,
+            agent_name,
+            session_id,
+        } if *process_id == process.id => {
+            process
+                .sessions
+                .insert(agent_name.clone(), session_id.clone());
+        }
+        Event::AgentFallback {
+            process_id,
+            fallback_agent,
+            ..
+        } if *process_id == process.id => {
+            set_served_by(process, fallback_agent);
+        }
+        _ => {}
+    }
+}
+
+/// Register a permission request of an agent and emit event.
+///
+/// The agent keeps waiting until `answer_permission` is called with the
+/// request id, or until the reply is dropped.
+///
+/// # Arguments
+///
+/// * `process` - The process whose agent asks
+/// * `events_tx` - Channel to send the log and request events
+/// * `request` - The request to show to the user
+/// * `reply` - Where the answer is sent
+pub async fn request_permission(
+    process: &mut Process,
+    events_tx: &Sender<Event>,
+    request: PermissionRequest,
+    reply: oneshot::Sender<bool>,
+) {
+    if let Ok(mut replies) = process.permission_replies.lock() {
+        replies.insert(request.request_id, reply);
+    }
+    log_to_process(
+        process,
+        events_tx,
+        format!(
+            "[Permission] {} asks to run {} ({})",
+            request.agent_name, request.title, request.tool
+        ),
+    )
+    .await;
+    let _ = events_tx
+        .send(Event::PermissionRequested {
+            process_id: process.id,
+            request,
+        })
+        .await;
+}
+
+/// Answer a pending permission request and emit event.
+///
+/// # Arguments
+///
+/// * `process` - The process whose agent asked
+/// * `events_tx` - Channel to send the resolution event
+/// * `request_id` - The request to answer
+/// * `allow` - Whether the tool call may run
+///
+/// # Returns
+///
+/// `false` if no such request is pending.
+pub async fn answer_permission(
+    process: &mut Process,
+    events_tx: &Sender<Event>,
+    request_id: Uuid,
+    allow: bool,
+) -> bool {
+    let reply = process
+        .permission_replies
+        .lock()
+        .ok()
+        .and_then(|mut replies| replies.remove(&request_id));
+    let Some(reply) = reply else {
+        return false;
+    };
+
+    // The agent may have stopped waiting; the request is settled either way
+    let _ = reply.send(allow);
+    process
+        .pending_permissions
+        .retain(|r| r.request_id != request_id);
+    let _ = events_tx
+        .send(Event::PermissionResolved {
+            process_id: process.id,
+            request_id,
+            allowed: allow,
+        })
+        .await;
+    true
+}
+
+/// Deny permission requests that are still pending, e.g. because the
+/// agent step that asked has ended.
+///
+/// # Arguments
+///
+/// * `process` - The process whose agent asked
+/// * `events_tx` - Channel to send the resolution events
+/// * `request_ids` - Requests to settle; already answered ones are skipped
+pub async fn expire_permissions(
+    process: &mut Process,
+    events_tx: &Sender<Event>,
+    request_ids: &[Uuid],
+) {
+    for request_id in request_ids {
+        answer_permission(process, events_tx, *request_id, false).await;
+    }
+}
+
+/// Record the conversation id an agent reported and emit event.
+///
+/// Later steps of the same agent in this process continue this session.
+///
+/// # Arguments
+///
+/// * `process` - The process the agent runs in
+/// * `events_tx` - Channel to send the session event
+/// * `agent_name` - The agent that reported the session
+/// * `session_id` - The agent's conversation id
+pub async fn record_session(
+    process: &mut Process,
+    events_tx: &Sender<Event>,
+    agent_name: &str,
+    session_id: String,
+) {
+    if process.sessions.get(agent_name) == Some(&session_id) {
+        return;
+    }
+    process
+        .sessions
+        .insert(agent_name.to_string(), session_id.clone());
+    let _ = events_tx
+        .send(Event::SessionStarted {
+            process_id: process.id,
+            agent_name: agent_name.to_string(),
+            session_id,
+        })
+        .await;
+}
+
+/// Record that a fallback agent took over the current step and emit event.
+///
+/// # Arguments
+///
+/// * `process` - The process the step runs in
+/// * `events_tx` - Channel to send the log and fallback events
+/// * `agent_name` - The agent that failed
+/// * `fallback_agent` - The agent taking over
+/// * `reason` - Why `agent_name` was given up on
+pub async fn record_fallback(
+    process: &mut Process,
+    events_tx: &Sender<Event>,
+    agent_name: &str,
+    fallback_agent: &str,
+    reason: String,
+) {
+    log_to_process(
+        process,
+        events_tx,
+        format!(
+            "[Fallback] {} -> {} ({})",
+            agent_name, fallback_agent, reason
+        ),
+    )
+    .await;
+    set_served_by(process, fallback_agent);
+    let _ = events_tx
+        .send(Event::AgentFallback {
+            process_id: process.id,
+            agent_name: agent_name.to_string(),
+            fallback_agent: fallback_agent.to_string(),
+            reason,
+        })
+        .await;
+}
+
+/// Note the agent serving the running record of the current step.
+fn set_served_by(process: &mut Process, agent_name: &str) {
+    let index = process.current_step_index;
+    if let Some(step) = process
+        .steps
+        .iter_mut()
+        .rev()
+        .find(|s| s.index == index && s.status == StepStatus::Running)
+    {
+        step.served_by = Some(agent_name.to_string());
+    }
+}
+
 /// Mark the process as killed and emit event.
 ///
 /// # Arguments
@@ -364,4 +732,131 @@ mod tests {
             }
         ));
     }
+
+    #[tokio::test]
+    async fn test_step_lifecycle() {
+        let mut process = create_process("test-pipeline".to_string());
+        let (tx, mut rx) = mpsc::channel(10);
+
+        start_step(
+            &mut process,
+            &tx,
+            StepKind::Agent,
+            Some("agent1".to_string()),
+            Some("Mock".to_string()),
+        )
+        .await;
+        assert_eq!(process.steps.len(), 1);
+        assert_eq!(process.steps[0].status, StepStatus::Running);
+
+        let event = rx.recv().await.unwrap();
+        assert!(matches!(
+            event,
+            Event::StepStarted { step, .. } if step.agent_name.as_deref() == Some("agent1")
+        ));
+
+        complete_step(&mut process, &tx).await;
+        assert_eq!(process.steps[0].status, StepStatus::Completed);
+        assert!(process.steps[0].completed_at.is_some());
+        assert!(process.steps[0].duration_ms.is_some());
+
+        let event = rx.recv().await.unwrap();
+        assert!(matches!(event, Event::StepCompleted { step, .. } if step.index == 0));
+    }
+
+    #[tokio::test]
+    async fn test_fail_step() {
+        let mut process = create_process("test-pipeline".to_string());
+        let (tx, mut rx) = mpsc::channel(10);
+
+        start_step(&mut process, &tx, StepKind::Agent, None, None).await;
+        let _ = rx.recv().await;
+
+        fail_step(&mut process, &tx, "boom".to_string()).await;
+        assert_eq!(process.steps[0].status, StepStatus::Failed);
+        assert_eq!(process.steps[0].error.as_deref(), Some("boom"));
+
+        let event = rx.recv().await.unwrap();
+        assert!(matches!(event, Event::StepFailed { step, .. } if step.error.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_complete_step_without_running_step_is_noop() {
+        let mut process = create_process("test-pipeline".to_string());
+        let (tx, mut rx) = mpsc::channel(10);
+
+        complete_step(&mut process, &tx).await;
+
+        assert!(process.steps.is_empty());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_apply_event_mirrors_engine_state() {
+        let mut engine_process = create_process("test-pipeline".to_string());
+        let mut mirror = engine_process.clone();
+        let (tx, mut rx) = mpsc::channel(20);
+
+        start_process(&mut engine_process, &tx).await;
+        log_to_process(&mut engine_process, &tx, "hello".to_string()).await;
+        start_step(&mut engine_process, &tx, StepKind::Agent, None, None).await;
+        complete_step(&mut engine_process, &tx).await;
+        drop(tx);
+
+        while let Some(event) = rx.recv().await {
+            apply_event(&mut mirror, &event);
+        }
+
+        assert_eq!(mirror.status, ProcessStatus::Running);
+        assert_eq!(mirror.logs, engine_process.logs);
+        assert_eq!(mirror.steps, engine_process.steps);
+    }
+
+    #[test]
+    fn test_apply_event_ignores_other_processes() {
+        let mut process = create_process("test-pipeline".to_string());
+
+        apply_event(
+            &mut process,
+            &Event::ProcessLogChunk {
+                process_id: Uuid::new_v4(),
+                content: "not mine".to_string(),
+            },
+        );
+
+        assert!(process.logs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_permission_request_is_answered_once() {
+        let mut process = create_process("test-pipeline".to_string());
+        let mut mirror = process.clone();
+        let (tx, mut rx) = mpsc::channel(10);
+        let (reply, answer) = oneshot::channel();
+        let request = PermissionRequest {
+            request_id: Uuid::new_v4(),
+            agent_name: "dev".to_string(),
+            tool: "execute".to_string(),
+            title: "cargo test".to_string(),
+            arguments: serde_json::Value::Null,
+        };
+        let request_id = request.request_id;
+
+        request_permission(&mut process, &tx, request, reply).await;
+        while let Ok(event) = rx.try_recv() {
+            apply_event(&mut mirror, &event);
+        }
+        assert_eq!(mirror.pending_permissions.len(), 1);
+
+        // Clones share the reply channels, so the mirror can answer
+        assert!(answer_permission(&mut mirror, &tx, request_id, true).await);
+        assert_eq!(answer.await, Ok(true));
+        assert!(mirror.pending_permissions.is_empty());
+        assert!(matches!(
+            rx.recv().await,
+            Some(Event::PermissionResolved { allowed: true, .. })
+        ));
+
+        assert!(!answer_permission(&mut mirror, &tx, request_id, false).await);
+    }
 }
@@ -0,0 +1,233 @@
+//! Integration tests for the ACP client against a mock ACP agent.
+
+use pk_core::agents::base::AgentError;
+use pk_core::agents::base::AgentEvent;
+use pk_core::agents::base::Attachment;
+use pk_core::agents::base::ExecutionContext;
+use pk_core::agents::AgentFactory;
+use pk_protocol::agent_models::Agent as AgentConfig;
+use pk_protocol::agent_models::AgentProvider;
+use pk_protocol::agent_models::CommandProtocol;
+use pk_protocol::agent_models::CustomCommand;
+use pk_protocol::agent_models::PermissionAction;
+use pk_protocol::agent_models::PermissionMode;
+use pk_protocol::agent_models::PermissionRule;
+use pk_protocol::process_models::FileChangeKind;
+use std::time::Duration;
+use tokio_stream::StreamExt;
+
+fn config(scenario: &str, permission_mode: Option<PermissionMode>) -> AgentConfig {
+    let script = format!("{}/tests/common/mock_acp.py", env!("CARGO_MANIFEST_DIR"));
+    AgentConfig {
+        name: "acp".to_string(),
+        model: "mock".to_string(),
+        provider: Some(AgentProvider::Custom),
+        permission_mode,
+        command: Some(CustomCommand {
+            program: "python3".to_string(),
+            args: vec![script, scenario.to_string()],
+            protocol: CommandProtocol::Acp,
+            prompt: Default::default(),
+            events: Default::default(),
+        }),
+        description: "Mock ACP agent".to_string(),
+        color: "blue".to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_acp_prompt_turn() {
+    let dir = tempfile::tempdir().unwrap();
+    let agent = AgentFactory::create(&config("turn", Some(PermissionMode::Edit))).unwrap();
+    let context = ExecutionContext::new("Take notes".to_string())
+        .with_project_path(dir.path().to_string_lossy().to_string());
+
+    let events: Vec<_> = agent
+        .execute(&context)
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    let chunks: Vec<_> = events
+        .iter()
+        .filter_map(|e| match e {
+            AgentEvent::MessageChunk(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    // Edit mode rejects shell commands; reads outside the project fail
+    assert_eq!(
+        chunks,
+        vec!["echo: Take notes", "permission=no read=second escape=True"]
+    );
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, AgentEvent::Thought(plan) if plan.contains("[~] Write notes"))));
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, AgentEvent::Warning(w) if w.contains("rm -rf build"))));
+    assert!(events.contains(&AgentEvent::FileChanged {
+        path: "notes.txt".to_string(),
+        kind: FileChangeKind::Created,
+    }));
+    assert_eq!(events.last(), Some(&AgentEvent::Completed));
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("notes.txt")).unwrap(),
+        "first\nsecond\n"
+    );
+}
+
+#[tokio::test]
+async fn test_acp_asks_for_permission_without_mode() {
+    let dir = tempfile::tempdir().unwrap();
+    let agent = AgentFactory::create(&config("turn", None)).unwrap();
+    let context = ExecutionContext::new("Take notes".to_string())
+        .with_project_path(dir.path().to_string_lossy().to_string());
+
+    let mut stream = agent.execute(&context).await.unwrap();
+    let mut chunks = Vec::new();
+    let mut asked = false;
+    while let Some(event) = stream.next().await {
+        match event.unwrap() {
+            AgentEvent::PermissionRequest {
+                tool, title, reply, ..
+            } => {
+                assert_eq!(tool, "execute");
+                assert_eq!(title, "rm -rf build");
+                reply.take().unwrap().send(true).unwrap();
+                asked = true;
+            }
+            AgentEvent::MessageChunk(text) => chunks.push(text),
+            _ => {}
+        }
+    }
+
+    assert!(asked);
+    assert_eq!(chunks[1], "permission=yes read=second escape=True");
+}
+
+#[tokio::test]
+async fn test_acp_rules_decide_without_asking() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = config("turn", None);
+    config.permission_rules = vec![PermissionRule {
+        tool: "execute".to_string(),
+        action: PermissionAction::Deny,
+    }];
+    let agent = AgentFactory::create(&config).unwrap();
+    let context = ExecutionContext::new("Take notes".to_string())
+        .with_project_path(dir.path().to_string_lossy().to_string());
+
+    let events: Vec<_> = agent
+        .execute(&context)
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert!(!events
+        .iter()
+        .any(|e| matches!(e, AgentEvent::PermissionRequest { .. })));
+    assert!(events.contains(&AgentEvent::MessageChunk(
+        "permission=no read=second escape=True".to_string()
+    )));
+}
+
+#[tokio::test]
+async fn test_acp_cancel_on_drop() {
+    let dir = tempfile::tempdir().unwrap();
+    let agent = AgentFactory::create(&config("hang", None)).unwrap();
+    let context = ExecutionContext::new("Wait".to_string())
+        .with_project_path(dir.path().to_string_lossy().to_string());
+
+    let mut stream = agent.execute(&context).await.unwrap();
+    let first = stream.next().await.unwrap().unwrap();
+    assert_eq!(first, AgentEvent::MessageChunk("echo: Wait".to_string()));
+    drop(stream);
+
+    // The agent is told to cancel and records it before exiting
+    let marker = dir.path().join("cancelled");
+    for _ in 0..50 {
+        if marker.exists() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("agent was not cancelled");
+}
+
+#[tokio::test]
+async fn test_acp_attachments_as_prompt_blocks() {
+    let dir = tempfile::tempdir().unwrap();
+    let agent = AgentFactory::create(&config("blocks", Some(PermissionMode::Edit))).unwrap();
+    let context = ExecutionContext::new("Review".to_string())
+        .with_project_path(dir.path().to_string_lossy().to_string())
+        .with_attachment(Attachment::File {
+            path: "docs/spec.md".to_string(),
+            content: "Spec".to_string(),
+        });
+
+    let events: Vec<_> = agent
+        .execute(&context)
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    let chunks: Vec<_> = events
+        .iter()
+        .filter_map(|e| match e {
+            AgentEvent::MessageChunk(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    let described = format!(
+        "resource file://{}/docs/spec.md Spec, text",
+        dir.path().display()
+    );
+    assert_eq!(chunks, vec!["echo: Review", described.as_str()]);
+
+    // Attachment paths stay relative to the project root in a working-dir
+    std::fs::create_dir(dir.path().join("service")).unwrap();
+    let mut in_subdir = config("blocks", Some(PermissionMode::Edit));
+    in_subdir.working_dir = Some("service".to_string());
+    let events: Vec<_> = AgentFactory::create(&in_subdir)
+        .unwrap()
+        .execute(&context)
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await;
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, Ok(AgentEvent::MessageChunk(text)) if text == &described)));
+
+    // The agent does not accept images
+    let image = dir.path().join("shot.png");
+    std::fs::write(&image, [0x89, 0x50]).unwrap();
+    let context = context.with_attachment(Attachment::Image {
+        path: image.to_string_lossy().to_string(),
+        mime_type: "image/png".to_string(),
+    });
+    let events: Vec<_> = agent
+        .execute(&context)
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await;
+    assert!(matches!(
+        events.last(),
+        Some(Err(AgentError::Unsupported(msg))) if msg.contains("image")
+    ));
+}
@@ -0,0 +1,153 @@
+//! Integration tests for the Anthropic Messages adapter against a local stub
+//! server.
+
+mod common;
+
+use common::http_stub::stub_server;
+use pk_core::agents::base::AgentError;
+use pk_core::agents::base::AgentEvent;
+use pk_core::agents::base::Attachment;
+use pk_core::agents::base::ExecutionContext;
+use pk_core::agents::AgentFactory;
+use pk_protocol::agent_models::Agent as AgentConfig;
+use pk_protocol::agent_models::AgentProvider;
+use tokio_stream::StreamExt;
+
+const KEY_ENV: &str = "PK_TEST_ANTHROPIC_KEY";
+
+fn config(base_url: &str) -> AgentConfig {
+    std::env::set_var(KEY_ENV, "sk-ant-test");
+    AgentConfig {
+        name: "reviewer".to_string(),
+        model: "claude-sonnet-4-5".to_string(),
+        provider: Some(AgentProvider::Anthropic),
+        base_url: Some(base_url.to_string()),
+        api_key_env: Some(KEY_ENV.to_string()),
+        description: "Reviews code".to_string(),
+        color: "green".to_string(),
+        system_prompt: "You review code.".to_string(),
+        ..Default::default()
+    }
+}
+
+/// Build an SSE body from `(event, data)` pairs.
+fn sse(events: &[(&str, &str)]) -> String {
+    events
+        .iter()
+        .map(|(event, data)| format!("event: {}\ndata: {}\n\n", event, data))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_anthropic_adapter_streams_message() {
+    let body = sse(&[
+        (
+            "message_start",
+            r#"{"type":"message_start","message":{"id":"msg_1","usage":{"input_tokens":30,"output_tokens":1}}}"#,
+        ),
+        (
+            "content_block_start",
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#,
+        ),
+        ("ping", r#"{"type":"ping"}"#),
+        (
+            "content_block_delta",
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Looks "}}"#,
+        ),
+        (
+            "content_block_delta",
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"good"}}"#,
+        ),
+        (
+            "content_block_stop",
+            r#"{"type":"content_block_stop","index":0}"#,
+        ),
+        (
+            "message_delta",
+            r#"{"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":7}}"#,
+        ),
+        ("message_stop", r#"{"type":"message_stop"}"#),
+    ]);
+    let (base_url, request) = stub_server("200 OK", &body).await;
+    let agent = AgentFactory::create(&config(&base_url)).unwrap();
+
+    let context =
+        ExecutionContext::new("Review the diff".to_string()).with_attachment(Attachment::File {
+            path: "diff.patch".to_string(),
+            content: "+fn added() {}".to_string(),
+        });
+    let events: Vec<_> = agent.execute(&context).await.unwrap().collect().await;
+
+    let text: String = events
+        .iter()
+        .filter_map(|e| match e {
+            Ok(AgentEvent::MessageChunk(text)) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(text, "Looks good");
+    assert!(events.iter().any(|e| matches!(
+        e,
+        Ok(AgentEvent::Usage(usage)) if usage.input_tokens == 30 && usage.output_tokens == 7
+    )));
+    assert!(matches!(events.last(), Some(Ok(AgentEvent::Completed))));
+
+    let request = request.await.unwrap();
+    assert!(request.head.starts_with("POST /v1/messages"));
+    let head = request.head.to_lowercase();
+    assert!(head.contains("x-api-key: sk-ant-test"));
+    assert!(head.contains("anthropic-version: 2023-06-01"));
+
+    let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+    assert_eq!(body["system"], "You review code.");
+    assert_eq!(body["stream"], true);
+    let content = &body["messages"][0]["content"];
+    assert_eq!(content[0]["type"], "document");
+    assert_eq!(content[0]["source"]["data"], "+fn added() {}");
+    assert_eq!(content[1]["text"], "Review the diff");
+}
+
+#[tokio::test]
+async fn test_anthropic_adapter_stream_error() {
+    let body = sse(&[
+        (
+            "message_start",
+            r#"{"type":"message_start","message":{"id":"msg_1","usage":{"input_tokens":3,"output_tokens":0}}}"#,
+        ),
+        (
+            "error",
+            r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#,
+        ),
+    ]);
+    let (base_url, _request) = stub_server("200 OK", &body).await;
+    let agent = AgentFactory::create(&config(&base_url)).unwrap();
+
+    let events: Vec<_> = agent
+        .execute(&ExecutionContext::new("Review".to_string()))
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert!(matches!(
+        events.last(),
+        Some(Err(AgentError::ApiError(msg))) if msg.contains("overloaded_error")
+    ));
+}
+
+#[tokio::test]
+async fn test_anthropic_adapter_http_error() {
+    let (base_url, _request) = stub_server(
+        "400 Bad Request",
+        r#"{"type":"error","error":{"type":"invalid_request_error","message":"max_tokens"}}"#,
+    )
+    .await;
+    let agent = AgentFactory::create(&config(&base_url)).unwrap();
+
+    let result = agent
+        .execute(&ExecutionContext::new("Review".to_string()))
+        .await;
+    assert!(matches!(
+        result,
+        Err(AgentError::ApiError(msg)) if msg.contains("400") && msg.contains("invalid_request_error")
+    ));
+}
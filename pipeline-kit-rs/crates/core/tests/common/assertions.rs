@@ -4,6 +4,7 @@ use pk_protocol::ipc::Event;
 use pk_protocol::process_models::ProcessStatus;
 
 /// Assert that a sequence of events contains a ProcessStarted event.
+#[allow(dead_code)]
 pub fn assert_has_process_started(events: &[Event]) -> bool {
     events
         .iter()
@@ -19,6 +20,7 @@ pub fn assert_has_process_completed(events: &[Event]) -> bool {
 }
 
 /// Assert that a sequence of events contains a ProcessStatusUpdate with specific status.
+#[allow(dead_code)]
 pub fn assert_has_status_update(events: &[Event], status: ProcessStatus) -> bool {
     events.iter().any(|e| {
         matches!(
@@ -1,6 +1,7 @@
 //! Test fixtures for creating sample configurations and test data.
 
 use pk_protocol::agent_models::Agent;
+use pk_protocol::agent_models::AgentProvider;
 use pk_protocol::pipeline_models::MasterAgentConfig;
 use pk_protocol::pipeline_models::Pipeline;
 use pk_protocol::pipeline_models::ProcessStep;
@@ -81,13 +82,16 @@ You are another helpful test agent."#;
 }
 
 /// Create a test Agent configuration.
+#[allow(dead_code)]
 pub fn create_test_agent(name: &str) -> Agent {
     Agent {
         name: name.to_string(),
         description: format!("Test agent {}", name),
         model: "test-model".to_string(),
+        provider: Some(AgentProvider::Mock),
         color: "blue".to_string(),
         system_prompt: "Test prompt".to_string(),
+        ..Default::default()
     }
 }
 
@@ -98,8 +102,10 @@ pub fn create_failure_agent(name: &str) -> Agent {
         name: name.to_string(),
         description: format!("Failing test agent {}", name),
         model: "test-failure-model".to_string(),
+        provider: Some(AgentProvider::Mock),
         color: "red".to_string(),
         system_prompt: "This agent will fail".to_string(),
+        ..Default::default()
     }
 }
 
@@ -110,8 +116,10 @@ pub fn create_unavailable_agent(name: &str) -> Agent {
         name: name.to_string(),
         description: format!("Unavailable test agent {}", name),
         model: "test-unavailable-model".to_string(),
+        provider: Some(AgentProvider::Mock),
         color: "gray".to_string(),
         system_prompt: "This agent is unavailable".to_string(),
+        ..Default::default()
     }
 }
 
@@ -128,11 +136,9 @@ pub fn create_test_pipeline(name: &str, steps: Vec<ProcessStep>) -> Pipeline {
         },
         sub_agents: steps
             .iter()
-            .filter_map(|step| match step {
-                ProcessStep::Agent(name) => Some(name.clone()),
-                _ => None,
-            })
+            .filter_map(|step| step.agent_name().map(str::to_string))
             .collect(),
+        budget: None,
     }
 }
 
@@ -167,6 +173,12 @@ pub fn create_test_process(pipeline_name: &str, status: ProcessStatus) -> Proces
         started_at: chrono::Utc::now(),
         completed_at: None,
         resume_notifier: std::sync::Arc::new(tokio::sync::Notify::new()),
+        usage: Default::default(),
+        steps: Vec::new(),
+        pending_permissions: Vec::new(),
+        sessions: Default::default(),
+        reference_file: None,
+        permission_replies: Default::default(),
     }
 }
 
@@ -0,0 +1,62 @@
+//! Minimal HTTP server for testing HTTP-based agent adapters.
+
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+/// A request received by the stub server.
+#[allow(dead_code)]
+pub struct Request {
+    /// Request line and headers.
+    pub head: String,
+    /// Request body.
+    pub body: String,
+}
+
+/// Serve one HTTP request with a canned response and report what was sent.
+///
+/// Returns the server's root URL (`http://127.0.0.1:<port>`) and a receiver for the
+/// request. The listener closes after the first connection.
+#[allow(dead_code)]
+pub async fn stub_server(status: &str, body: &str) -> (String, oneshot::Receiver<Request>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n{}",
+        status, body
+    );
+    let (tx, rx) = oneshot::channel();
+
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+
+        // Read the head, then as much body as Content-Length announces
+        let mut data = Vec::new();
+        let mut buf = [0u8; 4096];
+        let (head, body) = loop {
+            let n = socket.read(&mut buf).await.unwrap();
+            data.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&data).to_string();
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length = head
+                    .lines()
+                    .find_map(|l| {
+                        l.to_lowercase()
+                            .strip_prefix("content-length:")
+                            .map(|v| v.trim().parse::<usize>().unwrap())
+                    })
+                    .unwrap_or(0);
+                if body.len() >= length {
+                    break (head.to_string(), body.to_string());
+                }
+            }
+        };
+
+        socket.write_all(response.as_bytes()).await.unwrap();
+        socket.shutdown().await.unwrap();
+        let _ = tx.send(Request { head, body });
+    });
+
+    (base_url, rx)
+}
@@ -0,0 +1,117 @@
+#!/usr/bin/env python3
+"""Mock ACP agent speaking JSON-RPC over stdio for testing the ACP client.
+
+Usage: mock_acp.py <scenario>
+
+Scenarios:
+  turn   - streams updates, asks for permission, writes and reads a file
+  hang   - never finishes the prompt turn until it is cancelled
+  blocks - accepts embedded resources but no images, and describes the
+           prompt blocks it received
+"""
+
+import json
+import os
+import sys
+
+next_id = 100
+
+
+def send(message):
+    message["jsonrpc"] = "2.0"
+    print(json.dumps(message), flush=True)
+
+
+def read():
+    line = sys.stdin.readline()
+    if not line:
+        sys.exit(0)
+    return json.loads(line)
+
+
+def call(method, params):
+    """Send a reverse request and wait for the client's response."""
+    global next_id
+    next_id += 1
+    send({"id": next_id, "method": method, "params": params})
+    while True:
+        message = read()
+        if message.get("id") == next_id and "method" not in message:
+            return message
+
+
+def update(session_id, update):
+    send({"method": "session/update", "params": {"sessionId": session_id, "update": update}})
+
+
+def run_turn(session_id, cwd):
+    update(session_id, {
+        "sessionUpdate": "plan",
+        "entries": [{"content": "Write notes", "priority": "high", "status": "in_progress"}],
+    })
+    permission = call("session/request_permission", {
+        "sessionId": session_id,
+        "toolCall": {"toolCallId": "call_1", "title": "rm -rf build", "kind": "execute"},
+        "options": [
+            {"optionId": "yes", "name": "Allow", "kind": "allow_once"},
+            {"optionId": "no", "name": "Reject", "kind": "reject_once"},
+        ],
+    })
+    path = os.path.join(cwd, "notes.txt")
+    call("fs/write_text_file", {"sessionId": session_id, "path": path, "content": "first\nsecond\n"})
+    read_back = call("fs/read_text_file", {"sessionId": session_id, "path": path, "line": 2})
+    escape = call("fs/read_text_file", {"sessionId": session_id, "path": "/etc/passwd"})
+
+    summary = "permission=%s read=%s escape=%s" % (
+        permission["result"]["outcome"].get("optionId"),
+        read_back["result"]["content"],
+        "error" in escape,
+    )
+    update(session_id, {"sessionUpdate": "agent_message_chunk", "content": {"type": "text", "text": summary}})
+
+
+def main():
+    scenario = sys.argv[1] if len(sys.argv) > 1 else "turn"
+    cwd = None
+    while True:
+        message = read()
+        method = message.get("method")
+        if method == "initialize":
+            capabilities = {}
+            if scenario == "blocks":
+                capabilities = {"promptCapabilities": {"embeddedContext": True, "image": False}}
+            send({"id": message["id"], "result": {"protocolVersion": 1, "agentCapabilities": capabilities}})
+        elif method == "session/new":
+            cwd = message["params"]["cwd"]
+            send({"id": message["id"], "result": {"sessionId": "sess-1"}})
+        elif method == "session/prompt":
+            session_id = message["params"]["sessionId"]
+            prompt = message["params"]["prompt"]
+            text = next(block["text"] for block in prompt if block["type"] == "text")
+            update(session_id, {"sessionUpdate": "agent_message_chunk", "content": {"type": "text", "text": "echo: " + text}})
+            if scenario == "blocks":
+                described = []
+                for block in prompt:
+                    if block["type"] == "resource":
+                        resource = block["resource"]
+                        described.append("resource %s %s" % (resource["uri"], resource["text"]))
+                    else:
+                        described.append(block["type"])
+                update(session_id, {"sessionUpdate": "agent_message_chunk", "content": {"type": "text", "text": ", ".join(described)}})
+                send({"id": message["id"], "result": {"stopReason": "end_turn"}})
+                return
+            if scenario == "hang":
+                prompt_id = message["id"]
+                while read().get("method") != "session/cancel":
+                    pass
+                with open(os.path.join(cwd, "cancelled"), "w") as f:
+                    f.write("yes")
+                send({"id": prompt_id, "result": {"stopReason": "cancelled"}})
+                return
+            run_turn(session_id, cwd)
+            send({"id": message["id"], "result": {"stopReason": "end_turn"}})
+            return
+
+
+if __name__ == "__main__":
+    main()
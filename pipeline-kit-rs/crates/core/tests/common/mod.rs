@@ -4,10 +4,12 @@
 //! - Test fixtures (sample configs, pipelines)
 //! - Custom assertions
 //! - Mock agents
+//! - A stub HTTP server for HTTP adapters
 //! - Helper functions
 
 pub mod assertions;
 pub mod fixtures;
+pub mod http_stub;
 pub mod mock_agents;
 
 #[allow(unused_imports)]
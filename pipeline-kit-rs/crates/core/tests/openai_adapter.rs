@@ -0,0 +1,113 @@
+//! Integration tests for the OpenAI-compatible adapter against a local stub
+//! server.
+
+mod common;
+
+use common::http_stub::stub_server;
+use pk_core::agents::base::Agent;
+use pk_core::agents::base::AgentError;
+use pk_core::agents::base::AgentEvent;
+use pk_core::agents::base::ExecutionContext;
+use pk_core::agents::AgentFactory;
+use pk_protocol::agent_models::Agent as AgentConfig;
+use pk_protocol::agent_models::AgentProvider;
+use tokio_stream::StreamExt;
+
+fn config(base_url: &str) -> AgentConfig {
+    AgentConfig {
+        name: "local".to_string(),
+        model: "local-model".to_string(),
+        provider: Some(AgentProvider::OpenAi),
+        base_url: Some(format!("{}/v1", base_url)),
+        description: "Local model server".to_string(),
+        color: "blue".to_string(),
+        system_prompt: "Be brief.".to_string(),
+        ..Default::default()
+    }
+}
+
+async fn run(agent: &dyn Agent) -> Vec<Result<AgentEvent, AgentError>> {
+    match agent
+        .execute(&ExecutionContext::new("Say hello".to_string()))
+        .await
+    {
+        Ok(stream) => stream.collect().await,
+        Err(e) => vec![Err(e)],
+    }
+}
+
+#[tokio::test]
+async fn test_openai_adapter_streams_deltas() {
+    let sse = concat!(
+        "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"},\"finish_reason\":null}]}\n\n",
+        "data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"},\"finish_reason\":null}]}\n\n",
+        "data: {\"choices\":[{\"delta\":{\"content\":\"lo\"},\"finish_reason\":null}]}\n\n",
+        "data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n",
+        "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":2}}\n\n",
+        "data: [DONE]\n\n",
+    );
+    let (base_url, request) = stub_server("200 OK", sse).await;
+    let agent = AgentFactory::create(&config(&base_url)).unwrap();
+
+    let events = run(agent.as_ref()).await;
+    let chunks: Vec<_> = events
+        .iter()
+        .filter_map(|e| match e {
+            Ok(AgentEvent::MessageChunk(text)) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(chunks, vec!["Hel", "lo"]);
+    assert!(events.iter().any(|e| matches!(
+        e,
+        Ok(AgentEvent::Usage(usage)) if usage.input_tokens == 9 && usage.output_tokens == 2
+    )));
+    assert!(matches!(events.last(), Some(Ok(AgentEvent::Completed))));
+
+    let request = request.await.unwrap();
+    assert!(request.head.starts_with("POST /v1/chat/completions"));
+    let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+    assert_eq!(body["model"], "local-model");
+    assert_eq!(body["stream"], true);
+    assert_eq!(body["messages"][1]["content"], "Say hello");
+}
+
+#[tokio::test]
+async fn test_openai_adapter_sends_api_key() {
+    std::env::set_var("PK_TEST_OPENAI_KEY", "sk-test");
+    let (base_url, request) = stub_server("200 OK", "data: [DONE]\n\n").await;
+    let mut config = config(&base_url);
+    config.api_key_env = Some("PK_TEST_OPENAI_KEY".to_string());
+    let agent = AgentFactory::create(&config).unwrap();
+
+    let events = run(agent.as_ref()).await;
+    assert!(matches!(events.last(), Some(Ok(AgentEvent::Completed))));
+
+    let request = request.await.unwrap();
+    assert!(request
+        .head
+        .to_lowercase()
+        .contains("authorization: bearer sk-test"));
+}
+
+#[tokio::test]
+async fn test_openai_adapter_reports_http_errors() {
+    let (base_url, _request) = stub_server("401 Unauthorized", "{\"error\":\"invalid key\"}").await;
+    let agent = AgentFactory::create(&config(&base_url)).unwrap();
+
+    let events = run(agent.as_ref()).await;
+    assert!(matches!(
+        &events[0],
+        Err(AgentError::ApiError(msg)) if msg.contains("401") && msg.contains("invalid key")
+    ));
+}
+
+#[tokio::test]
+async fn test_openai_adapter_availability() {
+    let (base_url, _request) = stub_server("200 OK", "{\"data\":[]}").await;
+    let agent = AgentFactory::create(&config(&base_url)).unwrap();
+    assert!(agent.check_availability().await);
+
+    // Nothing listens on the port any more
+    assert!(!agent.check_availability().await);
+}
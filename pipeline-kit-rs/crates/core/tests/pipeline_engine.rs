@@ -9,6 +9,7 @@
 use pk_core::agents::manager::AgentManager;
 use pk_core::engine::PipelineEngine;
 use pk_protocol::agent_models::Agent as AgentConfig;
+use pk_protocol::agent_models::AgentProvider;
 use pk_protocol::ipc::Event;
 use pk_protocol::pipeline_models::MasterAgentConfig;
 use pk_protocol::pipeline_models::Pipeline;
@@ -22,8 +23,10 @@ fn create_test_agent_config(name: &str) -> AgentConfig {
         name: name.to_string(),
         description: format!("Test agent {}", name),
         model: "test-model".to_string(),
+        provider: Some(AgentProvider::Mock),
         color: "blue".to_string(),
         system_prompt: "Test prompt".to_string(),
+        ..Default::default()
     }
 }
 
@@ -38,6 +41,7 @@ fn create_test_pipeline(name: &str, steps: Vec<ProcessStep>) -> Pipeline {
             process: steps,
         },
         sub_agents: vec!["agent1".to_string(), "agent2".to_string()],
+        budget: None,
     }
 }
 
@@ -83,6 +87,12 @@ async fn test_pipeline_engine_sequential_execution_with_human_review() {
         started_at: chrono::Utc::now(),
         completed_at: None,
         resume_notifier: std::sync::Arc::new(tokio::sync::Notify::new()),
+        usage: Default::default(),
+        steps: Vec::new(),
+        pending_permissions: Vec::new(),
+        sessions: Default::default(),
+        reference_file: None,
+        permission_replies: Default::default(),
     };
 
     // Clone resume_notifier for manual resume in test
@@ -211,6 +221,12 @@ async fn test_pipeline_engine_completes_without_human_review() {
         started_at: chrono::Utc::now(),
         completed_at: None,
         resume_notifier: std::sync::Arc::new(tokio::sync::Notify::new()),
+        usage: Default::default(),
+        steps: Vec::new(),
+        pending_permissions: Vec::new(),
+        sessions: Default::default(),
+        reference_file: None,
+        permission_replies: Default::default(),
     };
 
     let handle = tokio::spawn(async move { engine.run(&pipeline, process, events_tx).await });
@@ -2,6 +2,7 @@
 
 use pk_core::agents::{AgentFactory, AgentManager, AgentType};
 use pk_protocol::agent_models::Agent as AgentConfig;
+use pk_protocol::agent_models::AgentProvider;
 
 #[tokio::test]
 async fn test_qwen_agent_from_config() {
@@ -12,6 +13,7 @@ async fn test_qwen_agent_from_config() {
         description: "Qwen development agent".to_string(),
         color: "purple".to_string(),
         system_prompt: "You are a helpful Qwen assistant.".to_string(),
+        ..Default::default()
     };
 
     // Test factory creation
@@ -39,13 +41,16 @@ async fn test_qwen_in_agent_manager() {
             description: "Qwen agent".to_string(),
             color: "purple".to_string(),
             system_prompt: "Qwen prompt".to_string(),
+            ..Default::default()
         },
         AgentConfig {
             name: "mock-agent".to_string(),
             model: "test-model".to_string(),
+            provider: Some(AgentProvider::Mock),
             description: "Mock agent".to_string(),
             color: "blue".to_string(),
             system_prompt: "Mock prompt".to_string(),
+            ..Default::default()
         },
     ];
 
@@ -76,17 +81,29 @@ async fn test_qwen_in_agent_manager() {
 #[test]
 fn test_qwen_agent_type_detection() {
     // Test various Qwen model names
-    assert_eq!(AgentType::from_model_name("qwen-coder"), AgentType::Qwen);
+    assert_eq!(
+        AgentType::from_model_name("qwen-coder"),
+        Some(AgentType::Qwen)
+    );
     assert_eq!(
         AgentType::from_model_name("Qwen3-Coder-Plus"),
-        AgentType::Qwen
+        Some(AgentType::Qwen)
+    );
+    assert_eq!(
+        AgentType::from_model_name("qwen2.5-coder"),
+        Some(AgentType::Qwen)
+    );
+    assert_eq!(
+        AgentType::from_model_name("QWEN-turbo"),
+        Some(AgentType::Qwen)
     );
-    assert_eq!(AgentType::from_model_name("qwen2.5-coder"), AgentType::Qwen);
-    assert_eq!(AgentType::from_model_name("QWEN-turbo"), AgentType::Qwen);
 
     // Test that non-Qwen models don't get detected as Qwen
-    assert_ne!(AgentType::from_model_name("claude-3"), AgentType::Qwen);
-    assert_ne!(AgentType::from_model_name("gpt-4"), AgentType::Qwen);
+    assert_ne!(
+        AgentType::from_model_name("claude-3"),
+        Some(AgentType::Qwen)
+    );
+    assert_ne!(AgentType::from_model_name("gpt-4"), Some(AgentType::Qwen));
 
     println!("✅ Qwen model name detection working correctly");
 }
@@ -101,13 +118,16 @@ async fn test_qwen_with_fallback() {
             description: "Primary Qwen agent".to_string(),
             color: "purple".to_string(),
             system_prompt: "Primary prompt".to_string(),
+            ..Default::default()
         },
         AgentConfig {
             name: "mock-fallback".to_string(),
             model: "test-model".to_string(),
+            provider: Some(AgentProvider::Mock),
             description: "Fallback mock agent".to_string(),
             color: "blue".to_string(),
             system_prompt: "Fallback prompt".to_string(),
+            ..Default::default()
         },
     ];
 
@@ -135,7 +155,7 @@ fn test_multiple_qwen_models() {
     for model in models {
         assert_eq!(
             AgentType::from_model_name(model),
-            AgentType::Qwen,
+            Some(AgentType::Qwen),
             "Model '{}' should be detected as Qwen",
             model
         );
@@ -5,6 +5,7 @@
 
 use serde::Deserialize;
 use serde::Serialize;
+use std::collections::HashMap;
 use ts_rs::TS;
 
 /// Represents an AI agent's configuration and system prompt.
@@ -19,6 +20,16 @@ use ts_rs::TS;
 /// name: code-reviewer
 /// description: Reviews code for quality and best practices
 /// model: claude-sonnet-4
+/// provider: claude
+/// permission-mode: read-only
+/// disallowed-tools: [Bash]
+/// permission-rules:
+///   - { tool: read, action: allow }
+/// max-turns: 20
+/// timeout-secs: 900
+/// working-dir: services/api
+/// env:
+///   RUST_LOG: info
 /// color: blue
 /// ---
 ///
@@ -28,7 +39,8 @@ use ts_rs::TS;
 /// - Security
 /// - Best practices
 /// ```
-#[derive(Serialize, Deserialize, Debug, Clone, TS)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, TS)]
+#[serde(rename_all = "kebab-case")]
 pub struct Agent {
     /// Unique identifier for this agent.
     ///
@@ -41,6 +53,94 @@ pub struct Agent {
     /// AI model to use for this agent (e.g., "claude-sonnet-4", "gpt-4").
     pub model: String,
 
+    /// Adapter that runs this agent.
+    ///
+    /// When omitted the provider is inferred from well-known model names;
+    /// models that match none of them are rejected.
+    #[serde(default)]
+    #[ts(optional)]
+    pub provider: Option<AgentProvider>,
+
+    /// What the agent may do without asking.
+    ///
+    /// When omitted each adapter keeps its non-interactive default, which
+    /// allows edits and commands.
+    #[serde(default)]
+    #[ts(optional)]
+    pub permission_mode: Option<PermissionMode>,
+
+    /// Tools the agent may use (adapter-specific names, e.g. `Read`, `Grep`).
+    ///
+    /// Empty means the adapter's default tool set.
+    #[serde(default)]
+    pub allowed_tools: Vec<String>,
+
+    /// Tools the agent must not use.
+    #[serde(default)]
+    pub disallowed_tools: Vec<String>,
+
+    /// Rules answering the agent's permission requests.
+    ///
+    /// The first rule matching a request decides it; requests no rule
+    /// matches are granted by `permission-mode` or, without one, asked of
+    /// the user. Only adapters that receive permission requests accept
+    /// rules.
+    #[serde(default)]
+    pub permission_rules: Vec<PermissionRule>,
+
+    /// Agents to try, in order, when this one is unavailable or fails with
+    /// a retryable error.
+    ///
+    /// Overrides the default chain from `config.toml`.
+    #[serde(default)]
+    pub fallback: Vec<String>,
+
+    /// Maximum number of agentic turns per step.
+    ///
+    /// Only adapters whose CLI exposes a turn limit accept this.
+    #[serde(default)]
+    #[ts(optional)]
+    pub max_turns: Option<u32>,
+
+    /// Extra environment variables for the agent process.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+
+    /// Directory the agent runs in, relative to the project root.
+    #[serde(default)]
+    #[ts(optional)]
+    pub working_dir: Option<String>,
+
+    /// Additional adapter-specific arguments appended to the CLI invocation.
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+
+    /// Seconds a single step of this agent may run before it is failed.
+    #[serde(default)]
+    #[ts(optional, type = "number")]
+    pub timeout_secs: Option<u64>,
+
+    /// Base URL of the HTTP API for `provider: openai` or `anthropic`
+    /// (e.g. `http://localhost:8000/v1`).
+    ///
+    /// Defaults to the provider's public endpoint.
+    #[serde(default)]
+    #[ts(optional)]
+    pub base_url: Option<String>,
+
+    /// Environment variable holding the API key for HTTP providers.
+    ///
+    /// Defaults to the provider's usual variable (`OPENAI_API_KEY` or
+    /// `ANTHROPIC_API_KEY`).
+    #[serde(default)]
+    #[ts(optional)]
+    pub api_key_env: Option<String>,
+
+    /// Command line and output mapping for `provider: custom`.
+    #[serde(default)]
+    #[ts(optional)]
+    pub command: Option<CustomCommand>,
+
     /// UI color hint for displaying this agent in the TUI.
     ///
     /// Defaults to empty string if not specified.
@@ -55,3 +155,269 @@ pub struct Agent {
     #[serde(skip)]
     pub system_prompt: String,
 }
+
+/// Adapter backing an agent, set with `provider:` in the front matter.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, TS)]
+#[serde(rename_all = "lowercase")]
+pub enum AgentProvider {
+    /// Claude Code CLI (`claude`).
+    Claude,
+    /// Cursor Agent CLI (`cursor-agent`).
+    Cursor,
+    /// Gemini CLI (`gemini`).
+    Gemini,
+    /// OpenAI Codex CLI (`codex`).
+    Codex,
+    /// Qwen Code CLI (`qwen`).
+    Qwen,
+    /// OpenAI-compatible `/chat/completions` HTTP endpoint.
+    OpenAi,
+    /// Anthropic Messages HTTP API.
+    Anthropic,
+    /// A user-defined command.
+    Custom,
+    /// Built-in mock agent for tests and dry runs; `model` selects its behaviour.
+    Mock,
+}
+
+/// What an agent may do without asking, set with `permission-mode:`.
+///
+/// Each adapter maps the mode to its CLI's native flags; adapters that cannot
+/// enforce a mode reject the agent configuration.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, TS)]
+#[serde(rename_all = "kebab-case")]
+pub enum PermissionMode {
+    /// Read and search only; no edits and no shell commands (e.g. a reviewer).
+    ReadOnly,
+    /// File edits are allowed; shell commands are not.
+    Edit,
+    /// Edits and shell commands are allowed (e.g. a developer).
+    Full,
+}
+
+/// Automatic answer to matching permission requests.
+///
+/// # Example
+///
+/// ```yaml
+/// permission-rules:
+///   - { tool: read, action: allow }
+///   - { tool: execute, action: ask }
+///   - { tool: fetch, action: deny }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, TS)]
+pub struct PermissionRule {
+    /// Tool kind reported by the agent (e.g. `read`, `edit`, `execute`,
+    /// `fetch`), compared case-insensitively; `*` matches any tool.
+    pub tool: String,
+
+    /// What to do with a matching request.
+    pub action: PermissionAction,
+}
+
+impl PermissionRule {
+    /// Whether this rule applies to a tool kind.
+    pub fn matches(&self, tool: &str) -> bool {
+        self.tool == "*" || self.tool.eq_ignore_ascii_case(tool)
+    }
+}
+
+/// Decision of a permission rule.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, TS)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionAction {
+    /// Grant the request.
+    Allow,
+    /// Ask the user through the front end.
+    Ask,
+    /// Refuse the request.
+    Deny,
+}
+
+/// How a `provider: custom` agent is launched, set with `command:`.
+///
+/// # Example
+///
+/// ```yaml
+/// provider: custom
+/// command:
+///   program: opencode
+///   args: [run, --format, json, --model, "{model}"]
+///   prompt: stdin
+///   events:
+///     message: { when: text, field: part.text }
+///     tool-call: { when: tool_use, name: part.tool, arguments: part.input, id: part.id }
+///     error: { when: error, field: message }
+///     completed: finish
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "kebab-case")]
+pub struct CustomCommand {
+    /// Executable to run, looked up on `PATH`.
+    pub program: String,
+
+    /// Arguments passed to the program.
+    ///
+    /// `{model}`, `{system_prompt}`, `{prompt}` and `{prompt_file}` are
+    /// replaced with the agent's model, its system prompt, the step
+    /// instruction and the path of the instruction file.
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Protocol the program speaks on stdio.
+    #[serde(default)]
+    pub protocol: CommandProtocol,
+
+    /// How the step instruction is handed to the program.
+    ///
+    /// Ignored for `acp`, which sends the instruction as a prompt turn.
+    #[serde(default)]
+    pub prompt: PromptInput,
+
+    /// How the program's JSON Lines output maps to agent events.
+    ///
+    /// Ignored for `acp`, whose session updates have a fixed shape.
+    #[serde(default)]
+    pub events: EventMapping,
+}
+
+/// Protocol spoken by a custom command.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, TS)]
+#[serde(rename_all = "kebab-case")]
+pub enum CommandProtocol {
+    /// One JSON object per output line, mapped with `events`.
+    #[default]
+    JsonLines,
+    /// Agent Client Protocol (JSON-RPC over stdio), e.g. `gemini --experimental-acp`.
+    Acp,
+}
+
+/// How a custom command receives the step instruction.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, TS)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptInput {
+    /// As an argument: substituted for `{prompt}`, or appended last.
+    #[default]
+    Arg,
+    /// Written to the program's stdin, which is then closed.
+    Stdin,
+    /// Written to a temporary file whose path is substituted for
+    /// `{prompt_file}`, or appended last.
+    File,
+}
+
+/// Mapping from JSON output lines to agent events.
+///
+/// Each line's `type-field` is compared with the `when` of every rule; the
+/// first matching rule produces the event. Fields are dot-separated paths
+/// into the line (e.g. `part.text`). Lines matching no rule are ignored.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "kebab-case")]
+pub struct EventMapping {
+    /// Field holding the event type of a line.
+    #[serde(default = "default_type_field")]
+    pub type_field: String,
+
+    /// Lines that carry response text.
+    #[serde(default)]
+    #[ts(optional)]
+    pub message: Option<TextRule>,
+
+    /// Lines that carry reasoning.
+    #[serde(default)]
+    #[ts(optional)]
+    pub thought: Option<TextRule>,
+
+    /// Lines that report a tool invocation.
+    #[serde(default)]
+    #[ts(optional)]
+    pub tool_call: Option<ToolCallRule>,
+
+    /// Lines that report a tool result.
+    #[serde(default)]
+    #[ts(optional)]
+    pub tool_result: Option<ToolResultRule>,
+
+    /// Lines that report a fatal error; the step fails with their text.
+    #[serde(default)]
+    #[ts(optional)]
+    pub error: Option<TextRule>,
+
+    /// Type value of the line that ends the run.
+    ///
+    /// When omitted the run completes when the program exits.
+    #[serde(default)]
+    #[ts(optional)]
+    pub completed: Option<String>,
+}
+
+impl Default for EventMapping {
+    fn default() -> Self {
+        Self {
+            type_field: default_type_field(),
+            message: None,
+            thought: None,
+            tool_call: None,
+            tool_result: None,
+            error: None,
+            completed: None,
+        }
+    }
+}
+
+fn default_type_field() -> String {
+    "type".to_string()
+}
+
+/// Rule for an event carrying a single piece of text.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, TS)]
+#[serde(rename_all = "kebab-case")]
+pub struct TextRule {
+    /// Type value that selects this rule.
+    pub when: String,
+
+    /// Field holding the text.
+    pub field: String,
+}
+
+/// Rule for a tool invocation line.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, TS)]
+#[serde(rename_all = "kebab-case")]
+pub struct ToolCallRule {
+    /// Type value that selects this rule.
+    pub when: String,
+
+    /// Field holding the tool name.
+    pub name: String,
+
+    /// Field holding the tool arguments.
+    #[serde(default)]
+    #[ts(optional)]
+    pub arguments: Option<String>,
+
+    /// Field holding the call id.
+    #[serde(default)]
+    #[ts(optional)]
+    pub id: Option<String>,
+}
+
+/// Rule for a tool result line.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, TS)]
+#[serde(rename_all = "kebab-case")]
+pub struct ToolResultRule {
+    /// Type value that selects this rule.
+    pub when: String,
+
+    /// Field holding the tool output.
+    pub output: String,
+
+    /// Field holding the id of the matching call.
+    #[serde(default)]
+    #[ts(optional)]
+    pub id: Option<String>,
+
+    /// Boolean field that is `true` when the tool failed.
+    #[serde(default)]
+    #[ts(optional)]
+    pub is_error: Option<String>,
+}
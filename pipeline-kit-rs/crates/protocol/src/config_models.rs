@@ -3,6 +3,7 @@
 //! This module defines the structure of the global configuration file that
 //! controls project-wide settings for pipeline-kit.
 
+use crate::pipeline_models::Budget;
 use serde::Deserialize;
 use serde::Serialize;
 use ts_rs::TS;
@@ -17,6 +18,10 @@ use ts_rs::TS;
 /// ```toml
 /// # .pipeline-kit/config.toml
 /// git = true
+/// fallback = ["claude", "codex"]
+///
+/// [budget]
+/// max-cost-usd = 10.0
 /// ```
 #[derive(Serialize, Deserialize, Debug, Clone, TS)]
 pub struct GlobalConfig {
@@ -26,4 +31,14 @@ pub struct GlobalConfig {
     /// during pipeline execution in git.
     #[serde(default)]
     pub git: bool,
+
+    /// Default budget for pipelines that do not declare their own.
+    #[serde(default)]
+    #[ts(optional)]
+    pub budget: Option<Budget>,
+
+    /// Agents to try, in order, for agents that declare no `fallback` of
+    /// their own.
+    #[serde(default)]
+    pub fallback: Vec<String>,
 }
@@ -0,0 +1,44 @@
+//! Environment diagnostics reported by `pipeline-kit doctor`.
+//!
+//! Each configured agent is checked for the program and environment
+//! variables its adapter needs, so missing setup is visible before a
+//! pipeline fails on it.
+
+use serde::Deserialize;
+use serde::Serialize;
+use ts_rs::TS;
+
+/// Diagnosis of one configured agent.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+pub struct AgentDiagnosis {
+    /// Agent name from its front matter.
+    pub agent_name: String,
+
+    /// Adapter backing the agent, e.g. "Claude".
+    pub adapter_type: String,
+
+    /// Program the adapter spawns (HTTP adapters spawn none).
+    #[ts(optional)]
+    pub binary: Option<String>,
+
+    /// Where `binary` was found on `PATH`.
+    #[ts(optional)]
+    pub binary_path: Option<String>,
+
+    /// First line of `binary --version`.
+    #[ts(optional)]
+    pub version: Option<String>,
+
+    /// Environment variables the adapter requires.
+    pub env_vars: Vec<EnvVarStatus>,
+
+    /// Whether the adapter's availability check passed.
+    pub available: bool,
+}
+
+/// Whether a required environment variable is set.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, TS)]
+pub struct EnvVarStatus {
+    pub name: String,
+    pub present: bool,
+}
@@ -16,7 +16,13 @@ use std::path::PathBuf;
 use ts_rs::TS;
 use uuid::Uuid;
 
+use crate::diagnostics::AgentDiagnosis;
+use crate::process_models::FileChangeKind;
+use crate::process_models::PermissionRequest;
+use crate::process_models::Process;
 use crate::process_models::ProcessStatus;
+use crate::process_models::StepRecord;
+use crate::process_models::TokenUsage;
 
 /// Operations sent from the UI (TUI) to the Core logic.
 ///
@@ -81,6 +87,21 @@ pub enum Op {
         process_id: Uuid,
     },
 
+    /// Answer a pending permission request of an agent.
+    AnswerPermission {
+        #[ts(type = "string")]
+        process_id: Uuid,
+        #[ts(type = "string")]
+        request_id: Uuid,
+        /// Whether the tool call may run.
+        allow: bool,
+    },
+
+    /// Check every configured agent's environment.
+    ///
+    /// Core will respond with a diagnostics report.
+    Diagnose,
+
     /// Shut down the application gracefully.
     ///
     /// All running processes will be terminated.
@@ -154,4 +175,164 @@ pub enum Event {
         #[ts(type = "string")]
         process_id: Uuid,
     },
+
+    /// An agent invoked a tool.
+    ToolCallStarted {
+        #[ts(type = "string")]
+        process_id: Uuid,
+        agent_name: String,
+        /// Provider-assigned call id, used to correlate the result.
+        call_id: Option<String>,
+        /// Tool name (e.g. `Bash`, `Edit`).
+        name: String,
+        /// Tool arguments as reported by the agent.
+        #[ts(type = "unknown")]
+        arguments: serde_json::Value,
+    },
+
+    /// A tool call finished.
+    ToolCallCompleted {
+        #[ts(type = "string")]
+        process_id: Uuid,
+        agent_name: String,
+        /// Id of the matching `ToolCallStarted`, when the agent reports one.
+        call_id: Option<String>,
+        /// Tool output (possibly truncated by the agent).
+        output: String,
+        /// Whether the tool reported a failure.
+        is_error: bool,
+    },
+
+    /// An agent reported token and cost usage.
+    UsageReported {
+        #[ts(type = "string")]
+        process_id: Uuid,
+        agent_name: String,
+        usage: TokenUsage,
+    },
+
+    /// An agent created, modified or deleted a file.
+    FileChanged {
+        #[ts(type = "string")]
+        process_id: Uuid,
+        agent_name: String,
+        path: String,
+        kind: FileChangeKind,
+    },
+
+    /// An agent reported a non-fatal problem.
+    AgentWarning {
+        #[ts(type = "string")]
+        process_id: Uuid,
+        agent_name: String,
+        message: String,
+    },
+
+    /// Outcome of an `Op` submitted by a front end.
+    ///
+    /// Echoes the original operation so the client can correlate the
+    /// result with what it sent, e.g. to report an unknown pipeline on
+    /// `startPipeline` or a missing process on `killProcess`.
+    CommandResult {
+        op: Op,
+        ok: bool,
+        #[ts(optional, type = "string")]
+        process_id: Option<Uuid>,
+        #[ts(optional)]
+        error: Option<String>,
+    },
+
+    /// Snapshot of every known process, sent in reply to `GetDashboardState`.
+    ///
+    /// Clients that join late or miss events can replace their local
+    /// state with this snapshot.
+    DashboardState { processes: Vec<Process> },
+
+    /// Snapshot of a single process including its logs and step timeline,
+    /// sent in reply to `GetProcessDetail`.
+    ProcessDetail { process: Process },
+
+    /// A pipeline step started.
+    StepStarted {
+        #[ts(type = "string")]
+        process_id: Uuid,
+        step: StepRecord,
+    },
+
+    /// A pipeline step finished successfully.
+    StepCompleted {
+        #[ts(type = "string")]
+        process_id: Uuid,
+        step: StepRecord,
+    },
+
+    /// A pipeline step failed.
+    StepFailed {
+        #[ts(type = "string")]
+        process_id: Uuid,
+        step: StepRecord,
+    },
+
+    /// A process crossed the warning threshold of its budget.
+    BudgetWarning {
+        #[ts(type = "string")]
+        process_id: Uuid,
+        usage: TokenUsage,
+        message: String,
+    },
+
+    /// A process used up its budget.
+    ///
+    /// Followed by a pause for human review or a process failure,
+    /// depending on the budget's `on-exceeded` action.
+    BudgetExceeded {
+        #[ts(type = "string")]
+        process_id: Uuid,
+        usage: TokenUsage,
+        message: String,
+    },
+
+    /// An agent asked for permission to run a tool.
+    ///
+    /// The agent waits until the request is answered with
+    /// `answerPermission`.
+    PermissionRequested {
+        #[ts(type = "string")]
+        process_id: Uuid,
+        request: PermissionRequest,
+    },
+
+    /// A permission request was answered, or dropped because the agent
+    /// stopped waiting for it.
+    PermissionResolved {
+        #[ts(type = "string")]
+        process_id: Uuid,
+        #[ts(type = "string")]
+        request_id: Uuid,
+        allowed: bool,
+    },
+
+    /// An agent reported the conversation id its next steps in this process
+    /// continue.
+    SessionStarted {
+        #[ts(type = "string")]
+        process_id: Uuid,
+        agent_name: String,
+        session_id: String,
+    },
+
+    /// Result of `Op::Diagnose`, one entry per configured agent.
+    DiagnosticsReport { agents: Vec<AgentDiagnosis> },
+
+    /// An agent step failed over to a fallback agent, which serves the rest
+    /// of the step.
+    AgentFallback {
+        #[ts(type = "string")]
+        process_id: Uuid,
+        /// The agent that failed.
+        agent_name: String,
+        /// The agent taking over.
+        fallback_agent: String,
+        reason: String,
+    },
 }
@@ -11,9 +11,11 @@
 //!
 //! - [`agent_models`]: Agent configuration structures
 //! - [`config_models`]: Global configuration from config.toml
+//! - [`diagnostics`]: Environment checks reported by `doctor`
 //! - [`pipeline_models`]: Pipeline definitions and process steps
 //! - [`process_models`]: Runtime process state and status
 //! - [`ipc`]: Operations and Events for Core-TUI communication
+//! - [`rpc`]: Request/response envelope for the stdio JSON-RPC mode
 //!
 //! ## Design Principles
 //!
@@ -23,13 +25,17 @@
 
 pub mod agent_models;
 pub mod config_models;
+pub mod diagnostics;
 pub mod ipc;
 pub mod pipeline_models;
 pub mod process_models;
+pub mod rpc;
 
 // Re-export all public types for convenience
 pub use agent_models::*;
 pub use config_models::*;
+pub use diagnostics::*;
 pub use ipc::*;
 pub use pipeline_models::*;
 pub use process_models::*;
+pub use rpc::*;
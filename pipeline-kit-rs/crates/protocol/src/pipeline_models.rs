@@ -15,7 +15,14 @@ use ts_rs::TS;
 /// - A special command like HUMAN_REVIEW that pauses for manual intervention
 ///
 /// The enum uses `#[serde(untagged)]` to allow flexible YAML syntax where
-/// steps can be simple strings.
+/// steps can be simple strings, or maps when an agent step needs options:
+///
+/// ```yaml
+/// process:
+///   - "developer"
+///   - "HUMAN_REVIEW"
+///   - { agent: "developer", session: fresh }
+/// ```
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, TS)]
 #[serde(untagged)]
 pub enum ProcessStep {
@@ -30,6 +37,55 @@ pub enum ProcessStep {
     /// The string should match the `name` field of an agent defined
     /// in `.pipeline-kit/agents/*.md`.
     Agent(String),
+
+    /// Execute an agent with per-step options.
+    AgentWithOptions(AgentStep),
+}
+
+impl ProcessStep {
+    /// Name of the agent this step runs, or `None` for HUMAN_REVIEW.
+    pub fn agent_name(&self) -> Option<&str> {
+        match self {
+            ProcessStep::HumanReview(_) => None,
+            ProcessStep::Agent(name) => Some(name),
+            ProcessStep::AgentWithOptions(step) => Some(&step.agent),
+        }
+    }
+
+    /// Session handling for this step; plain agent steps continue.
+    pub fn session_mode(&self) -> SessionMode {
+        match self {
+            ProcessStep::AgentWithOptions(step) => step.session,
+            _ => SessionMode::default(),
+        }
+    }
+}
+
+/// An agent step written as a map, with options for that step.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, TS)]
+#[serde(rename_all = "kebab-case")]
+pub struct AgentStep {
+    /// Name of the agent to execute.
+    pub agent: String,
+
+    /// Whether the agent continues its conversation from earlier steps.
+    #[serde(default)]
+    pub session: SessionMode,
+}
+
+/// How an agent step treats the conversation of earlier steps.
+///
+/// Sessions are tracked per process and agent, so concurrent processes and
+/// different agents never share a conversation.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, TS)]
+#[serde(rename_all = "kebab-case")]
+pub enum SessionMode {
+    /// Continue the conversation this agent had earlier in the process.
+    #[default]
+    Continue,
+
+    /// Start a new conversation; later steps continue the new one.
+    Fresh,
 }
 
 /// Marker type for HUMAN_REVIEW step that deserializes from the literal string "HUMAN_REVIEW".
@@ -110,6 +166,10 @@ pub struct MasterAgentConfig {
 ///   - "static-analyzer"
 ///   - "security-reviewer"
 ///   - "final-reporter"
+/// budget:
+///   max-tokens: 500000
+///   max-cost-usd: 5.0
+///   on-exceeded: pause
 /// ```
 #[derive(Serialize, Deserialize, Debug, Clone, TS)]
 #[serde(rename_all = "kebab-case")]
@@ -138,4 +198,73 @@ pub struct Pipeline {
     ///
     /// All agent names referenced in `master.process` should be listed here.
     pub sub_agents: Vec<String>,
+
+    /// Token and cost limits applied to each run of this pipeline.
+    ///
+    /// Falls back to the `[budget]` table in `config.toml` when omitted.
+    #[serde(default)]
+    #[ts(optional)]
+    pub budget: Option<Budget>,
+}
+
+/// What the engine does once a process has used up its budget.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, TS)]
+#[serde(rename_all = "kebab-case")]
+pub enum BudgetAction {
+    /// Pause for human confirmation before running further steps.
+    #[default]
+    Pause,
+
+    /// Fail the process.
+    Fail,
+}
+
+/// Token and cost limits for a single pipeline run.
+///
+/// Each limit is optional; a budget with neither limit never triggers.
+///
+/// # Example
+///
+/// ```yaml
+/// budget:
+///   max-tokens: 200000
+///   max-cost-usd: 2.5
+///   warn-at: 0.8
+///   on-exceeded: fail
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "kebab-case")]
+pub struct Budget {
+    /// Maximum number of input + output tokens.
+    #[serde(default)]
+    #[ts(optional, type = "number")]
+    pub max_tokens: Option<u64>,
+
+    /// Maximum cost in US dollars.
+    #[serde(default)]
+    #[ts(optional)]
+    pub max_cost_usd: Option<f64>,
+
+    /// Fraction of a limit (0.0-1.0) at which a warning is emitted.
+    #[serde(default = "default_warn_at")]
+    pub warn_at: f64,
+
+    /// Action taken when a limit is reached.
+    #[serde(default)]
+    pub on_exceeded: BudgetAction,
+}
+
+fn default_warn_at() -> f64 {
+    0.8
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Self {
+            max_tokens: None,
+            max_cost_usd: None,
+            warn_at: default_warn_at(),
+            on_exceeded: BudgetAction::default(),
+        }
+    }
 }
@@ -7,7 +7,10 @@ use chrono::DateTime;
 use chrono::Utc;
 use serde::Deserialize;
 use serde::Serialize;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::Mutex;
+use tokio::sync::oneshot;
 use tokio::sync::Notify;
 use ts_rs::TS;
 use uuid::Uuid;
@@ -98,4 +101,189 @@ pub struct Process {
     #[serde(skip)]
     #[ts(skip)]
     pub resume_notifier: Arc<Notify>,
+
+    /// Token and cost usage accumulated across all agent invocations.
+    #[serde(default)]
+    pub usage: TokenUsage,
+
+    /// Timeline of the steps started so far, in execution order.
+    #[serde(default)]
+    pub steps: Vec<StepRecord>,
+
+    /// Permission requests waiting for an answer from the user.
+    #[serde(default)]
+    pub pending_permissions: Vec<PermissionRequest>,
+
+    /// Conversation ids reported by the agents of this process, keyed by
+    /// agent name.
+    ///
+    /// A later step of the same agent continues the recorded conversation
+    /// unless the step asks for a fresh session.
+    #[serde(default)]
+    pub sessions: HashMap<String, String>,
+
+    /// Reference file given when the process was started, attached to every
+    /// agent step.
+    #[serde(default)]
+    #[ts(optional)]
+    pub reference_file: Option<String>,
+
+    /// Reply channels of pending permission requests, keyed by request id.
+    ///
+    /// Like `resume_notifier`, this is shared between clones of a process
+    /// so that whoever answers a request reaches the waiting agent.
+    #[serde(skip)]
+    #[ts(skip)]
+    pub permission_replies: PermissionReplies,
+}
+
+/// Reply channels of pending permission requests; `true` grants a request.
+pub type PermissionReplies = Arc<Mutex<HashMap<Uuid, oneshot::Sender<bool>>>>;
+
+/// A tool call an agent asked permission to run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+pub struct PermissionRequest {
+    /// Identifier used to answer the request.
+    #[ts(type = "string")]
+    pub request_id: Uuid,
+
+    /// Agent asking for permission.
+    pub agent_name: String,
+
+    /// Tool kind (e.g. `execute`), as matched by permission rules.
+    pub tool: String,
+
+    /// Human-readable description of the call, e.g. the command line.
+    pub title: String,
+
+    /// Tool arguments as reported by the agent.
+    #[ts(type = "unknown")]
+    pub arguments: serde_json::Value,
+}
+
+/// Kind of a pipeline step.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, TS)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum StepKind {
+    /// An agent invocation.
+    Agent,
+
+    /// A HUMAN_REVIEW pause.
+    HumanReview,
+}
+
+/// Lifecycle status of a single step.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, TS)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum StepStatus {
+    /// The step is executing (or waiting for review).
+    Running,
+
+    /// The step finished successfully.
+    Completed,
+
+    /// The step failed.
+    Failed,
+}
+
+/// Record of one executed step, as shown in the process timeline.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+pub struct StepRecord {
+    /// Zero-based index of the step in `master.process`.
+    pub index: usize,
+
+    /// What kind of step this is.
+    pub kind: StepKind,
+
+    /// Agent executed by this step (agent steps only).
+    #[ts(optional)]
+    pub agent_name: Option<String>,
+
+    /// Adapter backing the agent, e.g. "Claude" (agent steps only).
+    #[ts(optional)]
+    pub adapter_type: Option<String>,
+
+    /// Fallback agent that served the step because `agent_name` failed
+    /// (agent steps only).
+    #[serde(default)]
+    #[ts(optional)]
+    pub served_by: Option<String>,
+
+    /// Current status of the step.
+    pub status: StepStatus,
+
+    /// Timestamp when the step started.
+    #[ts(type = "string")]
+    pub started_at: DateTime<Utc>,
+
+    /// Timestamp when the step finished (if finished).
+    #[ts(optional, type = "string")]
+    pub completed_at: Option<DateTime<Utc>>,
+
+    /// Wall-clock duration of the step in milliseconds (if finished).
+    #[ts(optional, type = "number")]
+    pub duration_ms: Option<u64>,
+
+    /// Error message for failed steps.
+    #[ts(optional)]
+    pub error: Option<String>,
+}
+
+/// Token and cost usage reported by an agent.
+///
+/// Adapters report usage per invocation; fields an agent does not expose
+/// are left at zero / `None`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, TS)]
+pub struct TokenUsage {
+    /// Prompt tokens consumed.
+    #[ts(type = "number")]
+    pub input_tokens: u64,
+
+    /// Completion tokens produced.
+    #[ts(type = "number")]
+    pub output_tokens: u64,
+
+    /// Cost in US dollars, when the provider reports it.
+    pub cost_usd: Option<f64>,
+
+    /// Wall-clock duration of the invocation in milliseconds.
+    #[ts(optional, type = "number")]
+    pub duration_ms: Option<u64>,
+}
+
+impl TokenUsage {
+    /// Total number of tokens (input + output).
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+
+    /// Add another usage report to this running total.
+    ///
+    /// Costs and durations are summed when either side reports them.
+    pub fn accumulate(&mut self, other: &TokenUsage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cost_usd = match (self.cost_usd, other.cost_usd) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(0.0) + b.unwrap_or(0.0)),
+        };
+        self.duration_ms = match (self.duration_ms, other.duration_ms) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+        };
+    }
+}
+
+/// Kind of change an agent made to a file.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, TS)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FileChangeKind {
+    /// The file was created.
+    Created,
+
+    /// The file was modified (or overwritten).
+    Modified,
+
+    /// The file was deleted.
+    Deleted,
 }
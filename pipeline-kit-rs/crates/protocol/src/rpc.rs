@@ -0,0 +1,79 @@
+//! Line-delimited JSON-RPC envelope for `serve --stdio`.
+//!
+//! Clients that drive the core over stdin/stdout (the Node wrapper,
+//! editor integrations) wrap each `Op` in an `RpcRequest` and receive a
+//! stream of `RpcMessage`s: one `response` per request that carried an
+//! id, interleaved with every `event` emitted by the core.
+//!
+//! ```json
+//! {"id":1,"op":{"type":"startPipeline","payload":{"name":"simple-task","reference_file":null}}}
+//! ```
+//!
+//! ```json
+//! {"kind":"response","id":1,"ok":true,"process_id":"uuid-here","error":null}
+//! {"kind":"event","event":{"type":"processStarted","payload":{"process_id":"uuid-here","pipeline_name":"simple-task"}}}
+//! ```
+
+use serde::Deserialize;
+use serde::Serialize;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::ipc::Event;
+use crate::ipc::Op;
+
+/// Identifier chosen by the client to correlate a request with its response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(untagged)]
+pub enum RequestId {
+    /// Numeric id, e.g. an incrementing counter.
+    Number(u64),
+    /// String id, e.g. a UUID generated by the client.
+    String(String),
+}
+
+/// A single request line read from stdin.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+pub struct RpcRequest {
+    /// Optional request id.
+    ///
+    /// Requests without an id are notifications: they are applied but
+    /// no response is written.
+    #[serde(default)]
+    #[ts(optional)]
+    pub id: Option<RequestId>,
+
+    /// The operation to apply.
+    pub op: Op,
+}
+
+/// A single message line written to stdout.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum RpcMessage {
+    /// The outcome of a request.
+    Response {
+        /// The id of the request this responds to.
+        ///
+        /// `None` when the request line could not be parsed.
+        id: Option<RequestId>,
+
+        /// Whether the operation was applied successfully.
+        ok: bool,
+
+        /// The process created by a successful `startPipeline` request.
+        #[ts(optional, type = "string")]
+        process_id: Option<Uuid>,
+
+        /// Error message when `ok` is false.
+        #[ts(optional)]
+        error: Option<String>,
+    },
+
+    /// An event emitted by the core.
+    Event {
+        /// The wrapped event, boxed because `Event` is much larger than the
+        /// other variants.
+        event: Box<Event>,
+    },
+}
@@ -52,8 +52,10 @@ fn test_agent_serialization() {
         name: "test-agent".to_string(),
         description: "A test agent".to_string(),
         model: "claude-sonnet-4".to_string(),
+        provider: Some(AgentProvider::Claude),
         color: "blue".to_string(),
         system_prompt: "Be helpful".to_string(),
+        ..Default::default()
     };
 
     let json = serde_json::to_string(&agent).expect("Failed to serialize Agent");
@@ -62,11 +64,105 @@ fn test_agent_serialization() {
     assert_eq!(deserialized.name, agent.name);
     assert_eq!(deserialized.description, agent.description);
     assert_eq!(deserialized.model, agent.model);
+    assert_eq!(deserialized.provider, Some(AgentProvider::Claude));
     assert_eq!(deserialized.color, agent.color);
     // system_prompt is skipped in serialization
     assert_eq!(deserialized.system_prompt, "");
 }
 
+#[test]
+fn test_agent_provider_deserialization() {
+    let agent: Agent = serde_json::from_value(serde_json::json!({
+        "name": "local",
+        "description": "Local mock",
+        "model": "test-model",
+        "provider": "mock"
+    }))
+    .expect("Failed to deserialize Agent with provider");
+    assert_eq!(agent.provider, Some(AgentProvider::Mock));
+
+    // provider is optional
+    let agent: Agent = serde_json::from_value(serde_json::json!({
+        "name": "dev",
+        "description": "Developer",
+        "model": "claude-sonnet-4"
+    }))
+    .expect("Failed to deserialize Agent without provider");
+    assert_eq!(agent.provider, None);
+
+    let unknown: Result<Agent, _> = serde_json::from_value(serde_json::json!({
+        "name": "dev",
+        "description": "Developer",
+        "model": "x",
+        "provider": "azure"
+    }));
+    assert!(unknown.is_err());
+}
+
+#[test]
+fn test_custom_command_deserialization_from_yaml() {
+    let yaml = r#"
+name: opencode
+description: OpenCode agent
+model: local-model
+provider: custom
+command:
+  program: opencode
+  args: [run, --model, "{model}"]
+  prompt: stdin
+  events:
+    message: { when: text, field: part.text }
+    tool-call: { when: tool_use, name: part.tool, arguments: part.input }
+    completed: finish
+"#;
+    let agent: Agent = serde_yaml::from_str(yaml).expect("Failed to deserialize custom agent");
+    let command = agent.command.expect("command section");
+    assert_eq!(command.program, "opencode");
+    assert_eq!(command.args, vec!["run", "--model", "{model}"]);
+    assert_eq!(command.prompt, PromptInput::Stdin);
+    assert_eq!(command.events.type_field, "type");
+    assert_eq!(command.events.message.unwrap().field, "part.text");
+    assert_eq!(
+        command.events.tool_call.unwrap().arguments.as_deref(),
+        Some("part.input")
+    );
+    assert_eq!(command.events.completed.as_deref(), Some("finish"));
+
+    // Only the program is required
+    let command: CustomCommand =
+        serde_yaml::from_str("program: aider").expect("Failed to deserialize minimal command");
+    assert_eq!(command.protocol, CommandProtocol::JsonLines);
+    assert_eq!(command.prompt, PromptInput::Arg);
+    assert_eq!(command.events, EventMapping::default());
+
+    let command: CustomCommand =
+        serde_yaml::from_str("program: gemini\nargs: [--experimental-acp]\nprotocol: acp")
+            .expect("Failed to deserialize ACP command");
+    assert_eq!(command.protocol, CommandProtocol::Acp);
+}
+
+#[test]
+fn test_permission_rules_deserialization_from_yaml() {
+    let yaml = r#"
+name: developer
+description: Developer
+model: qwen3-coder-plus
+permission-rules:
+  - { tool: read, action: allow }
+  - { tool: execute, action: ask }
+  - { tool: fetch, action: deny }
+"#;
+    let agent: Agent = serde_yaml::from_str(yaml).expect("Failed to deserialize permission rules");
+    assert_eq!(agent.permission_rules.len(), 3);
+    assert_eq!(agent.permission_rules[1].action, PermissionAction::Ask);
+    assert!(agent.permission_rules[1].matches("Execute"));
+    assert!(!agent.permission_rules[1].matches("read"));
+
+    let agent: Agent = serde_yaml::from_str("name: dev\ndescription: Dev\nmodel: x")
+        .expect("Failed to deserialize");
+    assert!(agent.permission_rules.is_empty());
+}
+
 #[test]
 fn test_process_status_serialization() {
     let status = ProcessStatus::Running;
@@ -93,6 +189,12 @@ fn test_process_serialization() {
         completed_at: None,
         logs: vec!["Log entry 1".to_string(), "Log entry 2".to_string()],
         resume_notifier: std::sync::Arc::new(tokio::sync::Notify::new()),
+        usage: Default::default(),
+        steps: Vec::new(),
+        pending_permissions: Vec::new(),
+        sessions: Default::default(),
+        reference_file: None,
+        permission_replies: Default::default(),
     };
 
     let json = serde_json::to_string(&process).expect("Failed to serialize Process");
@@ -107,13 +209,18 @@ fn test_process_serialization() {
 
 #[test]
 fn test_global_config_serialization() {
-    let config = GlobalConfig { git: true };
+    let config = GlobalConfig {
+        git: true,
+        budget: None,
+        fallback: vec!["claude".to_string()],
+    };
 
     let json = serde_json::to_string(&config).expect("Failed to serialize GlobalConfig");
     let deserialized: GlobalConfig =
         serde_json::from_str(&json).expect("Failed to deserialize GlobalConfig");
 
     assert_eq!(deserialized.git, config.git);
+    assert_eq!(deserialized.fallback, vec!["claude".to_string()]);
 }
 
 #[test]
@@ -147,6 +254,15 @@ fn test_op_enum_serialization() {
     };
     let json = serde_json::to_value(&pause_op).expect("Failed to serialize Op::PauseProcess");
     assert_eq!(json["type"], "pauseProcess");
+
+    let answer_op = Op::AnswerPermission {
+        process_id: Uuid::new_v4(),
+        request_id: Uuid::new_v4(),
+        allow: true,
+    };
+    let json = serde_json::to_value(&answer_op).expect("Failed to serialize Op::AnswerPermission");
+    assert_eq!(json["type"], "answerPermission");
+    assert_eq!(json["payload"]["allow"], true);
 }
 
 #[test]
@@ -169,6 +285,23 @@ fn test_event_enum_serialization() {
     };
     let json = serde_json::to_value(&status_update).expect("Failed to serialize Event");
     assert_eq!(json["type"], "processStatusUpdate");
+
+    let requested = Event::PermissionRequested {
+        process_id: Uuid::new_v4(),
+        request: PermissionRequest {
+            request_id: Uuid::new_v4(),
+            agent_name: "developer".to_string(),
+            tool: "execute".to_string(),
+            title: "cargo test".to_string(),
+            arguments: serde_json::json!({ "command": "cargo test" }),
+        },
+    };
+    let json = serde_json::to_value(&requested).expect("Failed to serialize Event");
+    assert_eq!(json["type"], "permissionRequested");
+    assert_eq!(
+        json["payload"]["request"]["arguments"]["command"],
+        "cargo test"
+    );
 }
 
 #[test]
@@ -190,4 +323,209 @@ fn test_process_step_untagged_serialization() {
     let deserialized: ProcessStep =
         serde_json::from_str("\"HUMAN_REVIEW\"").expect("Failed to deserialize HUMAN_REVIEW");
     assert!(matches!(deserialized, ProcessStep::HumanReview(_)));
+
+    // Agent steps with options are written as maps
+    let steps: Vec<ProcessStep> = serde_yaml::from_str(
+        "- developer\n- { agent: developer, session: fresh }\n- agent: reviewer",
+    )
+    .expect("Failed to deserialize agent steps with options");
+    assert_eq!(steps[0].session_mode(), SessionMode::Continue);
+    assert_eq!(steps[1].agent_name(), Some("developer"));
+    assert_eq!(steps[1].session_mode(), SessionMode::Fresh);
+    assert_eq!(steps[2].agent_name(), Some("reviewer"));
+    assert_eq!(steps[2].session_mode(), SessionMode::Continue);
+}
+
+#[test]
+fn test_rpc_request_deserialization() {
+    let line = r#"{"id":7,"op":{"type":"getDashboardState"}}"#;
+    let request: RpcRequest = serde_json::from_str(line).expect("Failed to parse RpcRequest");
+    assert_eq!(request.id, Some(RequestId::Number(7)));
+    assert!(matches!(request.op, Op::GetDashboardState));
+
+    let line = r#"{"id":"abc","op":{"type":"killProcess","payload":{"process_id":"00000000-0000-0000-0000-000000000000"}}}"#;
+    let request: RpcRequest = serde_json::from_str(line).expect("Failed to parse RpcRequest");
+    assert_eq!(request.id, Some(RequestId::String("abc".to_string())));
+
+    // Notifications omit the id
+    let line = r#"{"op":{"type":"shutdown"}}"#;
+    let request: RpcRequest = serde_json::from_str(line).expect("Failed to parse RpcRequest");
+    assert!(request.id.is_none());
+}
+
+#[test]
+fn test_rpc_message_serialization() {
+    use uuid::Uuid;
+
+    let process_id = Uuid::new_v4();
+    let response = RpcMessage::Response {
+        id: Some(RequestId::Number(1)),
+        ok: true,
+        process_id: Some(process_id),
+        error: None,
+    };
+    let json = serde_json::to_value(&response).expect("Failed to serialize RpcMessage");
+    assert_eq!(json["kind"], "response");
+    assert_eq!(json["id"], 1);
+    assert_eq!(json["ok"], true);
+    assert_eq!(json["process_id"], process_id.to_string());
+    assert!(json["error"].is_null());
+
+    let event = RpcMessage::Event {
+        event: Box::new(Event::ProcessCompleted { process_id }),
+    };
+    let json = serde_json::to_value(&event).expect("Failed to serialize RpcMessage");
+    assert_eq!(json["kind"], "event");
+    assert_eq!(json["event"]["type"], "processCompleted");
+
+    let roundtrip: RpcMessage = serde_json::from_value(json).expect("Failed to parse RpcMessage");
+    assert!(matches!(roundtrip, RpcMessage::Event { .. }));
+}
+
+#[test]
+fn test_agent_activity_event_serialization() {
+    use uuid::Uuid;
+
+    let event = Event::ToolCallStarted {
+        process_id: Uuid::new_v4(),
+        agent_name: "developer".to_string(),
+        call_id: Some("toolu_1".to_string()),
+        name: "Bash".to_string(),
+        arguments: serde_json::json!({"command": "ls"}),
+    };
+    let json = serde_json::to_value(&event).expect("Failed to serialize Event");
+    assert_eq!(json["type"], "toolCallStarted");
+    assert_eq!(json["payload"]["arguments"]["command"], "ls");
+
+    let event = Event::UsageReported {
+        process_id: Uuid::new_v4(),
+        agent_name: "developer".to_string(),
+        usage: TokenUsage {
+            input_tokens: 10,
+            output_tokens: 5,
+            cost_usd: Some(0.25),
+            duration_ms: Some(1200),
+        },
+    };
+    let json = serde_json::to_value(&event).expect("Failed to serialize Event");
+    assert_eq!(json["type"], "usageReported");
+    assert_eq!(json["payload"]["usage"]["input_tokens"], 10);
+    assert_eq!(json["payload"]["usage"]["cost_usd"], 0.25);
+
+    let event = Event::FileChanged {
+        process_id: Uuid::new_v4(),
+        agent_name: "developer".to_string(),
+        path: "src/lib.rs".to_string(),
+        kind: FileChangeKind::Modified,
+    };
+    let json = serde_json::to_value(&event).expect("Failed to serialize Event");
+    assert_eq!(json["payload"]["kind"], "MODIFIED");
+}
+
+#[test]
+fn test_step_event_serialization() {
+    use uuid::Uuid;
+
+    let event = Event::StepCompleted {
+        process_id: Uuid::new_v4(),
+        step: StepRecord {
+            index: 2,
+            kind: StepKind::Agent,
+            agent_name: Some("developer".to_string()),
+            adapter_type: Some("Claude".to_string()),
+            served_by: Some("backup".to_string()),
+            status: StepStatus::Completed,
+            started_at: chrono::Utc::now(),
+            completed_at: Some(chrono::Utc::now()),
+            duration_ms: Some(3400),
+            error: None,
+        },
+    };
+
+    let json = serde_json::to_value(&event).expect("Failed to serialize Event");
+    assert_eq!(json["type"], "stepCompleted");
+    assert_eq!(json["payload"]["step"]["index"], 2);
+    assert_eq!(json["payload"]["step"]["kind"], "AGENT");
+    assert_eq!(json["payload"]["step"]["status"], "COMPLETED");
+    assert_eq!(json["payload"]["step"]["duration_ms"], 3400);
+    assert_eq!(json["payload"]["step"]["served_by"], "backup");
+
+    let deserialized: Event = serde_json::from_value(json).expect("Failed to deserialize Event");
+    assert!(
+        matches!(deserialized, Event::StepCompleted { step, .. } if step.agent_name.as_deref() == Some("developer"))
+    );
+}
+
+#[test]
+fn test_agent_fallback_event_serialization() {
+    use uuid::Uuid;
+
+    let event = Event::AgentFallback {
+        process_id: Uuid::new_v4(),
+        agent_name: "developer".to_string(),
+        fallback_agent: "backup".to_string(),
+        reason: "developer: not available".to_string(),
+    };
+
+    let json = serde_json::to_value(&event).expect("Failed to serialize Event");
+    assert_eq!(json["type"], "agentFallback");
+    assert_eq!(json["payload"]["fallback_agent"], "backup");
+
+    let deserialized: Event = serde_json::from_value(json).expect("Failed to deserialize Event");
+    assert!(
+        matches!(deserialized, Event::AgentFallback { fallback_agent, .. } if fallback_agent == "backup")
+    );
+}
+
+#[test]
+fn test_diagnostics_serialization() {
+    let op = serde_json::to_value(Op::Diagnose).expect("Failed to serialize Op");
+    assert_eq!(op["type"], "diagnose");
+
+    let event = Event::DiagnosticsReport {
+        agents: vec![AgentDiagnosis {
+            agent_name: "reviewer".to_string(),
+            adapter_type: "Codex".to_string(),
+            binary: Some("codex".to_string()),
+            binary_path: None,
+            version: None,
+            env_vars: vec![EnvVarStatus {
+                name: "OPENAI_API_KEY".to_string(),
+                present: false,
+            }],
+            available: false,
+        }],
+    };
+
+    let json = serde_json::to_value(&event).expect("Failed to serialize Event");
+    assert_eq!(json["type"], "diagnosticsReport");
+    assert_eq!(
+        json["payload"]["agents"][0]["env_vars"][0]["present"],
+        false
+    );
+
+    let deserialized: Event = serde_json::from_value(json).expect("Failed to deserialize Event");
+    assert!(
+        matches!(deserialized, Event::DiagnosticsReport { agents } if agents[0].binary.as_deref() == Some("codex"))
+    );
+}
+
+#[test]
+fn test_command_result_event_serialization() {
+    let event = Event::CommandResult {
+        op: Op::StartPipeline {
+            name: "missing".to_string(),
+            reference_file: None,
+        },
+        ok: false,
+        process_id: None,
+        error: Some("Pipeline not found: missing".to_string()),
+    };
+
+    let json = serde_json::to_value(&event).expect("Failed to serialize Event");
+    assert_eq!(json["type"], "commandResult");
+    assert_eq!(json["payload"]["op"]["type"], "startPipeline");
+    assert_eq!(json["payload"]["op"]["payload"]["name"], "missing");
+    assert_eq!(json["payload"]["ok"], false);
+    assert_eq!(json["payload"]["error"], "Pipeline not found: missing");
 }
@@ -5,6 +5,7 @@
 
 use anyhow::Result;
 use crossterm::event::KeyEvent;
+use pk_protocol::AgentDiagnosis;
 use pk_protocol::Event;
 use pk_protocol::Op;
 use pk_protocol::Process;
@@ -14,8 +15,6 @@ use ratatui::layout::Layout;
 use ratatui::layout::Rect;
 use ratatui::style::Color;
 use ratatui::style::Style;
-use ratatui::widgets::Block;
-use ratatui::widgets::Borders;
 use ratatui::widgets::Paragraph;
 use ratatui::Frame;
 use tokio::select;
@@ -28,7 +27,9 @@ use crate::event_handler;
 use crate::tui::Tui;
 use crate::tui::TuiEvent;
 use crate::widgets::dashboard;
+use crate::widgets::doctor_panel;
 use crate::widgets::CommandComposer;
+use crate::widgets::DetailView;
 
 /// Main TUI application state.
 ///
@@ -48,6 +49,10 @@ pub struct App {
     pub should_exit: bool,
     /// Error message to display (if any).
     pub error_message: Option<String>,
+    /// Detail view widget for the selected process.
+    pub detail_view: DetailView,
+    /// Latest `/doctor` report, shown instead of the detail view until closed.
+    pub diagnostics: Option<Vec<AgentDiagnosis>>,
 }
 
 impl App {
@@ -61,6 +66,8 @@ impl App {
             event_rx,
             should_exit: false,
             error_message: None,
+            detail_view: DetailView::new(),
+            diagnostics: None,
         }
     }
 
@@ -78,6 +85,8 @@ impl App {
             event_rx,
             should_exit: false,
             error_message: None,
+            detail_view: DetailView::new(),
+            diagnostics: None,
         }
     }
 
@@ -106,7 +115,22 @@ impl App {
 
     /// Handle events from the core (pk-core).
     fn handle_core_event(&mut self, event: Event) {
+        // Command results only affect the status line
+        if let Event::CommandResult { ok, error, .. } = &event {
+            self.error_message = if *ok { None } else { error.clone() };
+            return;
+        }
+        if let Event::DiagnosticsReport { agents } = event {
+            self.diagnostics = Some(agents);
+            return;
+        }
+
         event_handler::handle_core_event(&mut self.processes, event);
+
+        // A snapshot may have shrunk the process list
+        self.selected_index = self
+            .selected_index
+            .min(self.processes.len().saturating_sub(1));
     }
 
     /// Handle TUI events (keyboard input, resize, draw).
@@ -144,6 +168,25 @@ impl App {
             return;
         }
 
+        // With an empty composer, Esc closes the doctor panel
+        if self.diagnostics.is_some()
+            && key_event.code == KeyCode::Esc
+            && self.command_composer.input().is_empty()
+        {
+            self.diagnostics = None;
+            return;
+        }
+
+        // With an empty composer, y/n answer the selected process's oldest
+        // permission request
+        if self.command_composer.input().is_empty() {
+            if let KeyCode::Char(answer @ ('y' | 'n')) = key_event.code {
+                if self.answer_permission(answer == 'y') {
+                    return;
+                }
+            }
+        }
+
         // First, try to delegate to CommandComposer
         let status = self.command_composer.handle_key_event(key_event);
 
@@ -171,15 +214,11 @@ impl App {
             }
 
             // Process navigation (only when popup is not shown)
-            KeyCode::Up => {
-                if self.selected_index > 0 {
-                    self.selected_index -= 1;
-                }
+            KeyCode::Up if self.selected_index > 0 => {
+                self.selected_index -= 1;
             }
-            KeyCode::Down => {
-                if self.selected_index + 1 < self.processes.len() {
-                    self.selected_index += 1;
-                }
+            KeyCode::Down if self.selected_index + 1 < self.processes.len() => {
+                self.selected_index += 1;
             }
 
             // Command submission
@@ -191,6 +230,28 @@ impl App {
         }
     }
 
+    /// Answer the oldest pending permission request of the selected process.
+    ///
+    /// Returns `false` if the selected process has no pending request.
+    fn answer_permission(&mut self, allow: bool) -> bool {
+        let Some(process) = self.processes.get(self.selected_index) else {
+            return false;
+        };
+        let Some(request) = process.pending_permissions.first() else {
+            return false;
+        };
+
+        let op = Op::AnswerPermission {
+            process_id: process.id,
+            request_id: request.request_id,
+            allow,
+        };
+        if let Err(e) = self.op_tx.send(op) {
+            self.error_message = Some(format!("Failed to send command: {}", e));
+        }
+        true
+    }
+
     /// Handle command submission (Enter key).
     fn handle_command_submit(&mut self) {
         match self.command_composer.parse_command() {
@@ -240,24 +301,15 @@ impl App {
         dashboard::render_dashboard(frame, area, &self.processes, self.selected_index);
     }
 
-    /// Render the detail view (selected process logs).
+    /// Render the detail view (selected process steps and logs), or the
+    /// doctor panel while a `/doctor` report is open.
     fn render_detail(&self, frame: &mut Frame, area: Rect) {
-        let block = Block::default()
-            .borders(Borders::ALL)
-            .title("Detail - Process Logs");
-
-        let text = if let Some(process) = self.processes.get(self.selected_index) {
-            if process.logs.is_empty() {
-                "No logs yet.".to_string()
-            } else {
-                process.logs.join("\n")
-            }
-        } else {
-            "No process selected.".to_string()
-        };
-
-        let paragraph = Paragraph::new(text).block(block);
-        frame.render_widget(paragraph, area);
+        if let Some(diagnostics) = &self.diagnostics {
+            doctor_panel::render_doctor_panel(frame, area, diagnostics);
+            return;
+        }
+        self.detail_view
+            .render(frame, area, self.processes.get(self.selected_index));
     }
 
     /// Render the command input area.
@@ -366,6 +418,93 @@ mod tests {
         assert_eq!(app.processes[0].id, process_id);
     }
 
+    #[tokio::test]
+    async fn test_app_shows_command_failure_in_status_line() {
+        let (op_tx, _op_rx) = unbounded_channel();
+        let (_event_tx, event_rx) = unbounded_channel();
+
+        let mut app = App::new(op_tx, event_rx);
+
+        app.handle_core_event(Event::CommandResult {
+            op: Op::StartPipeline {
+                name: "missing".to_string(),
+                reference_file: None,
+            },
+            ok: false,
+            process_id: None,
+            error: Some("Pipeline not found: missing".to_string()),
+        });
+        assert_eq!(
+            app.error_message.as_deref(),
+            Some("Pipeline not found: missing")
+        );
+        assert!(app.processes.is_empty());
+
+        app.handle_core_event(Event::CommandResult {
+            op: Op::GetDashboardState,
+            ok: true,
+            process_id: None,
+            error: None,
+        });
+        assert!(app.error_message.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_app_answers_permission_request_with_y() {
+        let (op_tx, mut op_rx) = unbounded_channel();
+        let (_event_tx, event_rx) = unbounded_channel();
+
+        let mut app = App::new(op_tx, event_rx);
+        let process_id = Uuid::new_v4();
+        let request_id = Uuid::new_v4();
+        app.handle_core_event(Event::ProcessStarted {
+            process_id,
+            pipeline_name: "test-pipeline".to_string(),
+        });
+
+        // Without a pending request, y is ordinary input
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('y')));
+        assert!(op_rx.try_recv().is_err());
+        app.command_composer.clear();
+
+        app.handle_core_event(Event::PermissionRequested {
+            process_id,
+            request: pk_protocol::PermissionRequest {
+                request_id,
+                agent_name: "dev".to_string(),
+                tool: "execute".to_string(),
+                title: "cargo test".to_string(),
+                arguments: Default::default(),
+            },
+        });
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('y')));
+        assert!(matches!(
+            op_rx.try_recv(),
+            Ok(Op::AnswerPermission { request_id: id, allow: true, .. }) if id == request_id
+        ));
+        assert!(app.command_composer.input().is_empty());
+
+        app.handle_core_event(Event::PermissionResolved {
+            process_id,
+            request_id,
+            allowed: true,
+        });
+        assert!(app.processes[0].pending_permissions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_app_opens_and_closes_doctor_panel() {
+        let (op_tx, _op_rx) = unbounded_channel();
+        let (_event_tx, event_rx) = unbounded_channel();
+
+        let mut app = App::new(op_tx, event_rx);
+        app.handle_core_event(Event::DiagnosticsReport { agents: vec![] });
+        assert_eq!(app.diagnostics, Some(vec![]));
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        assert!(app.diagnostics.is_none());
+    }
+
     #[tokio::test]
     async fn test_app_navigation_with_arrow_keys() {
         let (op_tx, _op_rx) = unbounded_channel();
@@ -429,6 +568,12 @@ mod tests {
             started_at: Utc::now(),
             completed_at: None,
             resume_notifier: Arc::new(Notify::new()),
+            usage: Default::default(),
+            steps: Vec::new(),
+            pending_permissions: Vec::new(),
+            sessions: Default::default(),
+            reference_file: None,
+            permission_replies: Default::default(),
         };
 
         let process2 = Process {
@@ -440,6 +585,12 @@ mod tests {
             started_at: Utc::now(),
             completed_at: Some(Utc::now()),
             resume_notifier: Arc::new(Notify::new()),
+            usage: Default::default(),
+            steps: Vec::new(),
+            pending_permissions: Vec::new(),
+            sessions: Default::default(),
+            reference_file: None,
+            permission_replies: Default::default(),
         };
 
         app.processes.push(process1);
@@ -12,6 +12,7 @@ use pk_protocol::Event;
 use pk_protocol::Op;
 use pk_protocol::Process;
 use pk_protocol::ProcessStatus;
+use pk_protocol::StepStatus;
 use std::sync::Arc;
 use tokio::sync::mpsc::UnboundedSender;
 use tokio::sync::Notify;
@@ -32,6 +33,12 @@ pub fn handle_core_event(processes: &mut Vec<Process>, event: Event) {
                 started_at: chrono::Utc::now(),
                 completed_at: None,
                 resume_notifier: Arc::new(Notify::new()),
+                usage: Default::default(),
+                steps: Vec::new(),
+                pending_permissions: Vec::new(),
+                sessions: Default::default(),
+                reference_file: None,
+                permission_replies: Default::default(),
             };
             processes.push(process);
         }
@@ -79,6 +86,100 @@ pub fn handle_core_event(processes: &mut Vec<Process>, event: Event) {
                 process.logs.push("Process resumed".to_string());
             }
         }
+        Event::UsageReported {
+            process_id, usage, ..
+        } => {
+            if let Some(process) = processes.iter_mut().find(|p| p.id == process_id) {
+                process.usage.accumulate(&usage);
+            }
+        }
+        Event::DashboardState {
+            processes: snapshot,
+        } => {
+            // The core's view is authoritative; resynchronise completely
+            *processes = snapshot;
+        }
+        Event::ProcessDetail { process } => {
+            match processes.iter_mut().find(|p| p.id == process.id) {
+                Some(existing) => *existing = process,
+                None => processes.push(process),
+            }
+        }
+        Event::StepStarted { process_id, step }
+        | Event::StepCompleted { process_id, step }
+        | Event::StepFailed { process_id, step } => {
+            if let Some(process) = processes.iter_mut().find(|p| p.id == process_id) {
+                // Replace the running record for this step, or append a new one
+                match process
+                    .steps
+                    .iter_mut()
+                    .rev()
+                    .find(|s| s.index == step.index && s.started_at == step.started_at)
+                {
+                    Some(existing) => *existing = step,
+                    None => process.steps.push(step),
+                }
+            }
+        }
+        Event::PermissionRequested {
+            process_id,
+            request,
+        } => {
+            if let Some(process) = processes.iter_mut().find(|p| p.id == process_id) {
+                process.pending_permissions.push(request);
+            }
+        }
+        Event::PermissionResolved {
+            process_id,
+            request_id,
+            ..
+        } => {
+            if let Some(process) = processes.iter_mut().find(|p| p.id == process_id) {
+                process
+                    .pending_permissions
+                    .retain(|r| r.request_id != request_id);
+            }
+        }
+        Event::SessionStarted {
+            process_id,
+            agent_name,
+            session_id,
+        } => {
+            if let Some(process) = processes.iter_mut().find(|p| p.id == process_id) {
+                process.sessions.insert(agent_name, session_id);
+            }
+        }
+        Event::AgentFallback {
+            process_id,
+            fallback_agent,
+            ..
+        } => {
+            if let Some(process) = processes.iter_mut().find(|p| p.id == process_id) {
+                let index = process.current_step_index;
+                if let Some(step) = process
+                    .steps
+                    .iter_mut()
+                    .rev()
+                    .find(|s| s.index == index && s.status == StepStatus::Running)
+                {
+                    step.served_by = Some(fallback_agent);
+                }
+            }
+        }
+        Event::CommandResult { .. } => {
+            // Shown in the status line by App; no process state to update
+        }
+        Event::DiagnosticsReport { .. } => {
+            // Shown in the doctor panel by App; no process state to update
+        }
+        Event::ToolCallStarted { .. }
+        | Event::ToolCallCompleted { .. }
+        | Event::FileChanged { .. }
+        | Event::AgentWarning { .. }
+        | Event::BudgetWarning { .. }
+        | Event::BudgetExceeded { .. } => {
+            // Already mirrored into the process logs by the engine
+        }
     }
 }
 
@@ -100,15 +201,11 @@ pub fn handle_keyboard_event(
         KeyCode::Char('q') => {
             return true;
         }
-        KeyCode::Up => {
-            if *selected_index > 0 {
-                *selected_index -= 1;
-            }
+        KeyCode::Up if *selected_index > 0 => {
+            *selected_index -= 1;
         }
-        KeyCode::Down => {
-            if *selected_index < processes.len().saturating_sub(1) {
-                *selected_index += 1;
-            }
+        KeyCode::Down if *selected_index < processes.len().saturating_sub(1) => {
+            *selected_index += 1;
         }
         KeyCode::Char(c) => {
             command_input.push(c);
@@ -148,6 +245,9 @@ fn submit_command(
                     });
                 }
             }
+            Some("/list") => {
+                let _ = op_tx.send(Op::GetDashboardState);
+            }
             Some("/pause") => {
                 if let Some(process) = processes.get(selected_index) {
                     let _ = op_tx.send(Op::PauseProcess {
@@ -200,6 +300,125 @@ mod tests {
         assert_eq!(processes[0].pipeline_name, "test-pipeline");
     }
 
+    #[test]
+    fn test_handle_core_event_step_lifecycle_updates_timeline() {
+        let mut processes = Vec::new();
+        let process_id = Uuid::new_v4();
+        handle_core_event(
+            &mut processes,
+            Event::ProcessStarted {
+                process_id,
+                pipeline_name: "test-pipeline".to_string(),
+            },
+        );
+
+        let mut step = pk_protocol::StepRecord {
+            index: 0,
+            kind: pk_protocol::StepKind::Agent,
+            agent_name: Some("agent1".to_string()),
+            adapter_type: Some("Claude".to_string()),
+            served_by: None,
+            status: pk_protocol::StepStatus::Running,
+            started_at: chrono::Utc::now(),
+            completed_at: None,
+            duration_ms: None,
+            error: None,
+        };
+        handle_core_event(
+            &mut processes,
+            Event::StepStarted {
+                process_id,
+                step: step.clone(),
+            },
+        );
+        assert_eq!(processes[0].steps.len(), 1);
+
+        step.status = pk_protocol::StepStatus::Completed;
+        step.duration_ms = Some(1200);
+        handle_core_event(&mut processes, Event::StepCompleted { process_id, step });
+
+        assert_eq!(processes[0].steps.len(), 1);
+        assert_eq!(
+            processes[0].steps[0].status,
+            pk_protocol::StepStatus::Completed
+        );
+        assert_eq!(processes[0].steps[0].duration_ms, Some(1200));
+    }
+
+    #[test]
+    fn test_handle_core_event_dashboard_state_replaces_processes() {
+        let mut processes = Vec::new();
+        handle_core_event(
+            &mut processes,
+            Event::ProcessStarted {
+                process_id: Uuid::new_v4(),
+                pipeline_name: "stale".to_string(),
+            },
+        );
+
+        let mut snapshot = Vec::new();
+        let process_id = Uuid::new_v4();
+        handle_core_event(
+            &mut snapshot,
+            Event::ProcessStarted {
+                process_id,
+                pipeline_name: "fresh".to_string(),
+            },
+        );
+        snapshot[0].logs.push("line".to_string());
+
+        handle_core_event(
+            &mut processes,
+            Event::DashboardState {
+                processes: snapshot.clone(),
+            },
+        );
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].pipeline_name, "fresh");
+
+        snapshot[0].logs.push("another line".to_string());
+        handle_core_event(
+            &mut processes,
+            Event::ProcessDetail {
+                process: snapshot[0].clone(),
+            },
+        );
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].logs.len(), 2);
+    }
+
+    #[test]
+    fn test_handle_core_event_usage_reported_accumulates() {
+        let mut processes = Vec::new();
+        let process_id = Uuid::new_v4();
+        handle_core_event(
+            &mut processes,
+            Event::ProcessStarted {
+                process_id,
+                pipeline_name: "test-pipeline".to_string(),
+            },
+        );
+
+        for _ in 0..2 {
+            handle_core_event(
+                &mut processes,
+                Event::UsageReported {
+                    process_id,
+                    agent_name: "agent1".to_string(),
+                    usage: pk_protocol::TokenUsage {
+                        input_tokens: 100,
+                        output_tokens: 20,
+                        cost_usd: Some(0.25),
+                        duration_ms: None,
+                    },
+                },
+            );
+        }
+
+        assert_eq!(processes[0].usage.total_tokens(), 240);
+        assert_eq!(processes[0].usage.cost_usd, Some(0.5));
+    }
+
     #[test]
     fn test_handle_keyboard_event_quit() {
         let mut command_input = String::new();
@@ -235,6 +454,12 @@ mod tests {
                 started_at: chrono::Utc::now(),
                 completed_at: None,
                 resume_notifier: Arc::new(Notify::new()),
+                usage: Default::default(),
+                steps: Vec::new(),
+                pending_permissions: Vec::new(),
+                sessions: Default::default(),
+                reference_file: None,
+                permission_replies: Default::default(),
             },
             Process {
                 id: Uuid::new_v4(),
@@ -245,6 +470,12 @@ mod tests {
                 started_at: chrono::Utc::now(),
                 completed_at: None,
                 resume_notifier: Arc::new(Notify::new()),
+                usage: Default::default(),
+                steps: Vec::new(),
+                pending_permissions: Vec::new(),
+                sessions: Default::default(),
+                reference_file: None,
+                permission_replies: Default::default(),
             },
             Process {
                 id: Uuid::new_v4(),
@@ -255,6 +486,12 @@ mod tests {
                 started_at: chrono::Utc::now(),
                 completed_at: None,
                 resume_notifier: Arc::new(Notify::new()),
+                usage: Default::default(),
+                steps: Vec::new(),
+                pending_permissions: Vec::new(),
+                sessions: Default::default(),
+                reference_file: None,
+                permission_replies: Default::default(),
             },
         ];
 
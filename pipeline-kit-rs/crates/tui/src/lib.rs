@@ -23,8 +23,10 @@ use tokio::task::JoinHandle;
 // Core wiring: load config, manage agents/state, and speak protocol
 use pk_core::agents::manager::AgentManager;
 use pk_core::config::loader::load_config;
+use pk_core::state::dispatcher::OpDispatcher;
 use pk_core::state::manager::StateManager;
 use pk_protocol::ipc::{Event, Op};
+use std::sync::Arc;
 
 /// Run the TUI application.
 ///
@@ -50,7 +52,8 @@ pub async fn run_app() -> Result<()> {
     let config = load_config(&root).await?;
 
     // Initialize agent manager from config
-    let agent_manager = AgentManager::new(config.agents.clone());
+    let agent_manager = AgentManager::new(config.agents.clone())
+        .with_default_fallback(config.global.fallback.clone());
 
     // Bridge channels between Core (bounded) and TUI (unbounded)
     // - Core emits Events on a bounded channel expected by StateManager
@@ -59,7 +62,7 @@ pub async fn run_app() -> Result<()> {
     let (ui_event_tx, ui_event_rx) = mpsc::unbounded_channel::<Event>();
 
     // State manager drives pipeline/process lifecycle and emits events
-    let state_manager = StateManager::new(agent_manager, core_event_tx);
+    let state_manager = Arc::new(StateManager::new(agent_manager, core_event_tx));
 
     // UI sends Ops on an unbounded channel that Core will consume
     let (ui_op_tx, mut ui_op_rx) = mpsc::unbounded_channel::<Op>();
@@ -73,39 +76,12 @@ pub async fn run_app() -> Result<()> {
     });
 
     // Handle Ops from the UI by invoking StateManager
-    let state_manager_for_ops = state_manager;
-    let pipelines = config.pipelines.clone();
+    let dispatcher = OpDispatcher::new(state_manager, config.pipelines.clone());
     let _ops_handler: JoinHandle<()> = tokio::spawn(async move {
         while let Some(op) = ui_op_rx.recv().await {
-            match op {
-                Op::StartPipeline { name, .. } => {
-                    if let Some(pipeline_def) = pipelines.iter().find(|p| p.name == name).cloned() {
-                        // Fire-and-forget; StateManager emits lifecycle events
-                        let _id = state_manager_for_ops.start_pipeline(pipeline_def).await;
-                    } else {
-                        // No pipeline found: emit a ProcessError-like notice
-                        // Without a process_id, we cannot use ProcessError; skip emission.
-                    }
-                }
-                Op::PauseProcess { process_id } => {
-                    let _ = state_manager_for_ops.pause_process_by_id(process_id).await;
-                }
-                Op::ResumeProcess { process_id } => {
-                    let _ = state_manager_for_ops.resume_process_by_id(process_id).await;
-                }
-                Op::KillProcess { process_id } => {
-                    let _ = state_manager_for_ops.kill_process(process_id).await;
-                }
-                Op::GetDashboardState => {
-                    // Optional: could emit synthetic events or a summary; no-op for now.
-                }
-                Op::GetProcessDetail { .. } => {
-                    // Optional: detail query not implemented yet.
-                }
-                Op::Shutdown => {
-                    // Shutdown handled by TUI (quit keys); no-op here.
-                }
-            }
+            // Lifecycle events come from the StateManager; failures come
+            // back as CommandResult events for the status line
+            dispatcher.dispatch_and_report(op).await;
         }
     });
 
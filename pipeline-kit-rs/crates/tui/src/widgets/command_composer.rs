@@ -17,15 +17,17 @@ use ratatui::widgets::Block;
 use ratatui::widgets::Borders;
 use ratatui::widgets::Paragraph;
 use ratatui::widgets::Widget;
+use std::path::PathBuf;
 use uuid::Uuid;
 
 /// Available slash commands with their descriptions.
 const COMMANDS: &[(&str, &str)] = &[
-    ("/start <pipeline>", "Start a new pipeline"),
+    ("/start <pipeline>", "Start a new pipeline [reference file]"),
     ("/pause <process_id>", "Pause a running process"),
     ("/resume <process_id>", "Resume a paused process"),
     ("/kill <process_id>", "Kill a process"),
     ("/list", "List all processes"),
+    ("/doctor", "Check the configured agents' environment"),
 ];
 
 /// Command composer state.
@@ -251,8 +253,7 @@ impl CommandComposer {
         block.render(area, buf);
 
         // Render suggestions
-        let mut y = inner.y;
-        for (i, (cmd, desc)) in suggestions.iter().enumerate() {
+        for (y, (i, (cmd, desc))) in (inner.y..).zip(suggestions.iter().enumerate()) {
             if y >= inner.y + inner.height {
                 break;
             }
@@ -272,7 +273,6 @@ impl CommandComposer {
             ]);
 
             buf.set_line(inner.x, y, &line, inner.width);
-            y += 1;
         }
     }
 
@@ -395,7 +395,7 @@ impl CommandComposer {
                     let pipeline_name = parts.get(1).ok_or("Missing pipeline name")?;
                     Ok(Some(Op::StartPipeline {
                         name: pipeline_name.to_string(),
-                        reference_file: None,
+                        reference_file: parts.get(2).map(PathBuf::from),
                     }))
                 }
                 "/pause" => {
@@ -417,6 +417,7 @@ impl CommandComposer {
                     Ok(Some(Op::KillProcess { process_id }))
                 }
                 "/list" => Ok(Some(Op::GetDashboardState)),
+                "/doctor" => Ok(Some(Op::Diagnose)),
                 _ => Err(format!("Unknown command: {}", cmd)),
             }
         } else {
@@ -618,6 +619,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_start_command_with_reference_file() {
+        let mut composer = CommandComposer::new();
+        for c in "/start my-pipeline docs/spec.md".chars() {
+            composer.insert_char(c);
+        }
+
+        match composer.parse_command() {
+            Ok(Some(Op::StartPipeline {
+                name,
+                reference_file,
+            })) => {
+                assert_eq!(name, "my-pipeline");
+                assert_eq!(reference_file, Some(PathBuf::from("docs/spec.md")));
+            }
+            _ => panic!("Expected StartPipeline op"),
+        }
+    }
+
     #[test]
     fn test_parse_list_command() {
         let mut composer = CommandComposer::new();
@@ -637,6 +657,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_doctor_command() {
+        let mut composer = CommandComposer::new();
+        for c in "/doctor".chars() {
+            composer.insert_char(c);
+        }
+
+        assert!(matches!(composer.parse_command(), Ok(Some(Op::Diagnose))));
+    }
+
     #[test]
     fn test_parse_pause_command() {
         let process_id = Uuid::new_v4();
@@ -1,9 +1,10 @@
 //! Dashboard widget for displaying process list in a table.
 //!
 //! This module provides a table-based view of all running processes,
-//! showing their ID, name, status, and current step.
+//! showing their ID, name, status, current step, and usage.
 
 use pk_protocol::Process;
+use pk_protocol::TokenUsage;
 use ratatui::layout::Rect;
 use ratatui::style::Color;
 use ratatui::style::Modifier;
@@ -43,6 +44,7 @@ pub fn render_dashboard(frame: &mut Frame, area: Rect, processes: &[Process], se
                 Cell::from(p.pipeline_name.clone()),
                 Cell::from(format!("{:?}", p.status)).style(status_style),
                 Cell::from(format!("{}", p.current_step_index)),
+                Cell::from(format_usage(&p.usage)),
             ])
         })
         .collect();
@@ -53,6 +55,7 @@ pub fn render_dashboard(frame: &mut Frame, area: Rect, processes: &[Process], se
         Cell::from("Pipeline"),
         Cell::from("Status"),
         Cell::from("Step"),
+        Cell::from("Usage"),
     ])
     .style(
         Style::default()
@@ -63,9 +66,10 @@ pub fn render_dashboard(frame: &mut Frame, area: Rect, processes: &[Process], se
     // Create the table with proper widths
     let widths = [
         ratatui::layout::Constraint::Length(8), // Shortened UUID (first 8 chars)
-        ratatui::layout::Constraint::Percentage(50),
+        ratatui::layout::Constraint::Percentage(40),
         ratatui::layout::Constraint::Length(15),
         ratatui::layout::Constraint::Length(6),
+        ratatui::layout::Constraint::Length(18),
     ];
 
     let table = Table::new(rows, widths)
@@ -93,6 +97,24 @@ pub fn render_dashboard(frame: &mut Frame, area: Rect, processes: &[Process], se
     frame.render_stateful_widget(table, area, &mut table_state);
 }
 
+/// Format the running usage total as tokens plus cost when known.
+fn format_usage(usage: &TokenUsage) -> String {
+    let tokens = usage.total_tokens();
+    if tokens == 0 && usage.cost_usd.is_none() {
+        return "-".to_string();
+    }
+
+    let tokens = if tokens >= 1000 {
+        format!("{:.1}k", tokens as f64 / 1000.0)
+    } else {
+        tokens.to_string()
+    };
+    match usage.cost_usd {
+        Some(cost) => format!("{} ${:.2}", tokens, cost),
+        None => tokens,
+    }
+}
+
 /// Format UUID to show only the first 8 characters for better readability.
 fn format_uuid(uuid: &uuid::Uuid) -> String {
     let uuid_str = uuid.to_string();
@@ -155,6 +177,12 @@ mod tests {
             started_at: Utc::now(),
             completed_at: None,
             resume_notifier: Arc::new(Notify::new()),
+            usage: Default::default(),
+            steps: Vec::new(),
+            pending_permissions: Vec::new(),
+            sessions: Default::default(),
+            reference_file: None,
+            permission_replies: Default::default(),
         };
 
         let process2 = Process {
@@ -166,6 +194,12 @@ mod tests {
             started_at: Utc::now(),
             completed_at: Some(Utc::now()),
             resume_notifier: Arc::new(Notify::new()),
+            usage: Default::default(),
+            steps: Vec::new(),
+            pending_permissions: Vec::new(),
+            sessions: Default::default(),
+            reference_file: None,
+            permission_replies: Default::default(),
         };
 
         let processes = vec![process1.clone(), process2.clone()];
@@ -212,6 +246,12 @@ mod tests {
             started_at: Utc::now(),
             completed_at: None,
             resume_notifier: Arc::new(Notify::new()),
+            usage: Default::default(),
+            steps: Vec::new(),
+            pending_permissions: Vec::new(),
+            sessions: Default::default(),
+            reference_file: None,
+            permission_replies: Default::default(),
         };
 
         let process2 = Process {
@@ -223,6 +263,12 @@ mod tests {
             started_at: Utc::now(),
             completed_at: None,
             resume_notifier: Arc::new(Notify::new()),
+            usage: Default::default(),
+            steps: Vec::new(),
+            pending_permissions: Vec::new(),
+            sessions: Default::default(),
+            reference_file: None,
+            permission_replies: Default::default(),
         };
 
         let processes = vec![process1, process2];
@@ -257,4 +303,27 @@ mod tests {
             "Selected process row should be highlighted with blue background"
         );
     }
+
+    #[test]
+    fn test_format_usage() {
+        assert_eq!(format_usage(&TokenUsage::default()), "-");
+        assert_eq!(
+            format_usage(&TokenUsage {
+                input_tokens: 800,
+                output_tokens: 100,
+                cost_usd: None,
+                duration_ms: None,
+            }),
+            "900"
+        );
+        assert_eq!(
+            format_usage(&TokenUsage {
+                input_tokens: 12_000,
+                output_tokens: 345,
+                cost_usd: Some(0.5),
+                duration_ms: None,
+            }),
+            "12.3k $0.50"
+        );
+    }
 }
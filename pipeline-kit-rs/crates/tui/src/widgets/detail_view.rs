@@ -2,9 +2,21 @@
 //!
 //! This widget displays the logs and details of a selected process in a scrollable view.
 //! It supports keyboard navigation (j/k, PageUp/PageDown) and shows a scrollbar to indicate position.
+//! When the process has started any steps, a step timeline is shown above the logs.
+//! A pending permission request is shown as a prompt below the logs.
 
+use pk_protocol::PermissionRequest;
 use pk_protocol::Process;
+use pk_protocol::StepKind;
+use pk_protocol::StepRecord;
+use pk_protocol::StepStatus;
+use ratatui::layout::Constraint;
+use ratatui::layout::Layout;
 use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::text::Span;
 use ratatui::widgets::Block;
 use ratatui::widgets::Borders;
 use ratatui::widgets::Paragraph;
@@ -33,6 +45,31 @@ impl DetailView {
     /// * `area` - The area to render within
     /// * `process` - Optional reference to the process to display
     pub fn render(&self, frame: &mut Frame, area: Rect, process: Option<&Process>) {
+        // Split off the step timeline when there is one
+        let area = match process.filter(|p| !p.steps.is_empty()) {
+            Some(process) => {
+                let [timeline_area, logs_area] = Layout::vertical([
+                    Constraint::Length(process.steps.len() as u16 + 2),
+                    Constraint::Min(3),
+                ])
+                .areas(area);
+                render_timeline(frame, timeline_area, &process.steps);
+                logs_area
+            }
+            None => area,
+        };
+
+        // Split off the permission prompt while the agent is waiting on one
+        let area = match process.filter(|p| !p.pending_permissions.is_empty()) {
+            Some(process) => {
+                let [logs_area, prompt_area] =
+                    Layout::vertical([Constraint::Min(3), Constraint::Length(3)]).areas(area);
+                render_permission_prompt(frame, prompt_area, &process.pending_permissions);
+                logs_area
+            }
+            None => area,
+        };
+
         let block = Block::default()
             .borders(Borders::ALL)
             .title("Detail - Process Logs");
@@ -125,6 +162,76 @@ impl DetailView {
     }
 }
 
+/// Render the step timeline, one line per step.
+fn render_timeline(frame: &mut Frame, area: Rect, steps: &[StepRecord]) {
+    let lines: Vec<Line> = steps.iter().map(timeline_line).collect();
+    let paragraph =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Steps"));
+    frame.render_widget(paragraph, area);
+}
+
+/// Render the oldest pending permission request with its answer keys.
+fn render_permission_prompt(frame: &mut Frame, area: Rect, requests: &[PermissionRequest]) {
+    let request = &requests[0];
+    let mut spans = vec![
+        Span::styled("⚠ ", Style::default().fg(Color::Yellow)),
+        Span::raw(format!(
+            "{} asks to run {} ({})  ",
+            request.agent_name, request.title, request.tool
+        )),
+        Span::styled("y: allow  n: deny", Style::default().fg(Color::Cyan)),
+    ];
+    if requests.len() > 1 {
+        spans.push(Span::raw(format!("  (+{} more)", requests.len() - 1)));
+    }
+
+    let paragraph = Paragraph::new(Line::from(spans)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Permission Request"),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+/// Format a single step as `#1 agent (Adapter)  STATUS  duration`.
+fn timeline_line(step: &StepRecord) -> Line<'static> {
+    let (status, color) = match step.status {
+        StepStatus::Running => ("RUNNING", Color::Green),
+        StepStatus::Completed => ("DONE", Color::Cyan),
+        StepStatus::Failed => ("FAILED", Color::Red),
+    };
+
+    let label = match step.kind {
+        StepKind::Agent => {
+            let mut name = step.agent_name.clone().unwrap_or_default();
+            if let Some(served_by) = &step.served_by {
+                name = format!("{} → {}", name, served_by);
+            }
+            match &step.adapter_type {
+                Some(adapter) => format!("{} ({})", name, adapter),
+                None => name,
+            }
+        }
+        StepKind::HumanReview => "HUMAN_REVIEW".to_string(),
+    };
+
+    let mut spans = vec![
+        Span::raw(format!("#{} {}  ", step.index + 1, label)),
+        Span::styled(status, Style::default().fg(color)),
+    ];
+    if let Some(duration_ms) = step.duration_ms {
+        spans.push(Span::raw(format!("  {:.1}s", duration_ms as f64 / 1000.0)));
+    }
+    if let Some(error) = &step.error {
+        spans.push(Span::styled(
+            format!("  {}", error),
+            Style::default().fg(Color::Red),
+        ));
+    }
+
+    Line::from(spans)
+}
+
 impl Default for DetailView {
     fn default() -> Self {
         Self::new()
@@ -152,6 +259,12 @@ mod tests {
             completed_at: None,
             logs,
             resume_notifier: Arc::new(Notify::new()),
+            usage: Default::default(),
+            steps: Vec::new(),
+            pending_permissions: Vec::new(),
+            sessions: Default::default(),
+            reference_file: None,
+            permission_replies: Default::default(),
         }
     }
 
@@ -208,6 +321,95 @@ mod tests {
         assert!(content.contains("Log line 3"));
     }
 
+    #[test]
+    fn test_detail_view_renders_step_timeline() {
+        let detail_view = DetailView::new();
+        let mut process = create_test_process(vec!["Log line 1".to_string()]);
+        process.steps = vec![
+            StepRecord {
+                index: 0,
+                kind: StepKind::Agent,
+                agent_name: Some("researcher".to_string()),
+                adapter_type: Some("Claude".to_string()),
+                served_by: None,
+                status: StepStatus::Completed,
+                started_at: chrono::Utc::now(),
+                completed_at: Some(chrono::Utc::now()),
+                duration_ms: Some(2500),
+                error: None,
+            },
+            StepRecord {
+                index: 1,
+                kind: StepKind::HumanReview,
+                agent_name: None,
+                adapter_type: None,
+                served_by: None,
+                status: StepStatus::Running,
+                started_at: chrono::Utc::now(),
+                completed_at: None,
+                duration_ms: None,
+                error: None,
+            },
+        ];
+
+        let backend = TestBackend::new(80, 24);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        terminal
+            .draw(|frame| {
+                detail_view.render(frame, frame.area(), Some(&process));
+            })
+            .unwrap();
+
+        let content = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect::<String>();
+
+        assert!(content.contains("#1 researcher (Claude)"));
+        assert!(content.contains("2.5s"));
+        assert!(content.contains("#2 HUMAN_REVIEW"));
+        assert!(content.contains("RUNNING"));
+        assert!(content.contains("Log line 1"));
+    }
+
+    #[test]
+    fn test_detail_view_renders_permission_prompt() {
+        let detail_view = DetailView::new();
+        let mut process = create_test_process(vec!["Log line 1".to_string()]);
+        process.pending_permissions = vec![PermissionRequest {
+            request_id: Uuid::new_v4(),
+            agent_name: "developer".to_string(),
+            tool: "execute".to_string(),
+            title: "cargo test".to_string(),
+            arguments: Default::default(),
+        }];
+
+        let backend = TestBackend::new(100, 24);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        terminal
+            .draw(|frame| {
+                detail_view.render(frame, frame.area(), Some(&process));
+            })
+            .unwrap();
+
+        let content = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect::<String>();
+
+        assert!(content.contains("developer asks to run cargo test (execute)"));
+        assert!(content.contains("y: allow  n: deny"));
+        assert!(content.contains("Log line 1"));
+    }
+
     #[test]
     fn test_detail_view_scroll_up() {
         let mut detail_view = DetailView::new();
@@ -0,0 +1,119 @@
+//! Doctor panel widget for displaying agent environment diagnostics.
+//!
+//! Shown in place of the detail view after `/doctor`, with one line per
+//! configured agent: whether it can run, its adapter, the program it spawns
+//! and the environment variables it needs.
+
+use pk_protocol::AgentDiagnosis;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Block;
+use ratatui::widgets::Borders;
+use ratatui::widgets::Paragraph;
+use ratatui::Frame;
+
+/// Renders the diagnostics of all configured agents.
+///
+/// # Arguments
+/// * `frame` - The frame to render into
+/// * `area` - The area to render the panel in
+/// * `diagnoses` - One diagnosis per agent
+pub fn render_doctor_panel(frame: &mut Frame, area: Rect, diagnoses: &[AgentDiagnosis]) {
+    let mut lines: Vec<Line> = diagnoses.iter().map(diagnosis_line).collect();
+    if lines.is_empty() {
+        lines.push(Line::from("No agents configured"));
+    }
+
+    let panel = Paragraph::new(lines).block(
+        Block::default()
+            .title("Doctor (Esc to close)")
+            .borders(Borders::ALL),
+    );
+    frame.render_widget(panel, area);
+}
+
+/// Format a diagnosis as `✓ name (Adapter)  binary version  ENV ✓`.
+fn diagnosis_line(diagnosis: &AgentDiagnosis) -> Line<'static> {
+    let (mark, color) = if diagnosis.available {
+        ("✓", Color::Green)
+    } else {
+        ("✗", Color::Red)
+    };
+
+    let mut spans = vec![
+        Span::styled(mark, Style::default().fg(color)),
+        Span::raw(format!(
+            " {} ({})",
+            diagnosis.agent_name, diagnosis.adapter_type
+        )),
+    ];
+
+    if let Some(binary) = &diagnosis.binary {
+        match &diagnosis.binary_path {
+            Some(path) => spans.push(Span::raw(format!(
+                "  {} {}",
+                path,
+                diagnosis.version.as_deref().unwrap_or("(version unknown)")
+            ))),
+            None => spans.push(Span::styled(
+                format!("  {} not found", binary),
+                Style::default().fg(Color::Red),
+            )),
+        }
+    }
+
+    for env_var in &diagnosis.env_vars {
+        let (mark, color) = if env_var.present {
+            ("✓", Color::Green)
+        } else {
+            ("missing", Color::Red)
+        };
+        spans.push(Span::raw(format!("  {} ", env_var.name)));
+        spans.push(Span::styled(mark, Style::default().fg(color)));
+    }
+
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pk_protocol::EnvVarStatus;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    #[test]
+    fn test_doctor_panel_renders_missing_setup() {
+        let diagnoses = vec![AgentDiagnosis {
+            agent_name: "reviewer".to_string(),
+            adapter_type: "Codex".to_string(),
+            binary: Some("codex".to_string()),
+            binary_path: None,
+            version: None,
+            env_vars: vec![EnvVarStatus {
+                name: "OPENAI_API_KEY".to_string(),
+                present: false,
+            }],
+            available: false,
+        }];
+
+        let backend = TestBackend::new(80, 5);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal
+            .draw(|frame| render_doctor_panel(frame, frame.area(), &diagnoses))
+            .unwrap();
+
+        let content: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(content.contains("Doctor"));
+        assert!(content.contains("✗ reviewer (Codex)  codex not found  OPENAI_API_KEY missing"));
+    }
+}
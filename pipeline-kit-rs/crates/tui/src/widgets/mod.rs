@@ -5,6 +5,7 @@
 pub mod command_composer;
 pub mod dashboard;
 pub mod detail_view;
+pub mod doctor_panel;
 
 pub use command_composer::CommandComposer;
 pub use detail_view::DetailView;